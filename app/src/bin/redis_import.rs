@@ -0,0 +1,508 @@
+#![forbid(unsafe_code)]
+#![warn(rust_2018_idioms)]
+
+// Copies the state that the bot used to keep in Redis into Postgres. Run it
+// once against a database with all migrations applied:
+//
+//     DATABASE_URL=... REDIS_URL=redis://swissrpg-redis/0 redis-import
+//
+// Rows that already exist are left alone, so the import can be repeated after
+// an interruption. Short-lived keys (session scheduling flows, linking and
+// login links, CSRF tokens) are not imported, they would expire within
+// minutes anyway.
+
+use std::env;
+
+use lib::BoxedError;
+use redis::{aio::MultiplexedConnection, AsyncCommands};
+use sqlx::{
+    postgres::PgPoolOptions,
+    types::chrono::{DateTime, Utc},
+    PgPool,
+};
+
+#[tokio::main]
+async fn main() -> Result<(), BoxedError> {
+    let database_url = env::var("DATABASE_URL").expect("Found no DATABASE_URL in environment");
+    let redis_url = env::var("REDIS_URL").unwrap_or_else(|_| "redis://swissrpg-redis/0".into());
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(&database_url)
+        .await?;
+    let mut con = redis::Client::open(redis_url)?
+        .get_multiplexed_async_connection()
+        .await?;
+    import_members(&mut con, &pool).await?;
+    import_roles(&mut con, &pool).await?;
+    import_channels(&mut con, &pool).await?;
+    import_event_series(&mut con, &pool).await?;
+    import_removed_members(&mut con, &pool).await?;
+    import_events(&mut con, &pool).await?;
+    import_organizer_token(&mut con, &pool).await?;
+    import_bot_state(&mut con, &pool).await?;
+    println!("Done");
+    Ok(())
+}
+
+// Times were stored as RFC3339 strings
+async fn get_time(
+    con: &mut MultiplexedConnection,
+    key: &str,
+) -> Result<Option<DateTime<Utc>>, BoxedError> {
+    let time: Option<String> = con.get(key).await?;
+    let time = match time {
+        Some(time) => Some(DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc)),
+        None => None,
+    };
+    Ok(time)
+}
+
+async fn import_members(con: &mut MultiplexedConnection, pool: &PgPool) -> Result<(), BoxedError> {
+    let discord_ids: Vec<u64> = con.smembers("discord_users").await?;
+    println!("Importing {} linked Discord users", discord_ids.len());
+    for discord_id in discord_ids {
+        let meetup_id: Option<u64> = con
+            .get(format!("discord_user:{}:meetup_user", discord_id))
+            .await?;
+        let meetup_id = match meetup_id {
+            Some(meetup_id) => meetup_id,
+            None => continue,
+        };
+        let tokens_key = format!("meetup_user:{}:oauth2_tokens", meetup_id);
+        let access_token: Option<String> = con.hget(&tokens_key, "access_token").await?;
+        let refresh_token: Option<String> = con.hget(&tokens_key, "refresh_token").await?;
+        let last_refresh_time = get_time(con, &format!("{}:last_refresh_time", tokens_key)).await?;
+        let num_inserted = sqlx::query!(
+            r#"INSERT INTO "member" (meetup_id, discord_id, meetup_oauth2_access_token, meetup_oauth2_refresh_token, meetup_oauth2_last_token_refresh_time)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING"#,
+            meetup_id as i64,
+            discord_id as i64,
+            access_token,
+            refresh_token,
+            last_refresh_time
+        )
+        .execute(pool)
+        .await?
+        .rows_affected();
+        if num_inserted > 0 {
+            continue;
+        }
+        // Already imported, unless one of the IDs is linked to someone else
+        let existing = sqlx::query!(
+            r#"SELECT meetup_id, discord_id FROM "member" WHERE meetup_id = $1 OR discord_id = $2"#,
+            meetup_id as i64,
+            discord_id as i64
+        )
+        .fetch_all(pool)
+        .await?;
+        if existing.len() != 1
+            || existing[0].meetup_id != Some(meetup_id as i64)
+            || existing[0].discord_id != Some(discord_id as i64)
+        {
+            return Err(eyre::eyre!(
+                "{}<->{} was supposed to be inserted but conflicts with an existing member",
+                meetup_id,
+                discord_id
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn import_roles(con: &mut MultiplexedConnection, pool: &PgPool) -> Result<(), BoxedError> {
+    let role_ids: Vec<u64> = con.smembers("discord_roles").await?;
+    println!("Importing {} Discord roles", role_ids.len());
+    for role_id in role_ids {
+        sqlx::query!(
+            r#"INSERT INTO event_series_role (discord_id) VALUES ($1) ON CONFLICT DO NOTHING"#,
+            role_id as i64
+        )
+        .execute(pool)
+        .await?;
+    }
+    let host_role_ids: Vec<u64> = con.smembers("discord_host_roles").await?;
+    println!("Importing {} Discord host roles", host_role_ids.len());
+    for host_role_id in host_role_ids {
+        sqlx::query!(
+            r#"INSERT INTO event_series_host_role (discord_id) VALUES ($1) ON CONFLICT DO NOTHING"#,
+            host_role_id as i64
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn import_channels(con: &mut MultiplexedConnection, pool: &PgPool) -> Result<(), BoxedError> {
+    let channel_ids: Vec<u64> = con.smembers("discord_channels").await?;
+    println!("Importing {} Discord text channels", channel_ids.len());
+    for channel_id in channel_ids {
+        let key = format!("discord_channel:{}", channel_id);
+        let expiration_time = get_time(con, &format!("{}:expiration_time", key)).await?;
+        let last_expiration_reminder_time =
+            get_time(con, &format!("{}:last_expiration_reminder_time", key)).await?;
+        let snooze_until = get_time(con, &format!("{}:snooze_until", key)).await?;
+        let deletion_time = get_time(con, &format!("{}:deletion_time", key)).await?;
+        let role_id: Option<u64> = con.get(format!("{}:discord_role", key)).await?;
+        let host_role_id: Option<u64> = con.get(format!("{}:discord_host_role", key)).await?;
+        let mut tx = pool.begin().await?;
+        sqlx::query!(
+            r#"INSERT INTO event_series_text_channel (discord_id, expiration_time, last_expiration_reminder_time, snooze_until, deletion_time)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT DO NOTHING"#,
+            channel_id as i64,
+            expiration_time,
+            last_expiration_reminder_time,
+            snooze_until,
+            deletion_time
+        )
+        .execute(&mut *tx)
+        .await?;
+        // The roles are deleted together with their channel
+        if let Some(deletion_time) = deletion_time {
+            if let Some(role_id) = role_id {
+                sqlx::query!(
+                    r#"UPDATE event_series_role SET deletion_time = $2 WHERE discord_id = $1"#,
+                    role_id as i64,
+                    deletion_time
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            if let Some(host_role_id) = host_role_id {
+                sqlx::query!(
+                    r#"UPDATE event_series_host_role SET deletion_time = $2 WHERE discord_id = $1"#,
+                    host_role_id as i64,
+                    deletion_time
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+    }
+    let voice_channel_ids: Vec<u64> = con.smembers("discord_voice_channels").await?;
+    println!(
+        "Importing {} Discord voice channels",
+        voice_channel_ids.len()
+    );
+    for voice_channel_id in voice_channel_ids {
+        let deletion_time = get_time(
+            con,
+            &format!("discord_voice_channel:{}:deletion_time", voice_channel_id),
+        )
+        .await?;
+        sqlx::query!(
+            r#"INSERT INTO event_series_voice_channel (discord_id, deletion_time) VALUES ($1, $2)
+            ON CONFLICT DO NOTHING"#,
+            voice_channel_id as i64,
+            deletion_time
+        )
+        .execute(pool)
+        .await?;
+    }
+    let managed_channel_ids: Vec<u64> = con.smembers("managed_discord_channels").await?;
+    println!(
+        "Importing {} managed Discord channels",
+        managed_channel_ids.len()
+    );
+    for managed_channel_id in managed_channel_ids {
+        sqlx::query!(
+            r#"INSERT INTO managed_channel (discord_id) VALUES ($1) ON CONFLICT DO NOTHING"#,
+            managed_channel_id as i64
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn import_event_series(
+    con: &mut MultiplexedConnection,
+    pool: &PgPool,
+) -> Result<(), BoxedError> {
+    let series_ids: Vec<String> = con.smembers("event_series").await?;
+    println!("Importing {} event series", series_ids.len());
+    for series_id in series_ids {
+        let key = format!("event_series:{}", series_id);
+        let channel_id: Option<u64> = con.get(format!("{}:discord_channel", key)).await?;
+        let (role_id, host_role_id): (Option<u64>, Option<u64>) = match channel_id {
+            Some(channel_id) => (
+                con.get(format!("discord_channel:{}:discord_role", channel_id))
+                    .await?,
+                con.get(format!("discord_channel:{}:discord_host_role", channel_id))
+                    .await?,
+            ),
+            None => (None, None),
+        };
+        let voice_channel_id: Option<u64> =
+            con.get(format!("{}:discord_voice_channel", key)).await?;
+        let category_id: Option<u64> = con.get(format!("{}:discord_category", key)).await?;
+        let series_type: Option<String> = con.get(format!("{}:type", key)).await?;
+        let series_type = match series_type.as_deref() {
+            Some("campaign") => "campaign",
+            _ => "adventure",
+        };
+        sqlx::query!(
+            r#"INSERT INTO event_series (discord_text_channel_id, discord_voice_channel_id, discord_role_id, discord_host_role_id, discord_category_id, "type", redis_series_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (redis_series_id) DO NOTHING"#,
+            channel_id.map(|id| id as i64),
+            voice_channel_id.map(|id| id as i64),
+            role_id.map(|id| id as i64),
+            host_role_id.map(|id| id as i64),
+            category_id.map(|id| id as i64),
+            series_type,
+            series_id
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn member_for_discord_id(discord_id: u64, pool: &PgPool) -> Result<i32, BoxedError> {
+    let member_id = sqlx::query_scalar!(
+        r#"INSERT INTO "member" (discord_id) VALUES ($1)
+        ON CONFLICT (discord_id) DO UPDATE SET discord_id = EXCLUDED.discord_id
+        RETURNING id"#,
+        discord_id as i64
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(member_id)
+}
+
+async fn import_removed_members(
+    con: &mut MultiplexedConnection,
+    pool: &PgPool,
+) -> Result<(), BoxedError> {
+    let channel_ids: Vec<u64> = con.smembers("discord_channels").await?;
+    println!("Importing removed hosts and users");
+    for channel_id in channel_ids {
+        let series_ids = sqlx::query_scalar!(
+            r#"SELECT id FROM event_series WHERE discord_text_channel_id = $1"#,
+            channel_id as i64
+        )
+        .fetch_all(pool)
+        .await?;
+        let series_id = match series_ids.as_slice() {
+            [series_id] => *series_id,
+            _ => {
+                println!(
+                    "Found {} event series for channel {}, skipping",
+                    series_ids.len(),
+                    channel_id
+                );
+                continue;
+            }
+        };
+        let key = format!("discord_channel:{}", channel_id);
+        let removed_host_ids: Vec<u64> = con.smembers(format!("{}:removed_hosts", key)).await?;
+        for discord_id in removed_host_ids {
+            let member_id = member_for_discord_id(discord_id, pool).await?;
+            sqlx::query!(
+                r#"INSERT INTO event_series_removed_host (event_series_id, member_id)
+                SELECT $1, $2
+                WHERE NOT EXISTS (SELECT 1 FROM event_series_removed_host WHERE event_series_id = $1 AND member_id = $2)"#,
+                series_id,
+                member_id
+            )
+            .execute(pool)
+            .await?;
+        }
+        let removed_user_ids: Vec<u64> = con.smembers(format!("{}:removed_users", key)).await?;
+        for discord_id in removed_user_ids {
+            let member_id = member_for_discord_id(discord_id, pool).await?;
+            sqlx::query!(
+                r#"INSERT INTO event_series_removed_user (event_series_id, member_id)
+                SELECT $1, $2
+                WHERE NOT EXISTS (SELECT 1 FROM event_series_removed_user WHERE event_series_id = $1 AND member_id = $2)"#,
+                series_id,
+                member_id
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn member_for_meetup_id(
+    meetup_id: u64,
+    con: &mut MultiplexedConnection,
+    pool: &PgPool,
+) -> Result<i32, BoxedError> {
+    let member_id = sqlx::query_scalar!(
+        r#"SELECT id FROM "member" WHERE meetup_id = $1"#,
+        meetup_id as i64
+    )
+    .fetch_optional(pool)
+    .await?;
+    if let Some(member_id) = member_id {
+        return Ok(member_id);
+    }
+    let discord_id: Option<u64> = con
+        .get(format!("meetup_user:{}:discord_user", meetup_id))
+        .await?;
+    let member_id = sqlx::query_scalar!(
+        r#"INSERT INTO "member" (meetup_id, discord_id) VALUES ($1, $2) RETURNING id"#,
+        meetup_id as i64,
+        discord_id.map(|id| id as i64)
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(member_id)
+}
+
+async fn import_events(con: &mut MultiplexedConnection, pool: &PgPool) -> Result<(), BoxedError> {
+    let meetup_event_ids: Vec<String> = con.smembers("meetup_events").await?;
+    println!("Importing {} Meetup events", meetup_event_ids.len());
+    for meetup_event_id in meetup_event_ids {
+        let key = format!("meetup_event:{}", meetup_event_id);
+        let event_id = sqlx::query_scalar!(
+            r#"SELECT event_id FROM meetup_event WHERE meetup_id = $1"#,
+            &meetup_event_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        let event_id = match event_id {
+            Some(event_id) => event_id,
+            None => {
+                let redis_series_id: String = con.get(format!("{}:event_series", key)).await?;
+                let title: String = con.hget(&key, "name").await?;
+                let time: String = con.hget(&key, "time").await?;
+                let time = DateTime::parse_from_rfc3339(&time)?.with_timezone(&Utc);
+                let link: String = con.hget(&key, "link").await?;
+                let urlname: String = con.hget(&key, "urlname").await?;
+                let is_online: Option<String> = con.hget(&key, "is_online").await?;
+                let description: Option<String> = con.get(format!("{}:description", key)).await?;
+                let mut tx = pool.begin().await?;
+                let event_id = sqlx::query_scalar!(
+                    r#"INSERT INTO event (event_series_id, start_time, title, description, is_online)
+                    SELECT id, $2, $3, $4, $5 FROM event_series WHERE redis_series_id = $1
+                    RETURNING id"#,
+                    redis_series_id,
+                    time,
+                    title,
+                    description.unwrap_or_default(),
+                    is_online.as_deref() == Some("true")
+                )
+                .fetch_one(&mut *tx)
+                .await?;
+                sqlx::query!(
+                    r#"INSERT INTO meetup_event (event_id, meetup_id, url, urlname) VALUES ($1, $2, $3, $4)"#,
+                    event_id,
+                    &meetup_event_id,
+                    link,
+                    urlname
+                )
+                .execute(&mut *tx)
+                .await?;
+                tx.commit().await?;
+                event_id
+            }
+        };
+        let meetup_host_ids: Vec<u64> = con.smembers(format!("{}:meetup_hosts", key)).await?;
+        for meetup_host_id in meetup_host_ids {
+            let member_id = member_for_meetup_id(meetup_host_id, con, pool).await?;
+            sqlx::query!(
+                r#"INSERT INTO event_host (event_id, member_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+                event_id,
+                member_id
+            )
+            .execute(pool)
+            .await?;
+        }
+        let meetup_user_ids: Vec<u64> = con.smembers(format!("{}:meetup_users", key)).await?;
+        for meetup_user_id in meetup_user_ids {
+            let member_id = member_for_meetup_id(meetup_user_id, con, pool).await?;
+            sqlx::query!(
+                r#"INSERT INTO event_participant (event_id, member_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+                event_id,
+                member_id
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+async fn import_organizer_token(
+    con: &mut MultiplexedConnection,
+    pool: &PgPool,
+) -> Result<(), BoxedError> {
+    let access_token: Option<String> = con.get("meetup_access_token").await?;
+    let access_token = match access_token {
+        Some(access_token) => access_token,
+        None => return Ok(()),
+    };
+    println!("Importing the organizer token");
+    let refresh_token: Option<String> = con.get("meetup_refresh_token").await?;
+    let refresh_time = get_time(con, "meetup_access_token_refresh_time").await?;
+    sqlx::query!(
+        r#"INSERT INTO organizer_token (meetup_access_token, meetup_refresh_token, meetup_access_token_refresh_time)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (id) DO NOTHING"#,
+        access_token,
+        refresh_token,
+        refresh_time
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// The spam word list, orphaned channels and roles and the topic time of the
+// user topic voice channel
+async fn import_bot_state(
+    con: &mut MultiplexedConnection,
+    pool: &PgPool,
+) -> Result<(), BoxedError> {
+    let spam_words: Vec<String> = con.lrange("spam_word_list", 0, -1).await?;
+    println!("Importing {} spam words", spam_words.len());
+    for word in spam_words {
+        sqlx::query!(
+            r#"INSERT INTO spam_word (word) VALUES ($1) ON CONFLICT DO NOTHING"#,
+            word
+        )
+        .execute(pool)
+        .await?;
+    }
+    for (key, kind) in [
+        ("orphaned_discord_channels", "text"),
+        ("orphaned_discord_voice_channels", "voice"),
+        ("orphaned_discord_roles", "role"),
+    ] {
+        let discord_ids: Vec<u64> = con.smembers(key).await?;
+        println!(
+            "Importing {} orphaned objects from {}",
+            discord_ids.len(),
+            key
+        );
+        for discord_id in discord_ids {
+            sqlx::query!(
+                r#"INSERT INTO orphaned_discord_object (discord_id, kind) VALUES ($1, $2)
+                ON CONFLICT DO NOTHING"#,
+                discord_id as i64,
+                kind
+            )
+            .execute(pool)
+            .await?;
+        }
+    }
+    if let Some(topic_time) = get_time(con, "user_topic_voice_channel_topic_time").await? {
+        sqlx::query!(
+            r#"INSERT INTO ephemeral_settings (user_topic_voice_channel_topic_time)
+            VALUES ($1)
+            ON CONFLICT (id) DO UPDATE
+            SET user_topic_voice_channel_topic_time = $1"#,
+            topic_time
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
@@ -4,6 +4,7 @@
 use std::{
     env,
     num::NonZeroU64,
+    path::PathBuf,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
@@ -75,6 +76,10 @@ fn main() {
     if stripe_webhook_signing_secret.is_none() {
         eprintln!("No Stripe webhook signing secret set. Will not listen to Stripe webhooks.");
     }
+    let swissrpg_webhook_secret = env::var("SWISSRPG_WEBHOOK_SECRET").ok();
+    if swissrpg_webhook_secret.is_none() {
+        eprintln!("No SwissRPG webhook secret set. Will not listen to SwissRPG webhooks.");
+    }
     let api_keys: Vec<_> = env::var("API_KEYS")
         .as_deref()
         .unwrap_or("")
@@ -86,14 +91,7 @@ fn main() {
         eprintln!("No API keys set. Will not listen to API requests.");
     }
     let static_file_directory = env::var("STATIC_FILE_DIRECTORY").ok();
-
-    // Connect to the local Redis server
-    let redis_url = if cfg!(feature = "bottest") {
-        "redis://swissrpg-redis/1"
-    } else {
-        "redis://swissrpg-redis/0"
-    };
-    let redis_client = redis::Client::open(redis_url).expect("Could not create a Redis client");
+    let transcript_directory = env::var("TRANSCRIPT_DIRECTORY").ok();
 
     // Create a Meetup OAuth2 consumer
     let meetup_oauth2_consumer = Arc::new(
@@ -132,6 +130,13 @@ fn main() {
         }
     };
 
+    // Load the Discord guild configuration
+    let guild_config = lib::discord::guild_config::SharedGuildConfig::new(
+        async_runtime
+            .block_on(lib::discord::guild_config::GuildConfig::load(&pool))
+            .expect("Could not load the guild configuration from the database"),
+    );
+
     // Create SwissRPG API client
     let swissrpg_client = Arc::new(lib::swissrpg::client::SwissRPGClient::new(
         swissrpg_api_url.clone(),
@@ -153,17 +158,31 @@ fn main() {
         None => Arc::new(futures_util::lock::Mutex::new(None)),
     };
 
+    // Deleted channels' transcripts are stored here
+    let transcript_directory: Arc<PathBuf> = Arc::new(
+        transcript_directory
+            .unwrap_or_else(|| {
+                if cfg!(feature = "bottest") {
+                    "/usr/local/share/swissrpg-app-test/transcripts".into()
+                } else {
+                    "/usr/local/share/swissrpg-app/transcripts".into()
+                }
+            })
+            .into(),
+    );
+
     let bot_shutdown_signal = Arc::new(AtomicBool::new(false));
     let mut bot = async_runtime
         .block_on(ui::discord::bot::create_discord_client(
             &discord_token,
             discord_application_id,
-            redis_client.clone(),
             pool.clone(),
             async_meetup_client.clone(),
             meetup_oauth2_consumer.clone(),
             stripe_client.clone(),
             swissrpg_client.clone(),
+            guild_config.clone(),
+            transcript_directory.clone(),
             bot_shutdown_signal.clone(),
         ))
         .expect("Could not create the Discord bot");
@@ -208,16 +227,18 @@ fn main() {
     let web_server = ui::web::server::create_server(
         meetup_oauth2_consumer.clone(),
         ([0, 0, 0, 0], port).into(),
-        redis_client.clone(),
         pool.clone(),
         async_meetup_client.clone(),
         swissrpg_client.clone(),
         discord_api.clone(),
+        guild_config.clone(),
         bot_name,
         stripe_webhook_signing_secret,
         stripe_client.clone(),
+        swissrpg_webhook_secret,
         api_keys,
         static_file_directory,
+        transcript_directory.clone(),
         abort_web_server_signal,
     );
 
@@ -234,78 +255,193 @@ fn main() {
     //     pool.clone(),
     // );
 
-    // Schedule the end of game task
-    let end_of_game_task = lib::tasks::end_of_game::create_recurring_end_of_game_task(
-        pool.clone(),
-        discord_api.clone(),
-        bot_id,
-    );
-
-    // User topic voice channel reset task
-    let user_topic_voice_channel_reset_task =
-        lib::tasks::user_topic_voice_channel::reset_user_topic_voice_channel_task(
-            redis_client.clone(),
-            discord_api.clone(),
-        );
-
-    let static_file_prefix = Box::leak(format!("{}/static/", lib::urls::BASE_URL).into_boxed_str());
+    let static_file_prefix: &'static str =
+        Box::leak(format!("{}/static/", lib::urls::BASE_URL).into_boxed_str());
 
     // Create shared sync state for coordinating between sync tasks
     let sync_state = lib::tasks::sync::SyncState::default();
 
-    // Independent sync tasks
-    let meetup_sync_task = lib::tasks::sync::create_recurring_meetup_sync_task(
-        pool.clone(),
-        async_meetup_client.clone(),
-        sync_state.clone(),
-    );
-
-    let swissrpg_sync_task = lib::tasks::sync::create_recurring_swissrpg_sync_task(
-        pool.clone(),
-        swissrpg_client.clone(),
-        sync_state.clone(),
-    );
-
-    let discord_sync_task = lib::tasks::sync::create_recurring_discord_sync_task(
-        pool.clone(),
-        redis_client.clone(),
-        discord_api.clone(),
-        bot_id,
-        swissrpg_api_url,
-    );
-
-    let free_spots_task = lib::tasks::sync::create_recurring_free_spots_task(
-        sync_state,
-        async_meetup_client.clone(),
-        pool.clone(),
-        discord_api.clone(),
-        static_file_prefix,
-    );
-
-    let stripe_subscription_refresh_task =
-        lib::tasks::subscription_roles::stripe_subscriptions_refresh_task(
-            discord_api.clone(),
-            stripe_client.clone(),
-        );
+    // The recurring jobs. Schedules are in UTC.
+    let jobs = {
+        use lib::tasks::scheduler::Job;
+        let sync_timeout = std::time::Duration::from_secs(360);
+        vec![
+            Job::new("meetup-sync", "*/15 * * * *", {
+                let pool = pool.clone();
+                let async_meetup_client = async_meetup_client.clone();
+                let sync_state = sync_state.clone();
+                move || {
+                    lib::tasks::sync::meetup_sync_job(
+                        pool.clone(),
+                        async_meetup_client.clone(),
+                        sync_state.clone(),
+                    )
+                }
+            })
+            .timeout(sync_timeout),
+            Job::new("swissrpg-sync", "5-59/15 * * * *", {
+                let pool = pool.clone();
+                let swissrpg_client = swissrpg_client.clone();
+                let sync_state = sync_state.clone();
+                move || {
+                    lib::tasks::sync::swissrpg_sync_job(
+                        pool.clone(),
+                        swissrpg_client.clone(),
+                        sync_state.clone(),
+                    )
+                }
+            })
+            .timeout(sync_timeout),
+            Job::new("discord-sync", "10-59/15 * * * *", {
+                let pool = pool.clone();
+                let discord_api = discord_api.clone();
+                let guild_config = guild_config.clone();
+                move || {
+                    lib::tasks::sync::discord_sync_job(
+                        pool.clone(),
+                        discord_api.clone(),
+                        guild_config.clone(),
+                        bot_id,
+                        swissrpg_api_url.clone(),
+                    )
+                }
+            }),
+            Job::new("free-spots", "10-59/15 * * * *", {
+                let pool = pool.clone();
+                let async_meetup_client = async_meetup_client.clone();
+                let discord_api = discord_api.clone();
+                let guild_config = guild_config.clone();
+                move || {
+                    lib::tasks::sync::free_spots_job(
+                        sync_state.clone(),
+                        async_meetup_client.clone(),
+                        pool.clone(),
+                        discord_api.clone(),
+                        guild_config.clone(),
+                        static_file_prefix,
+                    )
+                }
+            }),
+            Job::new("end-of-game", "30 18 * * *", {
+                let pool = pool.clone();
+                let discord_api = discord_api.clone();
+                let guild_config = guild_config.clone();
+                let transcript_directory = transcript_directory.clone();
+                move || {
+                    let pool = pool.clone();
+                    let mut discord_api = discord_api.clone();
+                    let guild_config = guild_config.get();
+                    let transcript_directory = transcript_directory.clone();
+                    async move {
+                        lib::tasks::end_of_game::end_of_game_task(
+                            &pool,
+                            &mut discord_api,
+                            &guild_config,
+                            bot_id,
+                            &transcript_directory,
+                        )
+                        .await?;
+                        Ok(())
+                    }
+                }
+            }),
+            Job::new("session-reminders", "*/5 * * * *", {
+                let pool = pool.clone();
+                let discord_api = discord_api.clone();
+                move || {
+                    let pool = pool.clone();
+                    let discord_api = discord_api.clone();
+                    async move {
+                        lib::tasks::session_reminder::session_reminder_task(&pool, &discord_api)
+                            .await?;
+                        Ok(())
+                    }
+                }
+            })
+            .catch_up(false),
+            Job::new("attendance-requests", "*/15 * * * *", {
+                let pool = pool.clone();
+                let discord_api = discord_api.clone();
+                move || {
+                    let pool = pool.clone();
+                    let discord_api = discord_api.clone();
+                    async move {
+                        lib::attendance::attendance_request_task(&pool, &discord_api).await?;
+                        Ok(())
+                    }
+                }
+            })
+            .catch_up(false),
+            Job::new("user-topic-voice-channel-reset", "* * * * *", {
+                let pool = pool.clone();
+                let discord_api = discord_api.clone();
+                let guild_config = guild_config.clone();
+                move || {
+                    let pool = pool.clone();
+                    let discord_api = discord_api.clone();
+                    let guild_config = guild_config.get();
+                    async move {
+                        lib::tasks::user_topic_voice_channel::reset_user_topic_voice_channel_task(
+                            &pool,
+                            &discord_api,
+                            &guild_config,
+                        )
+                        .await?;
+                        Ok(())
+                    }
+                }
+            })
+            .catch_up(false),
+            Job::new("expired-links-cleanup", "45 * * * *", {
+                let pool = pool.clone();
+                move || {
+                    let pool = pool.clone();
+                    async move {
+                        lib::flow::delete_expired(&pool).await?;
+                        Ok(())
+                    }
+                }
+            })
+            .catch_up(false),
+            Job::new("stripe-subscriptions", "0 */8 * * *", {
+                let pool = pool.clone();
+                let discord_api = discord_api.clone();
+                let stripe_client = stripe_client.clone();
+                let guild_config = guild_config.clone();
+                move || {
+                    let pool = pool.clone();
+                    let discord_api = discord_api.clone();
+                    let stripe_client = stripe_client.clone();
+                    let guild_config = guild_config.get();
+                    async move {
+                        lib::tasks::subscription_roles::update_roles(
+                            &pool,
+                            &discord_api,
+                            &stripe_client,
+                            &guild_config,
+                        )
+                        .await?;
+                        Ok(())
+                    }
+                }
+            }),
+        ]
+    };
+    let scheduler = lib::tasks::scheduler::Scheduler::new(pool.clone(), jobs);
+    futures::executor::block_on(async {
+        bot.data
+            .write()
+            .await
+            .insert::<ui::discord::bot::SchedulerKey>(scheduler.clone())
+    });
+    let scheduler_task = scheduler.run();
 
     // Wrap the long-running tasks in abortable Futures
     let (organizer_token_refresh_task, abort_handle_organizer_token_refresh_task) =
         future::abortable(organizer_token_refresh_task);
     // let (users_token_refresh_task, abort_handle_users_token_refresh_task) =
     //     future::abortable(users_token_refresh_task);
-    let (end_of_game_task, abort_handle_end_of_game_task) = future::abortable(end_of_game_task);
-    let (user_topic_voice_channel_reset_task, abort_handle_user_topic_voice_channel_reset_task) =
-        future::abortable(user_topic_voice_channel_reset_task);
-
-    // Split sync tasks
-    let (meetup_sync_task, abort_handle_meetup_sync_task) = future::abortable(meetup_sync_task);
-    let (swissrpg_sync_task, abort_handle_swissrpg_sync_task) =
-        future::abortable(swissrpg_sync_task);
-    let (discord_sync_task, abort_handle_discord_sync_task) = future::abortable(discord_sync_task);
-    let (free_spots_task, abort_handle_free_spots_task) = future::abortable(free_spots_task);
-
-    let (stripe_subscription_refresh_task, abort_handle_stripe_subscription_refresh_task) =
-        future::abortable(stripe_subscription_refresh_task);
+    let (scheduler_task, abort_handle_scheduler_task) = future::abortable(scheduler_task);
 
     // Create a synchronization barrier that keeps the main thread from exiting
     // until the signal handler tells it to
@@ -348,35 +484,8 @@ fn main() {
         //     println!("User token refresh task shut down.");
         // });
         tokio::spawn(async {
-            let _ = end_of_game_task.await;
-            println!("End of game task shut down.");
-        });
-        tokio::spawn(async {
-            let _ = user_topic_voice_channel_reset_task.await;
-            println!("User topic voice channel reset task shut down.");
-        });
-
-        // Split sync tasks
-        tokio::spawn(async {
-            let _ = meetup_sync_task.await;
-            println!("Meetup sync task shut down.");
-        });
-        tokio::spawn(async {
-            let _ = swissrpg_sync_task.await;
-            println!("SwissRPG sync task shut down.");
-        });
-        tokio::spawn(async {
-            let _ = discord_sync_task.await;
-            println!("Discord sync task shut down.");
-        });
-        tokio::spawn(async {
-            let _ = free_spots_task.await;
-            println!("Free spots task shut down.");
-        });
-
-        tokio::spawn(async {
-            let _ = stripe_subscription_refresh_task.await;
-            println!("Stripe subscription refresh task shut down.");
+            let _ = scheduler_task.await;
+            println!("Job scheduler shut down.");
         });
         tokio::spawn(async {
             web_server.await;
@@ -400,16 +509,7 @@ fn main() {
     bot_shutdown_signal.store(true, Ordering::Release);
     abort_handle_organizer_token_refresh_task.abort();
     // abort_handle_users_token_refresh_task.abort();
-    abort_handle_end_of_game_task.abort();
-    abort_handle_user_topic_voice_channel_reset_task.abort();
-
-    // Abort split sync tasks
-    abort_handle_meetup_sync_task.abort();
-    abort_handle_swissrpg_sync_task.abort();
-    abort_handle_discord_sync_task.abort();
-    abort_handle_free_spots_task.abort();
-
-    abort_handle_stripe_subscription_refresh_task.abort();
+    abort_handle_scheduler_task.abort();
     abort_web_server_tx.send(()).ok();
     println!("About to shut down the tokio runtime.");
     // Give any currently running tasks a chance to finish
@@ -65,6 +65,100 @@ impl Parse for RegexAttribute {
     }
 }
 
+/// `#[slash(name = "...")]` overrides the slash command name, which defaults
+/// to the function name with dashes instead of underscores
+struct SlashAttribute {
+    name: syn::LitStr,
+}
+
+impl Parse for SlashAttribute {
+    fn parse(input: ParseStream<'_>) -> syn::parse::Result<Self> {
+        let key: syn::Ident = input.parse()?;
+        if key != "name" {
+            return Err(syn::Error::new(key.span(), "Expected `name = \"...\"`"));
+        }
+        let _: Token![=] = input.parse()?;
+        Ok(SlashAttribute {
+            name: input.parse()?,
+        })
+    }
+}
+
+/// `#[option(capture_name: Kind, "description", name = "...", optional, autocomplete = path)]`
+///
+/// Declares a slash command option whose value is handed to the command as
+/// the regex capture group `capture_name`. `path` names a function that
+/// returns the values to suggest while the option is typed.
+struct OptionAttribute {
+    capture: syn::Ident,
+    kind: syn::Ident,
+    description: syn::LitStr,
+    name: Option<syn::LitStr>,
+    optional: bool,
+    autocomplete: Option<syn::Path>,
+}
+
+impl Parse for OptionAttribute {
+    fn parse(input: ParseStream<'_>) -> syn::parse::Result<Self> {
+        let capture: syn::Ident = input.parse()?;
+        let _: Token![:] = input.parse()?;
+        let kind: syn::Ident = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let description: syn::LitStr = input.parse()?;
+        let mut name = None;
+        let mut optional = false;
+        let mut autocomplete = None;
+        while !input.is_empty() {
+            let _: Token![,] = input.parse()?;
+            let key: syn::Ident = input.parse()?;
+            match key.to_string().as_str() {
+                "name" => {
+                    let _: Token![=] = input.parse()?;
+                    name = Some(input.parse()?);
+                }
+                "optional" => optional = true,
+                "autocomplete" => {
+                    let _: Token![=] = input.parse()?;
+                    autocomplete = Some(input.parse()?);
+                }
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "Expected `name = \"...\"`, `optional` or `autocomplete = ...`",
+                    ))
+                }
+            }
+        }
+        Ok(OptionAttribute {
+            capture,
+            kind,
+            description,
+            name,
+            optional,
+            autocomplete,
+        })
+    }
+}
+
+// Discord only accepts lowercase names of up to 32 characters
+fn check_slash_name(name: &syn::LitStr) -> syn::Result<()> {
+    let span = name.span();
+    let name = name.value();
+    let valid = !name.is_empty()
+        && name.len() <= 32
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            span,
+            format!("Invalid slash command or option name \"{}\"", name),
+        ))
+    }
+}
+
 #[proc_macro_attribute]
 pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let command_fun = parse_macro_input!(item as CommandFun);
@@ -72,6 +166,8 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut command_level = None;
     let mut unknown_attrs = vec![];
     let mut help_texts = vec![];
+    let mut slash_name = None;
+    let mut slash_options = vec![];
     for attribute in &command_fun.attributes {
         // let meta_attribute = propagate_err!(attribute.parse_meta());
         let attr_ident = match attribute.path().get_ident() {
@@ -111,6 +207,17 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 let text = propagate_err!(attribute.parse_args_with(parser));
                 help_texts.push(text);
             }
+            "slash" => {
+                let slash_attribute = propagate_err!(attribute.parse_args::<SlashAttribute>());
+                if slash_name.is_some() {
+                    panic!("Multiple slash command names specified for the same command");
+                }
+                slash_name = Some(slash_attribute.name);
+            }
+            "option" => {
+                let option_attribute = propagate_err!(attribute.parse_args::<OptionAttribute>());
+                slash_options.push(option_attribute);
+            }
             _ => {
                 unknown_attrs.push(attribute);
                 continue;
@@ -134,6 +241,41 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     let fun_ident = command_fun.fun.sig.ident.clone();
+    let slash_name = slash_name.unwrap_or_else(|| {
+        syn::LitStr::new(&fun_ident.to_string().replace('_', "-"), fun_ident.span())
+    });
+    propagate_err!(check_slash_name(&slash_name));
+    let mut slash_option_entries = vec![];
+    for option in slash_options {
+        let capture = option.capture.to_string();
+        let name = option
+            .name
+            .unwrap_or_else(|| syn::LitStr::new(&capture.replace('_', "-"), option.capture.span()));
+        propagate_err!(check_slash_name(&name));
+        let kind = match option.kind.to_string().as_str() {
+            "User" | "String" | "Integer" => option.kind,
+            _ => {
+                return into_stream(syn::Error::new(
+                    option.kind.span(),
+                    "Expected one of User, String or Integer",
+                ))
+            }
+        };
+        let description = option.description;
+        let required = !option.optional;
+        let autocomplete = match option.autocomplete {
+            Some(path) => quote!(Some(#path)),
+            None => quote!(None),
+        };
+        slash_option_entries.push(quote! { crate::discord::commands::SlashOption {
+            capture: #capture,
+            name: #name,
+            kind: crate::discord::commands::SlashOptionKind::#kind,
+            description: #description,
+            required: #required,
+            autocomplete: #autocomplete,
+        } });
+    }
     let regex_fun_ident = format_ident!("{}_regex", fun_ident.to_string());
     let static_instance_name = format_ident!("{}_COMMAND", fun_ident.to_string().to_uppercase());
     let mut fun = command_fun.fun;
@@ -165,6 +307,10 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
             level: #command_level,
             fun: &#fun_ident,
             help: &[#(#help_entries,)*],
+            slash: crate::discord::commands::SlashCommand {
+                name: #slash_name,
+                options: &[#(#slash_option_entries,)*],
+            },
         };
     };
     // println!("{}", output.to_string());
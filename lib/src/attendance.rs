@@ -0,0 +1,393 @@
+// Attendance of the players who RSVP'd to a session. After a session the bot
+// posts an attendance sheet to the game channel where the hosts mark who
+// showed up, alternatively they can use the web form.
+
+use serenity::{
+    builder::{CreateActionRow, CreateButton, CreateMessage},
+    model::{application::ButtonStyle, id::ChannelId},
+};
+
+use crate::{db, strings};
+
+// The buttons on attendance sheets carry the event and the member in their
+// custom ID, e.g. "attendance:1234:56" or "attendance:1234:all"
+const CUSTOM_ID_PREFIX: &str = "attendance:";
+
+// Sessions that ended longer ago than this don't get an attendance sheet
+const MAX_REQUEST_DELAY_HOURS: i32 = 48;
+
+// Discord allows five rows of five buttons and the last row is reserved for
+// the "everyone else showed up" button. Larger sessions use the web form.
+const MAX_PLAYER_BUTTONS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttendanceAction {
+    // Switches a player between showed up and no-show
+    Toggle(db::MemberId),
+    // Marks everyone who isn't marked yet as showed up
+    RemainingAttended,
+}
+
+pub fn parse_custom_id(custom_id: &str) -> Option<(db::EventId, AttendanceAction)> {
+    let (event_id, target) = custom_id.strip_prefix(CUSTOM_ID_PREFIX)?.split_once(':')?;
+    let event_id = db::EventId(event_id.parse::<i32>().ok()?);
+    let action = match target {
+        "all" => AttendanceAction::RemainingAttended,
+        member_id => AttendanceAction::Toggle(db::MemberId(member_id.parse::<i32>().ok()?)),
+    };
+    Some((event_id, action))
+}
+
+pub fn form_url(event_id: db::EventId) -> String {
+    format!("{}/attendance/{}", crate::urls::BASE_URL, event_id.0)
+}
+
+pub fn member_stats_url(member_id: db::MemberId) -> String {
+    format!(
+        "{}/attendance/member/{}",
+        crate::urls::BASE_URL,
+        member_id.0
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerAttendance {
+    pub member_id: db::MemberId,
+    pub name: String,
+    // None until a host marked the player
+    pub attended: Option<bool>,
+}
+
+// The players of a session together with their attendance so far
+#[derive(Debug, Clone)]
+pub struct AttendanceSheet {
+    pub event_id: db::EventId,
+    pub title: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub players: Vec<PlayerAttendance>,
+}
+
+impl AttendanceSheet {
+    pub async fn load(
+        event_id: db::EventId,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<Option<Self>, crate::meetup::Error> {
+        let event = sqlx::query!(
+            r#"SELECT title, start_time FROM event WHERE id = $1 AND deleted IS NULL"#,
+            event_id.0
+        )
+        .fetch_optional(db_connection)
+        .await?;
+        let event = match event {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+        let players = sqlx::query!(
+            r#"SELECT "member".id AS member_id,
+                COALESCE("member".discord_nick, 'Member ' || "member".id) AS "name!",
+                event_attendance.attended AS "attended?"
+            FROM event_participant
+            INNER JOIN "member" ON event_participant.member_id = "member".id
+            LEFT OUTER JOIN event_attendance ON event_attendance.event_id = event_participant.event_id AND event_attendance.member_id = "member".id
+            WHERE event_participant.event_id = $1
+            ORDER BY "name!", "member".id"#,
+            event_id.0
+        )
+        .map(|row| PlayerAttendance {
+            member_id: db::MemberId(row.member_id),
+            name: row.name,
+            attended: row.attended,
+        })
+        .fetch_all(db_connection)
+        .await?;
+        Ok(Some(AttendanceSheet {
+            event_id,
+            title: event.title,
+            start_time: event.start_time,
+            players,
+        }))
+    }
+
+    pub fn content(&self) -> String {
+        let mut content =
+            strings::ATTENDANCE_REQUEST(&self.title, self.start_time, &form_url(self.event_id));
+        content.push('\n');
+        let players = self
+            .players
+            .iter()
+            .map(|player| {
+                let status = match player.attended {
+                    Some(true) => "\u{2705}",
+                    Some(false) => "\u{274C}",
+                    None => "\u{2754}",
+                };
+                format!("{} {}", status, player.name)
+            })
+            .collect::<Vec<_>>();
+        content.push_str(&players.join(" · "));
+        content
+    }
+
+    pub fn components(&self) -> Vec<CreateActionRow> {
+        let player_buttons = self
+            .players
+            .iter()
+            .take(MAX_PLAYER_BUTTONS)
+            .map(|player| {
+                let style = match player.attended {
+                    Some(true) => ButtonStyle::Success,
+                    Some(false) => ButtonStyle::Danger,
+                    None => ButtonStyle::Secondary,
+                };
+                let label: String = player.name.chars().take(80).collect();
+                CreateButton::new(format!(
+                    "{}{}:{}",
+                    CUSTOM_ID_PREFIX, self.event_id.0, player.member_id.0
+                ))
+                .label(label)
+                .style(style)
+            })
+            .collect::<Vec<_>>();
+        let mut rows = player_buttons
+            .chunks(5)
+            .map(|buttons| CreateActionRow::Buttons(buttons.to_vec()))
+            .collect::<Vec<_>>();
+        rows.push(CreateActionRow::Buttons(vec![CreateButton::new(format!(
+            "{}{}:all",
+            CUSTOM_ID_PREFIX, self.event_id.0
+        ))
+        .label("Everyone else showed up")
+        .style(ButtonStyle::Primary)]));
+        rows
+    }
+}
+
+// Hosts of the session may mark the attendance
+pub async fn may_mark_attendance(
+    event_id: db::EventId,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let is_host = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM event_host WHERE event_id = $1 AND member_id = $2) AS "exists!""#,
+        event_id.0,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(is_host)
+}
+
+pub async fn set_attendance(
+    event_id: db::EventId,
+    member_id: db::MemberId,
+    attended: bool,
+    marked_by: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO event_attendance (event_id, member_id, attended, marked_by)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (event_id, member_id) DO UPDATE
+        SET attended = EXCLUDED.attended, marked_by = EXCLUDED.marked_by, marked_time = NOW()"#,
+        event_id.0,
+        member_id.0,
+        attended,
+        marked_by.0
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+// Players who weren't marked yet count as showed up, a second click marks
+// them as no-show
+pub async fn toggle_attendance(
+    event_id: db::EventId,
+    member_id: db::MemberId,
+    marked_by: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO event_attendance (event_id, member_id, attended, marked_by)
+        SELECT $1, $2, TRUE, $3
+        WHERE EXISTS (SELECT 1 FROM event_participant WHERE event_id = $1 AND member_id = $2)
+        ON CONFLICT (event_id, member_id) DO UPDATE
+        SET attended = NOT event_attendance.attended, marked_by = EXCLUDED.marked_by, marked_time = NOW()"#,
+        event_id.0,
+        member_id.0,
+        marked_by.0
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+pub async fn mark_remaining_attended(
+    event_id: db::EventId,
+    marked_by: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO event_attendance (event_id, member_id, attended, marked_by)
+        SELECT event_id, member_id, TRUE, $2 FROM event_participant WHERE event_id = $1
+        ON CONFLICT (event_id, member_id) DO NOTHING"#,
+        event_id.0,
+        marked_by.0
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+struct EndedSession {
+    event_id: db::EventId,
+    channel_id: ChannelId,
+}
+
+// Posts an attendance sheet to the game channel of every session that ended
+// recently. Requests are marked before they are sent, so a restart never
+// posts a sheet twice.
+pub async fn attendance_request_task(
+    db_connection: &sqlx::PgPool,
+    discord_api: &crate::discord::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let sessions = sqlx::query!(
+        r#"WITH ended_session AS (
+            SELECT event.id, event_series_text_channel.discord_id AS channel_id
+            FROM event
+            INNER JOIN event_series ON event.event_series_id = event_series.id
+            INNER JOIN event_series_text_channel ON event_series.discord_text_channel_id = event_series_text_channel.discord_id
+            WHERE event.deleted IS NULL
+            AND COALESCE(event.end_time, event.start_time + make_interval(hours => $1)) < NOW()
+            AND COALESCE(event.end_time, event.start_time + make_interval(hours => $1)) > NOW() - make_interval(hours => $2)
+            AND event_series_text_channel.deleted IS NULL
+            AND event_series_text_channel.archived IS NULL
+            AND EXISTS (SELECT 1 FROM event_participant WHERE event_participant.event_id = event.id)
+        )
+        INSERT INTO event_attendance_request (event_id)
+        SELECT id FROM ended_session
+        ON CONFLICT DO NOTHING
+        RETURNING event_id, (SELECT channel_id FROM ended_session WHERE ended_session.id = event_attendance_request.event_id) AS "channel_id!""#,
+        db::DEFAULT_SESSION_DURATION_HOURS,
+        MAX_REQUEST_DELAY_HOURS
+    )
+    .map(|row| EndedSession {
+        event_id: db::EventId(row.event_id),
+        channel_id: ChannelId::new(row.channel_id as u64),
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let mut some_failed = false;
+    for session in sessions {
+        let sheet = match AttendanceSheet::load(session.event_id, db_connection).await? {
+            Some(sheet) => sheet,
+            None => continue,
+        };
+        let message = CreateMessage::new()
+            .content(sheet.content())
+            .components(sheet.components());
+        if let Err(err) = session
+            .channel_id
+            .send_message(&discord_api.http, message)
+            .await
+        {
+            eprintln!(
+                "Could not post the attendance sheet of event {}:\n{:#?}",
+                session.event_id.0, err
+            );
+            some_failed = true;
+        }
+    }
+    if some_failed {
+        Err(simple_error::SimpleError::new("Some attendance sheets could not be posted").into())
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MissedSession {
+    pub title: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+}
+
+// A member's attendance across all series
+#[derive(Debug, Clone)]
+pub struct AttendanceStats {
+    pub num_rsvps: i64,
+    pub num_attended: i64,
+    pub num_no_shows: i64,
+    pub recent_no_shows: Vec<MissedSession>,
+}
+
+impl AttendanceStats {
+    // Share of the marked sessions the member showed up to
+    pub fn attendance_rate(&self) -> Option<f64> {
+        let num_marked = self.num_attended + self.num_no_shows;
+        if num_marked == 0 {
+            None
+        } else {
+            Some(self.num_attended as f64 / num_marked as f64)
+        }
+    }
+}
+
+pub async fn get_member_stats(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<AttendanceStats, crate::meetup::Error> {
+    let counts = sqlx::query!(
+        r#"SELECT
+            (SELECT COUNT(*) FROM event_participant INNER JOIN event ON event_participant.event_id = event.id
+                WHERE event_participant.member_id = $1 AND event.deleted IS NULL AND event.start_time < NOW()) AS "num_rsvps!",
+            (SELECT COUNT(*) FROM event_attendance WHERE member_id = $1 AND attended) AS "num_attended!",
+            (SELECT COUNT(*) FROM event_attendance WHERE member_id = $1 AND NOT attended) AS "num_no_shows!""#,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    let recent_no_shows = sqlx::query!(
+        r#"SELECT event.title, event.start_time
+        FROM event_attendance
+        INNER JOIN event ON event_attendance.event_id = event.id
+        WHERE event_attendance.member_id = $1 AND NOT event_attendance.attended
+        ORDER BY event.start_time DESC
+        LIMIT 10"#,
+        member_id.0
+    )
+    .map(|row| MissedSession {
+        title: row.title,
+        start_time: row.start_time,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(AttendanceStats {
+        num_rsvps: counts.num_rsvps,
+        num_attended: counts.num_attended,
+        num_no_shows: counts.num_no_shows,
+        recent_no_shows,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_ids() {
+        assert_eq!(
+            parse_custom_id("attendance:1234:56"),
+            Some((
+                db::EventId(1234),
+                AttendanceAction::Toggle(db::MemberId(56))
+            ))
+        );
+        assert_eq!(
+            parse_custom_id("attendance:1234:all"),
+            Some((db::EventId(1234), AttendanceAction::RemainingAttended))
+        );
+        assert_eq!(parse_custom_id("attendance:1234:someone"), None);
+        assert_eq!(parse_custom_id("rsvp:join:1234"), None);
+    }
+}
@@ -0,0 +1,615 @@
+// Append-only log of the changes the bot makes on Discord: role assignments,
+// permission overwrites, deleted channels and roles and the timeouts and bans
+// of spammers, as well as exports and erasures of member data. Every entry
+// records who or what caused the change, and is optionally mirrored to a
+// private Discord channel. Erasing a member's data is the one exception to
+// append-only, it removes their Discord ID from the entries and the mirrors.
+
+use serenity::{
+    all::Mentionable,
+    builder::{CreateAllowedMentions, CreateMessage, EditMessage},
+    model::{
+        channel::{PermissionOverwrite, PermissionOverwriteType},
+        id::{ChannelId, MessageId, RoleId, UserId},
+        permissions::Permissions,
+    },
+};
+
+use crate::{
+    db,
+    discord::{guild_config::GuildConfig, CacheAndHttp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    AddRole,
+    RemoveRole,
+    ChangePermissions,
+    DeletePermissions,
+    DeleteChannel,
+    DeleteRole,
+    TimeOutMember,
+    EndTimeOut,
+    BanMember,
+    ExportData,
+    EraseData,
+}
+
+impl AuditAction {
+    const ALL: [AuditAction; 11] = [
+        AuditAction::AddRole,
+        AuditAction::RemoveRole,
+        AuditAction::ChangePermissions,
+        AuditAction::DeletePermissions,
+        AuditAction::DeleteChannel,
+        AuditAction::DeleteRole,
+        AuditAction::TimeOutMember,
+        AuditAction::EndTimeOut,
+        AuditAction::BanMember,
+        AuditAction::ExportData,
+        AuditAction::EraseData,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::AddRole => "add role",
+            AuditAction::RemoveRole => "remove role",
+            AuditAction::ChangePermissions => "change permissions",
+            AuditAction::DeletePermissions => "delete permissions",
+            AuditAction::DeleteChannel => "delete channel",
+            AuditAction::DeleteRole => "delete role",
+            AuditAction::TimeOutMember => "time out member",
+            AuditAction::EndTimeOut => "end time out",
+            AuditAction::BanMember => "ban member",
+            AuditAction::ExportData => "export data",
+            AuditAction::EraseData => "erase data",
+        }
+    }
+
+    fn from_str(action: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == action)
+    }
+}
+
+// What caused a change: a user running a command or using the website, or one
+// of the bot's tasks
+#[derive(Debug, Clone, Copy)]
+pub enum AuditSource {
+    Command {
+        actor: UserId,
+        command: &'static str,
+    },
+    Web {
+        actor: UserId,
+        page: &'static str,
+    },
+    Task(&'static str),
+}
+
+impl AuditSource {
+    fn actor(&self) -> Option<UserId> {
+        match self {
+            AuditSource::Command { actor, .. } | AuditSource::Web { actor, .. } => Some(*actor),
+            AuditSource::Task(_) => None,
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            AuditSource::Command { command, .. } => format!("command: {}", command),
+            AuditSource::Web { page, .. } => format!("web: {}", page),
+            AuditSource::Task(task) => format!("task: {}", task),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub action: AuditAction,
+    pub user_id: Option<UserId>,
+    pub channel_id: Option<ChannelId>,
+    pub role_id: Option<RoleId>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    fn new(action: AuditAction) -> Self {
+        AuditEntry {
+            action,
+            user_id: None,
+            channel_id: None,
+            role_id: None,
+            before: None,
+            after: None,
+            error: None,
+        }
+    }
+
+    pub fn role_added(user_id: UserId, role_id: RoleId) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            role_id: Some(role_id),
+            ..Self::new(AuditAction::AddRole)
+        }
+    }
+
+    pub fn role_removed(user_id: UserId, role_id: RoleId) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            role_id: Some(role_id),
+            ..Self::new(AuditAction::RemoveRole)
+        }
+    }
+
+    pub fn permissions_changed(
+        channel_id: ChannelId,
+        user_id: UserId,
+        before: Permissions,
+        after: Permissions,
+    ) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            channel_id: Some(channel_id),
+            before: Some(format_permissions(before)),
+            after: Some(format_permissions(after)),
+            ..Self::new(AuditAction::ChangePermissions)
+        }
+    }
+
+    // A role's or user's permission overwrite in a channel was created or
+    // replaced
+    pub fn permission_overwrite_changed(
+        channel_id: ChannelId,
+        before: Option<&PermissionOverwrite>,
+        after: &PermissionOverwrite,
+    ) -> Self {
+        let (user_id, role_id) = match after.kind {
+            PermissionOverwriteType::Member(user_id) => (Some(user_id), None),
+            PermissionOverwriteType::Role(role_id) => (None, Some(role_id)),
+            _ => (None, None),
+        };
+        AuditEntry {
+            user_id,
+            role_id,
+            channel_id: Some(channel_id),
+            before: Some(before.map_or("none".to_string(), format_permission_overwrite)),
+            after: Some(format_permission_overwrite(after)),
+            ..Self::new(AuditAction::ChangePermissions)
+        }
+    }
+
+    // All of a user's permission overwrites in a channel were removed
+    pub fn permissions_deleted(channel_id: ChannelId, user_id: UserId) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            channel_id: Some(channel_id),
+            ..Self::new(AuditAction::DeletePermissions)
+        }
+    }
+
+    pub fn channel_deleted(channel_id: ChannelId, name: Option<&str>) -> Self {
+        AuditEntry {
+            channel_id: Some(channel_id),
+            before: name.map(str::to_string),
+            ..Self::new(AuditAction::DeleteChannel)
+        }
+    }
+
+    pub fn role_deleted(role_id: RoleId, name: &str) -> Self {
+        AuditEntry {
+            role_id: Some(role_id),
+            before: Some(name.to_string()),
+            ..Self::new(AuditAction::DeleteRole)
+        }
+    }
+
+    pub fn member_timed_out(user_id: UserId, minutes: i32) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            after: Some(format!("{} minutes", minutes)),
+            ..Self::new(AuditAction::TimeOutMember)
+        }
+    }
+
+    pub fn time_out_ended(user_id: UserId) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            ..Self::new(AuditAction::EndTimeOut)
+        }
+    }
+
+    pub fn member_banned(user_id: UserId) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            ..Self::new(AuditAction::BanMember)
+        }
+    }
+
+    pub fn data_exported(user_id: UserId) -> Self {
+        AuditEntry {
+            user_id: Some(user_id),
+            ..Self::new(AuditAction::ExportData)
+        }
+    }
+
+    // Only the member ID is kept, the Discord ID is part of the erased data
+    pub fn data_erased(member_id: db::MemberId) -> Self {
+        AuditEntry {
+            before: Some(format!("member {}", member_id.0)),
+            ..Self::new(AuditAction::EraseData)
+        }
+    }
+
+    pub fn failed(mut self, error: impl std::fmt::Display) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+}
+
+fn format_permissions(permissions: Permissions) -> String {
+    if permissions.is_empty() {
+        "none".to_string()
+    } else {
+        permissions.get_permission_names().join(", ")
+    }
+}
+
+fn format_permission_overwrite(permission_overwrite: &PermissionOverwrite) -> String {
+    if permission_overwrite.deny.is_empty() {
+        format_permissions(permission_overwrite.allow)
+    } else {
+        format!(
+            "{}, denied {}",
+            format_permissions(permission_overwrite.allow),
+            format_permissions(permission_overwrite.deny)
+        )
+    }
+}
+
+// An entry as it was stored in the audit log
+#[derive(Debug, Clone)]
+pub struct AuditLogRow {
+    pub time: chrono::DateTime<chrono::Utc>,
+    pub actor: Option<UserId>,
+    pub source: String,
+    pub entry: AuditEntry,
+}
+
+impl AuditLogRow {
+    // One line of Discord markdown. The mentions should not ping anyone, so
+    // send this with empty allowed mentions.
+    pub fn describe(&self) -> String {
+        let entry = &self.entry;
+        let mut line = format!(
+            "<t:{}:f> **{}**",
+            self.time.timestamp(),
+            entry.action.as_str()
+        );
+        if let Some(role_id) = entry.role_id {
+            line += &format!(" {}", role_id.mention());
+        }
+        if let Some(user_id) = entry.user_id {
+            line += &format!(" for {}", user_id.mention());
+        }
+        if let Some(channel_id) = entry.channel_id {
+            line += &format!(" in {}", channel_id.mention());
+        }
+        match self.actor {
+            Some(actor) => line += &format!(" by {} ({})", actor.mention(), self.source),
+            None => line += &format!(" ({})", self.source),
+        }
+        match (&entry.before, &entry.after) {
+            (Some(before), Some(after)) => line += &format!(" · {} → {}", before, after),
+            (Some(before), None) => line += &format!(" · was {}", before),
+            (None, Some(after)) => line += &format!(" · now {}", after),
+            (None, None) => (),
+        }
+        if let Some(error) = &entry.error {
+            line += &format!(" · **failed**: {}", error);
+        }
+        line
+    }
+}
+
+// An audit_log row as it is stored in the database
+struct AuditLogRecord {
+    time: chrono::DateTime<chrono::Utc>,
+    actor_discord_id: Option<i64>,
+    source: String,
+    action: String,
+    target_user_discord_id: Option<i64>,
+    target_channel_discord_id: Option<i64>,
+    target_role_discord_id: Option<i64>,
+    before: Option<String>,
+    after: Option<String>,
+    error: Option<String>,
+    mirror_channel_id: Option<i64>,
+    mirror_message_id: Option<i64>,
+}
+
+impl AuditLogRecord {
+    // None if the action is unknown
+    fn into_row(self) -> Option<AuditLogRow> {
+        Some(AuditLogRow {
+            time: self.time,
+            actor: self.actor_discord_id.map(|id| UserId::new(id as u64)),
+            source: self.source,
+            entry: AuditEntry {
+                action: AuditAction::from_str(&self.action)?,
+                user_id: self.target_user_discord_id.map(|id| UserId::new(id as u64)),
+                channel_id: self
+                    .target_channel_discord_id
+                    .map(|id| ChannelId::new(id as u64)),
+                role_id: self.target_role_discord_id.map(|id| RoleId::new(id as u64)),
+                before: self.before,
+                after: self.after,
+                error: self.error,
+            },
+        })
+    }
+}
+
+// Records the changes made by one command or task
+pub struct Auditor<'a> {
+    source: AuditSource,
+    db_connection: &'a sqlx::PgPool,
+    discord_api: &'a CacheAndHttp,
+    log_channel_id: Option<ChannelId>,
+}
+
+impl<'a> Auditor<'a> {
+    pub fn new(
+        source: AuditSource,
+        db_connection: &'a sqlx::PgPool,
+        discord_api: &'a CacheAndHttp,
+        guild_config: &GuildConfig,
+    ) -> Self {
+        Auditor {
+            source,
+            db_connection,
+            discord_api,
+            log_channel_id: guild_config.audit_log_channel_id,
+        }
+    }
+
+    // Never fails, the change already happened on Discord and should not be
+    // reported as failed just because it could not be logged
+    pub async fn record(&self, entry: AuditEntry) {
+        self.record_row(self.source.actor(), entry).await
+    }
+
+    // Leaves out the actor if it is the given user, e.g. for members who erase
+    // their own data
+    pub async fn record_without_user(&self, user_id: Option<UserId>, entry: AuditEntry) {
+        let actor = self.source.actor().filter(|&actor| Some(actor) != user_id);
+        self.record_row(actor, entry).await
+    }
+
+    async fn record_row(&self, actor: Option<UserId>, entry: AuditEntry) {
+        let row = AuditLogRow {
+            time: chrono::Utc::now(),
+            actor,
+            source: self.source.description(),
+            entry,
+        };
+        let insert_result = sqlx::query_scalar!(
+            r#"INSERT INTO audit_log ("time", actor_discord_id, "source", "action", target_user_discord_id, target_channel_discord_id, target_role_discord_id, "before", "after", error)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            RETURNING id"#,
+            row.time,
+            row.actor.map(|id| id.get() as i64),
+            &row.source,
+            row.entry.action.as_str(),
+            row.entry.user_id.map(|id| id.get() as i64),
+            row.entry.channel_id.map(|id| id.get() as i64),
+            row.entry.role_id.map(|id| id.get() as i64),
+            row.entry.before.as_deref(),
+            row.entry.after.as_deref(),
+            row.entry.error.as_deref()
+        )
+        .fetch_one(self.db_connection)
+        .await;
+        let row_id = match insert_result {
+            Ok(row_id) => Some(row_id),
+            Err(err) => {
+                eprintln!("Could not write to the audit log:\n{:#?}\n{:#?}", row, err);
+                None
+            }
+        };
+        if let Some(log_channel_id) = self.log_channel_id {
+            let message = CreateMessage::new()
+                .content(row.describe())
+                .allowed_mentions(CreateAllowedMentions::new());
+            match log_channel_id
+                .send_message(&self.discord_api.http, message)
+                .await
+            {
+                // Remembered such that erasures can edit the message
+                Ok(message) => {
+                    if let Some(row_id) = row_id {
+                        sqlx::query!(
+                            r#"UPDATE audit_log SET mirror_channel_id = $2, mirror_message_id = $3 WHERE id = $1"#,
+                            row_id,
+                            log_channel_id.get() as i64,
+                            message.id.get() as i64
+                        )
+                        .execute(self.db_connection)
+                        .await
+                        .ok();
+                    }
+                }
+                Err(err) => eprintln!("Could not mirror to the audit log channel:\n{:#?}", err),
+            }
+        }
+    }
+
+    // Brings the mirrored messages of the given rows up to date after the rows
+    // were changed. Returns the number of messages that could not be edited.
+    pub async fn update_mirrors(&self, row_ids: &[i32]) -> usize {
+        let records = sqlx::query_as!(
+            AuditLogRecord,
+            r#"SELECT "time", actor_discord_id, "source", "action", target_user_discord_id, target_channel_discord_id, target_role_discord_id, "before", "after", error, mirror_channel_id, mirror_message_id
+            FROM audit_log
+            WHERE id = ANY($1) AND mirror_message_id IS NOT NULL"#,
+            row_ids
+        )
+        .fetch_all(self.db_connection)
+        .await;
+        let records = match records {
+            Ok(records) => records,
+            Err(err) => {
+                eprintln!("Could not read the audit log:\n{:#?}", err);
+                return row_ids.len();
+            }
+        };
+        let mut num_failed = 0;
+        for record in records {
+            let (Some(channel_id), Some(message_id)) =
+                (record.mirror_channel_id, record.mirror_message_id)
+            else {
+                continue;
+            };
+            let channel_id = ChannelId::new(channel_id as u64);
+            let message_id = MessageId::new(message_id as u64);
+            let content = match record.into_row() {
+                Some(row) => row.describe(),
+                None => continue,
+            };
+            let message = EditMessage::new()
+                .content(content)
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Err(err) = channel_id
+                .edit_message(&self.discord_api.http, message_id, message)
+                .await
+            {
+                eprintln!(
+                    "Could not edit message {} in the audit log channel:\n{:#?}",
+                    message_id, err
+                );
+                num_failed += 1;
+            }
+        }
+        num_failed
+    }
+}
+
+// The most recent changes affecting a user, newest first
+pub async fn get_user_history(
+    user_id: UserId,
+    limit: i64,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<AuditLogRow>, crate::meetup::Error> {
+    let records = sqlx::query_as!(
+        AuditLogRecord,
+        r#"SELECT "time", actor_discord_id, "source", "action", target_user_discord_id, target_channel_discord_id, target_role_discord_id, "before", "after", error, mirror_channel_id, mirror_message_id
+        FROM audit_log
+        WHERE target_user_discord_id = $1
+        ORDER BY "time" DESC, id DESC
+        LIMIT $2"#,
+        user_id.get() as i64,
+        limit
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let rows = records
+        .into_iter()
+        .filter_map(AuditLogRecord::into_row)
+        .collect();
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_audit_log_row() {
+        let time = chrono::DateTime::parse_from_rfc3339("2026-10-29T12:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let row = AuditLogRow {
+            time,
+            actor: Some(UserId::new(3)),
+            source: AuditSource::Command {
+                actor: UserId::new(3),
+                command: "remove user",
+            }
+            .description(),
+            entry: AuditEntry::permissions_changed(
+                ChannelId::new(2),
+                UserId::new(1),
+                Permissions::VIEW_CHANNEL | Permissions::MANAGE_MESSAGES,
+                Permissions::VIEW_CHANNEL,
+            ),
+        };
+        assert_eq!(
+            row.describe(),
+            "<t:1793275200:f> **change permissions** for <@1> in <#2> by <@3> (command: remove \
+             user) · Manage Messages, View Channel → View Channel"
+        );
+        let entry =
+            AuditEntry::role_removed(UserId::new(1), RoleId::new(4)).failed("Missing Access");
+        assert_eq!(
+            AuditAction::from_str(entry.action.as_str()),
+            Some(AuditAction::RemoveRole)
+        );
+        assert_eq!(entry.error.as_deref(), Some("Missing Access"));
+    }
+
+    #[test]
+    fn erasure_keeps_no_discord_id() {
+        let entry = AuditEntry::data_erased(db::MemberId(7));
+        assert_eq!(
+            AuditAction::from_str(entry.action.as_str()),
+            Some(AuditAction::EraseData)
+        );
+        assert_eq!(entry.user_id, None);
+        assert_eq!(entry.before.as_deref(), Some("member 7"));
+    }
+
+    #[test]
+    fn erased_record_mentions_nobody() {
+        // An erased member's timeout, as the mirror message is rewritten
+        let record = AuditLogRecord {
+            time: chrono::DateTime::parse_from_rfc3339("2026-10-29T12:00:00Z")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            actor_discord_id: None,
+            source: "task: spam filter".to_string(),
+            action: "time out member".to_string(),
+            target_user_discord_id: None,
+            target_channel_discord_id: None,
+            target_role_discord_id: None,
+            before: None,
+            after: Some("10 minutes".to_string()),
+            error: None,
+            mirror_channel_id: Some(2),
+            mirror_message_id: Some(3),
+        };
+        assert_eq!(
+            record.into_row().unwrap().describe(),
+            "<t:1793275200:f> **time out member** (task: spam filter) · now 10 minutes"
+        );
+    }
+
+    #[test]
+    fn role_permission_overwrite_changed() {
+        let entry = AuditEntry::permission_overwrite_changed(
+            ChannelId::new(2),
+            None,
+            &PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::VIEW_CHANNEL,
+                kind: PermissionOverwriteType::Role(RoleId::new(5)),
+            },
+        );
+        assert_eq!(entry.role_id, Some(RoleId::new(5)));
+        assert_eq!(entry.user_id, None);
+        assert_eq!(entry.before.as_deref(), Some("none"));
+        assert_eq!(entry.after.as_deref(), Some("none, denied View Channel"));
+    }
+}
@@ -0,0 +1,298 @@
+// iCalendar feeds of game sessions. Members get secret feed URLs for the
+// sessions they play in and the ones they host, and every event series has a
+// public feed.
+
+use serenity::model::id::UserId;
+
+use crate::db;
+
+// Sessions that started longer ago than this are left out of the feeds
+const PAST_SESSIONS_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberFeed {
+    // Sessions the member plays in or hosts
+    Sessions,
+    // Only the sessions the member hosts
+    Hosting,
+}
+
+#[derive(Debug, Clone)]
+pub struct CalendarEntry {
+    pub event_id: db::EventId,
+    pub title: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub is_online: bool,
+    // SwissRPG or Meetup event page
+    pub url: Option<String>,
+}
+
+pub fn member_feed_url(calendar_token: &str, feed: MemberFeed) -> String {
+    let file_name = match feed {
+        MemberFeed::Sessions => "sessions.ics",
+        MemberFeed::Hosting => "hosting.ics",
+    };
+    format!(
+        "{}/calendar/{}/{}",
+        crate::urls::BASE_URL,
+        calendar_token,
+        file_name
+    )
+}
+
+pub fn series_feed_url(series_id: db::EventSeriesId) -> String {
+    format!(
+        "{}/calendar/series/{}/sessions.ics",
+        crate::urls::BASE_URL,
+        series_id.0
+    )
+}
+
+pub async fn get_or_create_calendar_token(
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<String, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    let calendar_token = sqlx::query_scalar!(
+        r#"UPDATE "member" SET calendar_token = COALESCE(calendar_token, $2)
+        WHERE id = $1
+        RETURNING calendar_token AS "calendar_token!""#,
+        member_id.0,
+        crate::new_random_id(18)
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(calendar_token)
+}
+
+// Invalidates the old feed URLs, e.g. when they were shared by accident
+pub async fn reset_calendar_token(
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<String, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    let calendar_token = crate::new_random_id(18);
+    sqlx::query!(
+        r#"UPDATE "member" SET calendar_token = $2 WHERE id = $1"#,
+        member_id.0,
+        &calendar_token
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(calendar_token)
+}
+
+// Returns None if the token doesn't belong to any member
+pub async fn get_member_feed(
+    calendar_token: &str,
+    feed: MemberFeed,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<Vec<CalendarEntry>>, crate::meetup::Error> {
+    let member_id = sqlx::query_scalar!(
+        r#"SELECT id FROM "member" WHERE calendar_token = $1"#,
+        calendar_token
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    let member_id = match member_id {
+        Some(member_id) => member_id,
+        None => return Ok(None),
+    };
+    let only_hosting = feed == MemberFeed::Hosting;
+    let entries = sqlx::query!(
+        r#"SELECT event.id, event.title, event.start_time, event.end_time, event.is_online,
+            COALESCE(swissrpg_event.url, meetup_event.url) AS url
+        FROM event
+        LEFT OUTER JOIN swissrpg_event ON event.id = swissrpg_event.event_id
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.deleted IS NULL
+        AND event.start_time > NOW() - make_interval(days => $3)
+        AND (
+            EXISTS (SELECT 1 FROM event_host WHERE event_host.event_id = event.id AND event_host.member_id = $1)
+            OR (NOT $2 AND EXISTS (SELECT 1 FROM event_participant WHERE event_participant.event_id = event.id AND event_participant.member_id = $1))
+        )
+        ORDER BY event.start_time"#,
+        member_id,
+        only_hosting,
+        PAST_SESSIONS_DAYS as i32
+    )
+    .map(|row| CalendarEntry {
+        event_id: db::EventId(row.id),
+        title: row.title,
+        start_time: row.start_time,
+        end_time: row.end_time,
+        is_online: row.is_online,
+        url: row.url,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(Some(entries))
+}
+
+// Returns None if the series doesn't exist
+pub async fn get_series_feed(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<Vec<CalendarEntry>>, crate::meetup::Error> {
+    let series_exists = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) > 0 AS "exists!" FROM event_series WHERE id = $1"#,
+        series_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    if !series_exists {
+        return Ok(None);
+    }
+    let entries = sqlx::query!(
+        r#"SELECT event.id, event.title, event.start_time, event.end_time, event.is_online,
+            COALESCE(swissrpg_event.url, meetup_event.url) AS url
+        FROM event
+        LEFT OUTER JOIN swissrpg_event ON event.id = swissrpg_event.event_id
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.event_series_id = $1 AND event.deleted IS NULL
+        AND event.start_time > NOW() - make_interval(days => $2)
+        ORDER BY event.start_time"#,
+        series_id.0,
+        PAST_SESSIONS_DAYS as i32
+    )
+    .map(|row| CalendarEntry {
+        event_id: db::EventId(row.id),
+        title: row.title,
+        start_time: row.start_time,
+        end_time: row.end_time,
+        is_online: row.is_online,
+        url: row.url,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(Some(entries))
+}
+
+// Escapes a TEXT value (RFC 5545, section 3.3.11)
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Lines longer than 75 octets are folded (RFC 5545, section 3.1)
+fn push_line(ical: &mut String, line: &str) {
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            ical.push_str("\r\n ");
+            line_length = 1;
+        }
+        ical.push(c);
+        line_length += c.len_utf8();
+    }
+    ical.push_str("\r\n");
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+pub fn to_ical(calendar_name: &str, entries: &[CalendarEntry]) -> String {
+    let now = format_time(chrono::Utc::now());
+    let host = crate::urls::BASE_URL
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let mut ical = String::new();
+    push_line(&mut ical, "BEGIN:VCALENDAR");
+    push_line(&mut ical, "VERSION:2.0");
+    push_line(&mut ical, "PRODID:-//SwissRPG//Bot//EN");
+    push_line(&mut ical, "CALSCALE:GREGORIAN");
+    push_line(&mut ical, "METHOD:PUBLISH");
+    push_line(
+        &mut ical,
+        &format!("X-WR-CALNAME:{}", escape_text(calendar_name)),
+    );
+    for entry in entries {
+        let end_time = entry.end_time.unwrap_or_else(|| {
+            entry.start_time + chrono::Duration::hours(db::DEFAULT_SESSION_DURATION_HOURS.into())
+        });
+        push_line(&mut ical, "BEGIN:VEVENT");
+        push_line(
+            &mut ical,
+            &format!("UID:event-{}@{}", entry.event_id.0, host),
+        );
+        push_line(&mut ical, &format!("DTSTAMP:{}", now));
+        push_line(
+            &mut ical,
+            &format!("DTSTART:{}", format_time(entry.start_time)),
+        );
+        push_line(&mut ical, &format!("DTEND:{}", format_time(end_time)));
+        push_line(&mut ical, &format!("SUMMARY:{}", escape_text(&entry.title)));
+        let mut description = if entry.is_online {
+            "Online session".to_string()
+        } else {
+            "In-person session".to_string()
+        };
+        if let Some(url) = &entry.url {
+            push_line(&mut ical, &format!("URL:{}", url));
+            description.push('\n');
+            description.push_str(url);
+        }
+        if entry.is_online {
+            push_line(&mut ical, "LOCATION:Online");
+        }
+        push_line(
+            &mut ical,
+            &format!("DESCRIPTION:{}", escape_text(&description)),
+        );
+        push_line(&mut ical, "END:VEVENT");
+    }
+    push_line(&mut ical, "END:VCALENDAR");
+    ical
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ical_escaping_and_folding() {
+        let start_time = chrono::DateTime::parse_from_rfc3339("2026-10-24T18:00:00+02:00")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let entry = CalendarEntry {
+            event_id: db::EventId(1234),
+            title: "Dungeons; Dragons, and a very long title that needs to be folded over \
+                    multiple lines"
+                .to_string(),
+            start_time,
+            end_time: None,
+            is_online: true,
+            url: Some("https://example.com/event".to_string()),
+        };
+        let ical = to_ical("My sessions", &[entry]);
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+        assert!(ical.contains("DTSTART:20261024T160000Z\r\n"));
+        assert!(ical.contains("DTEND:20261024T200000Z\r\n"));
+        assert!(ical.contains("SUMMARY:Dungeons\\; Dragons\\, and a very long title"));
+        assert!(ical.contains("DESCRIPTION:Online session\\nhttps://example.com/event\r\n"));
+        assert!(ical
+            .lines()
+            .all(|line| line.trim_end_matches('\r').len() <= 75));
+        // Unfolding restores the title
+        assert!(ical
+            .replace("\r\n ", "")
+            .contains("folded over multiple lines\r\n"));
+    }
+}
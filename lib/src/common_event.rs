@@ -11,13 +11,17 @@ pub struct CommonEventDetails {
     pub num_free_spots: u32,
     pub rsvps_closed: bool,
     pub short_url: String,
+    // Names of the game systems, only known for SwissRPG events
+    pub game_systems: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct CommonVenue {
-    pub lat: f64,
-    pub lng: f64,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
     pub city: Option<String>,
+    // The location code of SwissRPG events
+    pub location_code: Option<String>,
 }
 
 impl CommonEventDetails {
@@ -37,9 +41,10 @@ impl From<crate::meetup::newapi::UpcomingEventDetails> for CommonEventDetails {
         let num_free_spots = meetup_event.num_free_spots();
 
         let venue = meetup_event.venue.map(|v| CommonVenue {
-            lat: v.lat,
-            lng: v.lng,
+            lat: Some(v.lat),
+            lng: Some(v.lng),
             city: v.city,
+            location_code: None,
         });
 
         CommonEventDetails {
@@ -52,6 +57,7 @@ impl From<crate::meetup::newapi::UpcomingEventDetails> for CommonEventDetails {
             num_free_spots,
             rsvps_closed,
             short_url: meetup_event.short_url,
+            game_systems: vec![],
         }
     }
 }
@@ -68,78 +74,20 @@ impl
             &crate::swissrpg::schema::Session,
         ),
     ) -> Self {
-        // For SwissRPG events, we need to determine location from tags
+        // For SwissRPG events, the location tag code refers to one of our
+        // locations. Online events don't have a venue.
         let venue = event_series
             .tags
             .iter()
             .find(|tag| tag.tag_type == crate::swissrpg::schema::TAG_TYPE_LOCATION)
-            .and_then(|location_tag| {
-                // Try to match location names to coordinates
-                // This is a simplified implementation - in production you might want
-                // to store coordinates in the location tags or have a lookup table
-                match location_tag.code.as_str() {
-                    "zurich" => Some(CommonVenue {
-                        lat: 47.376888,
-                        lng: 8.541694,
-                        city: Some("Zürich".to_string()),
-                    }),
-                    "basel" => Some(CommonVenue {
-                        lat: 47.559601,
-                        lng: 7.588576,
-                        city: Some("Basel".to_string()),
-                    }),
-                    "geneva" => Some(CommonVenue {
-                        lat: 46.204391,
-                        lng: 6.143158,
-                        city: Some("Geneva".to_string()),
-                    }),
-                    "lausanne" => Some(CommonVenue {
-                        lat: 46.519316,
-                        lng: 6.6345432,
-                        city: Some("Lausanne".to_string()),
-                    }),
-                    "bern" => Some(CommonVenue {
-                        lat: 46.9489217,
-                        lng: 7.4433158,
-                        city: Some("Bern".to_string()),
-                    }),
-                    "luzern" => Some(CommonVenue {
-                        lat: 47.045540,
-                        lng: 8.308010,
-                        city: Some("Luzern".to_string()),
-                    }),
-                    "lugano" => Some(CommonVenue {
-                        lat: 46.003601,
-                        lng: 8.953620,
-                        city: Some("Lugano".to_string()),
-                    }),
-                    "aarau" => Some(CommonVenue {
-                        lat: 47.3934732,
-                        lng: 8.0606556,
-                        city: Some("Aarau".to_string()),
-                    }),
-                    "chur" => Some(CommonVenue {
-                        lat: 46.8533507,
-                        lng: 9.5275838,
-                        city: Some("Chur".to_string()),
-                    }),
-                    "st_gallen" => Some(CommonVenue {
-                        lat: 47.4256037,
-                        lng: 9.3741491,
-                        city: Some("St. Gallen".to_string()),
-                    }),
-                    location_code => {
-                        if location_code == crate::swissrpg::schema::LOCATION_CODE_ONLINE {
-                            None
-                        } else {
-                            Some(CommonVenue {
-                                lat: 0.0,
-                                lng: 0.0,
-                                city: Some(location_tag.value.clone()),
-                            })
-                        }
-                    }
-                }
+            .filter(|location_tag| {
+                location_tag.code != crate::swissrpg::schema::LOCATION_CODE_ONLINE
+            })
+            .map(|location_tag| CommonVenue {
+                lat: None,
+                lng: None,
+                city: Some(location_tag.value.clone()),
+                location_code: Some(location_tag.code.clone()),
             });
 
         // Check if event is online based on location tag
@@ -155,6 +103,12 @@ impl
             num_free_spots: session.open_seats.max(0) as u32,
             rsvps_closed: !session.rsvp_open,
             short_url: event_series.public_url.clone(),
+            game_systems: event_series
+                .tags
+                .iter()
+                .filter(|tag| tag.tag_type == crate::swissrpg::schema::TAG_TYPE_GAME_SYSTEM)
+                .map(|tag| tag.value.clone())
+                .collect(),
         }
     }
 }
@@ -77,6 +77,10 @@ pub struct MemberId(pub i32);
 #[sqlx(transparent)]
 pub struct SwissRPGEventId(pub i32);
 
+// Sessions without an end time are assumed to take this long, e.g. in the
+// calendar feeds and for sending the attendance sheet
+pub const DEFAULT_SESSION_DURATION_HOURS: i32 = 4;
+
 pub struct MeetupEvent {
     pub id: MeetupEventId,
     pub meetup_id: String,
@@ -0,0 +1,254 @@
+use serenity::{
+    builder::EditChannel,
+    model::{
+        channel::{GuildChannel, PermissionOverwrite, PermissionOverwriteType},
+        id::ChannelId,
+        permissions::Permissions,
+    },
+};
+
+use super::guild_config::GuildConfig;
+
+pub const ARCHIVED_CHANNEL_PREFIX: &str = "archived-";
+
+// Discord's limit for channel names
+const MAX_CHANNEL_NAME_LENGTH: usize = 100;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum UnarchiveResult {
+    NotAGameChannel,
+    NotArchived,
+    AlreadyDeleted,
+    Unarchived,
+}
+
+// Nobody gets to write in archived channels
+fn archived_denied_permissions() -> Permissions {
+    Permissions::SEND_MESSAGES
+        | Permissions::SEND_MESSAGES_IN_THREADS
+        | Permissions::CREATE_PUBLIC_THREADS
+        | Permissions::CREATE_PRIVATE_THREADS
+        | Permissions::ADD_REACTIONS
+}
+
+pub fn archived_channel_name(name: &str) -> String {
+    if name.starts_with(ARCHIVED_CHANNEL_PREFIX) {
+        return name.to_string();
+    }
+    let mut archived_name = format!("{}{}", ARCHIVED_CHANNEL_PREFIX, name);
+    if archived_name.len() > MAX_CHANNEL_NAME_LENGTH {
+        let mut end = MAX_CHANNEL_NAME_LENGTH;
+        while !archived_name.is_char_boundary(end) {
+            end -= 1;
+        }
+        archived_name.truncate(end);
+    }
+    archived_name
+}
+
+// Removes the write permissions for everyone and detaches all roles except
+// for @everyone and the organisers
+fn archived_permission_overwrites(
+    current_overwrites: &[PermissionOverwrite],
+    guild_config: &GuildConfig,
+) -> Vec<PermissionOverwrite> {
+    let everyone_role_id = guild_config.guild_id.everyone_role();
+    let denied = archived_denied_permissions();
+    let mut overwrites: Vec<PermissionOverwrite> = current_overwrites
+        .iter()
+        .filter(|overwrite| match overwrite.kind {
+            PermissionOverwriteType::Role(role_id) => {
+                role_id == everyone_role_id || role_id == guild_config.organiser_role_id
+            }
+            _ => true,
+        })
+        .map(|overwrite| {
+            let is_everyone = overwrite.kind == PermissionOverwriteType::Role(everyone_role_id);
+            PermissionOverwrite {
+                allow: overwrite.allow - denied,
+                deny: if is_everyone {
+                    overwrite.deny | denied
+                } else {
+                    overwrite.deny
+                },
+                kind: overwrite.kind,
+            }
+        })
+        .collect();
+    if !overwrites
+        .iter()
+        .any(|overwrite| overwrite.kind == PermissionOverwriteType::Role(everyone_role_id))
+    {
+        overwrites.push(PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: denied,
+            kind: PermissionOverwriteType::Role(everyone_role_id),
+        });
+    }
+    overwrites
+}
+
+// Gives @everyone back the write permissions. The roles are added back by the
+// Discord sync.
+fn unarchived_permission_overwrites(
+    current_overwrites: &[PermissionOverwrite],
+    guild_config: &GuildConfig,
+) -> Vec<PermissionOverwrite> {
+    let everyone_role_id = guild_config.guild_id.everyone_role();
+    current_overwrites
+        .iter()
+        .map(|overwrite| {
+            if overwrite.kind == PermissionOverwriteType::Role(everyone_role_id) {
+                PermissionOverwrite {
+                    allow: overwrite.allow,
+                    deny: overwrite.deny - archived_denied_permissions(),
+                    kind: overwrite.kind,
+                }
+            } else {
+                overwrite.clone()
+            }
+        })
+        .collect()
+}
+
+// Moves the channel to the archive category and makes it read-only. The
+// channel gets deleted once the archive retention period is over.
+pub async fn archive_channel(
+    channel: &GuildChannel,
+    archive_category_id: ChannelId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+) -> Result<chrono::DateTime<chrono::Utc>, crate::meetup::Error> {
+    let deletion_time =
+        chrono::Utc::now() + chrono::Duration::days(guild_config.archive_retention_days as i64);
+    channel
+        .id
+        .edit(
+            &discord_api.http,
+            EditChannel::new()
+                .name(archived_channel_name(&channel.name))
+                .category(Some(archive_category_id))
+                .permissions(archived_permission_overwrites(
+                    &channel.permission_overwrites,
+                    guild_config,
+                )),
+        )
+        .await?;
+    sqlx::query!(
+        r#"UPDATE event_series_text_channel
+        SET archived = NOW(), deletion_time = $2, pre_archive_name = $3, pre_archive_category_id = $4
+        WHERE discord_id = $1"#,
+        channel.id.get() as i64,
+        deletion_time,
+        channel.name,
+        channel.parent_id.map(|id| id.get() as i64)
+    )
+    .execute(db_connection)
+    .await?;
+    println!(
+        "Archived channel {} \"{}\" until {}",
+        channel.id, channel.name, deletion_time
+    );
+    Ok(deletion_time)
+}
+
+// Restores the channel's name, category and write permissions and cancels its
+// deletion
+pub async fn unarchive_channel(
+    channel_id: ChannelId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+) -> Result<UnarchiveResult, crate::meetup::Error> {
+    let row = sqlx::query!(
+        r#"SELECT archived, deleted, pre_archive_name, pre_archive_category_id
+        FROM event_series_text_channel
+        WHERE discord_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(UnarchiveResult::NotAGameChannel),
+    };
+    if row.deleted.is_some() {
+        return Ok(UnarchiveResult::AlreadyDeleted);
+    }
+    if row.archived.is_none() {
+        return Ok(UnarchiveResult::NotArchived);
+    }
+    let channel = match channel_id.to_channel(discord_api).await?.guild() {
+        Some(channel) => channel,
+        None => return Ok(UnarchiveResult::NotAGameChannel),
+    };
+    let name = row.pre_archive_name.unwrap_or_else(|| {
+        channel
+            .name
+            .strip_prefix(ARCHIVED_CHANNEL_PREFIX)
+            .unwrap_or(&channel.name)
+            .to_string()
+    });
+    channel_id
+        .edit(
+            &discord_api.http,
+            EditChannel::new()
+                .name(name)
+                .category(
+                    row.pre_archive_category_id
+                        .map(|id| ChannelId::new(id as u64)),
+                )
+                .permissions(unarchived_permission_overwrites(
+                    &channel.permission_overwrites,
+                    guild_config,
+                )),
+        )
+        .await?;
+    // Don't remind the channel of its expiration right away
+    sqlx::query!(
+        r#"UPDATE event_series_text_channel
+        SET archived = NULL, deletion_time = NULL, pre_archive_name = NULL, pre_archive_category_id = NULL,
+            last_expiration_reminder_time = NOW()
+        WHERE discord_id = $1"#,
+        channel_id.get() as i64
+    )
+    .execute(db_connection)
+    .await?;
+    println!("Unarchived channel {}", channel_id);
+    Ok(UnarchiveResult::Unarchived)
+}
+
+pub async fn is_archived(
+    channel_id: ChannelId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let is_archived = sqlx::query_scalar!(
+        r#"SELECT archived IS NOT NULL AS "is_archived!" FROM event_series_text_channel WHERE discord_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(is_archived.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archived_names() {
+        assert_eq!(
+            archived_channel_name("curse-of-strahd"),
+            "archived-curse-of-strahd"
+        );
+        assert_eq!(
+            archived_channel_name("archived-curse-of-strahd"),
+            "archived-curse-of-strahd"
+        );
+        let long_name = "ä".repeat(60);
+        let archived_name = archived_channel_name(&long_name);
+        assert!(archived_name.len() <= MAX_CHANNEL_NAME_LENGTH);
+        assert!(archived_name.starts_with(ARCHIVED_CHANNEL_PREFIX));
+    }
+}
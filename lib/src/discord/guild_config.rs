@@ -0,0 +1,211 @@
+use std::sync::{Arc, RwLock};
+
+use serenity::model::{
+    channel::ChannelType,
+    id::{ChannelId, GuildId, RoleId},
+};
+use simple_error::SimpleError;
+
+// The Discord guild the bot works in, together with the roles, categories and
+// channels it needs to know about. Loaded from the `guild_config` table.
+#[derive(Debug, Clone)]
+pub struct GuildConfig {
+    pub guild_id: GuildId,
+    pub bot_admin_role_id: RoleId,
+    pub organiser_role_id: RoleId,
+    pub game_master_role_id: RoleId,
+    pub dice_roller_bot_role_id: Option<RoleId>,
+    pub admin_role_id: Option<RoleId>,
+    pub champion_role_id: RoleId,
+    pub insider_role_id: RoleId,
+    pub gm_champion_role_id: RoleId,
+    pub one_shot_category_ids: Vec<ChannelId>,
+    pub campaign_category_ids: Vec<ChannelId>,
+    pub voice_channel_category_ids: Vec<ChannelId>,
+    pub bot_alerts_channel_id: Option<ChannelId>,
+    pub free_spots_channel_id: Option<ChannelId>,
+    pub user_topic_voice_channel_id: Option<ChannelId>,
+    // Expired game channels are moved here instead of being deleted right away
+    pub archive_category_id: Option<ChannelId>,
+    pub archive_retention_days: i32,
+    // Changes the bot makes on Discord are mirrored here
+    pub audit_log_channel_id: Option<ChannelId>,
+    // Assigned to spammers by the spam rules that quarantine
+    pub spam_quarantine_role_id: Option<RoleId>,
+}
+
+impl GuildConfig {
+    pub async fn load(db_connection: &sqlx::PgPool) -> Result<Self, crate::meetup::Error> {
+        let row = sqlx::query!(
+            r#"SELECT guild_id, bot_admin_role_id, organiser_role_id, game_master_role_id,
+            dice_roller_bot_role_id, admin_role_id, champion_role_id, insider_role_id,
+            gm_champion_role_id, one_shot_category_ids, campaign_category_ids,
+            voice_channel_category_ids, bot_alerts_channel_id, free_spots_channel_id,
+            user_topic_voice_channel_id, archive_category_id, archive_retention_days,
+            audit_log_channel_id, spam_quarantine_role_id
+            FROM guild_config"#
+        )
+        .fetch_optional(db_connection)
+        .await?
+        .ok_or_else(|| SimpleError::new("The guild_config table is empty"))?;
+        let role = |id: i64| RoleId::new(id as u64);
+        let channel = |id: i64| ChannelId::new(id as u64);
+        Ok(GuildConfig {
+            guild_id: GuildId::new(row.guild_id as u64),
+            bot_admin_role_id: role(row.bot_admin_role_id),
+            organiser_role_id: role(row.organiser_role_id),
+            game_master_role_id: role(row.game_master_role_id),
+            dice_roller_bot_role_id: row.dice_roller_bot_role_id.map(role),
+            admin_role_id: row.admin_role_id.map(role),
+            champion_role_id: role(row.champion_role_id),
+            insider_role_id: role(row.insider_role_id),
+            gm_champion_role_id: role(row.gm_champion_role_id),
+            one_shot_category_ids: row.one_shot_category_ids.into_iter().map(channel).collect(),
+            campaign_category_ids: row.campaign_category_ids.into_iter().map(channel).collect(),
+            voice_channel_category_ids: row
+                .voice_channel_category_ids
+                .into_iter()
+                .map(channel)
+                .collect(),
+            bot_alerts_channel_id: row.bot_alerts_channel_id.map(channel),
+            free_spots_channel_id: row.free_spots_channel_id.map(channel),
+            user_topic_voice_channel_id: row.user_topic_voice_channel_id.map(channel),
+            archive_category_id: row.archive_category_id.map(channel),
+            archive_retention_days: row.archive_retention_days,
+            audit_log_channel_id: row.audit_log_channel_id.map(channel),
+            spam_quarantine_role_id: row.spam_quarantine_role_id.map(role),
+        })
+    }
+
+    fn roles(&self) -> Vec<(&'static str, RoleId)> {
+        let mut roles = vec![
+            ("bot admin role", self.bot_admin_role_id),
+            ("organiser role", self.organiser_role_id),
+            ("game master role", self.game_master_role_id),
+            ("champion role", self.champion_role_id),
+            ("insider role", self.insider_role_id),
+            ("GM champion role", self.gm_champion_role_id),
+        ];
+        if let Some(id) = self.dice_roller_bot_role_id {
+            roles.push(("dice roller bot role", id));
+        }
+        if let Some(id) = self.admin_role_id {
+            roles.push(("admin role", id));
+        }
+        if let Some(id) = self.spam_quarantine_role_id {
+            roles.push(("spam quarantine role", id));
+        }
+        roles
+    }
+
+    fn categories(&self) -> Vec<(&'static str, ChannelId)> {
+        let one_shot = self
+            .one_shot_category_ids
+            .iter()
+            .map(|&id| ("one-shot category", id));
+        let campaign = self
+            .campaign_category_ids
+            .iter()
+            .map(|&id| ("campaign category", id));
+        let voice = self
+            .voice_channel_category_ids
+            .iter()
+            .map(|&id| ("voice channel category", id));
+        let archive = self.archive_category_id.map(|id| ("archive category", id));
+        one_shot
+            .chain(campaign)
+            .chain(voice)
+            .chain(archive)
+            .collect()
+    }
+
+    fn channels(&self) -> Vec<(&'static str, ChannelId, ChannelType)> {
+        [
+            (
+                "bot alerts channel",
+                self.bot_alerts_channel_id,
+                ChannelType::Text,
+            ),
+            (
+                "free spots channel",
+                self.free_spots_channel_id,
+                ChannelType::Text,
+            ),
+            (
+                "user topic voice channel",
+                self.user_topic_voice_channel_id,
+                ChannelType::Voice,
+            ),
+            (
+                "audit log channel",
+                self.audit_log_channel_id,
+                ChannelType::Text,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, id, kind)| id.map(|id| (name, id, kind)))
+        .collect()
+    }
+
+    // Checks the configured IDs against the cached state of the guild and
+    // returns a description of every problem found
+    pub fn validate(&self, cache: &serenity::cache::Cache) -> Vec<String> {
+        let guild = match cache.guild(self.guild_id) {
+            Some(guild) => guild,
+            None => return vec![format!("Guild {} is not in the cache", self.guild_id)],
+        };
+        let mut problems = vec![];
+        for (name, id) in self.roles() {
+            if !guild.roles.contains_key(&id) {
+                problems.push(format!("The {} ({}) does not exist", name, id));
+            }
+        }
+        for (name, id) in self.categories() {
+            match guild.channels.get(&id) {
+                None => problems.push(format!("The {} ({}) does not exist", name, id)),
+                Some(channel) if channel.kind != ChannelType::Category => {
+                    problems.push(format!("The {} ({}) is not a category", name, id))
+                }
+                _ => (),
+            }
+        }
+        for (name, id, kind) in self.channels() {
+            match guild.channels.get(&id) {
+                None => problems.push(format!("The {} ({}) does not exist", name, id)),
+                Some(channel) if channel.kind != kind => problems.push(format!(
+                    "The {} ({}) is a {} channel",
+                    name,
+                    id,
+                    channel.kind.name()
+                )),
+                _ => (),
+            }
+        }
+        problems
+    }
+}
+
+// Handle to the current guild configuration which can be shared between the
+// bot, the web server and the recurring tasks. Reloading replaces the
+// configuration for everyone at once.
+#[derive(Debug, Clone)]
+pub struct SharedGuildConfig(Arc<RwLock<Arc<GuildConfig>>>);
+
+impl SharedGuildConfig {
+    pub fn new(config: GuildConfig) -> Self {
+        SharedGuildConfig(Arc::new(RwLock::new(Arc::new(config))))
+    }
+
+    pub fn get(&self) -> Arc<GuildConfig> {
+        self.0.read().expect("Guild config lock poisoned").clone()
+    }
+
+    pub async fn reload(
+        &self,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<Arc<GuildConfig>, crate::meetup::Error> {
+        let config = Arc::new(GuildConfig::load(db_connection).await?);
+        *self.0.write().expect("Guild config lock poisoned") = config.clone();
+        Ok(config)
+    }
+}
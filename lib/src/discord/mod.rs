@@ -1,8 +1,16 @@
+pub mod archive;
+pub mod guild_config;
+pub mod revive;
+pub mod rsvp;
 pub mod sync;
+pub mod sync_plan;
+pub mod transcript;
 pub mod util;
+pub mod waitlist;
 
 use std::sync::Arc;
 
+use crate::audit::{AuditEntry, Auditor};
 use serenity::model::{
     channel::{Channel, PermissionOverwrite, PermissionOverwriteType},
     id::{ChannelId, UserId},
@@ -69,7 +77,7 @@ pub async fn is_host(
         {
             let user = user_id.to_user(discord_api).await?;
             let is_host = user
-                .has_role(discord_api, sync::ids::GUILD_ID, host_role)
+                .has_role(discord_api, channel.guild_id, host_role)
                 .await
                 .unwrap_or(false);
             return Ok(is_host);
@@ -86,6 +94,7 @@ pub async fn add_channel_user_permissions(
     channel_id: ChannelId,
     user_id: UserId,
     permissions: Permissions,
+    auditor: &Auditor<'_>,
 ) -> Result<bool, crate::meetup::Error> {
     if permissions == Permissions::empty() {
         return Ok(false);
@@ -108,21 +117,78 @@ pub async fn add_channel_user_permissions(
     let mut new_permission_overwrites = current_permission_overwrites.clone();
     new_permission_overwrites.allow |= permissions;
     if new_permission_overwrites.allow != current_permission_overwrites.allow {
-        channel
+        let audit_entry = AuditEntry::permissions_changed(
+            channel_id,
+            user_id,
+            current_permission_overwrites.allow,
+            new_permission_overwrites.allow,
+        );
+        match channel
             .create_permission(&discord_api.http, new_permission_overwrites)
-            .await?;
+            .await
+        {
+            Ok(()) => auditor.record(audit_entry).await,
+            Err(err) => {
+                auditor.record(audit_entry.failed(&err)).await;
+                return Err(err.into());
+            }
+        }
         Ok(true)
     } else {
         Ok(false)
     }
 }
 
+// Replaces a role's or user's permission overwrite in a channel, unless it
+// is already set. True if the overwrite changed, false otherwise.
+pub async fn set_permission_overwrite(
+    discord_api: &CacheAndHttp,
+    channel_id: ChannelId,
+    permission_overwrite: PermissionOverwrite,
+    auditor: &Auditor<'_>,
+) -> Result<bool, crate::meetup::Error> {
+    let channel = if let Channel::Guild(channel) = channel_id.to_channel(discord_api).await? {
+        channel
+    } else {
+        return Err(simple_error::SimpleError::new(
+            "set_permission_overwrite: This is not a guild channel",
+        )
+        .into());
+    };
+    let current_permission_overwrite = channel
+        .permission_overwrites
+        .iter()
+        .find(|overwrite| overwrite.kind == permission_overwrite.kind);
+    if current_permission_overwrite.is_some_and(|overwrite| {
+        overwrite.allow == permission_overwrite.allow && overwrite.deny == permission_overwrite.deny
+    }) {
+        return Ok(false);
+    }
+    let audit_entry = AuditEntry::permission_overwrite_changed(
+        channel_id,
+        current_permission_overwrite,
+        &permission_overwrite,
+    );
+    match channel
+        .create_permission(&discord_api.http, permission_overwrite)
+        .await
+    {
+        Ok(()) => auditor.record(audit_entry).await,
+        Err(err) => {
+            auditor.record(audit_entry.failed(&err)).await;
+            return Err(err.into());
+        }
+    }
+    Ok(true)
+}
+
 // True if permissions changed, false otherwise
 pub async fn remove_channel_user_permissions(
     discord_api: &CacheAndHttp,
     channel_id: ChannelId,
     user_id: UserId,
     permissions: Permissions,
+    auditor: &Auditor<'_>,
 ) -> Result<bool, crate::meetup::Error> {
     if permissions == Permissions::empty() {
         return Ok(false);
@@ -145,16 +211,29 @@ pub async fn remove_channel_user_permissions(
     let mut new_permission_overwrites = current_permission_overwrites.clone();
     new_permission_overwrites.allow &= !permissions;
     if new_permission_overwrites.allow != current_permission_overwrites.allow {
-        if new_permission_overwrites.allow == Permissions::empty()
+        let audit_entry = AuditEntry::permissions_changed(
+            channel_id,
+            user_id,
+            current_permission_overwrites.allow,
+            new_permission_overwrites.allow,
+        );
+        let result = if new_permission_overwrites.allow == Permissions::empty()
             && new_permission_overwrites.deny == Permissions::empty()
         {
             channel
                 .delete_permission(&discord_api.http, new_permission_overwrites.kind)
-                .await?;
+                .await
         } else {
             channel
                 .create_permission(&discord_api.http, new_permission_overwrites)
-                .await?;
+                .await
+        };
+        match result {
+            Ok(()) => auditor.record(audit_entry).await,
+            Err(err) => {
+                auditor.record(audit_entry.failed(&err)).await;
+                return Err(err.into());
+            }
         }
         Ok(true)
     } else {
@@ -0,0 +1,122 @@
+use serenity::{
+    builder::CreateMessage,
+    model::id::{ChannelId, RoleId},
+};
+
+use crate::{db, strings};
+
+// If the series' channel has been deleted, unlinks it together with the
+// voice channel and roles that are gone as well, so that the sync creates new
+// ones. The old IDs are kept in the event_series_revival table.
+// Returns the deleted channel, if the series got revived.
+pub async fn revive_series(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<ChannelId>, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let series = sqlx::query!(
+        r#"SELECT event_series.discord_text_channel_id, event_series.discord_voice_channel_id,
+            event_series.discord_role_id, event_series.discord_host_role_id,
+            event_series_text_channel.deleted IS NOT NULL AS "text_channel_deleted!",
+            event_series_voice_channel.deleted IS NOT NULL AS "voice_channel_deleted!",
+            event_series_role.deleted IS NOT NULL AS "role_deleted!",
+            event_series_host_role.deleted IS NOT NULL AS "host_role_deleted!"
+        FROM event_series
+        LEFT JOIN event_series_text_channel ON event_series_text_channel.discord_id = event_series.discord_text_channel_id
+        LEFT JOIN event_series_voice_channel ON event_series_voice_channel.discord_id = event_series.discord_voice_channel_id
+        LEFT JOIN event_series_role ON event_series_role.discord_id = event_series.discord_role_id
+        LEFT JOIN event_series_host_role ON event_series_host_role.discord_id = event_series.discord_host_role_id
+        WHERE event_series.id = $1
+        FOR UPDATE OF event_series"#,
+        series_id.0
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let old_text_channel_id = match series.discord_text_channel_id {
+        Some(id) if series.text_channel_deleted => id,
+        _ => return Ok(None),
+    };
+    // Channels and roles that still exist are kept
+    let old_voice_channel_id = series
+        .discord_voice_channel_id
+        .filter(|_| series.voice_channel_deleted);
+    let old_role_id = series.discord_role_id.filter(|_| series.role_deleted);
+    let old_host_role_id = series
+        .discord_host_role_id
+        .filter(|_| series.host_role_deleted);
+    sqlx::query!(
+        r#"INSERT INTO event_series_revival (event_series_id, old_discord_text_channel_id, old_discord_voice_channel_id, old_discord_role_id, old_discord_host_role_id)
+        VALUES ($1, $2, $3, $4, $5)"#,
+        series_id.0,
+        old_text_channel_id,
+        old_voice_channel_id,
+        old_role_id,
+        old_host_role_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"UPDATE event_series
+        SET discord_text_channel_id = NULL,
+            discord_voice_channel_id = CASE WHEN $2 THEN NULL ELSE discord_voice_channel_id END,
+            discord_role_id = CASE WHEN $3 THEN NULL ELSE discord_role_id END,
+            discord_host_role_id = CASE WHEN $4 THEN NULL ELSE discord_host_role_id END
+        WHERE id = $1"#,
+        series_id.0,
+        old_voice_channel_id.is_some(),
+        old_role_id.is_some(),
+        old_host_role_id.is_some()
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(Some(ChannelId::new(old_text_channel_id as u64)))
+}
+
+// Greets the players in the revived series' new channel, linking the old
+// channel's transcript if there is one
+pub async fn send_welcome_back_message(
+    series_id: db::EventSeriesId,
+    channel_id: ChannelId,
+    channel_role_id: RoleId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let revivals = sqlx::query!(
+        r#"SELECT event_series_revival.old_discord_text_channel_id,
+            event_series_text_channel.transcript_file IS NOT NULL AS "has_transcript!"
+        FROM event_series_revival
+        LEFT JOIN event_series_text_channel ON event_series_text_channel.discord_id = event_series_revival.old_discord_text_channel_id
+        WHERE event_series_revival.event_series_id = $1 AND NOT event_series_revival.welcome_message_sent
+        ORDER BY event_series_revival.revived"#,
+        series_id.0
+    )
+    .fetch_all(db_connection)
+    .await?;
+    if revivals.is_empty() {
+        return Ok(());
+    }
+    // Link the most recent transcript
+    let transcript_url = revivals
+        .iter()
+        .rev()
+        .filter(|revival| revival.has_transcript)
+        .find_map(|revival| revival.old_discord_text_channel_id)
+        .map(|old_channel_id| format!("{}/transcripts/{}", crate::urls::BASE_URL, old_channel_id));
+    channel_id
+        .send_message(
+            &discord_api.http,
+            CreateMessage::new().content(strings::CAMPAIGN_WELCOME_BACK_MESSAGE(
+                channel_role_id,
+                transcript_url.as_deref(),
+            )),
+        )
+        .await?;
+    sqlx::query!(
+        r#"UPDATE event_series_revival SET welcome_message_sent = TRUE WHERE event_series_id = $1"#,
+        series_id.0
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use serenity::{
+    builder::{CreateActionRow, CreateButton, CreateEmbed, CreateMessage},
+    model::{application::ButtonStyle, id::ChannelId, id::UserId},
+};
+
+use crate::db;
+
+// The buttons on session announcements carry the action and the event ID in
+// their custom ID, e.g. "rsvp:join:1234"
+const CUSTOM_ID_PREFIX: &str = "rsvp:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsvpAction {
+    Join,
+    Leave,
+    Waitlist,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsvpResult {
+    Joined,
+    AlreadyJoined,
+    Full,
+    Closed,
+    Left,
+    NotJoined,
+    JoinedOnWebsite,
+}
+
+impl RsvpResult {
+    pub fn message(self) -> &'static str {
+        match self {
+            RsvpResult::Joined => crate::strings::RSVP_JOINED,
+            RsvpResult::AlreadyJoined => crate::strings::RSVP_ALREADY_JOINED,
+            RsvpResult::Full => crate::strings::RSVP_FULL,
+            RsvpResult::Closed => crate::strings::RSVP_CLOSED,
+            RsvpResult::Left => crate::strings::RSVP_LEFT,
+            RsvpResult::NotJoined => crate::strings::RSVP_NOT_JOINED,
+            RsvpResult::JoinedOnWebsite => crate::strings::RSVP_JOINED_ON_WEBSITE,
+        }
+    }
+}
+
+pub fn parse_custom_id(custom_id: &str) -> Option<(RsvpAction, db::EventId)> {
+    let (action, event_id) = custom_id.strip_prefix(CUSTOM_ID_PREFIX)?.split_once(':')?;
+    let action = match action {
+        "join" => RsvpAction::Join,
+        "leave" => RsvpAction::Leave,
+        "waitlist" => RsvpAction::Waitlist,
+        _ => return None,
+    };
+    let event_id = event_id.parse::<i32>().ok()?;
+    Some((action, db::EventId(event_id)))
+}
+
+fn buttons(event_id: db::EventId) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}join:{}", CUSTOM_ID_PREFIX, event_id.0))
+            .label("Join")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}leave:{}", CUSTOM_ID_PREFIX, event_id.0))
+            .label("Leave")
+            .style(ButtonStyle::Secondary),
+        CreateButton::new(format!("{}waitlist:{}", CUSTOM_ID_PREFIX, event_id.0))
+            .label("Waitlist")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+// Everything that is shown in the announcement embed
+pub struct RsvpState {
+    pub title: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub url: Option<String>,
+    pub num_participants: i64,
+    // Seats which are still free, taking RSVPs made on Discord and seats
+    // offered to the waitlist into account. None if there is no known limit.
+    pub free_seats: Option<i64>,
+}
+
+impl RsvpState {
+    pub async fn load(
+        event_id: db::EventId,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<Option<Self>, crate::meetup::Error> {
+        let row = sqlx::query!(
+            r#"SELECT event.title, event.start_time, event.open_seats,
+                (SELECT swissrpg_event.url FROM swissrpg_event WHERE swissrpg_event.event_id = event.id LIMIT 1) AS "url?",
+                (SELECT COUNT(*) FROM event_participant WHERE event_participant.event_id = event.id) AS "num_participants!",
+                (SELECT COUNT(*) FROM event_participant WHERE event_participant.event_id = event.id AND from_discord) AS "num_discord_participants!",
+                (SELECT COUNT(*) FROM event_waitlist WHERE event_waitlist.event_id = event.id AND claim_expires > NOW()) AS "num_offered_seats!"
+            FROM event
+            WHERE event.id = $1 AND event.deleted IS NULL"#,
+            event_id.0
+        )
+        .fetch_optional(db_connection)
+        .await?;
+        Ok(row.map(|row| RsvpState {
+            title: row.title,
+            start_time: row.start_time,
+            url: row.url,
+            num_participants: row.num_participants,
+            free_seats: row.open_seats.map(|open_seats| {
+                (open_seats as i64 - row.num_discord_participants - row.num_offered_seats).max(0)
+            }),
+        }))
+    }
+
+    pub fn embed(&self) -> CreateEmbed {
+        let seats = match self.free_seats {
+            Some(0) => "Full".to_string(),
+            Some(free_seats) => free_seats.to_string(),
+            None => "-".to_string(),
+        };
+        let mut embed = CreateEmbed::new()
+            .title(&self.title)
+            .description(format!("<t:{}:F>", self.start_time.timestamp()))
+            .field("Players", self.num_participants.to_string(), true)
+            .field("Free seats", seats, true);
+        if let Some(url) = &self.url {
+            embed = embed.url(url);
+        }
+        embed
+    }
+}
+
+// Posts a message together with an RSVP embed and the Join / Leave buttons
+// for the specified event into its game channel
+pub async fn announce_event(
+    event_id: db::EventId,
+    message: &str,
+    db_connection: &sqlx::PgPool,
+    discord_cache_http: &super::CacheAndHttp,
+) -> Result<(), crate::BoxedError> {
+    let channel_id = sqlx::query!(
+        r#"SELECT event_series.discord_text_channel_id as "discord_text_channel_id!"
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        WHERE event.id = $1"#,
+        event_id.0
+    )
+    .map(|row| ChannelId::new(row.discord_text_channel_id as u64))
+    .fetch_one(db_connection)
+    .await?;
+    let state = RsvpState::load(event_id, db_connection)
+        .await?
+        .ok_or_else(|| simple_error::SimpleError::new("Event to announce does not exist"))?;
+    let message = CreateMessage::new()
+        .content(message)
+        .embed(state.embed())
+        .components(buttons(event_id));
+    channel_id
+        .send_message(&discord_cache_http.http, message)
+        .await?;
+    Ok(())
+}
+
+pub async fn join(
+    event_id: db::EventId,
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<RsvpResult, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    // Lock the event such that concurrent clicks can't take the same seat
+    let event = sqlx::query!(
+        r#"SELECT start_time, open_seats FROM event
+        WHERE id = $1 AND deleted IS NULL
+        FOR UPDATE"#,
+        event_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let event = match event {
+        Some(event) if event.start_time > chrono::Utc::now() => event,
+        _ => return Ok(RsvpResult::Closed),
+    };
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    let is_participant = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM event_participant WHERE event_id = $1 AND member_id = $2) AS "exists!""#,
+        event_id.0,
+        member_id.0
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if is_participant {
+        return Ok(RsvpResult::AlreadyJoined);
+    }
+    // The open seats reported by SwissRPG don't know about RSVPs made on
+    // Discord, so those need to be subtracted. Seats offered to other players
+    // on the waitlist are taken as well.
+    if let Some(open_seats) = event.open_seats {
+        let num_discord_participants = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) AS "count!" FROM event_participant WHERE event_id = $1 AND from_discord"#,
+            event_id.0
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        let num_offered_seats =
+            super::waitlist::num_offered_seats(event_id, Some(member_id), &mut tx).await?;
+        if num_discord_participants + num_offered_seats >= open_seats as i64 {
+            return Ok(RsvpResult::Full);
+        }
+    }
+    sqlx::query!(
+        r#"INSERT INTO event_participant (event_id, member_id, from_discord) VALUES ($1, $2, TRUE)"#,
+        event_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_waitlist WHERE event_id = $1 AND member_id = $2"#,
+        event_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(RsvpResult::Joined)
+}
+
+pub async fn leave(
+    event_id: db::EventId,
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<RsvpResult, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let start_time = sqlx::query_scalar!(
+        r#"SELECT start_time FROM event WHERE id = $1 AND deleted IS NULL"#,
+        event_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    match start_time {
+        Some(start_time) if start_time > chrono::Utc::now() => (),
+        _ => return Ok(RsvpResult::Closed),
+    }
+    // RSVPs made elsewhere would be restored by the next sync, so we only
+    // remove the ones made on Discord
+    let from_discord = sqlx::query_scalar!(
+        r#"DELETE FROM event_participant
+        USING "member"
+        WHERE event_participant.member_id = "member".id AND event_participant.event_id = $1 AND "member".discord_id = $2
+        RETURNING event_participant.from_discord"#,
+        event_id.0,
+        user_id.get() as i64
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let result = match from_discord {
+        Some(true) => RsvpResult::Left,
+        // Dropping the transaction restores the RSVP
+        Some(false) => return Ok(RsvpResult::JoinedOnWebsite),
+        None => RsvpResult::NotJoined,
+    };
+    tx.commit().await?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_ids() {
+        assert_eq!(
+            parse_custom_id("rsvp:join:1234"),
+            Some((RsvpAction::Join, db::EventId(1234)))
+        );
+        assert_eq!(
+            parse_custom_id("rsvp:waitlist:1234"),
+            Some((RsvpAction::Waitlist, db::EventId(1234)))
+        );
+        assert_eq!(parse_custom_id("rsvp:maybe:1234"), None);
+        assert_eq!(parse_custom_id("other:join:1234"), None);
+    }
+}
@@ -1,76 +1,39 @@
 use futures::{stream, StreamExt};
 use lazy_static::lazy_static;
-use redis::{self, AsyncCommands};
 use serenity::{
     builder::{CreateChannel, CreateMessage, EditChannel, EditRole},
     http::CacheHttp,
     model::{
         channel::{PermissionOverwrite, PermissionOverwriteType},
-        id::{ChannelId, GuildId, RoleId, UserId},
+        id::{ChannelId, RoleId, UserId},
         permissions::Permissions,
     },
 };
 use simple_error::SimpleError;
+use std::{collections::HashMap, sync::Arc};
 
-#[cfg(feature = "bottest")]
-pub mod ids {
-    use super::*;
-    // Test server:
-    pub const GUILD_ID: GuildId = GuildId::new(601070848446824509);
-    pub const BOT_ADMIN_ID: RoleId = RoleId::new(606829075226689536);
-    pub const ORGANISER_ID: RoleId = RoleId::new(689914933357314090);
-    pub const GAME_MASTER_ID: RoleId = RoleId::new(606913167439822987);
-    pub const DICE_ROLLER_BOT_ID: Option<RoleId> = None;
-    pub const ONE_SHOT_CATEGORY_IDS: &'static [ChannelId] = &[ChannelId::new(607561808429056042)];
-    pub const CAMPAIGN_CATEGORY_IDS: &'static [ChannelId] = &[ChannelId::new(607561949651402772)];
-    pub const VOICE_CHANNELS_CATEGORY_IDS: &'static [ChannelId] =
-        &[ChannelId::new(601070848446824512)];
-    pub const BOT_ALERTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(650656330390175764));
-    pub const FREE_SPOTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(704988201038643270));
-    pub const USER_TOPIC_VOICE_CHANNEL_ID: Option<ChannelId> =
-        Some(ChannelId::new(807270405672140831));
-    pub const ADMIN_ROLE_ID: Option<RoleId> = None;
-}
-
-#[cfg(not(feature = "bottest"))]
-pub mod ids {
-    use super::*;
-    // SwissRPG server:
-    pub const GUILD_ID: GuildId = GuildId::new(401856510709202945);
-    pub const BOT_ADMIN_ID: RoleId = RoleId::new(610541498852966436);
-    pub const ORGANISER_ID: RoleId = RoleId::new(539447673988841492);
-    pub const GAME_MASTER_ID: RoleId = RoleId::new(412946716892069888);
-    pub const DICE_ROLLER_BOT_ID: Option<RoleId> = Some(RoleId::new(600612886368223274));
-    pub const ONE_SHOT_CATEGORY_IDS: &'static [ChannelId] = &[ChannelId::new(562607292176924694)];
-    pub const CAMPAIGN_CATEGORY_IDS: &'static [ChannelId] = &[
-        ChannelId::new(414074722259828736),
-        ChannelId::new(651006290998329354),
-    ];
-    pub const VOICE_CHANNELS_CATEGORY_IDS: &'static [ChannelId] = &[
-        ChannelId::new(401856511233753110),
-        ChannelId::new(831140794952843324),
-    ];
-    pub const BOT_ALERTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(650660608705822723));
-    pub const FREE_SPOTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(706131908102324345));
-    pub const USER_TOPIC_VOICE_CHANNEL_ID: Option<ChannelId> =
-        Some(ChannelId::new(811601700736729129));
-    pub const ADMIN_ROLE_ID: Option<RoleId> = Some(RoleId::new(412927099855437825));
-}
-
-use ids::*;
-
-use crate::{db, swissrpg::swissrpg_event_series_url};
+use super::guild_config::GuildConfig;
+use crate::{
+    audit::{AuditEntry, AuditSource, Auditor},
+    db,
+    series_settings::ChannelSettings,
+    swissrpg::swissrpg_event_series_url,
+};
 
 lazy_static! {
     static ref EVENT_NAME_REGEX: regex::Regex =
         regex::Regex::new(r"^\s*(?P<name>[^\[\(]+[^\s\[\(])").unwrap();
+    // One lock per event series, so that the global sync and targeted syncs
+    // never work on the same series at the same time
+    static ref SERIES_SYNC_LOCKS: std::sync::Mutex<HashMap<db::EventSeriesId, Arc<futures_util::lock::Mutex<()>>>> =
+        std::sync::Mutex::new(HashMap::new());
 }
 
 // Syncs Discord with the state of the database
 pub async fn sync_discord(
-    redis_connection: &mut redis::aio::MultiplexedConnection,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
     bot_id: UserId,
     swissrpg_base_url: &str,
 ) -> Result<(), crate::meetup::Error> {
@@ -80,11 +43,11 @@ pub async fn sync_discord(
         .await?;
     let mut some_failed = false;
     for series_id in event_series_ids {
-        if let Err(err) = sync_event_series(
+        if let Err(err) = sync_single_series(
             series_id,
-            redis_connection,
             db_connection,
             discord_api,
+            guild_config,
             bot_id,
             swissrpg_base_url,
         )
@@ -101,6 +64,43 @@ pub async fn sync_discord(
     }
 }
 
+// Syncs only the given event series, e.g. right after its players or sessions
+// changed, instead of waiting for the next global sync
+pub async fn sync_single_series(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+    bot_id: UserId,
+    swissrpg_base_url: &str,
+) -> Result<(), crate::meetup::Error> {
+    let series_lock = SERIES_SYNC_LOCKS
+        .lock()
+        .unwrap()
+        .entry(series_id)
+        .or_default()
+        .clone();
+    let result = {
+        let _guard = series_lock.lock().await;
+        sync_event_series(
+            series_id,
+            db_connection,
+            discord_api,
+            guild_config,
+            bot_id,
+            swissrpg_base_url,
+        )
+        .await
+    };
+    // Forget the lock unless another sync holds or waits for it. Others only
+    // get the lock from the map, so the count can't change while it's locked.
+    let mut series_sync_locks = SERIES_SYNC_LOCKS.lock().unwrap();
+    if Arc::strong_count(&series_lock) == 2 {
+        series_sync_locks.remove(&series_id);
+    }
+    result
+}
+
 /*
 For each event series:
   - create a channel if it doesn't exist yet
@@ -117,9 +117,9 @@ For each event series:
 */
 async fn sync_event_series(
     series_id: db::EventSeriesId,
-    redis_connection: &mut redis::aio::MultiplexedConnection,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
     bot_id: UserId,
     swissrpg_base_url: &str,
 ) -> Result<(), crate::meetup::Error> {
@@ -135,6 +135,14 @@ async fn sync_event_series(
             return Ok(());
         }
     };
+    // Archived channels stay untouched until they get unarchived
+    if series_channel_is_archived(series_id, db_connection).await? {
+        println!(
+            "Discord event sync: the channel of event series {} is archived, not syncing",
+            series_id.0
+        );
+        return Ok(());
+    }
     // Upgrade this event series to a campaign if there is more than one event
     let num_events = sqlx::query_scalar!(
         r#"SELECT COUNT(*) as "count!" FROM event WHERE event_series_id = $1"#,
@@ -169,96 +177,42 @@ async fn sync_event_series(
     }
 
     // Figure out the title of this event series
-    // Parse the series name from the event title
-    let series_name = match EVENT_NAME_REGEX.captures(&next_event.title) {
-        Some(captures) => captures.name("name").unwrap().as_str(),
-        None => {
-            return Err(SimpleError::new(format!(
-                "Could not extract a series name from the event \"{}\"",
-                next_event.title
-            ))
-            .into())
-        }
-    };
-    if series_name.len() < 2 || series_name.len() > 80 {
-        return Err(SimpleError::new(format!(
-            "Channel name \"{}\" is too short or too long",
-            series_name
-        ))
-        .into());
-    }
+    let channel_settings = ChannelSettings::load(series_id, db_connection).await?;
+    let series_name = channel_settings.channel_name(&next_event)?;
     // Query the RSVPd guests and hosts
-    let discord_guest_ids = sqlx::query!(
-        r#"
-        SELECT member.discord_id as "discord_id!"
-        FROM event
-        INNER JOIN event_participant ON event.id = event_participant.event_id
-        INNER JOIN member ON event_participant.member_id = member.id
-        WHERE event.id = $1 AND member.discord_id IS NOT NULL
-        "#,
-        next_event.id.0
-    )
-    .map(|row| UserId::new(row.discord_id as u64))
-    .fetch_all(db_connection)
-    .await?;
-    let discord_host_ids = sqlx::query!(
-        r#"
-        SELECT member.discord_id as "discord_id!"
-        FROM event
-        INNER JOIN event_host ON event.id = event_host.event_id
-        INNER JOIN member ON event_host.member_id = member.id
-        WHERE event.id = $1 AND member.discord_id IS NOT NULL
-        "#,
-        next_event.id.0
-    )
-    .map(|row| UserId::new(row.discord_id as u64))
-    .fetch_all(db_connection)
-    .await?;
+    let discord_guest_ids = event_discord_guest_ids(next_event.id, db_connection).await?;
+    let discord_host_ids = event_discord_host_ids(next_event.id, db_connection).await?;
 
+    // A campaign that continues after its channel has been deleted gets new
+    // channels and roles
+    if let Some(old_channel_id) = super::revive::revive_series(series_id, db_connection).await? {
+        println!(
+            "Discord event sync: reviving event series {} whose channel {} was deleted",
+            series_id.0, old_channel_id
+        );
+    }
     // Step 0: Make sure that event hosts have the guild's game master role
-    sync_game_master_role(series_id, db_connection, discord_api).await?;
-    // Convert host IDs to user objects
-    let discord_hosts: Vec<_> = stream::iter(&discord_host_ids)
-        .then(|&host_id| host_id.to_user(discord_api))
-        .filter_map(|res| async {
-            match res {
-                Ok(user) => Some(user),
-                Err(err) => {
-                    eprintln!(
-                        "Error converting Discord host ID to Discord user object: {}",
-                        err
-                    );
-                    None
-                }
-            }
-        })
-        .collect()
-        .await;
+    sync_game_master_role(series_id, db_connection, discord_api, guild_config).await?;
     // Step 1: Sync the channel
     let channel_id = sync_channel(
         ChannelType::Text,
         series_name,
         series_id,
         bot_id,
-        redis_connection,
         db_connection,
         discord_api,
+        guild_config,
     )
     .await?;
     // Step 2: Sync the channel's associated role
-    let guest_tag = if discord_hosts.is_empty() {
-        "Player".to_string()
-    } else {
-        itertools::join(discord_hosts.iter().map(|host| &host.name), ", ")
-    };
-    let guest_role_name = format!("[{}] {}", guest_tag, series_name);
+    let guest_role_name = guest_role_name(series_name, &discord_host_ids, discord_api).await;
     let channel_role_id = sync_role(
         &guest_role_name,
         /*is_host_role*/ false,
         series_id,
-        redis_connection,
         db_connection,
         discord_api,
+        guild_config,
     )
     .await?;
     // Step 3: Sync the channel's associated host role
@@ -271,15 +225,21 @@ async fn sync_event_series(
     //     discord_api,
     // )?;
     // Step 4: Sync the channel permissions
-    if let Err(err) = sync_channel_permissions(
-        channel_id,
+    let auditor = Auditor::new(
+        AuditSource::Task("Discord sync"),
+        db_connection,
+        discord_api,
+        guild_config,
+    );
+    let permission_overwrites = channel_permission_overwrites(
         ChannelType::Text,
-        channel_role_id,
+        Some(channel_role_id),
         &discord_host_ids,
         bot_id,
-        discord_api,
-    )
-    .await
+        guild_config,
+    );
+    if let Err(err) =
+        sync_channel_permissions(channel_id, permission_overwrites, discord_api, &auditor).await
     {
         eprintln!(
             "Error in sync_channel_permissions (for text channel):\n{:#?}",
@@ -293,9 +253,9 @@ async fn sync_event_series(
             series_name,
             series_id,
             bot_id,
-            redis_connection,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await
         {
@@ -304,13 +264,18 @@ async fn sync_event_series(
                 None
             }
             Ok(voice_channel_id) => {
-                if let Err(err) = sync_channel_permissions(
-                    voice_channel_id,
+                let permission_overwrites = channel_permission_overwrites(
                     ChannelType::Voice,
-                    channel_role_id,
+                    Some(channel_role_id),
                     &discord_host_ids,
                     bot_id,
+                    guild_config,
+                );
+                if let Err(err) = sync_channel_permissions(
+                    voice_channel_id,
+                    permission_overwrites,
                     discord_api,
+                    &auditor,
                 )
                 .await
                 {
@@ -343,12 +308,14 @@ async fn sync_event_series(
         channel_role_id,
         db_connection,
         discord_api,
+        guild_config,
     )
     .await?;
     // Step 6: Keep the channel's topic up-to-date
     sync_channel_topic(
         channel_id,
         &next_event,
+        &channel_settings,
         swissrpg_event_series_id,
         swissrpg_base_url,
         discord_api,
@@ -357,33 +324,153 @@ async fn sync_event_series(
     sync_channel_category(
         series_id,
         ChannelType::Text,
-        &next_event,
+        channel_settings.special_category(ChannelType::Text, &next_event),
         channel_id,
         db_connection,
         discord_api,
+        guild_config,
     )
     .await?;
     if let Some(voice_channel_id) = voice_channel_id {
         sync_channel_category(
             series_id,
             ChannelType::Voice,
-            &next_event,
+            channel_settings.special_category(ChannelType::Voice, &next_event),
             voice_channel_id,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await?;
     }
+    // Step 7: Greet the players of a revived series once they have access
+    super::revive::send_welcome_back_message(
+        series_id,
+        channel_id,
+        channel_role_id,
+        db_connection,
+        discord_api,
+    )
+    .await?;
     Ok(())
 }
 
+pub(crate) async fn series_channel_is_archived(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let is_archived = sqlx::query_scalar!(
+        r#"SELECT event_series_text_channel.archived IS NOT NULL AND event_series_text_channel.deleted IS NULL AS "is_archived!"
+        FROM event_series
+        INNER JOIN event_series_text_channel ON event_series_text_channel.discord_id = event_series.discord_text_channel_id
+        WHERE event_series.id = $1"#,
+        series_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(is_archived.unwrap_or(false))
+}
+
+// Parses the series name from the event title
+pub(crate) fn series_name(event_title: &str) -> Result<&str, crate::meetup::Error> {
+    let series_name = match EVENT_NAME_REGEX.captures(event_title) {
+        Some(captures) => captures.name("name").unwrap().as_str(),
+        None => {
+            return Err(SimpleError::new(format!(
+                "Could not extract a series name from the event \"{}\"",
+                event_title
+            ))
+            .into())
+        }
+    };
+    if series_name.len() < 2 || series_name.len() > 80 {
+        return Err(SimpleError::new(format!(
+            "Channel name \"{}\" is too short or too long",
+            series_name
+        ))
+        .into());
+    }
+    Ok(series_name)
+}
+
+pub(crate) async fn event_discord_guest_ids(
+    event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<UserId>, crate::meetup::Error> {
+    let discord_guest_ids = sqlx::query!(
+        r#"
+        SELECT member.discord_id as "discord_id!"
+        FROM event
+        INNER JOIN event_participant ON event.id = event_participant.event_id
+        INNER JOIN member ON event_participant.member_id = member.id
+        WHERE event.id = $1 AND member.discord_id IS NOT NULL
+        "#,
+        event_id.0
+    )
+    .map(|row| UserId::new(row.discord_id as u64))
+    .fetch_all(db_connection)
+    .await?;
+    Ok(discord_guest_ids)
+}
+
+pub(crate) async fn event_discord_host_ids(
+    event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<UserId>, crate::meetup::Error> {
+    let discord_host_ids = sqlx::query!(
+        r#"
+        SELECT member.discord_id as "discord_id!"
+        FROM event
+        INNER JOIN event_host ON event.id = event_host.event_id
+        INNER JOIN member ON event_host.member_id = member.id
+        WHERE event.id = $1 AND member.discord_id IS NOT NULL
+        "#,
+        event_id.0
+    )
+    .map(|row| UserId::new(row.discord_id as u64))
+    .fetch_all(db_connection)
+    .await?;
+    Ok(discord_host_ids)
+}
+
+// The series role is named after the series and its hosts
+pub(crate) async fn guest_role_name(
+    series_name: &str,
+    discord_host_ids: &[UserId],
+    discord_api: &super::CacheAndHttp,
+) -> String {
+    // Convert host IDs to user objects
+    let discord_hosts: Vec<_> = stream::iter(discord_host_ids)
+        .then(|&host_id| host_id.to_user(discord_api))
+        .filter_map(|res| async {
+            match res {
+                Ok(user) => Some(user),
+                Err(err) => {
+                    eprintln!(
+                        "Error converting Discord host ID to Discord user object: {}",
+                        err
+                    );
+                    None
+                }
+            }
+        })
+        .collect()
+        .await;
+    let guest_tag = if discord_hosts.is_empty() {
+        "Player".to_string()
+    } else {
+        itertools::join(discord_hosts.iter().map(|host| &host.name), ", ")
+    };
+    format!("[{}] {}", guest_tag, series_name)
+}
+
 async fn sync_role(
     role_name: &str,
     is_host_role: bool,
     event_series: db::EventSeriesId,
-    redis_connection: &mut redis::aio::MultiplexedConnection,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<RoleId, crate::meetup::Error> {
     let max_retries: u32 = 1;
     let mut current_num_try: u32 = 0;
@@ -396,14 +483,14 @@ async fn sync_role(
             role_name,
             is_host_role,
             event_series,
-            redis_connection,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await?;
         // Make sure that the role ID that was returned actually exists on Discord
         // First, check the cache
-        let role_exists = match GUILD_ID.to_guild_cached(&discord_api.cache) {
+        let role_exists = match guild_config.guild_id.to_guild_cached(&discord_api.cache) {
             Some(guild) => guild.roles.contains_key(&role),
             None => false,
         };
@@ -411,7 +498,10 @@ async fn sync_role(
         let role_exists = if role_exists {
             true
         } else {
-            let guild_roles = discord_api.http().get_guild_roles(GUILD_ID).await?;
+            let guild_roles = discord_api
+                .http()
+                .get_guild_roles(guild_config.guild_id)
+                .await?;
             guild_roles.iter().any(|guild_role| guild_role.id == role)
         };
         if !role_exists {
@@ -448,9 +538,9 @@ async fn sync_role_impl(
     role_name: &str,
     is_host_role: bool,
     series_id: db::EventSeriesId,
-    redis_connection: &mut redis::aio::MultiplexedConnection,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<RoleId, crate::meetup::Error> {
     let mut tx = db_connection.begin().await?;
     // Check if the role already exists
@@ -478,7 +568,8 @@ async fn sync_role_impl(
         .name(role_name)
         .colour(serenity::all::Colour::BLUE)
         .permissions(Permissions::empty());
-    let temp_channel_role = GUILD_ID
+    let temp_channel_role = guild_config
+        .guild_id
         .create_role(discord_api.http(), role_builder)
         .await?;
     println!(
@@ -524,7 +615,7 @@ async fn sync_role_impl(
             match discord_api
                 .http()
                 .delete_role(
-                    GUILD_ID,
+                    guild_config.guild_id,
                     temp_channel_role.id,
                     Some("sync_role_impl transaction failed"),
                 )
@@ -536,9 +627,8 @@ async fn sync_role_impl(
                         "Could not delete temporary channel role {}",
                         temp_channel_role.id.get()
                     );
-                    // Try to persist the information to Redis that we have an orphaned role now
-                    match redis_connection
-                        .sadd("orphaned_discord_roles", temp_channel_role.id.get())
+                    // Try to record that we have an orphaned role now
+                    match record_orphaned_object(temp_channel_role.id.get(), "role", db_connection)
                         .await
                     {
                         Err(_) => eprintln!(
@@ -564,8 +654,27 @@ async fn sync_role_impl(
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
-pub(crate) enum ChannelType {
+// Remembers a channel or role that was created by the bot but could not be
+// deleted again, such that it can be cleaned up by hand
+async fn record_orphaned_object(
+    discord_id: u64,
+    kind: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO orphaned_discord_object (discord_id, kind) VALUES ($1, $2)
+        ON CONFLICT (discord_id) DO NOTHING"#,
+        discord_id as i64,
+        kind
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelType {
     Text,
     Voice,
 }
@@ -575,9 +684,9 @@ async fn sync_channel(
     channel_name: &str,
     event_series_id: db::EventSeriesId,
     bot_id: UserId,
-    redis_connection: &mut redis::aio::MultiplexedConnection,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<ChannelId, crate::meetup::Error> {
     let max_retries: u32 = 1;
     let mut current_num_try: u32 = 0;
@@ -591,9 +700,9 @@ async fn sync_channel(
             channel_name,
             event_series_id,
             bot_id,
-            redis_connection,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await?;
         // Make sure that the channel ID that was returned actually exists on Discord
@@ -648,14 +757,49 @@ async fn sync_channel(
     }
 }
 
+// The permission overwrites a newly created channel starts out with
+pub(crate) fn initial_permission_overwrites(
+    channel_type: ChannelType,
+    bot_id: UserId,
+    guild_config: &GuildConfig,
+) -> Vec<PermissionOverwrite> {
+    // The @everyone role has the same id as the guild
+    match channel_type {
+        ChannelType::Text => vec![
+            PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::VIEW_CHANNEL,
+                kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
+            },
+            PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(bot_id),
+            },
+        ],
+        ChannelType::Voice => vec![
+            PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::CONNECT,
+                kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
+            },
+            PermissionOverwrite {
+                allow: Permissions::CONNECT,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(bot_id),
+            },
+        ],
+    }
+}
+
 async fn sync_channel_impl(
     channel_type: ChannelType,
     channel_name: &str,
     event_series_id: db::EventSeriesId,
     bot_id: UserId,
-    redis_connection: &mut redis::aio::MultiplexedConnection,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<ChannelId, crate::meetup::Error> {
     let mut tx = db_connection.begin().await?;
     // Check if the channel already exists
@@ -682,40 +826,15 @@ async fn sync_channel_impl(
         return Ok(channel_id);
     }
     // The channel doesn't exist yet -> try to create it
-    // The @everyone role has the same id as the guild
-    let permission_overwrites = match channel_type {
-        ChannelType::Text => vec![
-            PermissionOverwrite {
-                allow: Permissions::empty(),
-                deny: Permissions::VIEW_CHANNEL,
-                kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
-            },
-            PermissionOverwrite {
-                allow: Permissions::VIEW_CHANNEL,
-                deny: Permissions::empty(),
-                kind: PermissionOverwriteType::Member(bot_id),
-            },
-        ],
-        ChannelType::Voice => vec![
-            PermissionOverwrite {
-                allow: Permissions::empty(),
-                deny: Permissions::CONNECT,
-                kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
-            },
-            PermissionOverwrite {
-                allow: Permissions::CONNECT,
-                deny: Permissions::empty(),
-                kind: PermissionOverwriteType::Member(bot_id),
-            },
-        ],
-    };
+    let permission_overwrites = initial_permission_overwrites(channel_type, bot_id, guild_config);
     let channel_builder = CreateChannel::new(channel_name)
         .kind(match channel_type {
             ChannelType::Text => serenity::model::channel::ChannelType::Text,
             ChannelType::Voice => serenity::model::channel::ChannelType::Voice,
         })
         .permissions(permission_overwrites);
-    let temp_channel = GUILD_ID
+    let temp_channel = guild_config
+        .guild_id
         .create_channel(discord_api.http(), channel_builder)
         .await?;
     println!(
@@ -726,8 +845,10 @@ async fn sync_channel_impl(
     let insert_query = match channel_type {
         ChannelType::Text => {
             sqlx::query!(
-                "INSERT INTO event_series_text_channel (discord_id) VALUES ($1)",
-                temp_channel.id.get() as i64
+                "INSERT INTO event_series_text_channel (discord_id, event_series_id) VALUES ($1, \
+                 $2)",
+                temp_channel.id.get() as i64,
+                event_series_id.0
             )
         }
         ChannelType::Voice => {
@@ -764,6 +885,14 @@ async fn sync_channel_impl(
     match any_err {
         Some(err) => {
             println!("Trying to delete temporary channel");
+            let auditor = Auditor::new(
+                AuditSource::Task("Discord sync"),
+                db_connection,
+                discord_api,
+                guild_config,
+            );
+            let audit_entry =
+                AuditEntry::channel_deleted(temp_channel.id, Some(&temp_channel.name));
             match discord_api
                 .http()
                 .delete_channel(
@@ -772,21 +901,22 @@ async fn sync_channel_impl(
                 )
                 .await
             {
-                Ok(_) => println!("Successfully deleted temporary channel"),
-                Err(_) => {
+                Ok(_) => {
+                    println!("Successfully deleted temporary channel");
+                    auditor.record(audit_entry).await;
+                }
+                Err(delete_err) => {
+                    auditor.record(audit_entry.failed(&delete_err)).await;
                     eprintln!(
                         "Could not delete temporary channel {}",
                         temp_channel.id.get()
                     );
-                    // Try to persist the information to Redis that we have an orphaned channel now
-                    let redis_orphaned_channels_key = match channel_type {
-                        ChannelType::Text => "orphaned_discord_channels",
-                        ChannelType::Voice => "orphaned_discord_voice_channels",
+                    // Try to record that we have an orphaned channel now
+                    let kind = match channel_type {
+                        ChannelType::Text => "text",
+                        ChannelType::Voice => "voice",
                     };
-                    match redis_connection
-                        .sadd(redis_orphaned_channels_key, temp_channel.id.get())
-                        .await
-                    {
+                    match record_orphaned_object(temp_channel.id.get(), kind, db_connection).await {
                         Err(_) => {
                             eprintln!(
                                 "Could not record orphaned channel {}",
@@ -807,49 +937,49 @@ async fn sync_channel_impl(
     }
 }
 
-// Makes sure that the Discord channel has the appropriate permission
-// overwrites for the channel's role and host role.
-// Specifically does not remove any additional permission overwrites
-// that the channel might have.
-async fn sync_channel_permissions(
-    channel_id: ChannelId,
+// The permission overwrites that sync_channel_permissions makes sure a
+// channel has. The overwrite for the series role is left out if there is no
+// role yet.
+pub(crate) fn channel_permission_overwrites(
     channel_type: ChannelType,
-    role_id: RoleId,
+    role_id: Option<RoleId>,
     discord_host_ids: &[UserId],
     bot_id: UserId,
-    discord_api: &super::CacheAndHttp,
-) -> Result<(), crate::meetup::Error> {
+    guild_config: &GuildConfig,
+) -> Vec<PermissionOverwrite> {
     // Make this channel private.
     // This is achieved by denying @everyone the VIEW_CHANNEL permission
     // but allowing the new role the VIEW_CHANNEL permission.
     // see: https://support.discordapp.com/hc/en-us/articles/206143877-How-do-I-set-up-a-Role-Exclusive-channel-
-    let permission_overwrites = match channel_type {
+    match channel_type {
         ChannelType::Text => {
             let mut permission_overwrites = vec![
                 PermissionOverwrite {
                     allow: Permissions::empty(),
                     deny: Permissions::VIEW_CHANNEL,
-                    kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
+                    kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
                 },
                 PermissionOverwrite {
                     allow: Permissions::VIEW_CHANNEL,
                     deny: Permissions::empty(),
                     kind: PermissionOverwriteType::Member(bot_id),
                 },
-                PermissionOverwrite {
-                    allow: Permissions::VIEW_CHANNEL,
+            ];
+            if let Some(role_id) = role_id {
+                permission_overwrites.push(PermissionOverwrite {
+                    allow: series_role_permissions(channel_type),
                     deny: Permissions::empty(),
                     kind: PermissionOverwriteType::Role(role_id),
-                },
-                PermissionOverwrite {
-                    allow: Permissions::VIEW_CHANNEL
-                        | Permissions::MENTION_EVERYONE
-                        | Permissions::MANAGE_MESSAGES,
-                    deny: Permissions::empty(),
-                    kind: PermissionOverwriteType::Role(ORGANISER_ID),
-                },
-            ];
-            if let Some(dice_roller_bot_id) = DICE_ROLLER_BOT_ID {
+                });
+            }
+            permission_overwrites.push(PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL
+                    | Permissions::MENTION_EVERYONE
+                    | Permissions::MANAGE_MESSAGES,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Role(guild_config.organiser_role_id),
+            });
+            if let Some(dice_roller_bot_id) = guild_config.dice_roller_bot_role_id {
                 permission_overwrites.push(PermissionOverwrite {
                     allow: Permissions::VIEW_CHANNEL,
                     deny: Permissions::empty(),
@@ -872,28 +1002,30 @@ async fn sync_channel_permissions(
                 PermissionOverwrite {
                     allow: Permissions::empty(),
                     deny: Permissions::VIEW_CHANNEL | Permissions::CONNECT,
-                    kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
+                    kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
                 },
                 PermissionOverwrite {
                     allow: Permissions::VIEW_CHANNEL | Permissions::CONNECT,
                     deny: Permissions::empty(),
                     kind: PermissionOverwriteType::Member(bot_id),
                 },
-                PermissionOverwrite {
-                    allow: Permissions::VIEW_CHANNEL | Permissions::CONNECT,
+            ];
+            if let Some(role_id) = role_id {
+                permission_overwrites.push(PermissionOverwrite {
+                    allow: series_role_permissions(channel_type),
                     deny: Permissions::empty(),
                     kind: PermissionOverwriteType::Role(role_id),
-                },
-                PermissionOverwrite {
-                    allow: Permissions::VIEW_CHANNEL
-                        | Permissions::CONNECT
-                        | Permissions::MOVE_MEMBERS
-                        | Permissions::MUTE_MEMBERS
-                        | Permissions::DEAFEN_MEMBERS,
-                    deny: Permissions::empty(),
-                    kind: PermissionOverwriteType::Role(ORGANISER_ID),
-                },
-            ];
+                });
+            }
+            permission_overwrites.push(PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL
+                    | Permissions::CONNECT
+                    | Permissions::MOVE_MEMBERS
+                    | Permissions::MUTE_MEMBERS
+                    | Permissions::DEAFEN_MEMBERS,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Role(guild_config.organiser_role_id),
+            });
             for &host_id in discord_host_ids {
                 permission_overwrites.push(PermissionOverwrite {
                     allow: Permissions::CONNECT
@@ -907,10 +1039,46 @@ async fn sync_channel_permissions(
             }
             permission_overwrites
         }
-    };
+    }
+}
+
+pub(crate) fn series_role_permissions(channel_type: ChannelType) -> Permissions {
+    match channel_type {
+        ChannelType::Text => Permissions::VIEW_CHANNEL,
+        ChannelType::Voice => Permissions::VIEW_CHANNEL | Permissions::CONNECT,
+    }
+}
+
+// The permissions that sync_role_assignments_permissions adds to the hosts'
+// existing permission overwrites
+pub(crate) fn host_channel_permissions(channel_type: ChannelType) -> Permissions {
+    match channel_type {
+        ChannelType::Text => {
+            Permissions::VIEW_CHANNEL | Permissions::MENTION_EVERYONE | Permissions::MANAGE_MESSAGES
+        }
+        ChannelType::Voice => {
+            Permissions::VIEW_CHANNEL
+                | Permissions::CONNECT
+                | Permissions::MUTE_MEMBERS
+                | Permissions::DEAFEN_MEMBERS
+                | Permissions::MOVE_MEMBERS
+                | Permissions::PRIORITY_SPEAKER
+        }
+    }
+}
+
+// Makes sure that the Discord channel has the appropriate permission
+// overwrites for the channel's role and host role.
+// Specifically does not remove any additional permission overwrites
+// that the channel might have.
+async fn sync_channel_permissions(
+    channel_id: ChannelId,
+    permission_overwrites: Vec<PermissionOverwrite>,
+    discord_api: &super::CacheAndHttp,
+    auditor: &Auditor<'_>,
+) -> Result<(), crate::meetup::Error> {
     for permission_overwrite in permission_overwrites {
-        channel_id
-            .create_permission(discord_api.http(), permission_overwrite)
+        super::set_permission_overwrite(discord_api, channel_id, permission_overwrite, auditor)
             .await?;
     }
     Ok(())
@@ -920,21 +1088,29 @@ async fn sync_channel_permissions(
 /// Returns `Ok(true)` if the role was newly assigned, `Ok(false)` if they already had it.
 async fn try_assign_role(
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
     user_id: UserId,
     role_id: RoleId,
     reason: &str,
+    auditor: &Auditor<'_>,
 ) -> Result<bool, ()> {
     match user_id.to_user(discord_api).await {
-        Ok(user) => match user.has_role(discord_api, GUILD_ID, role_id).await {
+        Ok(user) => match user
+            .has_role(discord_api, guild_config.guild_id, role_id)
+            .await
+        {
             Ok(has_role) => {
                 if !has_role {
                     match discord_api
                         .http()
-                        .add_member_role(GUILD_ID, user_id, role_id, Some(reason))
+                        .add_member_role(guild_config.guild_id, user_id, role_id, Some(reason))
                         .await
                     {
                         Ok(_) => {
                             println!("Assigned user {} to role {}", user_id, role_id);
+                            auditor
+                                .record(AuditEntry::role_added(user_id, role_id))
+                                .await;
                             Ok(true)
                         }
                         Err(err) => {
@@ -942,6 +1118,9 @@ async fn try_assign_role(
                                 "Could not assign user {} to role {}: {}",
                                 user_id, role_id, err
                             );
+                            auditor
+                                .record(AuditEntry::role_added(user_id, role_id).failed(&err))
+                                .await;
                             Err(())
                         }
                     }
@@ -964,35 +1143,58 @@ async fn try_assign_role(
     }
 }
 
-async fn sync_role_assignments_permissions(
-    discord_user_ids: &[UserId],
-    discord_host_ids: &[UserId],
+// Don't automatically assign the user role to user that have been
+// manually removed from a channel
+pub(crate) async fn removed_discord_user_ids(
     series_id: db::EventSeriesId,
-    channel_id: ChannelId,
-    voice_channel_id: Option<ChannelId>,
-    user_role: RoleId,
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
-) -> Result<(), crate::meetup::Error> {
-    // Check whether any users have manually removed roles and don't add them back
-    // Don't automatically assign the user role to user that have been
-    // manually removed from a channel
-    let ignore_discord_user_ids = sqlx::query!(
+) -> Result<Vec<UserId>, crate::meetup::Error> {
+    let removed_user_ids = sqlx::query!(
         r#"SELECT member_id FROM event_series_removed_user WHERE event_series_id = $1"#,
         series_id.0
     )
     .map(|row| UserId::new(row.member_id as u64))
     .fetch_all(db_connection)
     .await?;
-    // Don't automatically assign the host role to users that have either
-    // been manually removed as a host or as a user from a channel
-    let ignore_discord_host_ids = sqlx::query!(
+    Ok(removed_user_ids)
+}
+
+// Don't automatically assign the host role to users that have either
+// been manually removed as a host or as a user from a channel
+pub(crate) async fn removed_discord_host_ids(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<UserId>, crate::meetup::Error> {
+    let removed_host_ids = sqlx::query!(
         r#"SELECT member_id FROM event_series_removed_host WHERE event_series_id = $1"#,
         series_id.0
     )
     .map(|row| UserId::new(row.member_id as u64))
     .fetch_all(db_connection)
     .await?;
+    Ok(removed_host_ids)
+}
+
+async fn sync_role_assignments_permissions(
+    discord_user_ids: &[UserId],
+    discord_host_ids: &[UserId],
+    series_id: db::EventSeriesId,
+    channel_id: ChannelId,
+    voice_channel_id: Option<ChannelId>,
+    user_role: RoleId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+) -> Result<(), crate::meetup::Error> {
+    let auditor = Auditor::new(
+        AuditSource::Task("Discord sync"),
+        db_connection,
+        discord_api,
+        guild_config,
+    );
+    // Check whether any users have manually removed roles and don't add them back
+    let ignore_discord_user_ids = removed_discord_user_ids(series_id, db_connection).await?;
+    let ignore_discord_host_ids = removed_discord_host_ids(series_id, db_connection).await?;
     // Assign the role to the Discord users
     let mut newly_added_user_ids = vec![];
     for &user_id in discord_user_ids {
@@ -1001,9 +1203,11 @@ async fn sync_role_assignments_permissions(
         }
         if let Ok(true) = try_assign_role(
             discord_api,
+            guild_config,
             user_id,
             user_role,
             "Automatic role assignment due to event participation",
+            &auditor,
         )
         .await
         {
@@ -1017,14 +1221,13 @@ async fn sync_role_assignments_permissions(
             continue;
         }
         // Assign text channel permissions
-        let new_permissions = Permissions::VIEW_CHANNEL
-            | Permissions::MENTION_EVERYONE
-            | Permissions::MANAGE_MESSAGES;
+        let new_permissions = host_channel_permissions(ChannelType::Text);
         match crate::discord::add_channel_user_permissions(
             discord_api,
             channel_id,
             host_id,
             new_permissions,
+            &auditor,
         )
         .await
         {
@@ -1040,17 +1243,13 @@ async fn sync_role_assignments_permissions(
         }
         // Also assign rights in the possibly existing voice channel
         if let Some(voice_channel_id) = voice_channel_id {
-            let new_permissions = Permissions::VIEW_CHANNEL
-                | Permissions::CONNECT
-                | Permissions::MUTE_MEMBERS
-                | Permissions::DEAFEN_MEMBERS
-                | Permissions::MOVE_MEMBERS
-                | Permissions::PRIORITY_SPEAKER;
+            let new_permissions = host_channel_permissions(ChannelType::Voice);
             match crate::discord::add_channel_user_permissions(
                 discord_api,
                 voice_channel_id,
                 host_id,
                 new_permissions,
+                &auditor,
             )
             .await
             {
@@ -1071,9 +1270,11 @@ async fn sync_role_assignments_permissions(
         // Also assign the role to the host
         let _ = try_assign_role(
             discord_api,
+            guild_config,
             host_id,
             user_role,
             "Automatic role assignment due to being a host",
+            &auditor,
         )
         .await;
     }
@@ -1107,12 +1308,11 @@ async fn sync_role_assignments_permissions(
     Ok(())
 }
 
-async fn sync_game_master_role(
+// Finds all Discord users that are a host for any of the events in this series
+pub(crate) async fn series_discord_host_ids(
     event_series_id: db::EventSeriesId,
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
-) -> Result<(), crate::meetup::Error> {
-    // Find all Discord users that are a host for any of the events in this series
+) -> Result<Vec<UserId>, crate::meetup::Error> {
     let discord_host_ids = sqlx::query!(
         r#"
         SELECT member.discord_id as "discord_id!"
@@ -1126,27 +1326,69 @@ async fn sync_game_master_role(
     .map(|row| UserId::new(row.discord_id as u64))
     .fetch_all(db_connection)
     .await?;
+    Ok(discord_host_ids)
+}
+
+async fn sync_game_master_role(
+    event_series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+) -> Result<(), crate::meetup::Error> {
+    let discord_host_ids = series_discord_host_ids(event_series_id, db_connection).await?;
+    let auditor = Auditor::new(
+        AuditSource::Task("Discord sync"),
+        db_connection,
+        discord_api,
+        guild_config,
+    );
     // Assign the Game Master role to the hosts
     for host_id in discord_host_ids {
         match host_id.to_user(discord_api).await {
-            Ok(user) => match user.has_role(discord_api, GUILD_ID, GAME_MASTER_ID).await {
+            Ok(user) => match user
+                .has_role(
+                    discord_api,
+                    guild_config.guild_id,
+                    guild_config.game_master_role_id,
+                )
+                .await
+            {
                 Ok(has_role) => {
                     if !has_role {
                         match discord_api
                             .http()
                             .add_member_role(
-                                GUILD_ID,
+                                guild_config.guild_id,
                                 host_id,
-                                GAME_MASTER_ID,
+                                guild_config.game_master_role_id,
                                 Some("Automatic role assignment due to being a game master"),
                             )
                             .await
                         {
-                            Ok(_) => println!("Assigned user {} to the game master role", host_id),
-                            Err(err) => eprintln!(
-                                "Could not assign user {} to the game master role: {}",
-                                host_id, err
-                            ),
+                            Ok(_) => {
+                                println!("Assigned user {} to the game master role", host_id);
+                                auditor
+                                    .record(AuditEntry::role_added(
+                                        host_id,
+                                        guild_config.game_master_role_id,
+                                    ))
+                                    .await;
+                            }
+                            Err(err) => {
+                                eprintln!(
+                                    "Could not assign user {} to the game master role: {}",
+                                    host_id, err
+                                );
+                                auditor
+                                    .record(
+                                        AuditEntry::role_added(
+                                            host_id,
+                                            guild_config.game_master_role_id,
+                                        )
+                                        .failed(&err),
+                                    )
+                                    .await;
+                            }
                         }
                     }
                 }
@@ -1161,32 +1403,44 @@ async fn sync_game_master_role(
     Ok(())
 }
 
-async fn sync_channel_topic(
-    channel_id: ChannelId,
+pub(crate) fn channel_topic(
     next_event: &db::Event,
+    channel_settings: &ChannelSettings,
     swissrpg_event_series_id: Option<uuid::Uuid>,
     swissrpg_base_url: &str,
-    discord_api: &super::CacheAndHttp,
-) -> Result<(), crate::meetup::Error> {
-    // Sync the topic
+) -> String {
     let next_session_time = next_event
         .time
         .with_timezone(&chrono_tz::Europe::Zurich)
         .format("%d.%m.%Y %H:%M")
         .to_string();
-    let topic = match swissrpg_event_series_id {
-        Some(swissrpg_event_series_id) => {
-            let swissrpg_url = next_event
-                .swissrpg_event
-                .as_ref()
-                .map(|swissrpg_event| swissrpg_event.url.clone())
-                .unwrap_or_else(|| {
-                    swissrpg_event_series_url(swissrpg_base_url, &swissrpg_event_series_id)
-                });
-            format!("Next Session: {} {}", next_session_time, swissrpg_url)
-        }
-        None => format!("Next Session: {}", next_session_time),
-    };
+    let swissrpg_url = swissrpg_event_series_id.map(|swissrpg_event_series_id| {
+        next_event
+            .swissrpg_event
+            .as_ref()
+            .map(|swissrpg_event| swissrpg_event.url.clone())
+            .unwrap_or_else(|| {
+                swissrpg_event_series_url(swissrpg_base_url, &swissrpg_event_series_id)
+            })
+    });
+    channel_settings.topic(&next_session_time, swissrpg_url.as_deref())
+}
+
+async fn sync_channel_topic(
+    channel_id: ChannelId,
+    next_event: &db::Event,
+    channel_settings: &ChannelSettings,
+    swissrpg_event_series_id: Option<uuid::Uuid>,
+    swissrpg_base_url: &str,
+    discord_api: &super::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    // Sync the topic
+    let topic = channel_topic(
+        next_event,
+        channel_settings,
+        swissrpg_event_series_id,
+        swissrpg_base_url,
+    );
     let channel = channel_id.to_channel(discord_api).await?;
     if let serenity::model::channel::Channel::Guild(channel) = channel {
         let topic_needs_update = if let Some(current_topic) = channel.topic {
@@ -1206,10 +1460,11 @@ async fn sync_channel_topic(
 async fn sync_channel_category(
     series_id: db::EventSeriesId,
     channel_type: ChannelType,
-    next_event: &db::Event,
+    special_category: Option<ChannelId>,
     channel_id: ChannelId,
     db_connection: &sqlx::PgPool,
     discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<(), crate::meetup::Error> {
     // Sync the category
     let event_series_type = sqlx::query_scalar!(
@@ -1218,38 +1473,16 @@ async fn sync_channel_category(
     )
     .fetch_one(db_connection)
     .await?;
-    let mut categories = if let Some(special_category) = next_event.discord_category {
-        vec![special_category]
-    } else {
-        vec![]
-    };
-    match channel_type {
-        ChannelType::Text => match event_series_type.as_str() {
-            "campaign" => categories.extend_from_slice(CAMPAIGN_CATEGORY_IDS),
-            "adventure" => categories.extend_from_slice(ONE_SHOT_CATEGORY_IDS),
-            _ => {
-                eprintln!(
-                    "Event series {} does not have a type of 'campaign' or 'adventure'",
-                    series_id.0
-                );
-                categories.extend_from_slice(CAMPAIGN_CATEGORY_IDS)
-            }
-        },
-        ChannelType::Voice => categories.extend_from_slice(VOICE_CHANNELS_CATEGORY_IDS),
-    }
+    let categories = channel_categories(
+        series_id,
+        &event_series_type,
+        channel_type,
+        special_category,
+        guild_config,
+    );
     let channel = channel_id.to_channel(discord_api).await?;
     if let serenity::model::channel::Channel::Guild(channel) = channel {
-        let category_needs_update = match channel.parent_id {
-            Some(channel_category) => {
-                if let Some(special_category) = next_event.discord_category {
-                    special_category != channel_category
-                } else {
-                    !categories.contains(&channel_category)
-                }
-            }
-            None => true,
-        };
-        if category_needs_update {
+        if category_needs_update(channel.parent_id, special_category, &categories) {
             // Try the categories in order and put the channel in the first
             // one that works. Meetup has an undocumented limit of 50 channels
             // per category, so an error will be returned if the category is full.
@@ -1268,3 +1501,53 @@ async fn sync_channel_category(
     }
     Ok(())
 }
+
+// The categories a channel may be in, in order of preference. The special
+// category is the one chosen by a host or a [category] shortcode.
+pub(crate) fn channel_categories(
+    series_id: db::EventSeriesId,
+    event_series_type: &str,
+    channel_type: ChannelType,
+    special_category: Option<ChannelId>,
+    guild_config: &GuildConfig,
+) -> Vec<ChannelId> {
+    let mut categories = if let Some(special_category) = special_category {
+        vec![special_category]
+    } else {
+        vec![]
+    };
+    match channel_type {
+        ChannelType::Text => match event_series_type {
+            "campaign" => categories.extend_from_slice(&guild_config.campaign_category_ids),
+            "adventure" => categories.extend_from_slice(&guild_config.one_shot_category_ids),
+            _ => {
+                eprintln!(
+                    "Event series {} does not have a type of 'campaign' or 'adventure'",
+                    series_id.0
+                );
+                categories.extend_from_slice(&guild_config.campaign_category_ids)
+            }
+        },
+        ChannelType::Voice => {
+            categories.extend_from_slice(&guild_config.voice_channel_category_ids)
+        }
+    }
+    categories
+}
+
+pub(crate) fn category_needs_update(
+    current_category: Option<ChannelId>,
+    special_category: Option<ChannelId>,
+    categories: &[ChannelId],
+) -> bool {
+    match current_category {
+        Some(channel_category) => {
+            if let Some(special_category) = special_category {
+                special_category != channel_category
+            } else {
+                !categories.contains(&channel_category)
+            }
+        }
+        None => true,
+    }
+}
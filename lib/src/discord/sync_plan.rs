@@ -0,0 +1,773 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serenity::{
+    http::CacheHttp,
+    model::{
+        channel::{GuildChannel, PermissionOverwrite, PermissionOverwriteType},
+        id::{ChannelId, RoleId, UserId},
+        permissions::Permissions,
+    },
+};
+
+use super::{
+    guild_config::GuildConfig,
+    sync::{self, ChannelType},
+};
+use crate::{db, series_settings::ChannelSettings};
+
+// A dry run of sync::sync_discord: everything that a sync would change on
+// Discord right now. Computing the plan only reads from Discord and the
+// database, nothing gets created, assigned or edited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SyncPlan {
+    pub series: Vec<SeriesPlan>,
+    pub failed: Vec<FailedSeries>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeriesPlan {
+    pub series_id: i32,
+    pub series_name: String,
+    pub actions: Vec<SyncAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedSeries {
+    pub series_id: i32,
+    pub error: String,
+}
+
+// Channel IDs are None for channels that the sync would create
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum SyncAction {
+    // The series' channel was deleted, so it gets new channels and roles
+    ReviveSeries {
+        old_channel_id: u64,
+    },
+    CreateChannel {
+        channel_type: ChannelType,
+        name: String,
+        // The channel stored in the database which no longer exists on Discord
+        replaces: Option<u64>,
+    },
+    CreateRole {
+        name: String,
+        // The role stored in the database which no longer exists on Discord
+        replaces: Option<u64>,
+    },
+    AssignRole {
+        user_id: u64,
+        // None for the series role if it would be newly created
+        role_id: Option<u64>,
+        role_name: String,
+    },
+    EditPermissionOverwrite {
+        channel_type: ChannelType,
+        channel_id: Option<u64>,
+        target: OverwriteTarget,
+        allow_added: Vec<String>,
+        allow_removed: Vec<String>,
+        deny_added: Vec<String>,
+        deny_removed: Vec<String>,
+    },
+    EditTopic {
+        channel_id: Option<u64>,
+        old_topic: Option<String>,
+        new_topic: String,
+    },
+    MoveChannel {
+        channel_type: ChannelType,
+        channel_id: Option<u64>,
+        old_category_id: Option<u64>,
+        new_category_id: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OverwriteTarget {
+    Role { role_id: u64 },
+    // The series role if it would be newly created
+    NewSeriesRole,
+    Member { user_id: u64 },
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty() && self.failed.is_empty()
+    }
+
+    pub fn num_actions(&self) -> usize {
+        self.series.iter().map(|series| series.actions.len()).sum()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "Discord is in sync, a sync would not change anything".to_string();
+        }
+        let mut summary = format!(
+            "A sync would make {} change(s) in {} event series\n",
+            self.num_actions(),
+            self.series.len()
+        );
+        for series in &self.series {
+            summary += &format!(
+                "\n**{}** (series {})\n",
+                series.series_name, series.series_id
+            );
+            for action in &series.actions {
+                summary += &format!("- {}\n", action);
+            }
+        }
+        for failed in &self.failed {
+            summary += &format!(
+                "\nCould not plan series {}: {}\n",
+                failed.series_id,
+                failed.error.lines().next().unwrap_or("")
+            );
+        }
+        summary
+    }
+}
+
+fn channel_mention(channel_type: ChannelType, channel_id: Option<u64>) -> String {
+    match (channel_type, channel_id) {
+        (_, Some(channel_id)) => format!("<#{}>", channel_id),
+        (ChannelType::Text, None) => "the new text channel".to_string(),
+        (ChannelType::Voice, None) => "the new voice channel".to_string(),
+    }
+}
+
+fn permission_list(prefix: &str, permissions: &[String]) -> Option<String> {
+    if permissions.is_empty() {
+        None
+    } else {
+        Some(format!("{} {}", prefix, permissions.join(", ")))
+    }
+}
+
+impl std::fmt::Display for SyncAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncAction::ReviveSeries { old_channel_id } => write!(
+                f,
+                "revive the series, whose channel {} was deleted, and welcome the players back",
+                old_channel_id
+            ),
+            SyncAction::CreateChannel {
+                channel_type,
+                name,
+                replaces,
+            } => {
+                let kind = match channel_type {
+                    ChannelType::Text => "text",
+                    ChannelType::Voice => "voice",
+                };
+                write!(f, "create {} channel \"{}\"", kind, name)?;
+                if let Some(replaces) = replaces {
+                    write!(f, " (replacing deleted channel {})", replaces)?;
+                }
+                Ok(())
+            }
+            SyncAction::CreateRole { name, replaces } => {
+                write!(f, "create role \"{}\"", name)?;
+                if let Some(replaces) = replaces {
+                    write!(f, " (replacing deleted role {})", replaces)?;
+                }
+                Ok(())
+            }
+            SyncAction::AssignRole {
+                user_id, role_name, ..
+            } => write!(f, "assign role \"{}\" to <@{}>", role_name, user_id),
+            SyncAction::EditPermissionOverwrite {
+                channel_type,
+                channel_id,
+                target,
+                allow_added,
+                allow_removed,
+                deny_added,
+                deny_removed,
+            } => {
+                let target = match target {
+                    OverwriteTarget::Role { role_id } => format!("<@&{}>", role_id),
+                    OverwriteTarget::NewSeriesRole => "the new series role".to_string(),
+                    OverwriteTarget::Member { user_id } => format!("<@{}>", user_id),
+                };
+                let changes: Vec<_> = [
+                    permission_list("allow", allow_added),
+                    permission_list("no longer allow", allow_removed),
+                    permission_list("deny", deny_added),
+                    permission_list("no longer deny", deny_removed),
+                ]
+                .into_iter()
+                .flatten()
+                .collect();
+                write!(
+                    f,
+                    "in {} {} for {}",
+                    channel_mention(*channel_type, *channel_id),
+                    changes.join("; "),
+                    target
+                )
+            }
+            SyncAction::EditTopic { new_topic, .. } => {
+                write!(f, "set the topic to \"{}\"", new_topic)
+            }
+            SyncAction::MoveChannel {
+                channel_type,
+                channel_id,
+                new_category_id,
+                ..
+            } => write!(
+                f,
+                "move {} to category <#{}>",
+                channel_mention(*channel_type, *channel_id),
+                new_category_id
+            ),
+        }
+    }
+}
+
+// Computes the plan for all event series, mirroring sync::sync_discord
+pub async fn plan_sync_discord(
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+    bot_id: UserId,
+    swissrpg_base_url: &str,
+) -> Result<SyncPlan, crate::meetup::Error> {
+    let guild_roles: HashMap<RoleId, String> = discord_api
+        .http()
+        .get_guild_roles(guild_config.guild_id)
+        .await?
+        .into_iter()
+        .map(|role| (role.id, role.name))
+        .collect();
+    let event_series_ids = sqlx::query!("SELECT id FROM event_series ORDER BY id")
+        .map(|row| db::EventSeriesId(row.id))
+        .fetch_all(db_connection)
+        .await?;
+    let mut plan = SyncPlan::default();
+    for series_id in event_series_ids {
+        match plan_event_series(
+            series_id,
+            &guild_roles,
+            db_connection,
+            discord_api,
+            guild_config,
+            bot_id,
+            swissrpg_base_url,
+        )
+        .await
+        {
+            Ok(Some(series_plan)) if !series_plan.actions.is_empty() => {
+                plan.series.push(series_plan)
+            }
+            Ok(_) => (),
+            Err(err) => plan.failed.push(FailedSeries {
+                series_id: series_id.0,
+                error: err.to_string(),
+            }),
+        }
+    }
+    Ok(plan)
+}
+
+// Follows the same steps as sync::sync_event_series
+async fn plan_event_series(
+    series_id: db::EventSeriesId,
+    guild_roles: &HashMap<RoleId, String>,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+    bot_id: UserId,
+    swissrpg_base_url: &str,
+) -> Result<Option<SeriesPlan>, crate::meetup::Error> {
+    // Only event series that have events in the future get synced
+    let next_event = match db::get_next_event_in_series(db_connection, series_id).await? {
+        Some(event) => event,
+        None => return Ok(None),
+    };
+    if sync::series_channel_is_archived(series_id, db_connection).await? {
+        return Ok(None);
+    }
+    let series = sqlx::query!(
+        r#"SELECT "type", swissrpg_event_series_id, discord_text_channel_id, discord_voice_channel_id, discord_role_id,
+            (SELECT COUNT(*) FROM event WHERE event_series_id = event_series.id) AS "num_events!",
+            (SELECT deleted IS NOT NULL FROM event_series_text_channel WHERE discord_id = event_series.discord_text_channel_id) AS text_channel_deleted
+        FROM event_series
+        WHERE id = $1"#,
+        series_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    // The sync upgrades series with more than one event to campaigns
+    let event_series_type = if series.num_events > 1 {
+        "campaign"
+    } else {
+        series.r#type.as_str()
+    };
+    let channel_settings = ChannelSettings::load(series_id, db_connection).await?;
+    let series_name = channel_settings.channel_name(&next_event)?;
+    let discord_guest_ids = sync::event_discord_guest_ids(next_event.id, db_connection).await?;
+    let discord_host_ids = sync::event_discord_host_ids(next_event.id, db_connection).await?;
+    let ignore_discord_user_ids = sync::removed_discord_user_ids(series_id, db_connection).await?;
+    let ignore_discord_host_ids = sync::removed_discord_host_ids(series_id, db_connection).await?;
+    let mut actions = vec![];
+
+    // A series whose channel was deleted gets revived with a new channel
+    let stored_text_channel_id = match series.discord_text_channel_id {
+        Some(old_channel_id) if series.text_channel_deleted == Some(true) => {
+            actions.push(SyncAction::ReviveSeries {
+                old_channel_id: old_channel_id as u64,
+            });
+            None
+        }
+        stored_text_channel_id => stored_text_channel_id,
+    };
+
+    // Game master role
+    let mut game_master_ids = sync::series_discord_host_ids(series_id, db_connection).await?;
+    // Hosts of several sessions show up more than once
+    game_master_ids.sort();
+    game_master_ids.dedup();
+    for host_id in game_master_ids {
+        if let Some(false) = member_has_role(
+            host_id,
+            guild_config.game_master_role_id,
+            discord_api,
+            guild_config,
+        )
+        .await
+        {
+            actions.push(SyncAction::AssignRole {
+                user_id: host_id.get(),
+                role_id: Some(guild_config.game_master_role_id.get()),
+                role_name: role_name(guild_roles, guild_config.game_master_role_id),
+            });
+        }
+    }
+
+    // Channels and the series role
+    let text_channel = plan_channel(
+        ChannelType::Text,
+        series_name,
+        stored_text_channel_id,
+        discord_api,
+        &mut actions,
+    )
+    .await?;
+    let guest_role_name = sync::guest_role_name(series_name, &discord_host_ids, discord_api).await;
+    let role_id = match series.discord_role_id.map(|id| RoleId::new(id as u64)) {
+        Some(role_id) if guild_roles.contains_key(&role_id) => Some(role_id),
+        stored_role_id => {
+            actions.push(SyncAction::CreateRole {
+                name: guest_role_name.clone(),
+                replaces: stored_role_id.map(RoleId::get),
+            });
+            None
+        }
+    };
+    let voice_channel = if next_event.is_online {
+        let voice_channel = plan_channel(
+            ChannelType::Voice,
+            series_name,
+            series.discord_voice_channel_id,
+            discord_api,
+            &mut actions,
+        )
+        .await?;
+        Some(voice_channel)
+    } else {
+        None
+    };
+
+    // Permission overwrites
+    let mut channels = vec![(ChannelType::Text, &text_channel)];
+    if let Some(voice_channel) = &voice_channel {
+        channels.push((ChannelType::Voice, voice_channel));
+    }
+    for &(channel_type, channel) in &channels {
+        let current_overwrites = match channel {
+            Some(channel) => &channel.permission_overwrites,
+            None => &sync::initial_permission_overwrites(channel_type, bot_id, guild_config),
+        };
+        let mut overwrites = OverwriteState::new(current_overwrites);
+        // sync_channel_permissions replaces these overwrites
+        for overwrite in sync::channel_permission_overwrites(
+            channel_type,
+            role_id,
+            &discord_host_ids,
+            bot_id,
+            guild_config,
+        ) {
+            overwrites.set_overwrite(&overwrite);
+        }
+        if role_id.is_none() {
+            overwrites.set(
+                OverwriteTarget::NewSeriesRole,
+                sync::series_role_permissions(channel_type),
+                Permissions::empty(),
+            );
+        }
+        // sync_role_assignments_permissions adds to the hosts' overwrites
+        for &host_id in &discord_host_ids {
+            if !ignore_discord_host_ids.contains(&host_id) {
+                overwrites.allow(
+                    OverwriteTarget::Member {
+                        user_id: host_id.get(),
+                    },
+                    sync::host_channel_permissions(channel_type),
+                );
+            }
+        }
+        actions.extend(overwrites.changes(channel_type, channel.as_ref().map(|c| c.id.get())));
+    }
+
+    // Role assignments
+    let mut assigned_user_ids = HashSet::new();
+    let guests = discord_guest_ids
+        .iter()
+        .filter(|user_id| !ignore_discord_user_ids.contains(user_id));
+    let hosts = discord_host_ids
+        .iter()
+        .filter(|host_id| !ignore_discord_host_ids.contains(host_id));
+    for &user_id in guests.chain(hosts) {
+        if !assigned_user_ids.insert(user_id) {
+            continue;
+        }
+        let needs_role = match role_id {
+            Some(role_id) => {
+                member_has_role(user_id, role_id, discord_api, guild_config).await == Some(false)
+            }
+            None => true,
+        };
+        if needs_role {
+            actions.push(SyncAction::AssignRole {
+                user_id: user_id.get(),
+                role_id: role_id.map(RoleId::get),
+                role_name: guest_role_name.clone(),
+            });
+        }
+    }
+
+    // Topic
+    let new_topic = sync::channel_topic(
+        &next_event,
+        &channel_settings,
+        series.swissrpg_event_series_id,
+        swissrpg_base_url,
+    );
+    let old_topic = text_channel
+        .as_ref()
+        .and_then(|channel| channel.topic.clone());
+    if old_topic.as_ref() != Some(&new_topic) {
+        actions.push(SyncAction::EditTopic {
+            channel_id: text_channel.as_ref().map(|channel| channel.id.get()),
+            old_topic,
+            new_topic,
+        });
+    }
+
+    // Categories
+    for (channel_type, channel) in channels {
+        let special_category = channel_settings.special_category(channel_type, &next_event);
+        let categories = sync::channel_categories(
+            series_id,
+            event_series_type,
+            channel_type,
+            special_category,
+            guild_config,
+        );
+        let old_category_id = channel.as_ref().and_then(|channel| channel.parent_id);
+        if !sync::category_needs_update(old_category_id, special_category, &categories) {
+            continue;
+        }
+        // The sync tries the categories in order, the first one is the
+        // expected outcome
+        if let Some(new_category_id) = categories.first() {
+            actions.push(SyncAction::MoveChannel {
+                channel_type,
+                channel_id: channel.as_ref().map(|channel| channel.id.get()),
+                old_category_id: old_category_id.map(ChannelId::get),
+                new_category_id: new_category_id.get(),
+            });
+        }
+    }
+
+    Ok(Some(SeriesPlan {
+        series_id: series_id.0,
+        series_name: series_name.to_string(),
+        actions,
+    }))
+}
+
+// Returns the channel if it exists on Discord, otherwise records that it
+// would be created
+async fn plan_channel(
+    channel_type: ChannelType,
+    channel_name: &str,
+    stored_channel_id: Option<i64>,
+    discord_api: &super::CacheAndHttp,
+    actions: &mut Vec<SyncAction>,
+) -> Result<Option<GuildChannel>, crate::meetup::Error> {
+    if let Some(channel_id) = stored_channel_id {
+        let channel_id = ChannelId::new(channel_id as u64);
+        match channel_id.to_channel(discord_api).await {
+            Ok(serenity::model::channel::Channel::Guild(channel)) => return Ok(Some(channel)),
+            Ok(_) => {
+                return Err(simple_error::SimpleError::new(format!(
+                    "Channel {} is not a guild channel",
+                    channel_id
+                ))
+                .into())
+            }
+            Err(serenity::Error::Http(serenity::http::HttpError::UnsuccessfulRequest(
+                response,
+            ))) if response.status_code == serenity::http::StatusCode::NOT_FOUND => (),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    actions.push(SyncAction::CreateChannel {
+        channel_type,
+        name: channel_name.to_string(),
+        replaces: stored_channel_id.map(|id| id as u64),
+    });
+    Ok(None)
+}
+
+// None if the member could not be found, in which case the sync would skip
+// them as well
+async fn member_has_role(
+    user_id: UserId,
+    role_id: RoleId,
+    discord_api: &super::CacheAndHttp,
+    guild_config: &GuildConfig,
+) -> Option<bool> {
+    match guild_config.guild_id.member(discord_api, user_id).await {
+        Ok(member) => Some(member.roles.contains(&role_id)),
+        Err(err) => {
+            eprintln!("Could not find the member {}: {}", user_id, err);
+            None
+        }
+    }
+}
+
+fn role_name(guild_roles: &HashMap<RoleId, String>, role_id: RoleId) -> String {
+    guild_roles
+        .get(&role_id)
+        .cloned()
+        .unwrap_or_else(|| role_id.to_string())
+}
+
+fn permission_names(permissions: Permissions) -> Vec<String> {
+    permissions
+        .get_permission_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+// Simulates the permission overwrite changes on a single channel, such that
+// changes which a sync makes and then reverts don't show up in the plan
+struct OverwriteState {
+    current: HashMap<OverwriteTarget, (Permissions, Permissions)>,
+    new: HashMap<OverwriteTarget, (Permissions, Permissions)>,
+    // Keeps the order in which the overwrites were set
+    targets: Vec<OverwriteTarget>,
+}
+
+impl OverwriteState {
+    fn new(current_overwrites: &[PermissionOverwrite]) -> Self {
+        let current: HashMap<_, _> = current_overwrites
+            .iter()
+            .filter_map(|overwrite| {
+                overwrite_target(&overwrite.kind)
+                    .map(|target| (target, (overwrite.allow, overwrite.deny)))
+            })
+            .collect();
+        OverwriteState {
+            new: current.clone(),
+            current,
+            targets: vec![],
+        }
+    }
+
+    fn set(&mut self, target: OverwriteTarget, allow: Permissions, deny: Permissions) {
+        if !self.targets.contains(&target) {
+            self.targets.push(target);
+        }
+        self.new.insert(target, (allow, deny));
+    }
+
+    fn set_overwrite(&mut self, overwrite: &PermissionOverwrite) {
+        if let Some(target) = overwrite_target(&overwrite.kind) {
+            self.set(target, overwrite.allow, overwrite.deny);
+        }
+    }
+
+    fn allow(&mut self, target: OverwriteTarget, permissions: Permissions) {
+        let (allow, deny) = self
+            .new
+            .get(&target)
+            .copied()
+            .unwrap_or((Permissions::empty(), Permissions::empty()));
+        self.set(target, allow | permissions, deny);
+    }
+
+    fn changes(&self, channel_type: ChannelType, channel_id: Option<u64>) -> Vec<SyncAction> {
+        let empty = (Permissions::empty(), Permissions::empty());
+        self.targets
+            .iter()
+            .filter_map(|target| {
+                let (old_allow, old_deny) = self.current.get(target).copied().unwrap_or(empty);
+                let (new_allow, new_deny) = self.new.get(target).copied().unwrap_or(empty);
+                if old_allow == new_allow && old_deny == new_deny {
+                    return None;
+                }
+                Some(SyncAction::EditPermissionOverwrite {
+                    channel_type,
+                    channel_id,
+                    target: *target,
+                    allow_added: permission_names(new_allow - old_allow),
+                    allow_removed: permission_names(old_allow - new_allow),
+                    deny_added: permission_names(new_deny - old_deny),
+                    deny_removed: permission_names(old_deny - new_deny),
+                })
+            })
+            .collect()
+    }
+}
+
+fn overwrite_target(kind: &PermissionOverwriteType) -> Option<OverwriteTarget> {
+    match kind {
+        PermissionOverwriteType::Role(role_id) => Some(OverwriteTarget::Role {
+            role_id: role_id.get(),
+        }),
+        PermissionOverwriteType::Member(user_id) => Some(OverwriteTarget::Member {
+            user_id: user_id.get(),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overwrite_changes() {
+        let host = OverwriteTarget::Member { user_id: 2 };
+        let mut overwrites = OverwriteState::new(&[
+            PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL | Permissions::MANAGE_MESSAGES,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(UserId::new(2)),
+            },
+            PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(UserId::new(3)),
+            },
+        ]);
+        // Replacing an overwrite drops permissions which are not part of it
+        overwrites.set(host, Permissions::CONNECT, Permissions::empty());
+        // Unchanged overwrites are not part of the plan
+        overwrites.set(
+            OverwriteTarget::Member { user_id: 3 },
+            Permissions::VIEW_CHANNEL,
+            Permissions::empty(),
+        );
+        // Permissions that get removed and added back are not a change
+        overwrites.allow(host, Permissions::VIEW_CHANNEL);
+        assert_eq!(
+            overwrites.changes(ChannelType::Voice, Some(1)),
+            vec![SyncAction::EditPermissionOverwrite {
+                channel_type: ChannelType::Voice,
+                channel_id: Some(1),
+                target: host,
+                allow_added: vec!["Connect".to_string()],
+                allow_removed: vec!["Manage Messages".to_string()],
+                deny_added: vec![],
+                deny_removed: vec![],
+            }]
+        );
+    }
+
+    #[test]
+    fn plan_json() {
+        let plan = SyncPlan {
+            series: vec![SeriesPlan {
+                series_id: 7,
+                series_name: "Dragon Heist".to_string(),
+                actions: vec![
+                    SyncAction::CreateChannel {
+                        channel_type: ChannelType::Text,
+                        name: "Dragon Heist".to_string(),
+                        replaces: None,
+                    },
+                    SyncAction::AssignRole {
+                        user_id: 42,
+                        role_id: None,
+                        role_name: "[Host] Dragon Heist".to_string(),
+                    },
+                    SyncAction::EditPermissionOverwrite {
+                        channel_type: ChannelType::Text,
+                        channel_id: None,
+                        target: OverwriteTarget::NewSeriesRole,
+                        allow_added: vec!["View Channel".to_string()],
+                        allow_removed: vec![],
+                        deny_added: vec![],
+                        deny_removed: vec![],
+                    },
+                ],
+            }],
+            failed: vec![],
+        };
+        let json: serde_json::Value = serde_json::from_str(&plan.to_json().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "series": [{
+                    "series_id": 7,
+                    "series_name": "Dragon Heist",
+                    "actions": [
+                        {
+                            "action": "create_channel",
+                            "channel_type": "text",
+                            "name": "Dragon Heist",
+                            "replaces": null
+                        },
+                        {
+                            "action": "assign_role",
+                            "user_id": 42,
+                            "role_id": null,
+                            "role_name": "[Host] Dragon Heist"
+                        },
+                        {
+                            "action": "edit_permission_overwrite",
+                            "channel_type": "text",
+                            "channel_id": null,
+                            "target": { "kind": "new_series_role" },
+                            "allow_added": ["View Channel"],
+                            "allow_removed": [],
+                            "deny_added": [],
+                            "deny_removed": []
+                        }
+                    ]
+                }],
+                "failed": []
+            })
+        );
+        assert_eq!(plan.num_actions(), 3);
+        assert!(plan
+            .summary()
+            .contains("- in the new text channel allow View Channel for the new series role"));
+    }
+}
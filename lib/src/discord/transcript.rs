@@ -0,0 +1,416 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::GetMessages,
+    model::{
+        channel::Message,
+        id::{ChannelId, UserId},
+    },
+};
+
+use crate::db;
+
+// Replaces the author of messages whose data has been erased
+pub const ERASED_AUTHOR_NAME: &str = "Deleted User";
+
+// A channel's messages, exported before the channel gets deleted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub channel_id: u64,
+    pub channel_name: String,
+    pub event_series_id: Option<i32>,
+    pub exported_at: String, // RFC 3339
+    pub messages: Vec<TranscriptMessage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    pub id: u64,
+    pub author_id: u64,
+    pub author_name: String,
+    pub timestamp: String, // RFC 3339
+    pub edited_timestamp: Option<String>,
+    pub content: String,
+    pub pinned: bool,
+    pub attachments: Vec<TranscriptAttachment>,
+    pub embeds: Vec<TranscriptEmbed>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptAttachment {
+    pub filename: String,
+    pub url: String,
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEmbed {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub url: Option<String>,
+}
+
+impl From<&Message> for TranscriptMessage {
+    fn from(message: &Message) -> Self {
+        TranscriptMessage {
+            id: message.id.get(),
+            author_id: message.author.id.get(),
+            author_name: message.author.name.clone(),
+            timestamp: message.timestamp.to_rfc3339().unwrap_or_default(),
+            edited_timestamp: message
+                .edited_timestamp
+                .and_then(|timestamp| timestamp.to_rfc3339()),
+            content: message.content.clone(),
+            pinned: message.pinned,
+            attachments: message
+                .attachments
+                .iter()
+                .map(|attachment| TranscriptAttachment {
+                    filename: attachment.filename.clone(),
+                    url: attachment.url.clone(),
+                    size: attachment.size,
+                })
+                .collect(),
+            embeds: message
+                .embeds
+                .iter()
+                .map(|embed| TranscriptEmbed {
+                    title: embed.title.clone(),
+                    description: embed.description.clone(),
+                    url: embed.url.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Transcript {
+    // The messages can be passed in any order, the transcript lists them oldest first
+    pub fn new(
+        channel_id: ChannelId,
+        channel_name: String,
+        event_series_id: Option<db::EventSeriesId>,
+        messages: &[Message],
+    ) -> Self {
+        let mut messages: Vec<TranscriptMessage> = messages.iter().map(Into::into).collect();
+        messages.sort_by_key(|message| message.id);
+        Transcript {
+            channel_id: channel_id.get(),
+            channel_name,
+            event_series_id: event_series_id.map(|id| id.0),
+            exported_at: chrono::Utc::now().to_rfc3339(),
+            messages,
+        }
+    }
+
+    pub fn is_author(&self, discord_id: u64) -> bool {
+        self.messages
+            .iter()
+            .any(|message| message.author_id == discord_id)
+    }
+
+    // Keeps only the messages of the given author
+    pub fn retain_author(&mut self, discord_id: u64) {
+        self.messages
+            .retain(|message| message.author_id == discord_id);
+    }
+
+    // The messages stay, but don't point to the author anymore. Returns
+    // whether the author wrote any message.
+    pub fn anonymise_author(&mut self, discord_id: u64) -> bool {
+        let mut is_author = false;
+        for message in &mut self.messages {
+            if message.author_id == discord_id {
+                message.author_id = 0;
+                message.author_name = ERASED_AUTHOR_NAME.to_string();
+                is_author = true;
+            }
+        }
+        is_author
+    }
+}
+
+// A transcript that a member may download
+#[derive(Debug, Clone)]
+pub struct TranscriptInfo {
+    pub channel_id: ChannelId,
+    pub title: Option<String>,
+    pub transcript_time: chrono::DateTime<chrono::Utc>,
+}
+
+pub fn transcript_file_name(channel_id: ChannelId) -> String {
+    format!("{}.json", channel_id.get())
+}
+
+// Fetches all of the channel's messages, newest first
+async fn fetch_all_messages(
+    channel_id: ChannelId,
+    discord_api: &super::CacheAndHttp,
+) -> Result<Vec<Message>, crate::meetup::Error> {
+    const PAGE_SIZE: u8 = 100;
+    let mut messages: Vec<Message> = vec![];
+    loop {
+        let mut request = GetMessages::new().limit(PAGE_SIZE);
+        if let Some(oldest) = messages.last() {
+            request = request.before(oldest.id);
+        }
+        let page = channel_id.messages(&discord_api.http, request).await?;
+        let is_last_page = page.len() < PAGE_SIZE as usize;
+        messages.extend(page);
+        if is_last_page {
+            break;
+        }
+    }
+    Ok(messages)
+}
+
+// Writes to a temporary file first, so that a failed export never leaves a
+// truncated transcript behind
+async fn write_transcript(
+    path: PathBuf,
+    transcript: &Transcript,
+) -> Result<(), crate::meetup::Error> {
+    let json = serde_json::to_vec_pretty(transcript)?;
+    tokio::task::spawn_blocking(move || {
+        if let Some(directory) = path.parent() {
+            std::fs::create_dir_all(directory)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, json)?;
+        std::fs::rename(&temp_path, &path)
+    })
+    .await??;
+    Ok(())
+}
+
+pub async fn load_transcript(
+    transcript_directory: &Path,
+    file_name: &str,
+) -> Result<Transcript, crate::meetup::Error> {
+    let path = transcript_directory.join(file_name);
+    let json = tokio::task::spawn_blocking(move || std::fs::read(path)).await??;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+// Exports the channel's messages to the transcript directory and links the
+// transcript from the channel's database entry
+pub async fn export_channel(
+    channel_id: ChannelId,
+    channel_name: &str,
+    transcript_directory: &Path,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let event_series_id = sqlx::query_scalar!(
+        r#"SELECT COALESCE(event_series_text_channel.event_series_id, event_series.id) AS event_series_id
+        FROM event_series_text_channel
+        LEFT JOIN event_series ON event_series.discord_text_channel_id = event_series_text_channel.discord_id
+        WHERE event_series_text_channel.discord_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten()
+    .map(db::EventSeriesId);
+    let messages = fetch_all_messages(channel_id, discord_api).await?;
+    let transcript = Transcript::new(
+        channel_id,
+        channel_name.to_string(),
+        event_series_id,
+        &messages,
+    );
+    let file_name = transcript_file_name(channel_id);
+    write_transcript(transcript_directory.join(&file_name), &transcript).await?;
+    sqlx::query!(
+        r#"UPDATE event_series_text_channel
+        SET transcript_file = $2, transcript_time = NOW(), event_series_id = COALESCE(event_series_id, $3)
+        WHERE discord_id = $1"#,
+        channel_id.get() as i64,
+        file_name,
+        event_series_id.map(|id| id.0)
+    )
+    .execute(db_connection)
+    .await?;
+    println!(
+        "Exported {} messages of channel {} to {}",
+        transcript.messages.len(),
+        channel_id.get(),
+        file_name
+    );
+    Ok(())
+}
+
+// All transcripts that still exist in the transcript directory
+async fn load_all_transcripts(
+    transcript_directory: &Path,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<(String, Transcript)>, crate::meetup::Error> {
+    let file_names = sqlx::query_scalar!(
+        r#"SELECT transcript_file AS "transcript_file!" FROM event_series_text_channel WHERE transcript_file IS NOT NULL"#
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let mut transcripts = vec![];
+    for file_name in file_names {
+        if !transcript_directory.join(&file_name).exists() {
+            continue;
+        }
+        let transcript = load_transcript(transcript_directory, &file_name).await?;
+        transcripts.push((file_name, transcript));
+    }
+    Ok(transcripts)
+}
+
+// The transcripts that the user wrote in, with only the user's messages
+pub async fn get_authored_messages(
+    discord_id: UserId,
+    transcript_directory: &Path,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<Transcript>, crate::meetup::Error> {
+    let transcripts = load_all_transcripts(transcript_directory, db_connection)
+        .await?
+        .into_iter()
+        .filter_map(|(_, mut transcript)| {
+            transcript.retain_author(discord_id.get());
+            (!transcript.messages.is_empty()).then_some(transcript)
+        })
+        .collect();
+    Ok(transcripts)
+}
+
+// Removes the user's ID and name from all transcripts. Returns the number of
+// changed transcripts.
+pub async fn anonymise_author(
+    discord_id: UserId,
+    transcript_directory: &Path,
+    db_connection: &sqlx::PgPool,
+) -> Result<usize, crate::meetup::Error> {
+    let mut num_transcripts = 0;
+    for (file_name, mut transcript) in
+        load_all_transcripts(transcript_directory, db_connection).await?
+    {
+        if transcript.anonymise_author(discord_id.get()) {
+            write_transcript(transcript_directory.join(&file_name), &transcript).await?;
+            num_transcripts += 1;
+        }
+    }
+    Ok(num_transcripts)
+}
+
+pub async fn get_transcript_file(
+    channel_id: ChannelId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<String>, crate::meetup::Error> {
+    let file_name = sqlx::query_scalar!(
+        r#"SELECT transcript_file FROM event_series_text_channel WHERE discord_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten();
+    Ok(file_name)
+}
+
+// The transcripts of all series that the member hosted or played in
+pub async fn get_member_transcripts(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<TranscriptInfo>, crate::meetup::Error> {
+    let transcripts = sqlx::query!(
+        r#"SELECT event_series_text_channel.discord_id, event_series_text_channel.transcript_time AS "transcript_time!",
+            (SELECT title FROM event WHERE event.event_series_id = event_series_text_channel.event_series_id ORDER BY start_time DESC LIMIT 1) AS title
+        FROM event_series_text_channel
+        WHERE event_series_text_channel.transcript_file IS NOT NULL
+        AND event_series_text_channel.transcript_time IS NOT NULL
+        AND EXISTS (
+            SELECT 1 FROM event
+            LEFT JOIN event_host ON event_host.event_id = event.id AND event_host.member_id = $1
+            LEFT JOIN event_participant ON event_participant.event_id = event.id AND event_participant.member_id = $1
+            WHERE event.event_series_id = event_series_text_channel.event_series_id
+            AND (event_host.member_id IS NOT NULL OR event_participant.member_id IS NOT NULL)
+        )
+        ORDER BY event_series_text_channel.transcript_time DESC"#,
+        member_id.0
+    )
+    .map(|row| TranscriptInfo {
+        channel_id: ChannelId::new(row.discord_id as u64),
+        title: row.title,
+        transcript_time: row.transcript_time,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(transcripts)
+}
+
+// Hosts and players of the channel's series as well as everyone who wrote in
+// the channel may read its transcript
+pub async fn member_may_access(
+    member_id: db::MemberId,
+    transcript: &Transcript,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let took_part = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM event
+            LEFT JOIN event_host ON event_host.event_id = event.id AND event_host.member_id = $1
+            LEFT JOIN event_participant ON event_participant.event_id = event.id AND event_participant.member_id = $1
+            WHERE event.event_series_id = $2
+            AND (event_host.member_id IS NOT NULL OR event_participant.member_id IS NOT NULL)
+        ) AS "took_part!""#,
+        member_id.0,
+        transcript.event_series_id
+    )
+    .fetch_one(db_connection)
+    .await?;
+    if took_part {
+        return Ok(true);
+    }
+    let discord_id = sqlx::query_scalar!(
+        r#"SELECT discord_id FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(discord_id.is_some_and(|discord_id| transcript.is_author(discord_id as u64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transcript_roundtrip() {
+        let transcript = Transcript {
+            channel_id: 1,
+            channel_name: "the-lost-mine".to_string(),
+            event_series_id: Some(1000),
+            exported_at: "2026-10-17T18:30:00+00:00".to_string(),
+            messages: vec![TranscriptMessage {
+                id: 2,
+                author_id: 3,
+                author_name: "Gandalf".to_string(),
+                timestamp: "2026-10-10T19:00:00+00:00".to_string(),
+                edited_timestamp: None,
+                content: "You shall not pass".to_string(),
+                pinned: true,
+                attachments: vec![TranscriptAttachment {
+                    filename: "map.png".to_string(),
+                    url: "https://cdn.discordapp.com/attachments/1/2/map.png".to_string(),
+                    size: 1024,
+                }],
+                embeds: vec![],
+            }],
+        };
+        let json = serde_json::to_string(&transcript).unwrap();
+        let parsed: Transcript = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, transcript);
+        assert!(parsed.is_author(3));
+        assert!(!parsed.is_author(2));
+        let mut anonymised = parsed.clone();
+        assert!(anonymised.anonymise_author(3));
+        assert!(!anonymised.is_author(3));
+        assert_eq!(anonymised.messages[0].author_name, ERASED_AUTHOR_NAME);
+        assert_eq!(anonymised.messages[0].content, "You shall not pass");
+    }
+}
@@ -45,8 +45,9 @@ pub async fn say_in_event_channel(
 pub async fn say_in_bot_alerts_channel(
     message: &str,
     discord_cache_http: &super::CacheAndHttp,
+    guild_config: &super::guild_config::GuildConfig,
 ) -> Result<(), crate::BoxedError> {
-    if let Some(channel_id) = super::sync::ids::BOT_ALERTS_CHANNEL_ID {
+    if let Some(channel_id) = guild_config.bot_alerts_channel_id {
         channel_id
             .say(&discord_cache_http.http, message)
             .await
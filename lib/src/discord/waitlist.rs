@@ -0,0 +1,486 @@
+use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::CreateMessage,
+    model::id::{ChannelId, UserId},
+};
+
+use crate::{db, strings};
+
+// How long a player has to claim a seat that was offered to them. Offers
+// never outlast the start of the session.
+const CLAIM_DURATION_HOURS: i64 = 12;
+
+// The link that lets a player claim the seat that is on offer to them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitlistClaim {
+    pub waitlist_id: i32,
+}
+
+impl crate::flow::FlowState for WaitlistClaim {
+    const KIND: &'static str = "waitlist_claim";
+    const TTL_MINUTES: i32 = CLAIM_DURATION_HOURS as i32 * 60;
+}
+
+pub type WaitlistClaimFlow = crate::flow::Flow<WaitlistClaim>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitlistResult {
+    Joined { position: i64 },
+    AlreadyWaiting { position: i64 },
+    AlreadyJoined,
+    NotFull,
+    Closed,
+    Left,
+    NotWaiting,
+}
+
+impl WaitlistResult {
+    pub fn message(self) -> String {
+        match self {
+            WaitlistResult::Joined { position } => strings::WAITLIST_JOINED(position),
+            WaitlistResult::AlreadyWaiting { position } => {
+                strings::WAITLIST_ALREADY_WAITING(position)
+            }
+            WaitlistResult::AlreadyJoined => strings::RSVP_ALREADY_JOINED.to_string(),
+            WaitlistResult::NotFull => strings::WAITLIST_NOT_FULL.to_string(),
+            WaitlistResult::Closed => strings::RSVP_CLOSED.to_string(),
+            WaitlistResult::Left => strings::WAITLIST_LEFT.to_string(),
+            WaitlistResult::NotWaiting => strings::WAITLIST_NOT_WAITING.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimResult {
+    Claimed {
+        event_series_id: db::EventSeriesId,
+        title: String,
+    },
+    // Someone took the seat in the meantime, the player stays in line
+    Full,
+    Expired,
+    Closed,
+    Invalid,
+}
+
+// A seat that is on offer to a player on the waitlist
+#[derive(Debug, Clone)]
+pub struct ClaimOffer {
+    pub title: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub claim_expires: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WaitlistEntry {
+    pub event_id: db::EventId,
+    pub discord_id: Option<UserId>,
+    // Set if a seat is on offer to this player
+    pub claim_expires: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// Seats that are reserved for players on the waitlist who have been offered
+// one, except for the specified member's
+pub(crate) async fn num_offered_seats(
+    event_id: db::EventId,
+    except_member_id: Option<db::MemberId>,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<i64, crate::meetup::Error> {
+    let num_offered_seats = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM event_waitlist
+        WHERE event_id = $1 AND claim_expires > NOW() AND member_id IS DISTINCT FROM $2"#,
+        event_id.0,
+        except_member_id.map(|id| id.0)
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(num_offered_seats)
+}
+
+// Seats that are still free for RSVPs made on Discord, None if there is no
+// known limit. Doesn't take offered seats into account.
+async fn num_free_seats(
+    event_id: db::EventId,
+    open_seats: Option<i32>,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<Option<i64>, crate::meetup::Error> {
+    let open_seats = match open_seats {
+        Some(open_seats) => open_seats as i64,
+        None => return Ok(None),
+    };
+    let num_discord_participants = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM event_participant WHERE event_id = $1 AND from_discord"#,
+        event_id.0
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(Some((open_seats - num_discord_participants).max(0)))
+}
+
+async fn waitlist_position(
+    event_id: db::EventId,
+    waitlist_id: i32,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<i64, crate::meetup::Error> {
+    let position = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!" FROM event_waitlist WHERE event_id = $1 AND id <= $2"#,
+        event_id.0,
+        waitlist_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+    Ok(position)
+}
+
+pub async fn join_waitlist(
+    event_id: db::EventId,
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<WaitlistResult, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let event = sqlx::query!(
+        r#"SELECT start_time, open_seats FROM event
+        WHERE id = $1 AND deleted IS NULL
+        FOR UPDATE"#,
+        event_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let event = match event {
+        Some(event) if event.start_time > chrono::Utc::now() => event,
+        _ => return Ok(WaitlistResult::Closed),
+    };
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    let is_participant = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM event_participant WHERE event_id = $1 AND member_id = $2) AS "exists!""#,
+        event_id.0,
+        member_id.0
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if is_participant {
+        return Ok(WaitlistResult::AlreadyJoined);
+    }
+    let waitlist_id = sqlx::query_scalar!(
+        r#"SELECT id FROM event_waitlist WHERE event_id = $1 AND member_id = $2"#,
+        event_id.0,
+        member_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(waitlist_id) = waitlist_id {
+        let position = waitlist_position(event_id, waitlist_id, &mut tx).await?;
+        return Ok(WaitlistResult::AlreadyWaiting { position });
+    }
+    let num_free_seats = match num_free_seats(event_id, event.open_seats, &mut tx).await? {
+        Some(num_free_seats) => num_free_seats,
+        None => return Ok(WaitlistResult::NotFull),
+    };
+    if num_free_seats > num_offered_seats(event_id, None, &mut tx).await? {
+        return Ok(WaitlistResult::NotFull);
+    }
+    let waitlist_id = sqlx::query_scalar!(
+        r#"INSERT INTO event_waitlist (event_id, member_id) VALUES ($1, $2) RETURNING id"#,
+        event_id.0,
+        member_id.0
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let position = waitlist_position(event_id, waitlist_id, &mut tx).await?;
+    tx.commit().await?;
+    Ok(WaitlistResult::Joined { position })
+}
+
+// If the player had been offered a seat, it should be offered to the next
+// one in line with offer_free_seats
+pub async fn leave_waitlist(
+    event_id: db::EventId,
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<WaitlistResult, crate::meetup::Error> {
+    let deleted = sqlx::query!(
+        r#"DELETE FROM event_waitlist
+        USING "member"
+        WHERE event_waitlist.member_id = "member".id AND event_waitlist.event_id = $1 AND "member".discord_id = $2"#,
+        event_id.0,
+        user_id.get() as i64
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected();
+    if deleted > 0 {
+        Ok(WaitlistResult::Left)
+    } else {
+        Ok(WaitlistResult::NotWaiting)
+    }
+}
+
+// Offers the event's free seats to the next players in line. Players who let
+// their offer expire are removed from the waitlist.
+pub async fn offer_free_seats(
+    event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let event = sqlx::query!(
+        r#"SELECT title, start_time, open_seats FROM event
+        WHERE id = $1 AND deleted IS NULL
+        FOR UPDATE"#,
+        event_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let event = match event {
+        Some(event) if event.start_time > chrono::Utc::now() => event,
+        _ => return Ok(()),
+    };
+    sqlx::query!(
+        r#"DELETE FROM event_waitlist WHERE event_id = $1 AND claim_expires <= NOW()"#,
+        event_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    let num_seats_to_offer = match num_free_seats(event_id, event.open_seats, &mut tx).await? {
+        Some(num_free_seats) => num_free_seats - num_offered_seats(event_id, None, &mut tx).await?,
+        // Without a limit, everybody gets a seat
+        None => i64::MAX,
+    };
+    if num_seats_to_offer <= 0 {
+        tx.commit().await?;
+        return Ok(());
+    }
+    let next_in_line = sqlx::query!(
+        r#"SELECT event_waitlist.id, "member".discord_id
+        FROM event_waitlist
+        INNER JOIN "member" ON event_waitlist.member_id = "member".id
+        WHERE event_waitlist.event_id = $1 AND event_waitlist.claim_expires IS NULL
+        ORDER BY event_waitlist.id
+        LIMIT $2"#,
+        event_id.0,
+        num_seats_to_offer
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let claim_expires = std::cmp::min(
+        chrono::Utc::now() + chrono::Duration::hours(CLAIM_DURATION_HOURS),
+        event.start_time,
+    );
+    let mut offers = Vec::with_capacity(next_in_line.len());
+    for entry in next_in_line {
+        sqlx::query!(
+            r#"UPDATE event_waitlist SET claim_expires = $2 WHERE id = $1"#,
+            entry.id,
+            claim_expires
+        )
+        .execute(&mut *tx)
+        .await?;
+        if let Some(discord_id) = entry.discord_id {
+            offers.push((UserId::new(discord_id as u64), entry.id));
+        }
+    }
+    tx.commit().await?;
+    // The link stays valid as long as the seat is reserved, unless the
+    // session starts first. Claiming a seat of a session that has started
+    // fails anyway.
+    for (user_id, waitlist_id) in offers {
+        let flow =
+            match WaitlistClaimFlow::new(db_connection, user_id, WaitlistClaim { waitlist_id })
+                .await
+            {
+                Ok(flow) => flow,
+                Err(err) => {
+                    eprintln!(
+                        "Could not create the seat claim link of event {} for {}:\n{:#?}",
+                        event_id.0, user_id, err
+                    );
+                    continue;
+                }
+            };
+        let claim_url = format!("{}/waitlist/claim/{}", crate::urls::BASE_URL, flow.id);
+        let message = strings::WAITLIST_SEAT_OFFERED(
+            &event.title,
+            event.start_time,
+            &claim_url,
+            claim_expires,
+        );
+        let dm_result = match user_id.create_dm_channel(&discord_api.http).await {
+            Ok(channel) => channel
+                .id
+                .send_message(&discord_api.http, CreateMessage::new().content(message))
+                .await
+                .map(|_| ()),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = dm_result {
+            eprintln!(
+                "Could not offer a seat of event {} to {}:\n{:#?}",
+                event_id.0, user_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+// Offers free seats for all upcoming events that have a waitlist. Free seats
+// show up when participants leave or the seat count rises during the sync.
+pub async fn offer_all_free_seats(
+    db_connection: &sqlx::PgPool,
+    discord_api: &super::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let event_ids = sqlx::query_scalar!(
+        r#"SELECT DISTINCT event.id
+        FROM event_waitlist
+        INNER JOIN event ON event_waitlist.event_id = event.id
+        WHERE event.start_time > NOW() AND event.deleted IS NULL"#
+    )
+    .fetch_all(db_connection)
+    .await?;
+    for event_id in event_ids {
+        if let Err(err) = offer_free_seats(db::EventId(event_id), db_connection, discord_api).await
+        {
+            eprintln!(
+                "Could not offer the free seats of event {}:\n{:#?}",
+                event_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_claim_offer(
+    claim: &WaitlistClaim,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<ClaimOffer>, crate::meetup::Error> {
+    let offer = sqlx::query!(
+        r#"SELECT event.title, event.start_time, event_waitlist.claim_expires AS "claim_expires!"
+        FROM event_waitlist
+        INNER JOIN event ON event_waitlist.event_id = event.id
+        WHERE event_waitlist.id = $1 AND event_waitlist.claim_expires > NOW()"#,
+        claim.waitlist_id
+    )
+    .map(|row| ClaimOffer {
+        title: row.title,
+        start_time: row.start_time,
+        claim_expires: row.claim_expires,
+    })
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(offer)
+}
+
+// The claim's flow should have been taken already, such that the link can
+// only be used once
+pub async fn claim_seat(
+    claim: &WaitlistClaim,
+    db_connection: &sqlx::PgPool,
+) -> Result<ClaimResult, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let entry = sqlx::query!(
+        r#"SELECT id, event_id, member_id, claim_expires
+        FROM event_waitlist
+        WHERE id = $1
+        FOR UPDATE"#,
+        claim.waitlist_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let entry = match entry {
+        Some(entry) => entry,
+        None => return Ok(ClaimResult::Invalid),
+    };
+    // The seat is only reserved while the offer lasts
+    if entry
+        .claim_expires
+        .is_none_or(|claim_expires| claim_expires <= chrono::Utc::now())
+    {
+        return Ok(ClaimResult::Expired);
+    }
+    let event_id = db::EventId(entry.event_id);
+    let event = sqlx::query!(
+        r#"SELECT event_series_id, title, start_time, open_seats FROM event
+        WHERE id = $1 AND deleted IS NULL
+        FOR UPDATE"#,
+        event_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let event = match event {
+        Some(event) if event.start_time > chrono::Utc::now() => event,
+        _ => return Ok(ClaimResult::Closed),
+    };
+    let member_id = db::MemberId(entry.member_id);
+    if let Some(num_free_seats) = num_free_seats(event_id, event.open_seats, &mut tx).await? {
+        if num_free_seats <= num_offered_seats(event_id, Some(member_id), &mut tx).await? {
+            // Back in line until the next seat frees up
+            sqlx::query!(
+                r#"UPDATE event_waitlist SET claim_expires = NULL WHERE id = $1"#,
+                entry.id
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(ClaimResult::Full);
+        }
+    }
+    sqlx::query!(
+        r#"INSERT INTO event_participant (event_id, member_id, from_discord) VALUES ($1, $2, TRUE)
+        ON CONFLICT DO NOTHING"#,
+        event_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(r#"DELETE FROM event_waitlist WHERE id = $1"#, entry.id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(ClaimResult::Claimed {
+        event_series_id: db::EventSeriesId(event.event_series_id),
+        title: event.title,
+    })
+}
+
+// The waitlists of the specified events, in order
+pub async fn get_waitlists(
+    event_ids: &[db::EventId],
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<WaitlistEntry>, crate::meetup::Error> {
+    let event_ids: Vec<i32> = event_ids.iter().map(|id| id.0).collect();
+    let entries = sqlx::query!(
+        r#"SELECT event_waitlist.event_id, event_waitlist.claim_expires, "member".discord_id
+        FROM event_waitlist
+        INNER JOIN "member" ON event_waitlist.member_id = "member".id
+        WHERE event_waitlist.event_id = ANY($1)
+        AND (event_waitlist.claim_expires IS NULL OR event_waitlist.claim_expires > NOW())
+        ORDER BY event_waitlist.event_id, event_waitlist.id"#,
+        &event_ids
+    )
+    .map(|row| WaitlistEntry {
+        event_id: db::EventId(row.event_id),
+        discord_id: row.discord_id.map(|id| UserId::new(id as u64)),
+        claim_expires: row.claim_expires,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(entries)
+}
+
+// The next upcoming event of the series that the channel belongs to
+pub async fn get_channel_next_event(
+    channel_id: ChannelId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<db::EventId>, crate::meetup::Error> {
+    let event_id = sqlx::query_scalar!(
+        r#"SELECT event.id
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        WHERE event_series.discord_text_channel_id = $1 AND event.start_time > NOW() AND event.deleted IS NULL
+        ORDER BY event.start_time
+        LIMIT 1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(event_id.map(db::EventId))
+}
@@ -35,7 +35,12 @@ pub async fn end_adventure(
         return Ok(EndAdventureResult::NotYetExpired);
     }
     // Schedule this channel for deletion
-    let new_deletion_time = chrono::Utc::now() + chrono::Duration::hours(8);
+    let deletion_delay = crate::expiration_policy::get_channel_policy(channel_id, db_connection)
+        .await?
+        .map(|policy| policy.effective())
+        .unwrap_or_default()
+        .deletion_delay();
+    let new_deletion_time = chrono::Utc::now() + deletion_delay;
     let current_deletion_time = sqlx::query_scalar!(
         r#"SELECT deletion_time FROM event_series_text_channel WHERE discord_id = $1"#,
         channel_id.get() as i64
@@ -0,0 +1,372 @@
+use serenity::model::id::ChannelId;
+
+use crate::db;
+
+// The longest delay or interval that can be configured, one year
+const MAX_HOURS: i32 = 365 * 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicySetting {
+    // How long after the last session the first expiration reminder is sent
+    ReminderDelay,
+    // How long to wait between expiration reminders
+    ReminderInterval,
+    // How long after "end adventure" the channel gets deleted
+    DeletionDelay,
+}
+
+impl PolicySetting {
+    pub const ALL: [PolicySetting; 3] = [
+        PolicySetting::ReminderDelay,
+        PolicySetting::ReminderInterval,
+        PolicySetting::DeletionDelay,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PolicySetting::ReminderDelay => "reminder delay",
+            PolicySetting::ReminderInterval => "reminder interval",
+            PolicySetting::DeletionDelay => "deletion delay",
+        }
+    }
+
+    // Accepts "reminder delay", "reminder-delay" and "reminder_delay"
+    pub fn parse(setting: &str) -> Option<Self> {
+        let setting = setting.trim().to_lowercase().replace(['-', '_'], " ");
+        let setting = itertools::join(setting.split_whitespace(), " ");
+        PolicySetting::ALL
+            .into_iter()
+            .find(|candidate| candidate.name() == setting)
+    }
+
+    fn min_hours(self) -> i32 {
+        match self {
+            PolicySetting::ReminderInterval => 1,
+            PolicySetting::ReminderDelay | PolicySetting::DeletionDelay => 0,
+        }
+    }
+
+    pub fn is_valid(self, hours: i32) -> bool {
+        (self.min_hours()..=MAX_HOURS).contains(&hours)
+    }
+
+    // Describes the valid values, for replying to invalid input
+    pub fn valid_range(self) -> String {
+        format!(
+            "The {} has to be between {} and {}",
+            self.name(),
+            format_hours(self.min_hours()),
+            format_hours(MAX_HOURS)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpirationPolicy {
+    pub reminder_delay_hours: i32,
+    pub reminder_interval_hours: i32,
+    pub deletion_delay_hours: i32,
+}
+
+impl Default for ExpirationPolicy {
+    // The policy of one-shot adventures
+    fn default() -> Self {
+        ExpirationPolicy {
+            reminder_delay_hours: 24,
+            reminder_interval_hours: 48,
+            deletion_delay_hours: 8,
+        }
+    }
+}
+
+impl ExpirationPolicy {
+    pub fn hours(&self, setting: PolicySetting) -> i32 {
+        match setting {
+            PolicySetting::ReminderDelay => self.reminder_delay_hours,
+            PolicySetting::ReminderInterval => self.reminder_interval_hours,
+            PolicySetting::DeletionDelay => self.deletion_delay_hours,
+        }
+    }
+
+    pub fn reminder_delay(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.reminder_delay_hours as i64)
+    }
+
+    pub fn reminder_interval(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.reminder_interval_hours as i64)
+    }
+
+    pub fn deletion_delay(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.deletion_delay_hours as i64)
+    }
+}
+
+// The series' own settings, None where the series type's setting applies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PolicyOverrides {
+    pub reminder_delay_hours: Option<i32>,
+    pub reminder_interval_hours: Option<i32>,
+    pub deletion_delay_hours: Option<i32>,
+}
+
+impl PolicyOverrides {
+    pub fn hours(&self, setting: PolicySetting) -> Option<i32> {
+        match setting {
+            PolicySetting::ReminderDelay => self.reminder_delay_hours,
+            PolicySetting::ReminderInterval => self.reminder_interval_hours,
+            PolicySetting::DeletionDelay => self.deletion_delay_hours,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeriesExpirationPolicy {
+    pub series_id: db::EventSeriesId,
+    pub series_type: String,
+    pub type_policy: ExpirationPolicy,
+    pub overrides: PolicyOverrides,
+}
+
+impl SeriesExpirationPolicy {
+    pub fn is_campaign(&self) -> bool {
+        self.series_type == "campaign"
+    }
+
+    // The policy that applies to the series
+    pub fn effective(&self) -> ExpirationPolicy {
+        ExpirationPolicy {
+            reminder_delay_hours: self
+                .overrides
+                .reminder_delay_hours
+                .unwrap_or(self.type_policy.reminder_delay_hours),
+            reminder_interval_hours: self
+                .overrides
+                .reminder_interval_hours
+                .unwrap_or(self.type_policy.reminder_interval_hours),
+            deletion_delay_hours: self
+                .overrides
+                .deletion_delay_hours
+                .unwrap_or(self.type_policy.deletion_delay_hours),
+        }
+    }
+}
+
+pub async fn get_series_policy(
+    series_id: db::EventSeriesId,
+    db_connection: &mut sqlx::PgConnection,
+) -> Result<SeriesExpirationPolicy, crate::meetup::Error> {
+    let policy = sqlx::query!(
+        r#"SELECT event_series."type",
+            event_series.reminder_delay_hours, event_series.reminder_interval_hours, event_series.deletion_delay_hours,
+            event_series_type.reminder_delay_hours AS type_reminder_delay_hours,
+            event_series_type.reminder_interval_hours AS type_reminder_interval_hours,
+            event_series_type.deletion_delay_hours AS type_deletion_delay_hours
+        FROM event_series
+        INNER JOIN event_series_type ON event_series."type" = event_series_type."type"
+        WHERE event_series.id = $1"#,
+        series_id.0
+    )
+    .map(|row| SeriesExpirationPolicy {
+        series_id,
+        series_type: row.r#type,
+        type_policy: ExpirationPolicy {
+            reminder_delay_hours: row.type_reminder_delay_hours,
+            reminder_interval_hours: row.type_reminder_interval_hours,
+            deletion_delay_hours: row.type_deletion_delay_hours,
+        },
+        overrides: PolicyOverrides {
+            reminder_delay_hours: row.reminder_delay_hours,
+            reminder_interval_hours: row.reminder_interval_hours,
+            deletion_delay_hours: row.deletion_delay_hours,
+        },
+    })
+    .fetch_one(db_connection)
+    .await?;
+    Ok(policy)
+}
+
+// The policy of the series that the channel belongs to, if any
+pub async fn get_channel_policy(
+    channel_id: ChannelId,
+    db_connection: &mut sqlx::PgConnection,
+) -> Result<Option<SeriesExpirationPolicy>, crate::meetup::Error> {
+    let series_id = sqlx::query_scalar!(
+        r#"SELECT id FROM event_series WHERE discord_text_channel_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(&mut *db_connection)
+    .await?;
+    match series_id {
+        Some(series_id) => Ok(Some(
+            get_series_policy(db::EventSeriesId(series_id), db_connection).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+// Sets or, with None, removes one of a series' overrides
+pub async fn set_series_override(
+    series_id: db::EventSeriesId,
+    setting: PolicySetting,
+    hours: Option<i32>,
+    db_connection: &mut sqlx::PgConnection,
+) -> Result<(), crate::meetup::Error> {
+    if let Some(hours) = hours {
+        check_hours(setting, hours)?;
+    }
+    match setting {
+        PolicySetting::ReminderDelay => {
+            sqlx::query!(
+                r#"UPDATE event_series SET reminder_delay_hours = $2 WHERE id = $1"#,
+                series_id.0,
+                hours
+            )
+            .execute(db_connection)
+            .await?
+        }
+        PolicySetting::ReminderInterval => {
+            sqlx::query!(
+                r#"UPDATE event_series SET reminder_interval_hours = $2 WHERE id = $1"#,
+                series_id.0,
+                hours
+            )
+            .execute(db_connection)
+            .await?
+        }
+        PolicySetting::DeletionDelay => {
+            sqlx::query!(
+                r#"UPDATE event_series SET deletion_delay_hours = $2 WHERE id = $1"#,
+                series_id.0,
+                hours
+            )
+            .execute(db_connection)
+            .await?
+        }
+    };
+    Ok(())
+}
+
+// Changes a setting for all series of a type that don't override it.
+// Returns false if there is no such series type.
+pub async fn set_type_default(
+    series_type: &str,
+    setting: PolicySetting,
+    hours: i32,
+    db_connection: &mut sqlx::PgConnection,
+) -> Result<bool, crate::meetup::Error> {
+    check_hours(setting, hours)?;
+    let result =
+        match setting {
+            PolicySetting::ReminderDelay => {
+                sqlx::query!(
+                    r#"UPDATE event_series_type SET reminder_delay_hours = $2 WHERE "type" = $1"#,
+                    series_type,
+                    hours
+                )
+                .execute(db_connection)
+                .await?
+            }
+            PolicySetting::ReminderInterval => sqlx::query!(
+                r#"UPDATE event_series_type SET reminder_interval_hours = $2 WHERE "type" = $1"#,
+                series_type,
+                hours
+            )
+            .execute(db_connection)
+            .await?,
+            PolicySetting::DeletionDelay => {
+                sqlx::query!(
+                    r#"UPDATE event_series_type SET deletion_delay_hours = $2 WHERE "type" = $1"#,
+                    series_type,
+                    hours
+                )
+                .execute(db_connection)
+                .await?
+            }
+        };
+    Ok(result.rows_affected() > 0)
+}
+
+fn check_hours(setting: PolicySetting, hours: i32) -> Result<(), crate::meetup::Error> {
+    if setting.is_valid(hours) {
+        Ok(())
+    } else {
+        Err(simple_error::SimpleError::new(setting.valid_range()).into())
+    }
+}
+
+// Parses durations like "3d", "12h", "2 days" or "1 hour" into hours
+pub fn parse_hours(value: &str) -> Option<i32> {
+    let value = value.trim().to_lowercase();
+    let number_end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let number: i32 = value[..number_end].parse().ok()?;
+    match value[number_end..].trim() {
+        "d" | "day" | "days" => number.checked_mul(24),
+        "h" | "hour" | "hours" => Some(number),
+        _ => None,
+    }
+}
+
+pub fn format_hours(hours: i32) -> String {
+    let plural = |count: i32, unit: &str| {
+        if count == 1 {
+            format!("1 {}", unit)
+        } else {
+            format!("{} {}s", count, unit)
+        }
+    };
+    match (hours / 24, hours % 24) {
+        (0, hours) => plural(hours, "hour"),
+        (days, 0) => plural(days, "day"),
+        (days, hours) => format!("{} {}", plural(days, "day"), plural(hours, "hour")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(parse_hours("3d"), Some(72));
+        assert_eq!(parse_hours("2 Days"), Some(48));
+        assert_eq!(parse_hours("12h"), Some(12));
+        assert_eq!(parse_hours("1 hour"), Some(1));
+        assert_eq!(parse_hours("12"), None);
+        assert_eq!(parse_hours("d"), None);
+        assert_eq!(format_hours(1), "1 hour");
+        assert_eq!(format_hours(48), "2 days");
+        assert_eq!(format_hours(30), "1 day 6 hours");
+        assert_eq!(
+            PolicySetting::parse("Reminder-Interval"),
+            Some(PolicySetting::ReminderInterval)
+        );
+        assert_eq!(PolicySetting::parse("reminder"), None);
+    }
+
+    #[test]
+    fn overrides() {
+        let policy = SeriesExpirationPolicy {
+            series_id: db::EventSeriesId(1),
+            series_type: "campaign".to_string(),
+            type_policy: ExpirationPolicy {
+                reminder_delay_hours: 72,
+                reminder_interval_hours: 96,
+                deletion_delay_hours: 8,
+            },
+            overrides: PolicyOverrides {
+                reminder_interval_hours: Some(30 * 24),
+                ..Default::default()
+            },
+        };
+        assert_eq!(
+            policy.effective(),
+            ExpirationPolicy {
+                reminder_delay_hours: 72,
+                reminder_interval_hours: 720,
+                deletion_delay_hours: 8,
+            }
+        );
+    }
+}
@@ -1,14 +1,17 @@
 // While syncing upcoming Meetup events, the code in this file is used to build
 // a list of events with free spots and post those to Discord.
 
-use crate::common_event::CommonEventDetails;
-use geo::{euclidean_distance::EuclideanDistance, Point};
+use crate::{
+    common_event::CommonEventDetails,
+    location::{Location, Locations},
+};
+use geo::Point;
 use lazy_static::lazy_static;
 use serenity::{
     builder::{
         CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage, EditMessage, GetMessages,
     },
-    model::id::ChannelId,
+    model::{channel::Message, id::ChannelId},
 };
 use std::{collections::HashMap, fmt::Write};
 
@@ -18,165 +21,12 @@ lazy_static! {
     pub static ref CLOSED_REGEX: regex::Regex = regex::Regex::new(CLOSED_PATTERN).unwrap();
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct EventCollector {
     // List of upcoming events and the number of free spots
     pub events: Vec<CommonEventDetails>,
 }
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
-enum Location {
-    Online,
-    Zurich,
-    Basel,
-    Luzern,
-    Lugano,
-    Geneva,
-    Lausanne,
-    Bern,
-    Aarau,
-    Chur,
-    StGallen,
-}
-
-// The ordering here is used for the ordering of posted messages
-static ALL_LOCATIONS: &[Location] = &[
-    Location::Zurich,
-    Location::StGallen,
-    Location::Basel,
-    Location::Luzern,
-    Location::Lugano,
-    Location::Geneva,
-    Location::Lausanne,
-    Location::Bern,
-    Location::Aarau,
-    Location::Chur,
-    Location::Online,
-];
-
-// Locations with a latitude and longitude
-static PHYSICAL_LOCATIONS: &[Location] = &[
-    Location::Zurich,
-    Location::Basel,
-    Location::Luzern,
-    Location::Lugano,
-    Location::Geneva,
-    Location::Lausanne,
-    Location::Bern,
-    Location::Aarau,
-    Location::Chur,
-    Location::StGallen,
-];
-
-impl Location {
-    pub fn lat_lon(&self) -> Option<Point<f64>> {
-        match self {
-            Location::Online => None,
-            Location::Zurich => Some(Point::new(8.541694, 47.376888)),
-            Location::Basel => Some(Point::new(7.588576, 47.559601)),
-            Location::Luzern => Some(Point::new(8.308010, 47.045540)),
-            Location::Lugano => Some(Point::new(8.953620, 46.003601)),
-            Location::Geneva => Some(Point::new(6.143158, 46.204391)),
-            Location::Lausanne => Some(Point::new(6.6345432, 46.519316)),
-            Location::Bern => Some(Point::new(7.4433158, 46.9489217)),
-            Location::Aarau => Some(Point::new(8.0606556, 47.3934732)),
-            Location::Chur => Some(Point::new(9.5275838, 46.8533507)),
-            Location::StGallen => Some(Point::new(9.3741491, 47.4256037)),
-        }
-    }
-
-    // Finds the closest location to the given point
-    pub fn closest(p: Point<f64>) -> Location {
-        let mut min_loc_dist = (
-            PHYSICAL_LOCATIONS[0],
-            PHYSICAL_LOCATIONS[0]
-                .lat_lon()
-                .unwrap()
-                .euclidean_distance(&p),
-        );
-        for &location in &PHYSICAL_LOCATIONS[1..] {
-            let dist = location.lat_lon().unwrap().euclidean_distance(&p);
-            if dist < min_loc_dist.1 {
-                min_loc_dist = (location, dist)
-            }
-        }
-        min_loc_dist.0
-    }
-
-    pub fn name(&self) -> &'static str {
-        match self {
-            Location::Online => "Online",
-            Location::Zurich => "Zürich",
-            Location::Basel => "Basel",
-            Location::Luzern => "Luzern",
-            Location::Lugano => "Lugano",
-            Location::Geneva => "Geneva",
-            Location::Lausanne => "Lausanne",
-            Location::Bern => "Bern",
-            Location::Aarau => "Aarau",
-            Location::Chur => "Chur",
-            Location::StGallen => "St. Gallen",
-        }
-    }
-
-    pub fn find_by_city(name: &str) -> Option<Location> {
-        ALL_LOCATIONS
-            .iter()
-            .find(|location| location.name() == name)
-            .copied()
-    }
-
-    pub fn flag_name(&self) -> &'static str {
-        match self {
-            Location::Online => "Online",
-            Location::Zurich => "Zurich",
-            Location::Basel => "Basel",
-            Location::Luzern => "Luzern",
-            Location::Lugano => "Ticino",
-            Location::Geneva => "Geneva",
-            Location::Lausanne => "Vaud",
-            Location::Bern => "Bern",
-            Location::Aarau => "Aargau",
-            Location::Chur => "Graubunden",
-            Location::StGallen => "St_Gallen",
-        }
-    }
-
-    pub fn color(&self) -> (u8, u8, u8) {
-        // Discord seems to turn perfect white and black into their
-        // counterparts depending on the user's color scheme, so we don't use
-        // perfect white and black here
-        match self {
-            Location::Online => (255, 23, 68),
-            Location::Zurich => (0, 115, 229),
-            Location::Basel => (1, 2, 2),
-            Location::Luzern => (38, 139, 204),
-            Location::Lugano => (232, 66, 63),
-            Location::Geneva => (232, 66, 63),
-            Location::Lausanne => (22, 167, 78),
-            Location::Bern => (255, 215, 48),
-            Location::Aarau => (38, 139, 204),
-            Location::Chur => (254, 254, 254),
-            Location::StGallen => (22, 167, 78),
-        }
-    }
-
-    pub fn meetup_group_link(&self) -> &'static str {
-        match self {
-            Location::Online | Location::Zurich | Location::StGallen => {
-                "https://www.meetup.com/SwissRPG-Zurich/"
-            }
-            Location::Basel
-            | Location::Luzern
-            | Location::Lugano
-            | Location::Bern
-            | Location::Aarau
-            | Location::Chur => "https://www.meetup.com/SwissRPG-Central/",
-            Location::Geneva | Location::Lausanne => "https://www.meetup.com/SwissRPG-Romandie/",
-        }
-    }
-}
-
 impl EventCollector {
     pub fn new() -> Self {
         EventCollector { events: vec![] }
@@ -186,17 +36,9 @@ impl EventCollector {
         self.events.push(event);
     }
 
-    pub async fn update_channel(
-        &self,
-        discord_api: &crate::discord::CacheAndHttp,
-        channel_id: ChannelId,
-        static_file_prefix: &str,
-    ) -> Result<(), crate::meetup::Error> {
-        let mut latest_messages = channel_id
-            .messages(&discord_api.http, GetMessages::new().limit(20))
-            .await?;
-        let relevant_events: Vec<&CommonEventDetails> = self
-            .events
+    // Events that players can still sign up for
+    pub fn open_events(&self) -> Vec<&CommonEventDetails> {
+        self.events
             .iter()
             // Discard events which don't have free spots
             .filter(|event| event.num_free_spots() > 0)
@@ -208,29 +50,34 @@ impl EventCollector {
             })
             // Discard events which are too far in the future
             .filter(|event| event.date_time < chrono::Utc::now() + chrono::Duration::days(30))
-            .collect();
-        let mut localized_events = Self::localized_events(&relevant_events);
-        for location in ALL_LOCATIONS {
+            .collect()
+    }
+
+    pub async fn update_channel(
+        &self,
+        discord_api: &crate::discord::CacheAndHttp,
+        channel_id: ChannelId,
+        locations: &Locations,
+        static_file_prefix: &str,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut latest_messages = channel_id
+            .messages(&discord_api.http, GetMessages::new().limit(20))
+            .await?;
+        let relevant_events = self.open_events();
+        let mut localized_events = Self::localized_events(&relevant_events, locations);
+        for location in locations.iter() {
             let location_events: &mut [&CommonEventDetails] = localized_events
-                .get_mut(location)
+                .get_mut(location.code.as_str())
                 .map(Vec::as_mut_slice)
                 .unwrap_or(&mut []);
             location_events.sort_unstable_by_key(|event| event.date_time);
             // Try to find an existing message that corresponds to this location
-            let embed_author = location.name();
-            let location_message = latest_messages.iter_mut().find(|message| {
-                message
-                    .embeds
-                    .first()
-                    .and_then(|embed| embed.author.as_ref())
-                    .map(|author| author.name == embed_author)
-                    .unwrap_or(false)
-            });
+            let location_message = find_location_message(&mut latest_messages, &location.name);
             if let Some(message) = location_message {
                 // Edit the existing message
                 let message_builder = EditMessage::new().embed(Self::build_embed(
                     static_file_prefix,
-                    *location,
+                    location,
                     location_events,
                 ));
                 message.edit(discord_api, message_builder).await?;
@@ -238,7 +85,7 @@ impl EventCollector {
                 // Post a new message
                 let message_builder = CreateMessage::new().embed(Self::build_embed(
                     static_file_prefix,
-                    *location,
+                    location,
                     location_events,
                 ));
                 channel_id
@@ -249,16 +96,45 @@ impl EventCollector {
         Ok(())
     }
 
+    // The embeds are found by their author, which is the location name. So
+    // when a location gets renamed its existing message needs to follow, or
+    // the next update posts a second message for it.
+    pub async fn rename_location_message(
+        discord_api: &crate::discord::CacheAndHttp,
+        channel_id: ChannelId,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut latest_messages = channel_id
+            .messages(&discord_api.http, GetMessages::new().limit(20))
+            .await?;
+        let Some(message) = find_location_message(&mut latest_messages, old_name) else {
+            return Ok(());
+        };
+        let embed = message.embeds[0].clone();
+        let author = CreateEmbedAuthor::from(
+            embed
+                .author
+                .clone()
+                .expect("Location message embed does not have an author"),
+        )
+        .name(new_name);
+        let message_builder = EditMessage::new().embed(CreateEmbed::from(embed).author(author));
+        message.edit(discord_api, message_builder).await?;
+        Ok(())
+    }
+
     fn build_embed<'a>(
         static_file_prefix: &'_ str,
-        location: Location,
+        location: &Location,
         events: &'_ [&'_ CommonEventDetails],
     ) -> serenity::builder::CreateEmbed {
         let footer_text = chrono::Utc::now()
             .with_timezone(&chrono_tz::Europe::Zurich)
             .format("Last update at %H:%M")
             .to_string();
-        let mut description = "Updated every 15 minutes".to_string();
+        let mut description =
+            "Updated every 15 minutes. React to get a DM about new games here.".to_string();
         for event in events {
             let free_spots = event.num_free_spots();
             description.push_str("\n\n");
@@ -282,17 +158,16 @@ impl EventCollector {
             )
             .ok();
         }
-        CreateEmbed::new()
-            .author(
-                CreateEmbedAuthor::new(location.name())
-                    .icon_url(format!("{}SwissRPG-logo-128.png", static_file_prefix))
-                    .url(location.meetup_group_link()),
-            )
-            .thumbnail(format!(
-                "{}thumbnail_{}.png",
-                static_file_prefix,
-                location.flag_name()
-            ))
+        let mut author = CreateEmbedAuthor::new(&location.name)
+            .icon_url(format!("{}SwissRPG-logo-128.png", static_file_prefix));
+        if let Some(group_link) = &location.group_link {
+            author = author.url(group_link);
+        }
+        let mut embed = CreateEmbed::new().author(author);
+        if let Some(icon_url) = location.icon_url(static_file_prefix) {
+            embed = embed.thumbnail(icon_url);
+        }
+        embed
             .title(if events.is_empty() {
                 "All games are fully booked. Roll for initiative!"
             } else {
@@ -305,49 +180,79 @@ impl EventCollector {
 
     // Returns all events for which a location can be determined, grouped by
     // their respective locations
-    fn localized_events<'event>(
+    fn localized_events<'event, 'location>(
         events: &[&'event CommonEventDetails],
-    ) -> HashMap<Location, Vec<&'event CommonEventDetails>> {
+        locations: &'location Locations,
+    ) -> HashMap<&'location str, Vec<&'event CommonEventDetails>> {
         // Try to assign each event to one of our cities or the online category
-        let mut location_events: HashMap<Location, Vec<&CommonEventDetails>> = HashMap::new();
+        let mut location_events: HashMap<&str, Vec<&CommonEventDetails>> = HashMap::new();
         for event in events {
-            if let Some(location) = Self::event_location(event) {
-                location_events.entry(location).or_default().push(event);
+            if let Some(location) = Self::event_location(event, locations) {
+                location_events
+                    .entry(location.code.as_str())
+                    .or_default()
+                    .push(event);
             }
         }
         location_events
     }
 
     // Figure out which location (if any) an event belongs to
-    fn event_location(event: &CommonEventDetails) -> Option<Location> {
+    pub(crate) fn event_location<'location>(
+        event: &CommonEventDetails,
+        locations: &'location Locations,
+    ) -> Option<&'location Location> {
         // Is this event online?
         if event.is_online
             || crate::meetup::sync::ONLINE_REGEX
                 .is_match(event.description.as_deref().unwrap_or(""))
         {
-            return Some(Location::Online);
+            return locations.online();
         }
         let venue = match &event.venue {
             Some(venue) => venue,
             None => return None,
         };
         // Doesn't seem to be an online event.
+        // SwissRPG events come with a location code
+        if let Some(location) = venue
+            .location_code
+            .as_deref()
+            .and_then(|code| locations.find_by_code(code))
+        {
+            return Some(location);
+        }
         // Check if we know the city by name
         if let Some(city) = &venue.city {
-            if let Some(location) = Location::find_by_city(city) {
+            if let Some(location) = locations.find_by_city(city) {
                 return Some(location);
             }
         }
         // We will use latitude and longitude to figure out the city instead
-        let point = Point::new(venue.lng, venue.lat);
-        let location = Location::closest(point);
-        Some(location)
+        match (venue.lat, venue.lng) {
+            (Some(lat), Some(lng)) => locations.closest(Point::new(lng, lat)),
+            _ => None,
+        }
     }
 }
 
+fn find_location_message<'a>(
+    messages: &'a mut [Message],
+    location_name: &str,
+) -> Option<&'a mut Message> {
+    messages.iter_mut().find(|message| {
+        message
+            .embeds
+            .first()
+            .and_then(|embed| embed.author.as_ref())
+            .is_some_and(|author| author.name == location_name)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::location::test_locations;
     use chrono::Utc;
     use uuid::Uuid;
 
@@ -362,6 +267,7 @@ mod tests {
             num_free_spots: 1,
             rsvps_closed: false,
             short_url: "https://example.com".to_string(),
+            game_systems: vec![],
         }
     }
 
@@ -369,14 +275,21 @@ mod tests {
     fn event_without_venue_is_not_classified_as_online_by_default() {
         let event = test_event(None, false);
 
-        assert_eq!(EventCollector::event_location(&event), None);
+        assert_eq!(
+            EventCollector::event_location(&event, &test_locations()),
+            None
+        );
     }
 
     #[test]
     fn event_without_venue_but_marked_online_is_classified_as_online() {
         let event = test_event(None, true);
 
-        assert_eq!(EventCollector::event_location(&event), Some(Location::Online));
+        let locations = test_locations();
+        assert_eq!(
+            EventCollector::event_location(&event, &locations),
+            locations.online()
+        );
     }
 
     #[test]
@@ -407,6 +320,10 @@ mod tests {
 
         let event = CommonEventDetails::from((&event_series, &session));
 
-        assert_eq!(EventCollector::event_location(&event), Some(Location::Online));
+        let locations = test_locations();
+        assert_eq!(
+            EventCollector::event_location(&event, &locations),
+            locations.online()
+        );
     }
 }
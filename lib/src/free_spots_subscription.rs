@@ -0,0 +1,472 @@
+// Players can subscribe to games with free spots instead of watching the free
+// spots channel. After each sync, subscribers get a DM about matching events
+// that they haven't been told about yet.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::Datelike;
+use serenity::{builder::CreateMessage, model::id::UserId};
+
+use crate::{
+    common_event::CommonEventDetails,
+    db,
+    free_spots::EventCollector,
+    location::{Location, Locations},
+    strings,
+};
+
+// More events than this are summarized in a single DM
+const MAX_EVENTS_PER_NOTIFICATION: usize = 10;
+
+// Filters that are not set match every event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriptionFilter {
+    // Location code
+    pub location: Option<String>,
+    pub is_online: Option<bool>,
+    pub weekdays: Vec<chrono::Weekday>,
+    pub game_system: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub id: i32,
+    pub filter: SubscriptionFilter,
+}
+
+fn parse_weekday(input: &str) -> Option<chrono::Weekday> {
+    let weekday = match input {
+        "mon" | "monday" | "mondays" => chrono::Weekday::Mon,
+        "tue" | "tuesday" | "tuesdays" => chrono::Weekday::Tue,
+        "wed" | "wednesday" | "wednesdays" => chrono::Weekday::Wed,
+        "thu" | "thursday" | "thursdays" => chrono::Weekday::Thu,
+        "fri" | "friday" | "fridays" => chrono::Weekday::Fri,
+        "sat" | "saturday" | "saturdays" => chrono::Weekday::Sat,
+        "sun" | "sunday" | "sundays" => chrono::Weekday::Sun,
+        _ => return None,
+    };
+    Some(weekday)
+}
+
+impl SubscriptionFilter {
+    // Parses filters like "zurich in-person sat,sun system:Pathfinder". The
+    // game system has to come last since its name may contain spaces.
+    pub fn parse(input: &str, locations: &Locations) -> Result<Self, String> {
+        let mut filter = SubscriptionFilter::default();
+        // ASCII lowercasing keeps the byte offsets of the original input
+        let (input, game_system) = match input.to_ascii_lowercase().find("system:") {
+            Some(index) => (
+                &input[..index],
+                Some(input[index + "system:".len()..].trim()),
+            ),
+            None => (input, None),
+        };
+        if let Some(game_system) = game_system {
+            if game_system.is_empty() {
+                return Err("Please specify a game system after \"system:\"".to_string());
+            }
+            filter.game_system = Some(game_system.to_string());
+        }
+        for token in input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+        {
+            let token = token.to_lowercase();
+            if let Some(weekday) = parse_weekday(&token) {
+                if !filter.weekdays.contains(&weekday) {
+                    filter.weekdays.push(weekday);
+                }
+                continue;
+            }
+            match token.as_str() {
+                "online" => filter.is_online = Some(true),
+                "in-person" | "inperson" | "offline" => filter.is_online = Some(false),
+                _ => match locations.find_by_name(&token) {
+                    Some(location) => filter.location = Some(location.code.clone()),
+                    None => return Err(format!("I don't know what \"{}\" means", token)),
+                },
+            }
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, event: &CommonEventDetails, locations: &Locations) -> bool {
+        let location = EventCollector::event_location(event, locations);
+        if let Some(wanted_location) = &self.location {
+            if location.map(|location| &location.code) != Some(wanted_location) {
+                return false;
+            }
+        }
+        if let Some(is_online) = self.is_online {
+            if location.is_some_and(|location| location.is_online) != is_online {
+                return false;
+            }
+        }
+        if !self.weekdays.is_empty() {
+            let weekday = event
+                .date_time
+                .with_timezone(&chrono_tz::Europe::Zurich)
+                .weekday();
+            if !self.weekdays.contains(&weekday) {
+                return false;
+            }
+        }
+        if let Some(game_system) = &self.game_system {
+            let game_system = game_system.to_lowercase();
+            if !event
+                .game_systems
+                .iter()
+                .any(|system| system.to_lowercase().contains(&game_system))
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn describe(&self, locations: &Locations) -> String {
+        let mut parts = vec![];
+        if let Some(code) = &self.location {
+            match locations.find_by_code(code) {
+                Some(location) => parts.push(location.name.clone()),
+                None => parts.push(code.clone()),
+            }
+        }
+        match self.is_online {
+            Some(true) => parts.push("online".to_string()),
+            Some(false) => parts.push("in person".to_string()),
+            None => (),
+        }
+        if !self.weekdays.is_empty() {
+            parts.push(
+                self.weekdays
+                    .iter()
+                    .map(|weekday| weekday.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+        if let Some(game_system) = &self.game_system {
+            parts.push(format!("system: {}", game_system));
+        }
+        if parts.is_empty() {
+            "all games".to_string()
+        } else {
+            parts.join(" · ")
+        }
+    }
+
+    fn from_db(
+        location: Option<String>,
+        is_online: Option<bool>,
+        weekdays: Option<Vec<i32>>,
+        game_system: Option<String>,
+    ) -> Self {
+        SubscriptionFilter {
+            location,
+            is_online,
+            weekdays: weekdays
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|weekday| {
+                    chrono::Weekday::try_from((weekday - 1).clamp(0, 6) as u8).ok()
+                })
+                .collect(),
+            game_system,
+        }
+    }
+}
+
+pub async fn subscribe(
+    user_id: UserId,
+    filter: &SubscriptionFilter,
+    db_connection: &sqlx::PgPool,
+) -> Result<i32, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    let weekdays: Option<Vec<i32>> = if filter.weekdays.is_empty() {
+        None
+    } else {
+        Some(
+            filter
+                .weekdays
+                .iter()
+                .map(|weekday| weekday.number_from_monday() as i32)
+                .collect(),
+        )
+    };
+    let subscription_id = sqlx::query_scalar!(
+        r#"INSERT INTO free_spots_subscription (member_id, "location", is_online, weekdays, game_system)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING id"#,
+        member_id.0,
+        filter.location.as_deref(),
+        filter.is_online,
+        weekdays.as_deref(),
+        filter.game_system.as_deref()
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(subscription_id)
+}
+
+// Removes a single subscription or all of them. Returns the number of removed
+// subscriptions.
+pub async fn unsubscribe(
+    user_id: UserId,
+    subscription_id: Option<i32>,
+    db_connection: &sqlx::PgPool,
+) -> Result<u64, crate::meetup::Error> {
+    let num_removed = sqlx::query!(
+        r#"DELETE FROM free_spots_subscription
+        USING "member"
+        WHERE free_spots_subscription.member_id = "member".id AND "member".discord_id = $1
+        AND ($2::integer IS NULL OR free_spots_subscription.id = $2)"#,
+        user_id.get() as i64,
+        subscription_id
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected();
+    Ok(num_removed)
+}
+
+// Used for reactions in the free spots channel. Reacting more than once to
+// the same location doesn't create duplicate subscriptions.
+pub async fn subscribe_location(
+    user_id: UserId,
+    location: &Location,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    sqlx::query!(
+        r#"INSERT INTO free_spots_subscription (member_id, "location")
+        SELECT $1, $2
+        WHERE NOT EXISTS (
+            SELECT 1 FROM free_spots_subscription
+            WHERE member_id = $1 AND "location" = $2 AND is_online IS NULL AND weekdays IS NULL AND game_system IS NULL
+        )"#,
+        member_id.0,
+        &location.code
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn unsubscribe_location(
+    user_id: UserId,
+    location: &Location,
+    db_connection: &sqlx::PgPool,
+) -> Result<u64, crate::meetup::Error> {
+    let num_removed = sqlx::query!(
+        r#"DELETE FROM free_spots_subscription
+        USING "member"
+        WHERE free_spots_subscription.member_id = "member".id AND "member".discord_id = $1
+        AND free_spots_subscription."location" = $2 AND is_online IS NULL AND weekdays IS NULL AND game_system IS NULL"#,
+        user_id.get() as i64,
+        &location.code
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected();
+    Ok(num_removed)
+}
+
+pub async fn get_subscriptions(
+    user_id: UserId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<Subscription>, crate::meetup::Error> {
+    let subscriptions = sqlx::query!(
+        r#"SELECT free_spots_subscription.id, free_spots_subscription."location", free_spots_subscription.is_online,
+            free_spots_subscription.weekdays, free_spots_subscription.game_system
+        FROM free_spots_subscription
+        INNER JOIN "member" ON free_spots_subscription.member_id = "member".id
+        WHERE "member".discord_id = $1
+        ORDER BY free_spots_subscription.id"#,
+        user_id.get() as i64
+    )
+    .map(|row| Subscription {
+        id: row.id,
+        filter: SubscriptionFilter::from_db(
+            row.location,
+            row.is_online,
+            row.weekdays,
+            row.game_system,
+        ),
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(subscriptions)
+}
+
+impl EventCollector {
+    // DMs subscribers about open events matching their filters. Every member
+    // hears about every event at most once.
+    pub async fn notify_subscribers(
+        &self,
+        locations: &Locations,
+        db_connection: &sqlx::PgPool,
+        discord_api: &crate::discord::CacheAndHttp,
+    ) -> Result<(), crate::meetup::Error> {
+        let events = self.open_events();
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut subscribers: HashMap<db::MemberId, (UserId, Vec<SubscriptionFilter>)> =
+            HashMap::new();
+        let subscriptions = sqlx::query!(
+            r#"SELECT free_spots_subscription.member_id, "member".discord_id AS "discord_id!",
+                free_spots_subscription."location", free_spots_subscription.is_online,
+                free_spots_subscription.weekdays, free_spots_subscription.game_system
+            FROM free_spots_subscription
+            INNER JOIN "member" ON free_spots_subscription.member_id = "member".id
+            WHERE "member".discord_id IS NOT NULL"#
+        )
+        .fetch_all(db_connection)
+        .await?;
+        for row in subscriptions {
+            subscribers
+                .entry(db::MemberId(row.member_id))
+                .or_insert_with(|| (UserId::new(row.discord_id as u64), vec![]))
+                .1
+                .push(SubscriptionFilter::from_db(
+                    row.location,
+                    row.is_online,
+                    row.weekdays,
+                    row.game_system,
+                ));
+        }
+        if subscribers.is_empty() {
+            return Ok(());
+        }
+        let event_keys: Vec<String> = events.iter().map(|event| event.id.clone()).collect();
+        let already_notified: HashSet<(i32, String)> = sqlx::query!(
+            r#"SELECT member_id, event_key FROM free_spots_notification WHERE event_key = ANY($1)"#,
+            &event_keys
+        )
+        .map(|row| (row.member_id, row.event_key))
+        .fetch_all(db_connection)
+        .await?
+        .into_iter()
+        .collect();
+        for (member_id, (user_id, filters)) in subscribers {
+            let new_events: Vec<&CommonEventDetails> = events
+                .iter()
+                .copied()
+                .filter(|event| {
+                    filters
+                        .iter()
+                        .any(|filter| filter.matches(event, locations))
+                })
+                .filter(|event| !already_notified.contains(&(member_id.0, event.id.clone())))
+                .collect();
+            if new_events.is_empty() {
+                continue;
+            }
+            let message =
+                strings::FREE_SPOTS_NOTIFICATION(&new_events, MAX_EVENTS_PER_NOTIFICATION);
+            let dm_result = match user_id.create_dm_channel(&discord_api.http).await {
+                Ok(channel) => channel
+                    .id
+                    .send_message(&discord_api.http, CreateMessage::new().content(message))
+                    .await
+                    .map(|_| ()),
+                Err(err) => Err(err),
+            };
+            if let Err(err) = dm_result {
+                eprintln!(
+                    "Could not send free spots notification to {}:\n{:#?}",
+                    user_id, err
+                );
+            }
+            // Also remembered if the DM failed (e.g. because the user doesn't
+            // accept DMs), so that we don't retry on every sync
+            let new_event_keys: Vec<String> =
+                new_events.iter().map(|event| event.id.clone()).collect();
+            sqlx::query!(
+                r#"INSERT INTO free_spots_notification (member_id, event_key)
+                SELECT $1, event_key FROM UNNEST($2::text[]) AS event_key
+                ON CONFLICT DO NOTHING"#,
+                member_id.0,
+                &new_event_keys
+            )
+            .execute(db_connection)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::test_locations;
+
+    #[test]
+    fn parse_filters() {
+        let locations = test_locations();
+        let parse = |input| SubscriptionFilter::parse(input, &locations);
+        assert_eq!(parse("").unwrap(), SubscriptionFilter::default());
+        let filter = parse("Zürich in-person sat,Sunday system: Call of Cthulhu").unwrap();
+        assert_eq!(filter.location.as_deref(), Some("zurich"));
+        assert_eq!(filter.is_online, Some(false));
+        assert_eq!(
+            filter.weekdays,
+            vec![chrono::Weekday::Sat, chrono::Weekday::Sun]
+        );
+        assert_eq!(filter.game_system.as_deref(), Some("Call of Cthulhu"));
+        assert_eq!(
+            parse("st.gallen").unwrap().location.as_deref(),
+            Some("st_gallen")
+        );
+        assert!(parse("atlantis").is_err());
+        assert!(parse("system:").is_err());
+        assert!(parse("İİİİİİİİ system:Ōkami").is_err());
+        assert_eq!(
+            parse("online system:Das Schwarze Auge")
+                .unwrap()
+                .game_system
+                .as_deref(),
+            Some("Das Schwarze Auge")
+        );
+        assert_eq!(
+            parse("Zürich SYSTEM:Ōkami").unwrap().game_system.as_deref(),
+            Some("Ōkami")
+        );
+    }
+
+    #[test]
+    fn filter_matching() {
+        let locations = test_locations();
+        let matches = |input, event: &CommonEventDetails| {
+            SubscriptionFilter::parse(input, &locations)
+                .unwrap()
+                .matches(event, &locations)
+        };
+        let event = CommonEventDetails {
+            id: "1".to_string(),
+            title: "Test".to_string(),
+            description: None,
+            // A Saturday evening in Zurich
+            date_time: chrono::DateTime::parse_from_rfc3339("2026-10-24T18:00:00+02:00")
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+            venue: None,
+            is_online: true,
+            num_free_spots: 2,
+            rsvps_closed: false,
+            short_url: "https://example.com".to_string(),
+            game_systems: vec!["Pathfinder 2e".to_string()],
+        };
+        assert!(matches("", &event));
+        assert!(matches("online saturday system:pathfinder", &event));
+        assert!(!matches("in-person", &event));
+        assert!(!matches("basel", &event));
+        assert!(!matches("sunday", &event));
+        assert!(!matches("system:D&D", &event));
+    }
+}
@@ -0,0 +1,416 @@
+// Everything the bot stores about a member, for data export requests, and the
+// erasure of it. Past sessions, channels and transcripts stay, but nothing in
+// them points to the erased member anymore. The member's messages in
+// transcripts are kept without their author.
+
+use std::path::Path;
+
+use serde_json::json;
+use serenity::model::id::UserId;
+
+use crate::{
+    audit::{AuditEntry, Auditor},
+    db,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErasureResult {
+    Erased {
+        num_stripe_customers: usize,
+        // Messages in the audit log channel that still mention the member
+        num_unedited_log_messages: usize,
+    },
+    // Hosts have to hand their upcoming sessions over first
+    HostsUpcomingSessions,
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.to_rfc3339()
+}
+
+async fn member_discord_id(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<UserId>, crate::meetup::Error> {
+    let discord_id = sqlx::query_scalar!(
+        r#"SELECT discord_id FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten();
+    Ok(discord_id.map(|id| UserId::new(id as u64)))
+}
+
+// All records about the member as JSON. Secrets like OAuth2 tokens are only
+// reported as present, not exported.
+pub async fn export_member_data(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+    stripe_client: &stripe::Client,
+    transcript_directory: &Path,
+) -> Result<serde_json::Value, crate::meetup::Error> {
+    let member = sqlx::query!(
+        r#"SELECT id, meetup_id, discord_id, discord_nick, supporter_tier,
+            meetup_oauth2_access_token IS NOT NULL AS "has_meetup_oauth2_tokens!",
+            meetup_oauth2_last_token_refresh_time,
+            calendar_token IS NOT NULL AS "has_calendar_token!"
+        FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    let sessions: Vec<_> = sqlx::query!(
+        r#"SELECT event.id, event.event_series_id, event.title, event.start_time, event_participant.from_discord
+        FROM event_participant
+        INNER JOIN event ON event_participant.event_id = event.id
+        WHERE event_participant.member_id = $1
+        ORDER BY event.start_time"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "event_id": row.id,
+            "event_series_id": row.event_series_id,
+            "title": row.title,
+            "start_time": format_time(row.start_time),
+            "signed_up_on_discord": row.from_discord,
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let hosted_sessions: Vec<_> = sqlx::query!(
+        r#"SELECT event.id, event.event_series_id, event.title, event.start_time
+        FROM event_host
+        INNER JOIN event ON event_host.event_id = event.id
+        WHERE event_host.member_id = $1
+        ORDER BY event.start_time"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "event_id": row.id,
+            "event_series_id": row.event_series_id,
+            "title": row.title,
+            "start_time": format_time(row.start_time),
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let waitlists: Vec<_> = sqlx::query!(
+        r#"SELECT event.id, event.title, event_waitlist.joined
+        FROM event_waitlist
+        INNER JOIN event ON event_waitlist.event_id = event.id
+        WHERE event_waitlist.member_id = $1
+        ORDER BY event_waitlist.joined"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "event_id": row.id,
+            "title": row.title,
+            "joined": format_time(row.joined),
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let attendance: Vec<_> = sqlx::query!(
+        r#"SELECT event.id, event.title, event_attendance.attended, event_attendance.marked_time
+        FROM event_attendance
+        INNER JOIN event ON event_attendance.event_id = event.id
+        WHERE event_attendance.member_id = $1
+        ORDER BY event.start_time"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "event_id": row.id,
+            "title": row.title,
+            "attended": row.attended,
+            "marked_time": format_time(row.marked_time),
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let removals: Vec<_> = sqlx::query!(
+        r#"SELECT event_series_id AS "event_series_id!", removal_time AS "removal_time!", "role" AS "role!"
+        FROM (
+            SELECT event_series_id, removal_time, 'host' AS "role" FROM event_series_removed_host WHERE member_id = $1
+            UNION ALL
+            SELECT event_series_id, removal_time, 'player' AS "role" FROM event_series_removed_user WHERE member_id = $1
+        ) AS removal
+        ORDER BY removal_time"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "event_series_id": row.event_series_id,
+            "removed_as": row.role,
+            "removal_time": format_time(row.removal_time),
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let free_spots_subscriptions: Vec<_> = sqlx::query!(
+        r#"SELECT "location", is_online, weekdays, game_system, created
+        FROM free_spots_subscription
+        WHERE member_id = $1
+        ORDER BY created"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "location": row.location,
+            "is_online": row.is_online,
+            "weekdays": row.weekdays,
+            "game_system": row.game_system,
+            "created": format_time(row.created),
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let free_spots_notifications: Vec<_> = sqlx::query!(
+        r#"SELECT event_key, notified FROM free_spots_notification WHERE member_id = $1 ORDER BY notified"#,
+        member_id.0
+    )
+    .map(|row| {
+        json!({
+            "event": row.event_key,
+            "notified": format_time(row.notified),
+        })
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let web_sessions: Vec<_> = sqlx::query!(
+        r#"SELECT last_used FROM web_session WHERE member_id = $1 ORDER BY last_used"#,
+        member_id.0
+    )
+    .map(|row| json!({ "last_used": format_time(row.last_used) }))
+    .fetch_all(db_connection)
+    .await?;
+    let (audit_log, stripe_customers, transcripts) = match member.discord_id {
+        Some(discord_id) => {
+            let audit_log: Vec<_> = sqlx::query!(
+                r#"SELECT "time", "source", "action", actor_discord_id = $1 AS "is_actor!"
+                FROM audit_log
+                WHERE actor_discord_id = $1 OR target_user_discord_id = $1
+                ORDER BY "time", id"#,
+                discord_id
+            )
+            .map(|row| {
+                json!({
+                    "time": format_time(row.time),
+                    "source": row.source,
+                    "action": row.action,
+                    "done_by_member": row.is_actor,
+                })
+            })
+            .fetch_all(db_connection)
+            .await?;
+            let stripe_customers: Vec<_> = crate::stripe::find_customers_by_discord_id(
+                stripe_client,
+                UserId::new(discord_id as u64),
+            )
+            .await?
+            .into_iter()
+            .map(|customer| {
+                let metadata = customer.metadata.unwrap_or_default();
+                json!({
+                    "customer_id": customer.id.as_str(),
+                    "discord_id": metadata.get(crate::stripe::DISCORD_ID_METADATA_KEY),
+                    "discord_username": metadata.get(crate::stripe::DISCORD_USERNAME_METADATA_KEY),
+                })
+            })
+            .collect();
+            let transcripts = crate::discord::transcript::get_authored_messages(
+                UserId::new(discord_id as u64),
+                transcript_directory,
+                db_connection,
+            )
+            .await?;
+            (audit_log, stripe_customers, transcripts)
+        }
+        None => (vec![], vec![], vec![]),
+    };
+    Ok(json!({
+        "exported": format_time(chrono::Utc::now()),
+        "member": {
+            "id": member.id,
+            "discord_id": member.discord_id.map(|id| id.to_string()),
+            "discord_nick": member.discord_nick,
+            "meetup_id": member.meetup_id.map(|id| id.to_string()),
+            "supporter_tier": member.supporter_tier,
+            "has_meetup_oauth2_tokens": member.has_meetup_oauth2_tokens,
+            "meetup_oauth2_last_token_refresh_time": member.meetup_oauth2_last_token_refresh_time.map(format_time),
+            "has_calendar_token": member.has_calendar_token,
+        },
+        "sessions": sessions,
+        "hosted_sessions": hosted_sessions,
+        "waitlists": waitlists,
+        "attendance": attendance,
+        "removed_from_games": removals,
+        "free_spots_subscriptions": free_spots_subscriptions,
+        "free_spots_notifications": free_spots_notifications,
+        "web_sessions": web_sessions,
+        "audit_log": audit_log,
+        "stripe_customers": stripe_customers,
+        "transcript_messages": transcripts,
+    }))
+}
+
+// Deletes the member with everything that refers to them and removes their
+// Discord ID from the audit log and its mirrored messages, the transcripts and
+// Stripe. Signing up for a
+// game again creates a new member. Every erasure is recorded, but without the
+// Discord ID, even if the member erased their own data.
+pub async fn erase_member_data(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+    stripe_client: &stripe::Client,
+    transcript_directory: &Path,
+    auditor: &Auditor<'_>,
+) -> Result<ErasureResult, crate::meetup::Error> {
+    let hosts_upcoming_sessions = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM event_host
+            INNER JOIN event ON event_host.event_id = event.id
+            WHERE event_host.member_id = $1 AND event.start_time > NOW() AND event.deleted IS NULL
+        ) AS "exists!""#,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    if hosts_upcoming_sessions {
+        return Ok(ErasureResult::HostsUpcomingSessions);
+    }
+    let discord_id = member_discord_id(member_id, db_connection).await?;
+    let audit_entry = AuditEntry::data_erased(member_id);
+    let erasure_result = erase(
+        member_id,
+        discord_id,
+        db_connection,
+        stripe_client,
+        transcript_directory,
+        auditor,
+    )
+    .await;
+    let audit_entry = match &erasure_result {
+        Ok(_) => audit_entry,
+        Err(err) => audit_entry.failed(err),
+    };
+    auditor.record_without_user(discord_id, audit_entry).await;
+    erasure_result
+}
+
+async fn erase(
+    member_id: db::MemberId,
+    discord_id: Option<UserId>,
+    db_connection: &sqlx::PgPool,
+    stripe_client: &stripe::Client,
+    transcript_directory: &Path,
+    auditor: &Auditor<'_>,
+) -> Result<ErasureResult, crate::meetup::Error> {
+    // Stripe and the transcripts come first, such that a failure there can be
+    // retried while the member still exists
+    let mut num_stripe_customers = 0;
+    if let Some(discord_id) = discord_id {
+        for customer in
+            crate::stripe::find_customers_by_discord_id(stripe_client, discord_id).await?
+        {
+            crate::stripe::remove_discord_metadata(stripe_client, &customer.id).await?;
+            num_stripe_customers += 1;
+        }
+        crate::discord::transcript::anonymise_author(
+            discord_id,
+            transcript_directory,
+            db_connection,
+        )
+        .await?;
+    }
+    let mut tx = db_connection.begin().await?;
+    let mut audit_log_ids = vec![];
+    if let Some(discord_id) = discord_id {
+        let discord_id = discord_id.get() as i64;
+        sqlx::query!(r#"DELETE FROM web_flow WHERE discord_id = $1"#, discord_id)
+            .execute(&mut *tx)
+            .await?;
+        audit_log_ids = sqlx::query_scalar!(
+            r#"UPDATE audit_log
+            SET actor_discord_id = NULLIF(actor_discord_id, $1), target_user_discord_id = NULLIF(target_user_discord_id, $1)
+            WHERE actor_discord_id = $1 OR target_user_discord_id = $1
+            RETURNING id"#,
+            discord_id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+    }
+    sqlx::query!(
+        r#"UPDATE event_attendance SET marked_by = NULL WHERE marked_by = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_attendance WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_participant WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_host WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_waitlist WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_series_removed_host WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM event_series_removed_user WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM free_spots_subscription WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM free_spots_notification WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"DELETE FROM web_session WHERE member_id = $1"#,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(r#"DELETE FROM "member" WHERE id = $1"#, member_id.0)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    let num_unedited_log_messages = auditor.update_mirrors(&audit_log_ids).await;
+    Ok(ErasureResult::Erased {
+        num_stripe_customers,
+        num_unedited_log_messages,
+    })
+}
@@ -1,15 +1,24 @@
 #![forbid(unsafe_code)]
 #![warn(rust_2018_idioms)]
+pub mod attendance;
+pub mod audit;
+pub mod calendar;
 pub mod common_event;
 pub mod db;
 pub mod discord;
 pub mod end_adventure;
 pub mod error;
+pub mod expiration_policy;
 pub mod flow;
-mod free_spots;
+pub mod free_spots;
+pub mod free_spots_subscription;
+pub mod gdpr;
+pub mod location;
 pub mod meetup;
-pub mod redis;
+pub mod profile;
 pub mod role_shortcode;
+pub mod series_settings;
+pub mod stats;
 pub mod strings;
 pub mod stripe;
 pub mod swissrpg;
@@ -0,0 +1,436 @@
+// Cities (and "online") that games are grouped by, e.g. in the free spots
+// channel. They live in the database so that organisers can open a new city
+// with the "location" commands instead of a code change.
+
+use geo::{euclidean_distance::EuclideanDistance, Point};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    // Same as the SwissRPG location tag code
+    pub code: String,
+    pub name: String,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    // "#RRGGBB"
+    pub color: String,
+    // File name in the static file directory or a full URL
+    pub icon: Option<String>,
+    pub group_link: Option<String>,
+    pub sort_order: i32,
+    pub is_online: bool,
+    // Other names that lookups accept, e.g. "Ticino" for Lugano
+    pub aliases: Vec<String>,
+}
+
+impl Location {
+    pub fn lat_lon(&self) -> Option<Point<f64>> {
+        match (self.lat, self.lng) {
+            (Some(lat), Some(lng)) => Some(Point::new(lng, lat)),
+            _ => None,
+        }
+    }
+
+    pub fn color(&self) -> (u8, u8, u8) {
+        let color = u32::from_str_radix(self.color.trim_start_matches('#'), 16).unwrap_or(0);
+        ((color >> 16) as u8, (color >> 8) as u8, color as u8)
+    }
+
+    pub fn icon_url(&self, static_file_prefix: &str) -> Option<String> {
+        self.icon.as_ref().map(|icon| {
+            if icon.starts_with("http://") || icon.starts_with("https://") {
+                icon.clone()
+            } else {
+                format!("{}{}", static_file_prefix, icon)
+            }
+        })
+    }
+}
+
+// All locations in display order
+#[derive(Debug, Clone, Default)]
+pub struct Locations {
+    locations: Vec<Location>,
+}
+
+impl Locations {
+    pub fn new(mut locations: Vec<Location>) -> Self {
+        locations.sort_by(|a, b| {
+            a.sort_order
+                .cmp(&b.sort_order)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        Locations { locations }
+    }
+
+    pub async fn load(db_connection: &sqlx::PgPool) -> Result<Self, crate::meetup::Error> {
+        let locations = sqlx::query_as!(
+            Location,
+            r#"SELECT code, "name", lat, lng, color, icon, group_link, sort_order, is_online,
+            aliases
+            FROM "location""#
+        )
+        .fetch_all(db_connection)
+        .await?;
+        Ok(Self::new(locations))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Location> {
+        self.locations.iter()
+    }
+
+    pub fn online(&self) -> Option<&Location> {
+        self.locations.iter().find(|location| location.is_online)
+    }
+
+    // Finds the closest location with coordinates to the given point
+    pub fn closest(&self, p: Point<f64>) -> Option<&Location> {
+        self.locations
+            .iter()
+            .filter_map(|location| {
+                location
+                    .lat_lon()
+                    .map(|lat_lon| (location, lat_lon.euclidean_distance(&p)))
+            })
+            .min_by(|(_, dist1), (_, dist2)| dist1.total_cmp(dist2))
+            .map(|(location, _)| location)
+    }
+
+    pub fn find_by_code(&self, code: &str) -> Option<&Location> {
+        self.locations.iter().find(|location| location.code == code)
+    }
+
+    pub fn find_by_city(&self, name: &str) -> Option<&Location> {
+        self.locations.iter().find(|location| location.name == name)
+    }
+
+    // Lenient lookup for user input like "zurich", "Zürich", "St. Gallen" or
+    // an alias like "Ticino"
+    pub fn find_by_name(&self, name: &str) -> Option<&Location> {
+        let normalize = |name: &str| -> String {
+            name.chars()
+                .map(|c| match c {
+                    'ä' | 'Ä' => 'a',
+                    'ö' | 'Ö' => 'o',
+                    'ü' | 'Ü' => 'u',
+                    'é' | 'É' | 'è' | 'È' => 'e',
+                    c => c.to_ascii_lowercase(),
+                })
+                .filter(char::is_ascii_alphanumeric)
+                .collect()
+        };
+        let name = normalize(name);
+        self.locations.iter().find(|location| {
+            normalize(&location.name) == name
+                || normalize(&location.code) == name
+                || location
+                    .aliases
+                    .iter()
+                    .any(|alias| normalize(alias) == name)
+        })
+    }
+}
+
+// A change to a single field of a location
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocationUpdate {
+    Name(String),
+    Coordinates(Option<(f64, f64)>),
+    Color(String),
+    Icon(Option<String>),
+    GroupLink(Option<String>),
+    SortOrder(i32),
+    Online(bool),
+    Aliases(Vec<String>),
+}
+
+impl LocationUpdate {
+    // "none" removes optional values
+    pub fn parse(field: &str, value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let optional = |value: &str| {
+            if value.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(value.to_string())
+            }
+        };
+        let update = match field.to_lowercase().as_str() {
+            "name" => LocationUpdate::Name(value.to_string()),
+            "coordinates" => {
+                if value.eq_ignore_ascii_case("none") {
+                    LocationUpdate::Coordinates(None)
+                } else {
+                    let coordinates = value
+                        .split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|part| !part.is_empty())
+                        .map(str::parse::<f64>)
+                        .collect::<Result<Vec<_>, _>>();
+                    match coordinates.as_deref() {
+                        Ok(&[lat, lng])
+                            if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) =>
+                        {
+                            LocationUpdate::Coordinates(Some((lat, lng)))
+                        }
+                        _ => {
+                            return Err(
+                                "Coordinates need to look like \"47.3769, 8.5417\"".to_string()
+                            )
+                        }
+                    }
+                }
+            }
+            "color" | "colour" => {
+                let color = value.trim_start_matches('#');
+                if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err("Colours need to look like \"#0073E5\"".to_string());
+                }
+                LocationUpdate::Color(format!("#{}", color.to_uppercase()))
+            }
+            "icon" => LocationUpdate::Icon(optional(value)),
+            "link" => LocationUpdate::GroupLink(optional(value)),
+            "order" => LocationUpdate::SortOrder(
+                value
+                    .parse()
+                    .map_err(|_| "The order needs to be a number".to_string())?,
+            ),
+            "online" => LocationUpdate::Online(match value.to_lowercase().as_str() {
+                "yes" | "true" => true,
+                "no" | "false" => false,
+                _ => return Err("Online needs to be \"yes\" or \"no\"".to_string()),
+            }),
+            "aliases" => LocationUpdate::Aliases(if value.eq_ignore_ascii_case("none") {
+                vec![]
+            } else {
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|alias| !alias.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            }),
+            _ => return Err(format!("Unknown location field \"{}\"", field)),
+        };
+        Ok(update)
+    }
+}
+
+// Codes are lowercase like the SwissRPG location tag codes, e.g. "st_gallen"
+pub fn is_valid_code(code: &str) -> bool {
+    !code.is_empty()
+        && code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+// Returns false if a location with that code already exists
+pub async fn add_location(
+    code: &str,
+    name: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let result = sqlx::query!(
+        r#"INSERT INTO "location" (code, "name", sort_order)
+        SELECT $1, $2, COALESCE(MAX(sort_order), 0) + 10 FROM "location"
+        ON CONFLICT (code) DO NOTHING"#,
+        code,
+        name
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// Returns false if there is no location with that code
+pub async fn update_location(
+    code: &str,
+    update: &LocationUpdate,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let result = match update {
+        LocationUpdate::Name(name) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET "name" = $2 WHERE code = $1"#,
+                code,
+                name
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::Coordinates(coordinates) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET lat = $2, lng = $3 WHERE code = $1"#,
+                code,
+                coordinates.map(|(lat, _)| lat),
+                coordinates.map(|(_, lng)| lng)
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::Color(color) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET color = $2 WHERE code = $1"#,
+                code,
+                color
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::Icon(icon) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET icon = $2 WHERE code = $1"#,
+                code,
+                icon.as_deref()
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::GroupLink(group_link) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET group_link = $2 WHERE code = $1"#,
+                code,
+                group_link.as_deref()
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::SortOrder(sort_order) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET sort_order = $2 WHERE code = $1"#,
+                code,
+                sort_order
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::Online(is_online) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET is_online = $2 WHERE code = $1"#,
+                code,
+                is_online
+            )
+            .execute(db_connection)
+            .await?
+        }
+        LocationUpdate::Aliases(aliases) => {
+            sqlx::query!(
+                r#"UPDATE "location" SET aliases = $2 WHERE code = $1"#,
+                code,
+                aliases
+            )
+            .execute(db_connection)
+            .await?
+        }
+    };
+    Ok(result.rows_affected() > 0)
+}
+
+// Adds the SwissRPG location tags that we don't know yet. They still need
+// coordinates and an icon afterwards. Returns the codes of the new locations.
+pub async fn import_swissrpg_locations(
+    swissrpg_client: &crate::swissrpg::client::SwissRPGClient,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<String>, crate::BoxedError> {
+    let tags = swissrpg_client
+        .get_tags_by_type(crate::swissrpg::schema::TAG_TYPE_LOCATION)
+        .await?;
+    let mut new_codes = vec![];
+    for tag in tags {
+        let is_online = tag.code == crate::swissrpg::schema::LOCATION_CODE_ONLINE;
+        let inserted = sqlx::query!(
+            r#"INSERT INTO "location" (code, "name", sort_order, is_online)
+            SELECT $1, $2, COALESCE(MAX(sort_order), 0) + 10, $3 FROM "location"
+            ON CONFLICT (code) DO NOTHING"#,
+            &tag.code,
+            &tag.value,
+            is_online
+        )
+        .execute(db_connection)
+        .await?
+        .rows_affected()
+            > 0;
+        if inserted {
+            new_codes.push(tag.code);
+        }
+    }
+    Ok(new_codes)
+}
+
+// A few locations for tests that don't have a database
+#[cfg(test)]
+pub(crate) fn test_locations() -> Locations {
+    let location = |code: &str, name: &str, lat_lng: Option<(f64, f64)>, sort_order| Location {
+        code: code.to_string(),
+        name: name.to_string(),
+        lat: lat_lng.map(|(lat, _)| lat),
+        lng: lat_lng.map(|(_, lng)| lng),
+        color: "#99AAB5".to_string(),
+        icon: None,
+        group_link: None,
+        sort_order,
+        is_online: code == "online",
+        aliases: vec![],
+    };
+    Locations::new(vec![
+        location("online", "Online", None, 40),
+        location("zurich", "Zürich", Some((47.376888, 8.541694)), 10),
+        location("st_gallen", "St. Gallen", Some((47.4256037, 9.3741491)), 20),
+        location("basel", "Basel", Some((47.559601, 7.588576)), 30),
+        Location {
+            aliases: vec!["Ticino".to_string()],
+            ..location("lugano", "Lugano", Some((46.003601, 8.953620)), 35)
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn location_lookups() {
+        let locations = test_locations();
+        assert_eq!(
+            locations
+                .iter()
+                .map(|location| location.code.as_str())
+                .collect::<Vec<_>>(),
+            vec!["zurich", "st_gallen", "basel", "lugano", "online"]
+        );
+        assert_eq!(locations.online().unwrap().code, "online");
+        assert_eq!(locations.find_by_name("zurich").unwrap().code, "zurich");
+        assert_eq!(
+            locations.find_by_name("St.Gallen").unwrap().code,
+            "st_gallen"
+        );
+        assert_eq!(locations.find_by_name("ticino").unwrap().code, "lugano");
+        assert!(locations.find_by_name("Graubünden").is_none());
+        // Winterthur is closest to Zurich
+        assert_eq!(
+            locations.closest(Point::new(8.7241, 47.4988)).unwrap().code,
+            "zurich"
+        );
+        assert_eq!(
+            LocationUpdate::parse("color", "0073e5"),
+            Ok(LocationUpdate::Color("#0073E5".to_string()))
+        );
+        assert_eq!(
+            LocationUpdate::parse("coordinates", "47.5, 8.7"),
+            Ok(LocationUpdate::Coordinates(Some((47.5, 8.7))))
+        );
+        assert!(LocationUpdate::parse("coordinates", "north").is_err());
+        assert!(is_valid_code("st_gallen"));
+        assert!(!is_valid_code("St. Gallen"));
+        assert!(!is_valid_code(""));
+        assert_eq!(
+            LocationUpdate::parse("aliases", "Ticino, Tessin"),
+            Ok(LocationUpdate::Aliases(vec![
+                "Ticino".to_string(),
+                "Tessin".to_string()
+            ]))
+        );
+        assert_eq!(
+            LocationUpdate::parse("aliases", "none"),
+            Ok(LocationUpdate::Aliases(vec![]))
+        );
+    }
+}
@@ -3,7 +3,6 @@ use std::num::ParseIntError;
 use askama::Error as AskamaError;
 use chrono::format::ParseError as ChronoParseError;
 use hyper::http::Error as HttpError;
-use redis::RedisError;
 use regex::Error as RegexError;
 use reqwest::Error as ReqwestError;
 use serenity::Error as SerenityError;
@@ -72,12 +71,6 @@ impl From<SimpleError> for Error {
     }
 }
 
-impl From<RedisError> for Error {
-    fn from(err: RedisError) -> Self {
-        Error::CommonError(err.into())
-    }
-}
-
 impl From<ChronoParseError> for Error {
     fn from(err: ChronoParseError) -> Self {
         Error::CommonError(err.into())
@@ -144,8 +137,20 @@ impl From<Elapsed> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::CommonError(err.into())
+    }
+}
+
 impl From<sqlx::Error> for Error {
     fn from(err: sqlx::Error) -> Self {
         Error::CommonError(err.into())
     }
 }
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::CommonError(err.into())
+    }
+}
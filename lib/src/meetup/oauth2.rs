@@ -1,29 +1,31 @@
 use oauth2::{
     basic::BasicClient, AuthUrl, ClientId, ClientSecret, RedirectUrl, TokenResponse, TokenUrl,
 };
-use redis::AsyncCommands;
 use serenity::model::id::UserId;
 use simple_error::SimpleError;
 use std::sync::Arc;
 
 use crate::db;
 
-// TODO: move into flow?
+// Linking a Meetup account through the link that the bot sends in a DM. The
+// link is valid for a day, but linking.rs shortens this to 10 minutes once it
+// was opened. The flow's CSRF token doubles as the OAuth2 state.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct MeetupLinking {}
+
+impl crate::flow::FlowState for MeetupLinking {
+    const KIND: &'static str = "meetup_linking";
+    const TTL_MINUTES: i32 = 24 * 60;
+}
+
+pub type MeetupLinkingFlow = crate::flow::Flow<MeetupLinking>;
+
 pub async fn generate_meetup_linking_link(
-    redis_connection: &mut redis::aio::MultiplexedConnection,
+    db_connection: &sqlx::PgPool,
     discord_id: UserId,
 ) -> Result<String, super::Error> {
-    let linking_id = crate::new_random_id(16);
-    // TODO: expire after a day or so? linking.rs adds a 10 min expiration after first opening
-    let redis_key = format!("meetup_linking:{}:discord_user", &linking_id);
-    if let Err::<(), _>(err) = redis_connection.set(&redis_key, discord_id.get()).await {
-        return Err(SimpleError::new(format!(
-            "Redis error when trying to generate Meetup linking link:\n{:#?}",
-            err
-        ))
-        .into());
-    }
-    return Ok(format!("{}/link/{}", crate::urls::BASE_URL, &linking_id));
+    let flow = MeetupLinkingFlow::new(db_connection, discord_id, MeetupLinking {}).await?;
+    return Ok(format!("{}/link/{}", crate::urls::BASE_URL, &flow.id));
 }
 
 #[derive(Clone)]
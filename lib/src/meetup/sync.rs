@@ -489,7 +489,8 @@ pub async fn sync_rsvps(
     }
     // Remove all users which are not attending
     sqlx::query!(
-        r#"DELETE FROM event_participant WHERE event_id = $1 AND NOT (member_id = ANY($2))"#,
+        r#"DELETE FROM event_participant
+        WHERE event_id = $1 AND NOT from_discord AND NOT (member_id = ANY($2))"#,
         event_id.0,
         &rsvp_yes_member_ids
             .iter()
@@ -501,7 +502,8 @@ pub async fn sync_rsvps(
     for member_id in rsvp_yes_member_ids {
         // Mark this member as attending
         sqlx::query!(
-            r#"INSERT INTO event_participant (event_id, member_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+            r#"INSERT INTO event_participant (event_id, member_id) VALUES ($1, $2)
+            ON CONFLICT (event_id, member_id) DO UPDATE SET from_discord = FALSE"#,
             event_id.0,
             member_id.0
         ).execute(&mut *tx).await?;
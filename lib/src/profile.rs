@@ -0,0 +1,73 @@
+// What the bot knows about a member, as shown on their profile page
+
+use serenity::model::id::UserId;
+
+use crate::db;
+
+pub struct Profile {
+    pub member_id: db::MemberId,
+    pub discord_id: Option<UserId>,
+    pub discord_nick: Option<String>,
+    pub meetup_id: Option<u64>,
+    pub supporter_tier: Option<String>,
+}
+
+pub struct ProfileSession {
+    pub series_id: db::EventSeriesId,
+    pub title: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub is_host: bool,
+}
+
+pub async fn get_profile(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<Profile>, crate::meetup::Error> {
+    let profile = sqlx::query!(
+        r#"SELECT discord_id, discord_nick, meetup_id, supporter_tier FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .map(|row| Profile {
+        member_id,
+        discord_id: row.discord_id.map(|id| UserId::new(id as u64)),
+        discord_nick: row.discord_nick,
+        meetup_id: row.meetup_id.map(|id| id as u64),
+        supporter_tier: row.supporter_tier,
+    })
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(profile)
+}
+
+// All sessions that the member signed up for or hosts, latest first
+pub async fn get_sessions(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<ProfileSession>, crate::meetup::Error> {
+    let sessions = sqlx::query!(
+        r#"SELECT event.event_series_id, event.title, event.start_time,
+            EXISTS (
+                SELECT 1 FROM event_host WHERE event_host.event_id = event.id AND event_host.member_id = $1
+            ) AS "is_host!"
+        FROM event
+        WHERE event.deleted IS NULL AND (
+            EXISTS (
+                SELECT 1 FROM event_participant
+                WHERE event_participant.event_id = event.id AND event_participant.member_id = $1
+            ) OR EXISTS (
+                SELECT 1 FROM event_host WHERE event_host.event_id = event.id AND event_host.member_id = $1
+            )
+        )
+        ORDER BY event.start_time DESC"#,
+        member_id.0
+    )
+    .map(|row| ProfileSession {
+        series_id: db::EventSeriesId(row.event_series_id),
+        title: row.title,
+        start_time: row.start_time,
+        is_host: row.is_host,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(sessions)
+}
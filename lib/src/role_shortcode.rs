@@ -13,6 +13,7 @@ impl EventCollector {
         meetup_client: Arc<Mutex<Option<Arc<super::meetup::newapi::AsyncClient>>>>,
         db_connection: &sqlx::PgPool,
         discord_api: &crate::discord::CacheAndHttp,
+        guild_config: &crate::discord::guild_config::GuildConfig,
     ) -> Result<(), crate::meetup::Error> {
         let meetup_client = {
             let guard = meetup_client.lock().await;
@@ -22,6 +23,12 @@ impl EventCollector {
             }
             // The Mutex guard will be dropped here
         };
+        let auditor = crate::audit::Auditor::new(
+            crate::audit::AuditSource::Task("role shortcode"),
+            db_connection,
+            discord_api,
+            guild_config,
+        );
         println!("Role shortcode: Checking {} events", self.events.len());
         for event in &self.events {
             // Check whether this event uses the role shortcode
@@ -108,7 +115,8 @@ impl EventCollector {
                 } else {
                     continue;
                 };
-                let discord_member = match crate::discord::sync::ids::GUILD_ID
+                let discord_member = match guild_config
+                    .guild_id
                     .member(discord_api, discord_user_id)
                     .await
                 {
@@ -123,12 +131,14 @@ impl EventCollector {
                         // Assign the role
                         if let Ok(_) = crate::tasks::subscription_roles::add_member_role(
                             discord_api,
+                            guild_config.guild_id,
                             discord_user_id,
                             role_id,
                             Some(
                                 "Automatic role assignment due to being enrolled in an event with \
                                  role shortcode",
                             ),
+                            &auditor,
                         )
                         .await
                         {
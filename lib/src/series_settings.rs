@@ -0,0 +1,352 @@
+// Settings of an event series that hosts change on the series settings page.
+// The channel name, topic and category replace what the Discord sync would
+// otherwise derive from the next session.
+
+use serenity::{
+    builder::EditChannel,
+    model::{
+        id::{ChannelId, UserId},
+        permissions::Permissions,
+    },
+};
+
+use crate::{
+    audit::Auditor,
+    db,
+    discord::{guild_config::GuildConfig, sync::ChannelType, CacheAndHttp},
+};
+
+// The placeholders are replaced with the time and the link of the next session
+pub const DEFAULT_TOPIC_TEMPLATE: &str = "Next Session: {next_session} {link}";
+
+// Discord's limit for channel topics
+const MAX_TOPIC_LEN: usize = 1024;
+
+// Leave some room for the placeholders
+pub const MAX_TOPIC_TEMPLATE_LEN: usize = 900;
+
+pub fn page_url(series_id: db::EventSeriesId) -> String {
+    format!("{}/series/{}", crate::urls::BASE_URL, series_id.0)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelSettings {
+    pub channel_name: Option<String>,
+    pub topic_template: Option<String>,
+    pub category_id: Option<ChannelId>,
+}
+
+impl ChannelSettings {
+    pub async fn load(
+        series_id: db::EventSeriesId,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<Self, crate::meetup::Error> {
+        let row = sqlx::query!(
+            r#"SELECT channel_name, channel_topic_template, channel_category_id FROM event_series WHERE id = $1"#,
+            series_id.0
+        )
+        .fetch_one(db_connection)
+        .await?;
+        Ok(ChannelSettings {
+            channel_name: row.channel_name,
+            topic_template: row.channel_topic_template,
+            category_id: row.channel_category_id.map(|id| ChannelId::new(id as u64)),
+        })
+    }
+
+    pub async fn save(
+        &self,
+        series_id: db::EventSeriesId,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<(), crate::meetup::Error> {
+        sqlx::query!(
+            r#"UPDATE event_series SET channel_name = $2, channel_topic_template = $3, channel_category_id = $4 WHERE id = $1"#,
+            series_id.0,
+            self.channel_name,
+            self.topic_template,
+            self.category_id.map(|id| id.get() as i64)
+        )
+        .execute(db_connection)
+        .await?;
+        Ok(())
+    }
+
+    // The host's channel name or the one derived from the session title
+    pub fn channel_name<'a>(
+        &'a self,
+        next_event: &'a db::Event,
+    ) -> Result<&'a str, crate::meetup::Error> {
+        match &self.channel_name {
+            Some(channel_name) => Ok(channel_name),
+            None => crate::discord::sync::series_name(&next_event.title),
+        }
+    }
+
+    // The host's category takes precedence over the [category] shortcode of
+    // the next session. Voice channels stay in the voice channel categories.
+    pub fn special_category(
+        &self,
+        channel_type: ChannelType,
+        next_event: &db::Event,
+    ) -> Option<ChannelId> {
+        match channel_type {
+            ChannelType::Text => self.category_id.or(next_event.discord_category),
+            ChannelType::Voice => next_event.discord_category,
+        }
+    }
+
+    // Cut off at Discord's limit, since the placeholders can be repeated
+    pub fn topic(&self, next_session: &str, link: Option<&str>) -> String {
+        let template = self
+            .topic_template
+            .as_deref()
+            .unwrap_or(DEFAULT_TOPIC_TEMPLATE);
+        let topic = template
+            .replace("{next_session}", next_session)
+            .replace("{link}", link.unwrap_or(""));
+        topic.trim().chars().take(MAX_TOPIC_LEN).collect()
+    }
+}
+
+// Same limits as for the names derived from session titles
+pub fn is_valid_channel_name(channel_name: &str) -> bool {
+    (2..=80).contains(&channel_name.len())
+}
+
+pub fn is_valid_topic_template(topic_template: &str) -> bool {
+    topic_template.chars().count() <= MAX_TOPIC_TEMPLATE_LEN
+}
+
+// Hosts can only move their channel between the game categories
+pub fn selectable_categories(guild_config: &GuildConfig) -> Vec<ChannelId> {
+    let mut categories = guild_config.campaign_category_ids.clone();
+    for &category in &guild_config.one_shot_category_ids {
+        if !categories.contains(&category) {
+            categories.push(category);
+        }
+    }
+    categories
+}
+
+// Whether the member hosts an upcoming session of the series and hasn't been
+// removed as a host since
+pub async fn is_series_host(
+    series_id: db::EventSeriesId,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let is_host = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM event
+            INNER JOIN event_host ON event_host.event_id = event.id
+            WHERE event.event_series_id = $1 AND event_host.member_id = $2
+                AND event.start_time > NOW() AND event.deleted IS NULL
+        ) AND NOT EXISTS (
+            SELECT 1 FROM event_series_removed_host
+            WHERE event_series_id = $1 AND member_id = $2
+        ) AS "exists!""#,
+        series_id.0,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(is_host)
+}
+
+pub struct HostedSeries {
+    pub series_id: db::EventSeriesId,
+    pub title: String,
+    pub next_session: chrono::DateTime<chrono::Utc>,
+}
+
+// The series in which the member hosts upcoming sessions, next session first
+pub async fn hosted_series(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<HostedSeries>, crate::meetup::Error> {
+    let mut series = sqlx::query!(
+        r#"SELECT DISTINCT ON (event.event_series_id) event.event_series_id, event.title, event.start_time
+        FROM event
+        WHERE event.start_time > NOW() AND event.deleted IS NULL
+            AND EXISTS (
+                SELECT 1 FROM event AS hosted_event
+                INNER JOIN event_host ON event_host.event_id = hosted_event.id
+                WHERE hosted_event.event_series_id = event.event_series_id
+                    AND event_host.member_id = $1
+                    AND hosted_event.start_time > NOW() AND hosted_event.deleted IS NULL
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM event_series_removed_host
+                WHERE event_series_id = event.event_series_id AND member_id = $1
+            )
+        ORDER BY event.event_series_id, event.start_time"#,
+        member_id.0
+    )
+    .map(|row| HostedSeries {
+        series_id: db::EventSeriesId(row.event_series_id),
+        title: row.title,
+        next_session: row.start_time,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    series.sort_by_key(|series| series.next_session);
+    Ok(series)
+}
+
+// Makes the member a host of all upcoming sessions of the series. Returns false
+// if the member already hosts all of them or there are none.
+pub async fn add_co_host(
+    series_id: db::EventSeriesId,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let num_events = sqlx::query!(
+        r#"INSERT INTO event_host (event_id, member_id)
+        SELECT id, $2 FROM event WHERE event_series_id = $1 AND start_time > NOW() AND deleted IS NULL
+        ON CONFLICT DO NOTHING"#,
+        series_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    // The member might have been removed as a host before
+    sqlx::query!(
+        r#"DELETE FROM event_series_removed_host WHERE event_series_id = $1 AND member_id = $2"#,
+        series_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(num_events > 0)
+}
+
+// Removes the member as a host from all upcoming sessions of the series and
+// remembers the removal like the "remove host" command does
+pub async fn remove_co_host(
+    series_id: db::EventSeriesId,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    sqlx::query!(
+        r#"DELETE FROM event_host
+        USING event
+        WHERE event_host.event_id = event.id AND event.event_series_id = $1
+            AND event.start_time > NOW() AND event_host.member_id = $2"#,
+        series_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO event_series_removed_host (event_series_id, member_id, removal_time) VALUES ($1, $2, NOW())"#,
+        series_id.0,
+        member_id.0
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// The Discord sync only names channels when it creates them, so a new name is
+// applied to the existing text and voice channel right away
+pub async fn rename_channels(
+    series_id: db::EventSeriesId,
+    channel_name: &str,
+    db_connection: &sqlx::PgPool,
+    discord_api: &CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let text_channel_id = crate::get_series_text_channel(series_id, &mut tx).await?;
+    let voice_channel_id = crate::get_series_voice_channel(series_id, &mut tx).await?;
+    tx.commit().await?;
+    for channel_id in text_channel_id.into_iter().chain(voice_channel_id) {
+        channel_id
+            .edit(&discord_api.http, EditChannel::new().name(channel_name))
+            .await?;
+    }
+    Ok(())
+}
+
+// Takes away the host permissions that the Discord sync gave a removed co-host.
+// The channel role still grants access to the channels.
+pub async fn revoke_host_permissions(
+    series_id: db::EventSeriesId,
+    discord_id: UserId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &CacheAndHttp,
+    auditor: &Auditor<'_>,
+) -> Result<(), crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let text_channel_id = crate::get_series_text_channel(series_id, &mut tx).await?;
+    let voice_channel_id = crate::get_series_voice_channel(series_id, &mut tx).await?;
+    tx.commit().await?;
+    if let Some(text_channel_id) = text_channel_id {
+        crate::discord::remove_channel_user_permissions(
+            discord_api,
+            text_channel_id,
+            discord_id,
+            Permissions::MANAGE_MESSAGES | Permissions::MENTION_EVERYONE,
+            auditor,
+        )
+        .await?;
+    }
+    if let Some(voice_channel_id) = voice_channel_id {
+        crate::discord::remove_channel_user_permissions(
+            discord_api,
+            voice_channel_id,
+            discord_id,
+            Permissions::MUTE_MEMBERS
+                | Permissions::DEAFEN_MEMBERS
+                | Permissions::MOVE_MEMBERS
+                | Permissions::PRIORITY_SPEAKER,
+            auditor,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn topic_and_names() {
+        let settings = ChannelSettings::default();
+        assert_eq!(
+            settings.topic("01.05.2026 19:00", Some("https://example.com")),
+            "Next Session: 01.05.2026 19:00 https://example.com"
+        );
+        assert_eq!(
+            settings.topic("01.05.2026 19:00", None),
+            "Next Session: 01.05.2026 19:00"
+        );
+        let settings = ChannelSettings {
+            topic_template: Some("Bring dice! {next_session}".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings.topic("01.05.2026 19:00", None),
+            "Bring dice! 01.05.2026 19:00"
+        );
+        assert!(is_valid_channel_name("tomb-of-annihilation"));
+        assert!(!is_valid_channel_name("x"));
+        assert!(!is_valid_topic_template(&"x".repeat(1000)));
+        let settings = ChannelSettings {
+            topic_template: Some("{link}".repeat(100)),
+            ..Default::default()
+        };
+        assert_eq!(
+            settings
+                .topic("01.05.2026 19:00", Some("https://example.com"))
+                .chars()
+                .count(),
+            MAX_TOPIC_LEN
+        );
+    }
+}
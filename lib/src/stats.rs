@@ -0,0 +1,333 @@
+// Aggregate activity statistics for the organisers: sessions run, players,
+// retention, host workload and the online vs in-person split. Everything is
+// computed from the past, non-deleted sessions and grouped by calendar month
+// in Swiss time.
+
+use chrono::{Datelike, Months, NaiveDate};
+
+use crate::db;
+
+// How many months the statistics cover by default
+pub const DEFAULT_NUM_MONTHS: u32 = 12;
+
+// The web page and the CSV export don't go further back than this
+pub const MAX_NUM_MONTHS: u32 = 120;
+
+pub fn page_url() -> String {
+    format!("{}/stats", crate::urls::BASE_URL)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonthlyStats {
+    // First day of the month
+    pub month: NaiveDate,
+    pub num_sessions: i64,
+    pub num_online_sessions: i64,
+    // Unique players with at least one session this month
+    pub num_players: i64,
+    // Players whose first session ever was this month
+    pub num_new_players: i64,
+    // Players of this month who also played the month before
+    pub num_returning_players: i64,
+    // Unique players of the month before
+    pub num_previous_players: i64,
+    pub num_hosts: i64,
+    // Sum of the players of all sessions
+    pub num_player_seats: i64,
+}
+
+impl MonthlyStats {
+    pub fn num_in_person_sessions(&self) -> i64 {
+        self.num_sessions - self.num_online_sessions
+    }
+
+    // Share of last month's players who came back this month
+    pub fn retention(&self) -> Option<f64> {
+        if self.num_previous_players > 0 {
+            Some(self.num_returning_players as f64 / self.num_previous_players as f64)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HostStats {
+    pub member_id: db::MemberId,
+    pub name: String,
+    pub num_sessions: i64,
+    pub num_online_sessions: i64,
+    pub num_series: i64,
+    pub num_player_seats: i64,
+    pub last_session: chrono::DateTime<chrono::Utc>,
+}
+
+// Totals over the whole period. Unique counts can't be summed up from the
+// monthly numbers, so they are queried separately.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub num_sessions: i64,
+    pub num_online_sessions: i64,
+    pub num_players: i64,
+    pub num_new_players: i64,
+    pub num_hosts: i64,
+    pub num_player_seats: i64,
+}
+
+impl Summary {
+    pub fn num_in_person_sessions(&self) -> i64 {
+        self.num_sessions - self.num_online_sessions
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ActivityStats {
+    pub first_month: NaiveDate,
+    // Oldest month first, including the current month
+    pub months: Vec<MonthlyStats>,
+    // Busiest host first
+    pub hosts: Vec<HostStats>,
+    pub summary: Summary,
+}
+
+// The first day of the month `num_months - 1` months before the current one
+fn first_month(num_months: u32, today: NaiveDate) -> NaiveDate {
+    let this_month = today.with_day(1).unwrap_or(today);
+    this_month
+        .checked_sub_months(Months::new(num_months.clamp(1, MAX_NUM_MONTHS) - 1))
+        .unwrap_or(this_month)
+}
+
+impl ActivityStats {
+    pub async fn load(
+        num_months: u32,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<Self, crate::meetup::Error> {
+        let today = chrono::Utc::now()
+            .with_timezone(&chrono_tz::Europe::Zurich)
+            .date_naive();
+        let first_month = first_month(num_months, today);
+        let months = sqlx::query!(
+            r#"WITH past_event AS (
+                SELECT id, is_online, date_trunc('month', start_time AT TIME ZONE 'Europe/Zurich')::date AS "month"
+                FROM event
+                WHERE deleted IS NULL AND start_time < NOW()
+            ),
+            player_month AS (
+                SELECT DISTINCT past_event."month", event_participant.member_id
+                FROM past_event
+                INNER JOIN event_participant ON event_participant.event_id = past_event.id
+            ),
+            player_first_month AS (
+                SELECT member_id, MIN("month") AS "month" FROM player_month GROUP BY member_id
+            ),
+            stats_month AS (
+                SELECT generate_series($1::date, date_trunc('month', NOW() AT TIME ZONE 'Europe/Zurich')::date, '1 month')::date AS "month"
+            )
+            SELECT stats_month."month" AS "month!",
+                (SELECT COUNT(*) FROM past_event WHERE past_event."month" = stats_month."month") AS "num_sessions!",
+                (SELECT COUNT(*) FROM past_event WHERE past_event."month" = stats_month."month" AND past_event.is_online) AS "num_online_sessions!",
+                (SELECT COUNT(*) FROM player_month WHERE player_month."month" = stats_month."month") AS "num_players!",
+                (SELECT COUNT(*) FROM player_first_month WHERE player_first_month."month" = stats_month."month") AS "num_new_players!",
+                (SELECT COUNT(*) FROM player_month
+                    WHERE player_month."month" = stats_month."month"
+                    AND EXISTS (SELECT 1 FROM player_month AS previous WHERE previous.member_id = player_month.member_id AND previous."month" = (stats_month."month" - interval '1 month')::date)
+                ) AS "num_returning_players!",
+                (SELECT COUNT(*) FROM player_month WHERE player_month."month" = (stats_month."month" - interval '1 month')::date) AS "num_previous_players!",
+                (SELECT COUNT(DISTINCT event_host.member_id) FROM past_event INNER JOIN event_host ON event_host.event_id = past_event.id WHERE past_event."month" = stats_month."month") AS "num_hosts!",
+                (SELECT COUNT(*) FROM past_event INNER JOIN event_participant ON event_participant.event_id = past_event.id WHERE past_event."month" = stats_month."month") AS "num_player_seats!"
+            FROM stats_month
+            ORDER BY stats_month."month""#,
+            first_month
+        )
+        .fetch_all(db_connection)
+        .await?
+        .into_iter()
+        .map(|row| MonthlyStats {
+            month: row.month,
+            num_sessions: row.num_sessions,
+            num_online_sessions: row.num_online_sessions,
+            num_players: row.num_players,
+            num_new_players: row.num_new_players,
+            num_returning_players: row.num_returning_players,
+            num_previous_players: row.num_previous_players,
+            num_hosts: row.num_hosts,
+            num_player_seats: row.num_player_seats,
+        })
+        .collect();
+        let hosts = sqlx::query!(
+            r#"SELECT "member".id AS member_id, COALESCE("member".discord_nick, 'Member ' || "member".id) AS "name!",
+                COUNT(*) AS "num_sessions!",
+                COUNT(*) FILTER (WHERE event.is_online) AS "num_online_sessions!",
+                COUNT(DISTINCT event.event_series_id) AS "num_series!",
+                SUM((SELECT COUNT(*) FROM event_participant WHERE event_participant.event_id = event.id))::bigint AS "num_player_seats!",
+                MAX(event.start_time) AS "last_session!"
+            FROM event_host
+            INNER JOIN event ON event.id = event_host.event_id
+            INNER JOIN "member" ON "member".id = event_host.member_id
+            WHERE event.deleted IS NULL AND event.start_time < NOW()
+                AND (event.start_time AT TIME ZONE 'Europe/Zurich')::date >= $1
+            GROUP BY "member".id
+            ORDER BY "num_sessions!" DESC, "name!""#,
+            first_month
+        )
+        .fetch_all(db_connection)
+        .await?
+        .into_iter()
+        .map(|row| HostStats {
+            member_id: db::MemberId(row.member_id),
+            name: row.name,
+            num_sessions: row.num_sessions,
+            num_online_sessions: row.num_online_sessions,
+            num_series: row.num_series,
+            num_player_seats: row.num_player_seats,
+            last_session: row.last_session,
+        })
+        .collect();
+        let summary = sqlx::query!(
+            r#"WITH past_event AS (
+                SELECT id, is_online, start_time
+                FROM event
+                WHERE deleted IS NULL AND start_time < NOW()
+            ),
+            period_event AS (
+                SELECT * FROM past_event WHERE (start_time AT TIME ZONE 'Europe/Zurich')::date >= $1
+            )
+            SELECT
+                (SELECT COUNT(*) FROM period_event) AS "num_sessions!",
+                (SELECT COUNT(*) FROM period_event WHERE is_online) AS "num_online_sessions!",
+                (SELECT COUNT(DISTINCT member_id) FROM period_event INNER JOIN event_participant ON event_participant.event_id = period_event.id) AS "num_players!",
+                (SELECT COUNT(*) FROM (
+                    SELECT member_id FROM past_event INNER JOIN event_participant ON event_participant.event_id = past_event.id
+                    GROUP BY member_id
+                    HAVING (MIN(start_time) AT TIME ZONE 'Europe/Zurich')::date >= $1
+                ) AS new_player) AS "num_new_players!",
+                (SELECT COUNT(DISTINCT member_id) FROM period_event INNER JOIN event_host ON event_host.event_id = period_event.id) AS "num_hosts!",
+                (SELECT COUNT(*) FROM period_event INNER JOIN event_participant ON event_participant.event_id = period_event.id) AS "num_player_seats!""#,
+            first_month
+        )
+        .fetch_one(db_connection)
+        .await?;
+        let summary = Summary {
+            num_sessions: summary.num_sessions,
+            num_online_sessions: summary.num_online_sessions,
+            num_players: summary.num_players,
+            num_new_players: summary.num_new_players,
+            num_hosts: summary.num_hosts,
+            num_player_seats: summary.num_player_seats,
+        };
+        Ok(ActivityStats {
+            first_month,
+            months,
+            hosts,
+            summary,
+        })
+    }
+
+    pub fn monthly_csv(&self) -> String {
+        let mut csv = "month,sessions,online sessions,in-person sessions,players,new players,\
+                       returning players,retention,hosts,player seats\n"
+            .to_string();
+        for month in &self.months {
+            csv += &format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                month.month.format("%Y-%m"),
+                month.num_sessions,
+                month.num_online_sessions,
+                month.num_in_person_sessions(),
+                month.num_players,
+                month.num_new_players,
+                month.num_returning_players,
+                month
+                    .retention()
+                    .map(|retention| format!("{:.3}", retention))
+                    .unwrap_or_default(),
+                month.num_hosts,
+                month.num_player_seats
+            );
+        }
+        csv
+    }
+
+    pub fn hosts_csv(&self) -> String {
+        let mut csv = "member id,name,sessions,online sessions,series,player seats,last session\n"
+            .to_string();
+        for host in &self.hosts {
+            csv += &format!(
+                "{},{},{},{},{},{},{}\n",
+                host.member_id.0,
+                csv_field(&host.name),
+                host.num_sessions,
+                host.num_online_sessions,
+                host.num_series,
+                host.num_player_seats,
+                host.last_session
+                    .with_timezone(&chrono_tz::Europe::Zurich)
+                    .format("%Y-%m-%d")
+            );
+        }
+        csv
+    }
+}
+
+// Quotes a field if it contains a separator, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months_and_csv() {
+        let today = NaiveDate::from_ymd_opt(2026, 3, 17).unwrap();
+        assert_eq!(
+            first_month(12, today),
+            NaiveDate::from_ymd_opt(2025, 4, 1).unwrap()
+        );
+        assert_eq!(
+            first_month(0, today),
+            NaiveDate::from_ymd_opt(2026, 3, 1).unwrap()
+        );
+        let month = MonthlyStats {
+            month: NaiveDate::from_ymd_opt(2026, 3, 1).unwrap(),
+            num_sessions: 10,
+            num_online_sessions: 4,
+            num_players: 20,
+            num_new_players: 5,
+            num_returning_players: 12,
+            num_previous_players: 16,
+            num_hosts: 3,
+            num_player_seats: 45,
+        };
+        assert_eq!(month.num_in_person_sessions(), 6);
+        assert_eq!(month.retention(), Some(0.75));
+        let stats = ActivityStats {
+            first_month: month.month,
+            months: vec![month],
+            hosts: vec![],
+            summary: Summary {
+                num_sessions: 10,
+                num_online_sessions: 4,
+                num_players: 20,
+                num_new_players: 5,
+                num_hosts: 3,
+                num_player_seats: 45,
+            },
+        };
+        assert_eq!(
+            stats.monthly_csv().lines().nth(1),
+            Some("2026-03,10,4,6,20,5,12,0.750,3,45")
+        );
+        assert_eq!(
+            csv_field("Dungeon, \"Master\""),
+            "\"Dungeon, \"\"Master\"\"\""
+        );
+        assert_eq!(csv_field("Alice"), "Alice");
+    }
+}
@@ -167,9 +167,33 @@ pub const CHANNEL_NOT_YET_CLOSEABLE: &'static str = "Too soon mate. Please wait
 pub const CHANNEL_NO_EXPIRATION: &'static str =
     "This channel has no expiration date, so I will not close it.";
 
-pub const CHANNEL_MARKED_FOR_CLOSING: &'static str =
-    "Roger that. I've marked this channel to be closed in the next 24 hours.\nThanks for playing \
-     and hope to see you at another game soon.";
+#[allow(non_snake_case)]
+pub fn CHANNEL_MARKED_FOR_CLOSING(deletion_time: chrono::DateTime<chrono::Utc>) -> String {
+    format!(
+        "Roger that. I've marked this channel to be closed <t:{}:R>.\nThanks for playing and hope \
+         to see you at another game soon.",
+        deletion_time.timestamp()
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn CAMPAIGN_WELCOME_BACK_MESSAGE(
+    channel_role_id: RoleId,
+    transcript_url: Option<&str>,
+) -> String {
+    let mut message = format!(
+        "Welcome back, {}! This campaign is continuing with a new session, so here is a fresh \
+         channel for it.",
+        channel_role_id.mention()
+    );
+    if let Some(transcript_url) = transcript_url {
+        message += &format!(
+            "\nCatch up on what happened in the old channel here: {}",
+            transcript_url
+        );
+    }
+    message
+}
 
 pub const CHANNEL_ALREADY_MARKED_FOR_CLOSING: &'static str =
     "Deja vu! This channel is already marked for closing. The black hole is on its way. Patience.";
@@ -231,6 +255,216 @@ pub fn CHANNEL_ADDED_NEW_HOST(discord_id: UserId) -> String {
 pub const CHANNEL_ADD_USER_INVALID_DISCORD: &'static str =
     "Seems like the specified Discord ID is invalid";
 
+// ** Session RSVPs **
+
+pub const RSVP_JOINED: &'static str = "You're in! See you at the table :game_die:";
+
+pub const RSVP_ALREADY_JOINED: &'static str = "You are already signed up for this session.";
+
+pub const RSVP_FULL: &'static str =
+    "Sorry, this session is full. Click \"Waitlist\" and I will message you as soon as a seat \
+     frees up.";
+
+pub const RSVP_CLOSED: &'static str =
+    "Sorry, this session has already started or does not exist anymore.";
+
+pub const RSVP_LEFT: &'static str = "You are no longer signed up for this session.";
+
+pub const RSVP_NOT_JOINED: &'static str = "You are not signed up for this session.";
+
+pub const RSVP_JOINED_ON_WEBSITE: &'static str =
+    "You signed up for this session on the website, please cancel your RSVP there.";
+
+// ** Session waitlists **
+
+#[allow(non_snake_case)]
+pub fn WAITLIST_JOINED(position: i64) -> String {
+    format!(
+        "You are number {} on the waitlist. I will send you a message as soon as a seat frees up.",
+        position
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn WAITLIST_ALREADY_WAITING(position: i64) -> String {
+    format!("You are already number {} on the waitlist.", position)
+}
+
+pub const WAITLIST_NOT_FULL: &'static str =
+    "This session still has free seats, you can join it right away!";
+
+pub const WAITLIST_LEFT: &'static str = "You are no longer on the waitlist for this session.";
+
+pub const WAITLIST_NOT_WAITING: &'static str = "You are not on the waitlist for this session.";
+
+pub const WAITLIST_NO_UPCOMING_SESSION: &'static str =
+    "This channel has no upcoming session to wait for.";
+
+#[allow(non_snake_case)]
+pub fn WAITLIST_SEAT_OFFERED(
+    title: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+    claim_url: &str,
+    claim_expires: chrono::DateTime<chrono::Utc>,
+) -> String {
+    format!(
+        "Good news! A seat in **{title}** on <t:{start}:F> just freed up and it's yours if you \
+         want it. Claim it here before <t:{expires}:f>, after that it goes to the next person in \
+         line:\n{claim_url}",
+        title = title,
+        start = start_time.timestamp(),
+        expires = claim_expires.timestamp(),
+        claim_url = claim_url
+    )
+}
+
+// ** Free spots subscriptions **
+
+#[allow(non_snake_case)]
+pub fn FREE_SPOTS_SUBSCRIBED(subscription_id: i32, filter: &str) -> String {
+    format!(
+        "Got it! I will send you a message when there is a free spot in a game matching \
+         \"{}\" (subscription #{}).",
+        filter, subscription_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn FREE_SPOTS_INVALID_FILTER(error: &str, bot_id: UserId) -> String {
+    format!(
+        "{}. You can filter by city, \"online\" or \"in-person\", weekdays and game system, \
+         for example: <@{}> free spots subscribe zurich saturday sunday system:Pathfinder",
+        error, bot_id
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn FREE_SPOTS_UNSUBSCRIBED(num_removed: u64) -> String {
+    match num_removed {
+        0 => "You don't have any matching free spots subscriptions.".to_string(),
+        1 => "Removed your free spots subscription.".to_string(),
+        _ => format!("Removed {} free spots subscriptions.", num_removed),
+    }
+}
+
+pub const FREE_SPOTS_NO_SUBSCRIPTIONS: &'static str =
+    "You are not subscribed to any free spots notifications.";
+
+#[allow(non_snake_case)]
+pub fn FREE_SPOTS_NOTIFICATION(
+    events: &[&crate::common_event::CommonEventDetails],
+    max_events: usize,
+) -> String {
+    let mut message =
+        "Hey there adventurer! These games you might like have free spots:".to_string();
+    for event in events.iter().take(max_events) {
+        let free_spots = event.num_free_spots();
+        message.push_str(&format!(
+            "\n• **{}** on <t:{}:F> — {} {} (<{}>)",
+            event.title.replace("*", r"\*"),
+            event.date_time.timestamp(),
+            free_spots,
+            if free_spots == 1 { "spot" } else { "spots" },
+            event.short_url
+        ));
+    }
+    if events.len() > max_events {
+        message.push_str(&format!(
+            "\n…and {} more in the free spots channel.",
+            events.len() - max_events
+        ));
+    }
+    message
+}
+
+// ** Session reminders **
+
+fn format_zurich_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.with_timezone(&chrono_tz::Europe::Zurich)
+        .format("%A, %d.%m.%Y at %H:%M")
+        .to_string()
+}
+
+#[allow(non_snake_case)]
+pub fn SESSION_REMINDER(
+    role_id: Option<RoleId>,
+    title: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+) -> String {
+    let mention = role_id.map_or(String::new(), |role_id| format!("{} ", role_id.mention()));
+    format!(
+        "{}Get your dice ready! The next session of **{}** starts <t:{}:R> ({} Swiss time).",
+        mention,
+        title,
+        start_time.timestamp(),
+        format_zurich_time(start_time)
+    )
+}
+
+#[allow(non_snake_case)]
+pub fn SESSION_REMINDER_DM(
+    title: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+    channel_id: ChannelId,
+) -> String {
+    format!(
+        "Reminder: the next session of **{}** starts <t:{}:R> ({} Swiss time). See you in {}!",
+        title,
+        start_time.timestamp(),
+        format_zurich_time(start_time),
+        channel_id.mention()
+    )
+}
+
+// ** Attendance **
+
+pub const ATTENDANCE_NOT_A_HOST: &'static str =
+    "Only the hosts of this session can mark who showed up.";
+
+#[allow(non_snake_case)]
+pub fn ATTENDANCE_REQUEST(
+    title: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+    form_url: &str,
+) -> String {
+    format!(
+        "**Who showed up to {}** on {}?\nHosts, click a player to mark them as showed up, click \
+         again for a no-show. You can also use the form: <{}>",
+        title,
+        format_zurich_time(start_time),
+        form_url
+    )
+}
+
+// ** Calendar feeds **
+
+#[allow(non_snake_case)]
+pub fn CALENDAR_LINKS_MESSAGE(
+    sessions_url: &str,
+    hosting_url: &str,
+    series_url: Option<&str>,
+    bot_id: UserId,
+) -> String {
+    let mut message = format!(
+        "Add these links to your calendar app (usually \"subscribe to calendar\" or \"add \
+         calendar from URL\") to always have your sessions in your calendar.\n**All your \
+         sessions:** <{}>\n**Sessions you host:** <{}>",
+        sessions_url, hosting_url
+    );
+    if let Some(series_url) = series_url {
+        message.push_str(&format!(
+            "\n**All sessions of this game:** <{}>",
+            series_url
+        ));
+    }
+    message.push_str(&format!(
+        "\n***These links are private and meant just for you.*** If you shared them by accident, \
+         write \"<@{}> calendar reset\" to get new ones.",
+        bot_id
+    ));
+    message
+}
+
 // **************************************
 // *** Meetup linking webpage replies ***
 // **************************************
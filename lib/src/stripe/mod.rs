@@ -1,3 +1,5 @@
+use serenity::model::id::UserId;
+
 pub async fn list_active_subscriptions(
     client: &stripe::Client,
 ) -> Result<Vec<stripe::Subscription>, crate::meetup::Error> {
@@ -20,3 +22,69 @@ pub async fn list_active_subscriptions(
     }
     Ok(all_subscriptions)
 }
+
+// The Discord user of a customer, stored by the subscription roles task
+pub const DISCORD_ID_METADATA_KEY: &str = "_hyperion_discord_id";
+// The Discord username that the customer entered at checkout
+pub const DISCORD_USERNAME_METADATA_KEY: &str = "Discord";
+
+pub async fn list_all_customers(
+    client: &stripe::Client,
+) -> Result<Vec<stripe::Customer>, crate::meetup::Error> {
+    let params = stripe::ListCustomers::default();
+    let mut paginator = stripe::Customer::list(client, &params)
+        .await?
+        .paginate(params);
+    let mut all_customers = vec![];
+    loop {
+        all_customers.extend(paginator.page.data.iter().cloned());
+        if paginator.page.has_more {
+            paginator = paginator.next(client).await?;
+        } else {
+            break;
+        }
+    }
+    Ok(all_customers)
+}
+
+// All customers that are linked to the Discord user, including former
+// subscribers
+pub async fn find_customers_by_discord_id(
+    client: &stripe::Client,
+    discord_id: UserId,
+) -> Result<Vec<stripe::Customer>, crate::meetup::Error> {
+    let discord_id = discord_id.to_string();
+    let customers = list_all_customers(client)
+        .await?
+        .into_iter()
+        .filter(|customer| {
+            customer
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.get(DISCORD_ID_METADATA_KEY))
+                .is_some_and(|id| *id == discord_id)
+        })
+        .collect();
+    Ok(customers)
+}
+
+// Stripe deletes metadata keys that are set to an empty string
+pub async fn remove_discord_metadata(
+    client: &stripe::Client,
+    customer_id: &stripe::CustomerId,
+) -> Result<(), crate::meetup::Error> {
+    let metadata = [DISCORD_ID_METADATA_KEY, DISCORD_USERNAME_METADATA_KEY]
+        .into_iter()
+        .map(|key| (key.to_string(), String::new()))
+        .collect();
+    stripe::Customer::update(
+        client,
+        customer_id,
+        stripe::UpdateCustomer {
+            metadata: Some(metadata),
+            ..Default::default()
+        },
+    )
+    .await?;
+    Ok(())
+}
@@ -1,6 +1,7 @@
 pub mod client;
 pub mod schema;
 pub mod sync;
+pub mod webhook;
 
 pub fn swissrpg_event_series_url(base_url: &str, event_series_id: &uuid::Uuid) -> String {
     format!(
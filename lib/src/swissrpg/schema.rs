@@ -19,6 +19,7 @@ pub struct Tag {
 
 pub(crate) const TAG_TYPE_LOCATION: &str = "location";
 pub(crate) const LOCATION_CODE_ONLINE: &str = "online";
+pub(crate) const TAG_TYPE_GAME_SYSTEM: &str = "game_system";
 
 pub(crate) fn event_series_is_online(tags: &[Tag]) -> bool {
     tags.iter()
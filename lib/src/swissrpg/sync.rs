@@ -49,6 +49,32 @@ pub async fn sync_task(
     Ok(event_collector)
 }
 
+/// Sync the current and upcoming sessions of a single SwissRPG event series,
+/// as received by the webhook. Returns the database event series ID.
+#[tracing::instrument(skip(event_series, db_connection), fields(event_series_uuid = %event_series.uuid, event_series_title = %event_series.title))]
+pub async fn sync_single_event_series(
+    event_series: &Event,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<db::EventSeriesId>, crate::BoxedError> {
+    let now = Utc::now();
+    for event in event_series
+        .current_session
+        .iter()
+        .chain(event_series.upcoming_sessions.iter())
+    {
+        if event.start > now {
+            sync_event(event_series, event, db_connection).await?;
+        }
+    }
+    let series_id = sqlx::query_scalar!(
+        r#"SELECT id FROM event_series WHERE swissrpg_event_series_id = $1"#,
+        event_series.uuid
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(series_id.map(db::EventSeriesId))
+}
+
 /// Sync SwissRPG event series, establishing the proper series relationship
 #[tracing::instrument(skip(event_series), fields(event_series_uuid = %event_series.uuid, event_series_title = %event_series.title))]
 async fn sync_event_series(
@@ -185,7 +211,7 @@ pub async fn sync_event(
         // Update existing event
         sqlx::query_scalar!(
             r#"UPDATE event
-            SET event_series_id = $1, start_time = $2, title = $3, description = $4, is_online = $5, discord_category_id = $6
+            SET event_series_id = $1, start_time = $2, title = $3, description = $4, is_online = $5, discord_category_id = $6, open_seats = $8
             WHERE id = $7
             RETURNING id"#,
             series_id,
@@ -194,13 +220,14 @@ pub async fn sync_event(
             event_series.description,
             is_online,
             None as Option<i64>, // TODO: category_id
-            db_event_id
+            db_event_id,
+            event.open_seats
         ).fetch_one(&mut *tx).await?
     } else {
         // Create new event
         sqlx::query_scalar!(
-            r#"INSERT INTO event (event_series_id, start_time, title, description, is_online, discord_category_id)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            r#"INSERT INTO event (event_series_id, start_time, title, description, is_online, discord_category_id, open_seats)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING id"#,
             series_id,
             event.start,
@@ -208,6 +235,7 @@ pub async fn sync_event(
             event_series.description,
             is_online,
             None as Option<i64>, // TODO: category_id
+            event.open_seats,
         ).fetch_one(&mut *tx).await?
     };
 
@@ -266,7 +294,8 @@ pub async fn sync_event(
 
     // Remove participants who are no longer attending
     sqlx::query!(
-        r#"DELETE FROM event_participant WHERE event_id = $1 AND NOT (member_id = ANY($2))"#,
+        r#"DELETE FROM event_participant
+        WHERE event_id = $1 AND NOT from_discord AND NOT (member_id = ANY($2))"#,
         db_event_id,
         &attendee_member_ids
             .iter()
@@ -279,10 +308,13 @@ pub async fn sync_event(
     // Add current participants
     for member_id in attendee_member_ids {
         sqlx::query!(
-            r#"INSERT INTO event_participant (event_id, member_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+            r#"INSERT INTO event_participant (event_id, member_id) VALUES ($1, $2)
+            ON CONFLICT (event_id, member_id) DO UPDATE SET from_discord = FALSE"#,
             db_event_id,
             member_id.0
-        ).execute(&mut *tx).await?;
+        )
+        .execute(&mut *tx)
+        .await?;
     }
 
     tx.commit().await?;
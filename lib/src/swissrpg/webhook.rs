@@ -0,0 +1,129 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use super::schema::Event;
+
+// The header looks like "t=1700000000,v1=<hex encoded HMAC-SHA256>", where
+// the HMAC is computed over "<t>.<body>" with the shared webhook secret
+pub const SIGNATURE_HEADER: &str = "swissrpg-signature";
+
+// Requests signed longer ago than this are rejected to prevent replays
+const TIMESTAMP_TOLERANCE_SECONDS: i64 = 5 * 60;
+
+// Sent by the SwissRPG platform whenever an event, one of its sessions or an
+// RSVP changes. Contains the event series in the same form as /api/events.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct WebhookPayload {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub event: Event,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    Malformed,
+    Expired,
+    Mismatch,
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::Malformed => write!(f, "Malformed webhook signature header"),
+            SignatureError::Expired => write!(f, "Webhook signature timestamp is too old"),
+            SignatureError::Mismatch => write!(f, "Webhook signature does not match"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+fn signature_mac(secret: &str, timestamp: i64, payload: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(payload);
+    mac
+}
+
+// Creates the signature header value for a payload
+pub fn sign(payload: &[u8], secret: &str, timestamp: i64) -> String {
+    let signature = signature_mac(secret, timestamp, payload)
+        .finalize()
+        .into_bytes();
+    format!("t={},v1={}", timestamp, hex::encode(signature))
+}
+
+pub fn verify_signature(
+    payload: &[u8],
+    signature_header: &str,
+    secret: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<(), SignatureError> {
+    let mut timestamp = None;
+    let mut signatures = vec![];
+    for part in signature_header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => {
+                timestamp = Some(
+                    value
+                        .parse::<i64>()
+                        .map_err(|_| SignatureError::Malformed)?,
+                )
+            }
+            Some(("v1", value)) => {
+                signatures.push(hex::decode(value).map_err(|_| SignatureError::Malformed)?)
+            }
+            // Ignore unknown schemes
+            _ => (),
+        }
+    }
+    let timestamp = timestamp.ok_or(SignatureError::Malformed)?;
+    if signatures.is_empty() {
+        return Err(SignatureError::Malformed);
+    }
+    if (now.timestamp() - timestamp).abs() > TIMESTAMP_TOLERANCE_SECONDS {
+        return Err(SignatureError::Expired);
+    }
+    // Several signatures are allowed while the secret is being rotated
+    let mac = signature_mac(secret, timestamp, payload);
+    if signatures
+        .iter()
+        .any(|signature| mac.clone().verify_slice(signature).is_ok())
+    {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures() {
+        let payload = br#"{"type":"rsvp.changed"}"#;
+        let now = chrono::Utc::now();
+        let header = sign(payload, "secret", now.timestamp());
+        assert_eq!(verify_signature(payload, &header, "secret", now), Ok(()));
+        assert_eq!(
+            verify_signature(payload, &header, "other secret", now),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify_signature(b"{}", &header, "secret", now),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify_signature(payload, &header, "secret", now + chrono::Duration::hours(1)),
+            Err(SignatureError::Expired)
+        );
+        assert_eq!(
+            verify_signature(payload, "v1=abc", "secret", now),
+            Err(SignatureError::Malformed)
+        );
+    }
+}
@@ -1,5 +1,9 @@
-use crate::{db, discord::sync::ChannelType, strings};
-use chrono::{NaiveTime, TimeZone};
+use crate::{
+    audit::{AuditEntry, AuditSource, Auditor},
+    db,
+    discord::{guild_config::GuildConfig, sync::ChannelType},
+    strings,
+};
 use serenity::{
     builder::CreateMessage,
     model::{
@@ -9,50 +13,16 @@ use serenity::{
     },
 };
 use simple_error::SimpleError;
-use std::collections::HashMap;
-
-// Sends channel deletion reminders to expired Discord channels
-pub async fn create_recurring_end_of_game_task(
-    db_connection: sqlx::PgPool,
-    mut discord_api: crate::discord::CacheAndHttp,
-    bot_id: UserId,
-) -> ! {
-    let next_end_of_game_task_time = {
-        let mut task_time = chrono::Utc
-            .from_local_datetime(
-                &chrono::Utc::now()
-                    .date_naive()
-                    .and_time(NaiveTime::from_hms_opt(18, 30, 0).unwrap()),
-            )
-            .earliest()
-            .expect("18:30 o'clock exists today");
-        // Check if it is later than 6:30pm
-        // In that case, run the task tomorrow
-        if chrono::Utc::now() > task_time {
-            task_time = task_time + chrono::Duration::days(1);
-        }
-        task_time
-    };
-    let wait_duration = next_end_of_game_task_time - chrono::Utc::now();
-    // Do this once a day, starting from the specified time
-    let mut interval_timer = tokio::time::interval_at(
-        tokio::time::Instant::now() + wait_duration.to_std().unwrap_or_default(),
-        chrono::Duration::days(1).to_std().unwrap(),
-    );
-    // Run forever
-    loop {
-        // Wait for the next interval tick
-        interval_timer.tick().await;
-        if let Err(err) = end_of_game_task(&db_connection, &mut discord_api, bot_id).await {
-            eprintln!("End of game task failed:\n{:#?}", err);
-        }
-    }
-}
+use std::{collections::HashMap, path::Path};
 
+// Sends channel deletion reminders to expired Discord channels.
+// Text channels are exported to the transcript directory before they get deleted.
 pub async fn end_of_game_task(
     db_connection: &sqlx::PgPool,
     discord_api: &mut crate::discord::CacheAndHttp,
+    guild_config: &GuildConfig,
     bot_id: UserId,
+    transcript_directory: &Path,
 ) -> Result<(), crate::meetup::Error> {
     let event_series = sqlx::query!(
         r#"SELECT event_series.id as "event_series_id!"
@@ -68,9 +38,7 @@ pub async fn end_of_game_task(
             eprintln!("Series channel expiration update failed: {:#}", err);
         }
     }
-    let existing_channels = crate::discord::sync::ids::GUILD_ID
-        .channels(&discord_api.http)
-        .await?;
+    let existing_channels = guild_config.guild_id.channels(&discord_api.http).await?;
     let discord_channels = sqlx::query!(
         r#"
         SELECT discord_id as "discord_text_channel_id!"
@@ -86,8 +54,10 @@ pub async fn end_of_game_task(
             ChannelType::Text,
             channel,
             &existing_channels,
+            transcript_directory,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await
         {
@@ -127,8 +97,10 @@ pub async fn end_of_game_task(
             ChannelType::Voice,
             channel,
             &existing_channels,
+            transcript_directory,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await
         {
@@ -136,9 +108,13 @@ pub async fn end_of_game_task(
             eprintln!("Error during voice channel deletion: {:#}", err);
         }
     }
-    let existing_roles = crate::discord::sync::ids::GUILD_ID
-        .roles(&discord_api.http)
-        .await?;
+    let existing_roles = guild_config.guild_id.roles(&discord_api.http).await?;
+    let auditor = Auditor::new(
+        AuditSource::Task("end of game"),
+        db_connection,
+        discord_api,
+        guild_config,
+    );
     let discord_roles = sqlx::query!(
         r#"SELECT discord_id as "discord_role_id!"
             FROM event_series_role
@@ -149,8 +125,15 @@ pub async fn end_of_game_task(
     .await?;
     for role in discord_roles {
         // Check if the role is due for deletion
-        if let Err(err) =
-            delete_marked_role(false, role, &existing_roles, db_connection, discord_api).await
+        if let Err(err) = delete_marked_role(
+            false,
+            role,
+            &existing_roles,
+            db_connection,
+            discord_api,
+            &auditor,
+        )
+        .await
         {
             some_failed = true;
             eprintln!("Error during role deletion: {:#}", err);
@@ -166,8 +149,15 @@ pub async fn end_of_game_task(
     .await?;
     for role in discord_host_roles {
         // Check if the host role is due for deletion
-        if let Err(err) =
-            delete_marked_role(true, role, &existing_roles, db_connection, discord_api).await
+        if let Err(err) = delete_marked_role(
+            true,
+            role,
+            &existing_roles,
+            db_connection,
+            discord_api,
+            &auditor,
+        )
+        .await
         {
             some_failed = true;
             eprintln!("Error during host role deletion: {:#}", err);
@@ -205,29 +195,21 @@ async fn update_series_channel_expiration(
     // Get last event in this series
     let last_event = db::get_last_event_in_series(db_connection, series_id).await?;
     // Query the channel's current expiration time
-    let current_expiration_time = sqlx::query_scalar!(
-        r#"SELECT expiration_time FROM event_series_text_channel WHERE discord_id = $1"#,
+    let channel = sqlx::query!(
+        r#"SELECT expiration_time, archived IS NOT NULL AS "is_archived!"
+        FROM event_series_text_channel WHERE discord_id = $1"#,
         discord_text_channel_id.get() as i64
     )
     .fetch_one(db_connection)
     .await?;
-    // The last element in this vector will be the last event in the series
-    let (new_expiration_time, needs_update) = if let Some(last_event) = last_event {
-        let new_expiration_time = last_event.time;
-        let needs_update = current_expiration_time
-            .map(|current| current != new_expiration_time)
-            .unwrap_or(true);
-        (new_expiration_time, needs_update)
-    } else {
-        // No event in this series, expire the channel immediately
-        let new_expiration_time = chrono::Utc::now();
-        let needs_update = current_expiration_time
-            .map(|current| current > new_expiration_time)
-            .unwrap_or(true);
-        (new_expiration_time, needs_update)
-    };
+    let new_expiration_time = new_expiration_time(
+        channel.expiration_time,
+        last_event.map(|event| event.time),
+        channel.is_archived,
+        chrono::Utc::now(),
+    );
     // Store the new expiration time
-    if needs_update {
+    if let Some(new_expiration_time) = new_expiration_time {
         // Also delete any possibly stored deletion times from the channel, the
         // possibly associated voice channel and roles
         let mut tx = db_connection.begin().await?;
@@ -271,46 +253,71 @@ async fn update_series_channel_expiration(
     Ok(())
 }
 
+// The channel's new expiration time, if it changed. The channel expires with
+// the last session of the series or right away if there is none. Archived
+// channels keep their retention deadline, even if the series gets a new
+// session, until they are unarchived.
+fn new_expiration_time(
+    current_expiration_time: Option<chrono::DateTime<chrono::Utc>>,
+    last_event_time: Option<chrono::DateTime<chrono::Utc>>,
+    is_archived: bool,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    if is_archived {
+        return None;
+    }
+    let needs_update = match (current_expiration_time, last_event_time) {
+        (Some(current), Some(last_event_time)) => current != last_event_time,
+        (Some(current), None) => current > now,
+        (None, _) => true,
+    };
+    if needs_update {
+        Some(last_event_time.unwrap_or(now))
+    } else {
+        None
+    }
+}
+
 async fn send_channel_expiration_reminder(
     channel_id: ChannelId,
     db_connection: &sqlx::PgPool,
     discord_api: &mut crate::discord::CacheAndHttp,
     bot_id: UserId,
 ) -> Result<(), crate::meetup::Error> {
-    let (expiration_time, last_reminder_time, snooze_until, deletion_time) = sqlx::query!(
-        r#"SELECT expiration_time, last_expiration_reminder_time, snooze_until, deletion_time
-        FROM event_series_text_channel
-        WHERE discord_id = $1"#,
-        channel_id.get() as i64
-    )
-    .map(|row| {
-        (
-            row.expiration_time,
-            row.last_expiration_reminder_time,
-            row.snooze_until,
-            row.deletion_time,
+    let (expiration_time, last_reminder_time, snooze_until, deletion_time, archived) =
+        sqlx::query!(
+            r#"SELECT expiration_time, last_expiration_reminder_time, snooze_until, deletion_time, archived
+            FROM event_series_text_channel
+            WHERE discord_id = $1"#,
+            channel_id.get() as i64
         )
-    })
-    .fetch_one(db_connection)
-    .await?;
-    if deletion_time.is_some() {
-        // This channel is already marked for deletion, don't send another reminder
+        .map(|row| {
+            (
+                row.expiration_time,
+                row.last_expiration_reminder_time,
+                row.snooze_until,
+                row.deletion_time,
+                row.archived,
+            )
+        })
+        .fetch_one(db_connection)
+        .await?;
+    if deletion_time.is_some() || archived.is_some() {
+        // This channel is already marked for deletion or archived and
+        // read-only, don't send another reminder
         return Ok(());
     }
     if let Some(expiration_time) = expiration_time {
-        // Check if this is a one-shot or a campaign series
-        let is_campaign = sqlx::query_scalar!(
-            r#"SELECT "type" = 'campaign' as "is_campaign!" FROM event_series WHERE discord_text_channel_id = $1"#,
-            channel_id.get() as i64
+        let policy = crate::expiration_policy::get_channel_policy(
+            channel_id,
+            &mut *db_connection.acquire().await?,
         )
-        .fetch_one(db_connection)
         .await?;
+        // Check if this is a one-shot or a campaign series
+        let is_campaign = policy.as_ref().is_some_and(|policy| policy.is_campaign());
+        let policy = policy.map(|policy| policy.effective()).unwrap_or_default();
         // We only remind a certain time after expiration
-        let reminder_time = if is_campaign {
-            expiration_time + chrono::Duration::days(3)
-        } else {
-            expiration_time + chrono::Duration::days(1)
-        };
+        let reminder_time = expiration_time + policy.reminder_delay();
         let now = chrono::Utc::now();
         if reminder_time > now {
             // The reminder time hasn't come yet
@@ -323,13 +330,9 @@ async fn send_channel_expiration_reminder(
             }
         }
         if let Some(last_reminder_time) = last_reminder_time {
-            // Reminders will be sent with an interval of two days for
-            // one-shots and four days for campaign channels
-            let reminder_interval = if is_campaign {
-                chrono::Duration::days(4) - chrono::Duration::hours(2)
-            } else {
-                chrono::Duration::days(2) - chrono::Duration::hours(2)
-            };
+            // Leave some slack since this task doesn't run at the exact same
+            // time every day
+            let reminder_interval = policy.reminder_interval() - chrono::Duration::hours(2);
             if last_reminder_time + reminder_interval > chrono::Utc::now() {
                 // We already sent a reminder recently
                 return Ok(());
@@ -360,6 +363,7 @@ async fn send_channel_expiration_reminder(
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 enum DeletionStatus {
     Deleted,
+    Archived,
     NotDeleted,
     AlreadyDeleted,
 }
@@ -368,8 +372,10 @@ async fn delete_marked_channel(
     channel_type: ChannelType,
     channel_id: ChannelId,
     existing_channels: &HashMap<ChannelId, GuildChannel>,
+    transcript_directory: &Path,
     db_connection: &sqlx::PgPool,
     discord_api: &crate::discord::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<DeletionStatus, crate::meetup::Error> {
     let mark_channel_as_deleted = || async {
         match channel_type {
@@ -414,8 +420,50 @@ async fn delete_marked_channel(
     if deletion_time > chrono::Utc::now() {
         return Ok(DeletionStatus::NotDeleted);
     }
+    if channel_type == ChannelType::Text {
+        // If there is an archive, expired channels go there first and only
+        // get deleted after the retention period
+        if let Some(archive_category_id) = guild_config.archive_category_id {
+            if !crate::discord::archive::is_archived(channel_id, db_connection).await? {
+                crate::discord::archive::archive_channel(
+                    channel,
+                    archive_category_id,
+                    db_connection,
+                    discord_api,
+                    guild_config,
+                )
+                .await?;
+                return Ok(DeletionStatus::Archived);
+            }
+        }
+        // Keep the channel's history. If the export fails, the channel is kept
+        // and the deletion retried next time.
+        let channel_name = channel
+            .name
+            .strip_prefix(crate::discord::archive::ARCHIVED_CHANNEL_PREFIX)
+            .unwrap_or(&channel.name);
+        crate::discord::transcript::export_channel(
+            channel_id,
+            channel_name,
+            transcript_directory,
+            db_connection,
+            discord_api,
+        )
+        .await?;
+    }
     // Delete the channel from Discord
-    channel.delete(discord_api).await?;
+    let auditor = Auditor::new(
+        AuditSource::Task("end of game"),
+        db_connection,
+        discord_api,
+        guild_config,
+    );
+    let audit_entry = AuditEntry::channel_deleted(channel_id, Some(&channel.name));
+    if let Err(err) = channel.delete(discord_api).await {
+        auditor.record(audit_entry.failed(&err)).await;
+        return Err(err.into());
+    }
+    auditor.record(audit_entry).await;
     // Mark the channel as deleted
     mark_channel_as_deleted().await?;
     Ok(DeletionStatus::Deleted)
@@ -427,6 +475,7 @@ async fn delete_marked_role(
     existing_roles: &HashMap<RoleId, Role>,
     db_connection: &sqlx::PgPool,
     discord_api: &crate::discord::CacheAndHttp,
+    auditor: &Auditor<'_>,
 ) -> Result<DeletionStatus, crate::meetup::Error> {
     let mark_role_as_deleted = || async {
         if is_host_role {
@@ -466,8 +515,38 @@ async fn delete_marked_role(
         return Ok(DeletionStatus::NotDeleted);
     }
     // Delete the role from Discord
-    role.delete(&discord_api.http).await?;
+    let audit_entry = AuditEntry::role_deleted(role_id, &role.name);
+    if let Err(err) = role.delete(&discord_api.http).await {
+        auditor.record(audit_entry.failed(&err)).await;
+        return Err(err.into());
+    }
+    auditor.record(audit_entry).await;
     // Mark the role as deleted
     mark_role_as_deleted().await?;
     Ok(DeletionStatus::Deleted)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archived_channels_keep_their_deadline() {
+        let now = chrono::Utc::now();
+        let expired = now - chrono::Duration::days(10);
+        let new_session = now + chrono::Duration::days(7);
+        assert_eq!(
+            new_expiration_time(Some(expired), Some(new_session), false, now),
+            Some(new_session)
+        );
+        assert_eq!(
+            new_expiration_time(Some(expired), Some(new_session), true, now),
+            None
+        );
+        assert_eq!(
+            new_expiration_time(Some(new_session), Some(new_session), false, now),
+            None
+        );
+        assert_eq!(new_expiration_time(None, None, false, now), Some(now));
+    }
+}
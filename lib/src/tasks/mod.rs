@@ -1,4 +1,6 @@
 pub mod end_of_game;
+pub mod scheduler;
+pub mod session_reminder;
 pub mod subscription_roles;
 pub mod sync;
 pub mod token_refresh;
@@ -0,0 +1,519 @@
+use std::{future::Future, pin::Pin, str::FromStr, sync::Arc, time::Duration};
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Timelike, Utc};
+use futures_util::lock::{Mutex, OwnedMutexGuard};
+use simple_error::SimpleError;
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), crate::BoxedError>> + Send>>;
+
+// Gives the Discord client time to connect and fill its cache before a run
+// that was missed while the bot was down is made up for
+const CATCH_UP_DELAY: Duration = Duration::from_secs(10 * 60);
+
+// A schedule in the classic five field cron format
+// "minute hour day-of-month month day-of-week" (UTC). Every field supports
+// "*", single values, ranges ("1-5"), lists ("0,30") and steps ("*/15", "5-59/15").
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<u64, SimpleError> {
+    let invalid = || SimpleError::new(format!("Invalid cron field \"{}\"", field));
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().map_err(|_| invalid())?)),
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse::<u32>().map_err(|_| invalid())?,
+                end.parse::<u32>().map_err(|_| invalid())?,
+            )
+        } else {
+            let value = range.parse::<u32>().map_err(|_| invalid())?;
+            // "5/15" means "every 15, starting at 5"
+            (value, if step.is_some() { max } else { value })
+        };
+        if start < min || end > max || start > end || step == Some(0) {
+            return Err(invalid());
+        }
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+impl FromStr for CronSchedule {
+    type Err = SimpleError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields.as_slice() else {
+            return Err(SimpleError::new(format!(
+                "Cron expression \"{}\" does not have five fields",
+                expression
+            )));
+        };
+        let mut days_of_week_mask = parse_cron_field(days_of_week, 0, 7)?;
+        // Both 0 and 7 are Sunday
+        if days_of_week_mask & (1 << 7) != 0 {
+            days_of_week_mask = (days_of_week_mask | 1) & !(1 << 7);
+        }
+        Ok(CronSchedule {
+            expression: fields.join(" "),
+            minutes: parse_cron_field(minutes, 0, 59)?,
+            hours: parse_cron_field(hours, 0, 23)?,
+            days_of_month: parse_cron_field(days_of_month, 1, 31)?,
+            months: parse_cron_field(months, 1, 12)?,
+            days_of_week: days_of_week_mask,
+            days_of_month_restricted: *days_of_month != "*",
+            days_of_week_restricted: *days_of_week != "*",
+        })
+    }
+}
+
+impl std::fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl CronSchedule {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day_of_month = self.days_of_month & (1 << date.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+        // Like cron: if both fields are restricted, either one has to match
+        match (self.days_of_month_restricted, self.days_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    // The first point in time matching the schedule that is strictly after
+    // the given time. None if there is no such time within the next few years
+    // (e.g. for "0 0 31 2 *").
+    pub fn next_after(&self, time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = time.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let limit = time + chrono::Duration::days(5 * 366);
+        while time < limit {
+            let date = time.date_naive();
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = Utc.from_utc_datetime(
+                    &NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?,
+                );
+            } else if !self.matches_day(date) {
+                time = Utc.from_utc_datetime(&date.succ_opt()?.and_hms_opt(0, 0, 0)?);
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + chrono::Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += chrono::Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+// A recurring job. Runs of the same job never overlap, no matter whether
+// they were scheduled or triggered manually.
+pub struct Job {
+    name: &'static str,
+    schedule: CronSchedule,
+    timeout: Option<Duration>,
+    catch_up: bool,
+    run: Box<dyn Fn() -> JobFuture + Send + Sync>,
+    running: Arc<Mutex<()>>,
+}
+
+impl Job {
+    pub fn new<F, Fut>(name: &'static str, schedule: &str, run: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), crate::BoxedError>> + Send + 'static,
+    {
+        Job {
+            name,
+            schedule: schedule.parse().expect("Invalid job schedule"),
+            timeout: None,
+            catch_up: true,
+            run: Box::new(move || Box::pin(run())),
+            running: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    // Whether a run that was missed while the bot was down should be made up
+    // for shortly after startup. Defaults to true.
+    pub fn catch_up(mut self, catch_up: bool) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    Success,
+    Failure,
+    Timeout,
+}
+
+impl JobOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobOutcome::Success => "success",
+            JobOutcome::Failure => "failure",
+            JobOutcome::Timeout => "timeout",
+        }
+    }
+
+    fn parse(outcome: &str) -> Option<Self> {
+        match outcome {
+            "success" => Some(JobOutcome::Success),
+            "failure" => Some(JobOutcome::Failure),
+            "timeout" => Some(JobOutcome::Timeout),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub schedule: String,
+    pub is_running: bool,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_start_time: Option<DateTime<Utc>>,
+    pub last_duration: Option<Duration>,
+    pub last_outcome: Option<JobOutcome>,
+    pub last_error: Option<String>,
+    pub last_success_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriggerResult {
+    Started,
+    AlreadyRunning,
+    UnknownJob,
+}
+
+// Runs the recurring jobs according to their schedules and keeps track of
+// their runs in the `scheduled_job` table
+#[derive(Clone)]
+pub struct Scheduler {
+    db_connection: sqlx::PgPool,
+    jobs: Arc<Vec<Arc<Job>>>,
+}
+
+impl Scheduler {
+    pub fn new(db_connection: sqlx::PgPool, jobs: Vec<Job>) -> Self {
+        Scheduler {
+            db_connection,
+            jobs: Arc::new(jobs.into_iter().map(Arc::new).collect()),
+        }
+    }
+
+    pub async fn run(self) -> ! {
+        let schedules = self
+            .jobs
+            .iter()
+            .map(|job| run_schedule(self.db_connection.clone(), job.clone()));
+        futures::future::join_all(schedules).await;
+        unreachable!("Job schedules never finish")
+    }
+
+    pub fn job_names(&self) -> Vec<&'static str> {
+        self.jobs.iter().map(|job| job.name).collect()
+    }
+
+    // Starts a run of the job right away, unless it is already running
+    pub fn trigger(&self, name: &str) -> TriggerResult {
+        let job = match self.jobs.iter().find(|job| job.name == name) {
+            Some(job) => job.clone(),
+            None => return TriggerResult::UnknownJob,
+        };
+        let guard = match job.running.try_lock_owned() {
+            Some(guard) => guard,
+            None => return TriggerResult::AlreadyRunning,
+        };
+        let db_connection = self.db_connection.clone();
+        tokio::spawn(async move { run_job(&db_connection, &job, guard, None).await });
+        TriggerResult::Started
+    }
+
+    pub async fn status(&self) -> Result<Vec<JobStatus>, crate::meetup::Error> {
+        let rows = sqlx::query!(
+            r#"SELECT name, last_start_time, last_duration_ms, last_outcome, last_error, last_success_time
+            FROM scheduled_job"#
+        )
+        .fetch_all(&self.db_connection)
+        .await?;
+        let now = Utc::now();
+        let statuses = self
+            .jobs
+            .iter()
+            .map(|job| {
+                let row = rows.iter().find(|row| row.name == job.name);
+                JobStatus {
+                    name: job.name,
+                    schedule: job.schedule.to_string(),
+                    is_running: job.running.try_lock().is_none(),
+                    next_run: job.schedule.next_after(now),
+                    last_start_time: row.and_then(|row| row.last_start_time),
+                    last_duration: row
+                        .and_then(|row| row.last_duration_ms)
+                        .map(|ms| Duration::from_millis(ms as u64)),
+                    last_outcome: row
+                        .and_then(|row| row.last_outcome.as_deref())
+                        .and_then(JobOutcome::parse),
+                    last_error: row.and_then(|row| row.last_error.clone()),
+                    last_success_time: row.and_then(|row| row.last_success_time),
+                }
+            })
+            .collect();
+        Ok(statuses)
+    }
+}
+
+async fn run_schedule(db_connection: sqlx::PgPool, job: Arc<Job>) -> ! {
+    // Check whether a run was missed while we were down
+    let last_scheduled_time = sqlx::query_scalar!(
+        r#"SELECT last_scheduled_time FROM scheduled_job WHERE name = $1"#,
+        job.name
+    )
+    .fetch_optional(&db_connection)
+    .await;
+    let last_scheduled_time = match last_scheduled_time {
+        Ok(time) => time.flatten(),
+        Err(err) => {
+            eprintln!(
+                "Scheduler: Could not load the last run of job {}:\n{:#?}",
+                job.name, err
+            );
+            None
+        }
+    };
+    let now = Utc::now();
+    let missed_run = last_scheduled_time
+        .and_then(|time| job.schedule.next_after(time))
+        .filter(|&time| time <= now);
+    let mut next_run = job.schedule.next_after(now);
+    if let (true, Some(missed_run)) = (job.catch_up, missed_run) {
+        let catch_up_time = catch_up_time(next_run, now);
+        if next_run != Some(catch_up_time) {
+            println!(
+                "Scheduler: Catching up on job {} which was due at {}",
+                job.name,
+                missed_run.to_rfc3339()
+            );
+        }
+        next_run = Some(catch_up_time);
+    }
+    loop {
+        let Some(scheduled_time) = next_run else {
+            eprintln!("Scheduler: Job {} will never run again", job.name);
+            futures::future::pending::<()>().await;
+            unreachable!();
+        };
+        let wait_duration = (scheduled_time - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait_duration).await;
+        match job.running.try_lock_owned() {
+            Some(guard) => run_job(&db_connection, &job, guard, Some(scheduled_time)).await,
+            None => println!(
+                "Scheduler: Skipping job {} since it is still running",
+                job.name
+            ),
+        }
+        next_run = job.schedule.next_after(scheduled_time.max(Utc::now()));
+    }
+}
+
+// A missed run is made up for after the startup delay, unless the next
+// regular run comes first anyway
+fn catch_up_time(next_run: Option<DateTime<Utc>>, now: DateTime<Utc>) -> DateTime<Utc> {
+    let catch_up_time = now + CATCH_UP_DELAY;
+    next_run.map_or(catch_up_time, |next_run| next_run.min(catch_up_time))
+}
+
+// Runs the job and records the outcome. The guard makes sure that only one
+// run of the job happens at a time.
+async fn run_job(
+    db_connection: &sqlx::PgPool,
+    job: &Job,
+    _guard: OwnedMutexGuard<()>,
+    scheduled_time: Option<DateTime<Utc>>,
+) {
+    let start_time = Utc::now();
+    if let Err(err) = sqlx::query!(
+        r#"INSERT INTO scheduled_job (name, last_scheduled_time, last_start_time)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (name) DO UPDATE
+        SET last_scheduled_time = COALESCE(EXCLUDED.last_scheduled_time, scheduled_job.last_scheduled_time),
+            last_start_time = EXCLUDED.last_start_time"#,
+        job.name,
+        scheduled_time,
+        start_time
+    )
+    .execute(db_connection)
+    .await
+    {
+        eprintln!(
+            "Scheduler: Could not record the start of job {}:\n{:#?}",
+            job.name, err
+        );
+    }
+    println!("Scheduler: Running job {}", job.name);
+    // Spawn the job, such that a panic doesn't take the scheduler down with it
+    let mut join_handle = tokio::spawn((job.run)());
+    let result = match job.timeout {
+        Some(timeout) => match tokio::time::timeout(timeout, &mut join_handle).await {
+            Ok(result) => Some(result),
+            Err(_) => {
+                join_handle.abort();
+                None
+            }
+        },
+        None => Some(join_handle.await),
+    };
+    let (outcome, error) = match result {
+        Some(Ok(Ok(()))) => (JobOutcome::Success, None),
+        Some(Ok(Err(err))) => (JobOutcome::Failure, Some(format!("{:#?}", err))),
+        Some(Err(err)) => (JobOutcome::Failure, Some(format!("{:#?}", err))),
+        None => (JobOutcome::Timeout, None),
+    };
+    let duration = Utc::now() - start_time;
+    match &error {
+        Some(error) => eprintln!("Scheduler: Job {} failed:\n{}", job.name, error),
+        None => println!(
+            "Scheduler: Job {} finished ({}) after {}s",
+            job.name,
+            outcome.as_str(),
+            duration.num_seconds()
+        ),
+    }
+    if let Err(err) = sqlx::query!(
+        r#"UPDATE scheduled_job
+        SET last_duration_ms = $2, last_outcome = $3, last_error = $4,
+            last_success_time = CASE WHEN $3 = 'success' THEN $5 ELSE last_success_time END
+        WHERE name = $1"#,
+        job.name,
+        duration.num_milliseconds(),
+        outcome.as_str(),
+        error,
+        start_time
+    )
+    .execute(db_connection)
+    .await
+    {
+        eprintln!(
+            "Scheduler: Could not record the outcome of job {}:\n{:#?}",
+            job.name, err
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn every_quarter_hour_with_offset() {
+        let schedule: CronSchedule = "10-59/15 * * * *".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(utc(2026, 10, 17, 12, 10)),
+            Some(utc(2026, 10, 17, 12, 25))
+        );
+        assert_eq!(
+            schedule.next_after(utc(2026, 10, 17, 23, 56)),
+            Some(utc(2026, 10, 18, 0, 10))
+        );
+    }
+
+    #[test]
+    fn daily() {
+        let schedule: CronSchedule = "30 18 * * *".parse().unwrap();
+        assert_eq!(
+            schedule.next_after(utc(2026, 10, 17, 18, 30)),
+            Some(utc(2026, 10, 18, 18, 30))
+        );
+        assert_eq!(
+            schedule.next_after(utc(2026, 12, 31, 19, 0)),
+            Some(utc(2027, 1, 1, 18, 30))
+        );
+    }
+
+    #[test]
+    fn day_of_month_or_day_of_week() {
+        // The 1st of the month or any Sunday (0 and 7 both being Sunday)
+        let schedule: CronSchedule = "0 0 1 * 7".parse().unwrap();
+        // 2026-10-17 is a Saturday
+        assert_eq!(
+            schedule.next_after(utc(2026, 10, 17, 0, 0)),
+            Some(utc(2026, 10, 18, 0, 0))
+        );
+        assert_eq!(
+            schedule.next_after(utc(2026, 10, 31, 0, 0)),
+            Some(utc(2026, 11, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn invalid_schedules() {
+        for expression in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(
+                expression.parse::<CronSchedule>().is_err(),
+                "{}",
+                expression
+            );
+        }
+        let schedule: CronSchedule = "0 0 31 2 *".parse().unwrap();
+        assert_eq!(schedule.next_after(utc(2026, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn missed_runs_are_made_up_for_after_the_startup_delay() {
+        let now = utc(2026, 10, 17, 12, 0);
+        assert_eq!(
+            catch_up_time(Some(utc(2026, 10, 18, 18, 30)), now),
+            utc(2026, 10, 17, 12, 10)
+        );
+        assert_eq!(catch_up_time(None, now), utc(2026, 10, 17, 12, 10));
+        // The next regular run makes up for it
+        assert_eq!(
+            catch_up_time(Some(utc(2026, 10, 17, 12, 5)), now),
+            utc(2026, 10, 17, 12, 5)
+        );
+    }
+}
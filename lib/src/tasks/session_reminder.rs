@@ -0,0 +1,316 @@
+// Reminds game channels (and optionally the players by DM) of upcoming
+// sessions. Sent reminders are stored in the database so that a restart
+// doesn't post them twice.
+
+use serenity::{
+    builder::{CreateAllowedMentions, CreateMessage},
+    model::id::{ChannelId, RoleId, UserId},
+};
+
+use crate::{db, strings};
+
+// Reminders can be sent at most this long before a session
+pub const MAX_REMINDER_MINUTES: i32 = 14 * 24 * 60;
+pub const MIN_REMINDER_MINUTES: i32 = 5;
+pub const MAX_NUM_REMINDERS: usize = 5;
+
+#[derive(Debug, Clone)]
+pub struct SeriesReminderSettings {
+    // None means that the series uses the default of its type
+    pub reminder_minutes: Option<Vec<i32>>,
+    pub type_reminder_minutes: Vec<i32>,
+    pub series_type: String,
+    pub dm: bool,
+}
+
+impl SeriesReminderSettings {
+    pub fn effective_minutes(&self) -> &[i32] {
+        self.reminder_minutes
+            .as_deref()
+            .unwrap_or(&self.type_reminder_minutes)
+    }
+}
+
+// Parses reminder times like "24h 1h", "2d, 30m" or "90 minutes"
+pub fn parse_reminder_minutes(value: &str) -> Option<Vec<i32>> {
+    let value = value.to_lowercase();
+    let mut minutes = vec![];
+    let mut rest = value.trim();
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let number: i32 = rest[..number_end].parse().ok()?;
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let factor = match &rest[..unit_end] {
+            "d" | "day" | "days" => 24 * 60,
+            "h" | "hour" | "hours" => 60,
+            "m" | "min" | "mins" | "minute" | "minutes" => 1,
+            _ => return None,
+        };
+        minutes.push(number.checked_mul(factor)?);
+        rest = rest[unit_end..].trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+    }
+    if minutes.is_empty() {
+        return None;
+    }
+    // Longest first, without duplicates
+    minutes.sort_unstable_by(|a, b| b.cmp(a));
+    minutes.dedup();
+    Some(minutes)
+}
+
+pub fn are_valid_reminder_minutes(minutes: &[i32]) -> bool {
+    minutes.len() <= MAX_NUM_REMINDERS
+        && minutes
+            .iter()
+            .all(|&minutes| (MIN_REMINDER_MINUTES..=MAX_REMINDER_MINUTES).contains(&minutes))
+}
+
+pub fn format_reminder_minutes(minutes: &[i32]) -> String {
+    if minutes.is_empty() {
+        return "off".to_string();
+    }
+    minutes
+        .iter()
+        .map(|&minutes| match (minutes / 60, minutes % 60) {
+            (0, minutes) => format!("{} min", minutes),
+            (hours, 0) => crate::expiration_policy::format_hours(hours),
+            (hours, minutes) => format!(
+                "{} {} min",
+                crate::expiration_policy::format_hours(hours),
+                minutes
+            ),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub async fn get_series_settings(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<SeriesReminderSettings, crate::meetup::Error> {
+    let row = sqlx::query!(
+        r#"SELECT event_series.session_reminder_minutes, event_series.session_reminder_dm,
+            event_series_type."type", event_series_type.session_reminder_minutes AS type_session_reminder_minutes
+        FROM event_series
+        INNER JOIN event_series_type ON event_series."type" = event_series_type."type"
+        WHERE event_series.id = $1"#,
+        series_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(SeriesReminderSettings {
+        reminder_minutes: row.session_reminder_minutes,
+        type_reminder_minutes: row.type_session_reminder_minutes,
+        series_type: row.r#type,
+        dm: row.session_reminder_dm,
+    })
+}
+
+// None goes back to the default of the series type, an empty list mutes the
+// reminders
+pub async fn set_series_reminder_minutes(
+    series_id: db::EventSeriesId,
+    minutes: Option<&[i32]>,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"UPDATE event_series SET session_reminder_minutes = $2 WHERE id = $1"#,
+        series_id.0,
+        minutes
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+pub async fn set_series_reminder_dm(
+    series_id: db::EventSeriesId,
+    dm: bool,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"UPDATE event_series SET session_reminder_dm = $2 WHERE id = $1"#,
+        series_id.0,
+        dm
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+struct UpcomingSession {
+    event_id: db::EventId,
+    title: String,
+    start_time: chrono::DateTime<chrono::Utc>,
+    channel_id: ChannelId,
+    role_id: Option<RoleId>,
+    reminder_minutes: Vec<i32>,
+    sent_reminder_minutes: Vec<i32>,
+    dm: bool,
+}
+
+// Sends the reminders that are due. If a session has several due reminders
+// (e.g. because the bot was offline) only one message gets sent.
+pub async fn session_reminder_task(
+    db_connection: &sqlx::PgPool,
+    discord_api: &crate::discord::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let sessions = sqlx::query!(
+        r#"SELECT event.id, event.title, event.start_time,
+            event_series_text_channel.discord_id AS channel_id,
+            event_series.discord_role_id,
+            COALESCE(event_series.session_reminder_minutes, event_series_type.session_reminder_minutes) AS "reminder_minutes!",
+            ARRAY(
+                SELECT event_reminder.reminder_minutes FROM event_reminder
+                WHERE event_reminder.event_id = event.id AND event_reminder.start_time = event.start_time
+            ) AS "sent_reminder_minutes!",
+            event_series.session_reminder_dm
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        INNER JOIN event_series_type ON event_series."type" = event_series_type."type"
+        INNER JOIN event_series_text_channel ON event_series.discord_text_channel_id = event_series_text_channel.discord_id
+        WHERE event.deleted IS NULL
+        AND event.start_time > NOW()
+        AND event.start_time < NOW() + make_interval(mins => $1)
+        AND event_series_text_channel.deleted IS NULL
+        AND event_series_text_channel.archived IS NULL"#,
+        MAX_REMINDER_MINUTES
+    )
+    .map(|row| UpcomingSession {
+        event_id: db::EventId(row.id),
+        title: row.title,
+        start_time: row.start_time,
+        channel_id: ChannelId::new(row.channel_id as u64),
+        role_id: row.discord_role_id.map(|id| RoleId::new(id as u64)),
+        reminder_minutes: row.reminder_minutes,
+        sent_reminder_minutes: row.sent_reminder_minutes,
+        dm: row.session_reminder_dm,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let now = chrono::Utc::now();
+    let mut some_failed = false;
+    for session in sessions {
+        let due_minutes: Vec<i32> = session
+            .reminder_minutes
+            .iter()
+            .copied()
+            .filter(|minutes| !session.sent_reminder_minutes.contains(minutes))
+            .filter(|&minutes| {
+                session.start_time - chrono::Duration::minutes(minutes as i64) <= now
+            })
+            .collect();
+        if due_minutes.is_empty() {
+            continue;
+        }
+        // Marked as sent before sending, so a crash in between rather loses a
+        // reminder than posting it twice
+        let newly_marked = sqlx::query_scalar!(
+            r#"INSERT INTO event_reminder (event_id, start_time, reminder_minutes)
+            SELECT $1, $2, reminder_minutes FROM UNNEST($3::integer[]) AS reminder_minutes
+            ON CONFLICT DO NOTHING
+            RETURNING reminder_minutes"#,
+            session.event_id.0,
+            session.start_time,
+            &due_minutes
+        )
+        .fetch_all(db_connection)
+        .await?;
+        if newly_marked.is_empty() {
+            continue;
+        }
+        if let Err(err) = send_reminder(&session, db_connection, discord_api).await {
+            eprintln!(
+                "Could not send the session reminder for event {}:\n{:#?}",
+                session.event_id.0, err
+            );
+            some_failed = true;
+        }
+    }
+    if some_failed {
+        Err(simple_error::SimpleError::new("Some session reminders failed").into())
+    } else {
+        Ok(())
+    }
+}
+
+async fn send_reminder(
+    session: &UpcomingSession,
+    db_connection: &sqlx::PgPool,
+    discord_api: &crate::discord::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let content = strings::SESSION_REMINDER(session.role_id, &session.title, session.start_time);
+    let allowed_mentions = match session.role_id {
+        Some(role_id) => CreateAllowedMentions::new().roles(vec![role_id]),
+        None => CreateAllowedMentions::new(),
+    };
+    session
+        .channel_id
+        .send_message(
+            &discord_api.http,
+            CreateMessage::new()
+                .content(content)
+                .allowed_mentions(allowed_mentions),
+        )
+        .await?;
+    if !session.dm {
+        return Ok(());
+    }
+    let players = sqlx::query_scalar!(
+        r#"SELECT "member".discord_id AS "discord_id!"
+        FROM event_participant
+        INNER JOIN "member" ON event_participant.member_id = "member".id
+        WHERE event_participant.event_id = $1 AND "member".discord_id IS NOT NULL"#,
+        session.event_id.0
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let dm_content =
+        strings::SESSION_REMINDER_DM(&session.title, session.start_time, session.channel_id);
+    for discord_id in players {
+        let user_id = UserId::new(discord_id as u64);
+        let dm_result = match user_id.create_dm_channel(&discord_api.http).await {
+            Ok(channel) => channel
+                .id
+                .send_message(
+                    &discord_api.http,
+                    CreateMessage::new().content(dm_content.clone()),
+                )
+                .await
+                .map(|_| ()),
+            Err(err) => Err(err),
+        };
+        // Players who don't accept DMs still get the channel reminder
+        if let Err(err) = dm_result {
+            eprintln!(
+                "Could not send a session reminder DM to {}:\n{:#?}",
+                user_id, err
+            );
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_reminder_minutes() {
+        assert_eq!(parse_reminder_minutes("1h 24h"), Some(vec![1440, 60]));
+        assert_eq!(parse_reminder_minutes("2d, 30m"), Some(vec![2880, 30]));
+        assert_eq!(parse_reminder_minutes("90 minutes"), Some(vec![90]));
+        assert_eq!(parse_reminder_minutes("1h 60m"), Some(vec![60]));
+        assert_eq!(parse_reminder_minutes("soon"), None);
+        assert_eq!(parse_reminder_minutes(""), None);
+        assert!(are_valid_reminder_minutes(&[1440, 60]));
+        assert!(!are_valid_reminder_minutes(&[1]));
+        assert_eq!(format_reminder_minutes(&[1440, 90]), "1 day, 1 hour 30 min");
+        assert_eq!(format_reminder_minutes(&[]), "off");
+    }
+}
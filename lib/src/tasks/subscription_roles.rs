@@ -1,13 +1,15 @@
-use std::{
-    collections::{HashMap, HashSet},
-    sync::Arc,
-};
+use std::collections::{HashMap, HashSet};
 
 use lazy_static::lazy_static;
 use serenity::futures::StreamExt;
 use serenity::{
     http::CacheHttp,
-    model::id::{RoleId, UserId},
+    model::id::{GuildId, RoleId, UserId},
+};
+
+use crate::{
+    audit::{AuditEntry, AuditSource, Auditor},
+    discord::guild_config::GuildConfig,
 };
 
 pub const CHAMPION_PRODUCT_PATTERN: &'static str =
@@ -21,67 +23,25 @@ lazy_static! {
         regex::Regex::new(INSIDER_PRODUCT_PATTERN).unwrap();
 }
 
-#[cfg(feature = "bottest")]
-pub mod ids {
-    use super::*;
-    // Test server:
-    pub const CHAMPION_ID: RoleId = RoleId::new(670250507436294144);
-    pub const INSIDER_ID: RoleId = RoleId::new(670250754422079488);
-    pub const GM_CHAMPION_ID: RoleId = RoleId::new(671107703703207940);
-}
-
-#[cfg(not(feature = "bottest"))]
-pub mod ids {
-    use super::*;
-    // SwissRPG server:
-    pub const CHAMPION_ID: RoleId = RoleId::new(670197555166052362);
-    pub const INSIDER_ID: RoleId = RoleId::new(670201953883783169);
-    pub const GM_CHAMPION_ID: RoleId = RoleId::new(671111220119470093);
-}
-
-pub async fn stripe_subscriptions_refresh_task(
-    discord_api: crate::discord::CacheAndHttp,
-    stripe_client: Arc<stripe::Client>,
-) -> ! {
-    // Sync every 8 hours, starting in an hour from now
-    let mut interval_timer = tokio::time::interval_at(
-        tokio::time::Instant::now() + tokio::time::Duration::from_secs(60 * 60),
-        tokio::time::Duration::from_secs(8 * 60 * 60),
-    );
-    // Run forever
-    loop {
-        // Wait for the next interval tick
-        interval_timer.tick().await;
-        println!("Refreshing Stripe subscription information");
-        let join_handle = {
-            let discord_api = discord_api.clone();
-            let stripe_client = stripe_client.clone();
-            tokio::spawn(async move { update_roles(&discord_api, &stripe_client).await })
-        };
-        match join_handle.await {
-            Err(err) => {
-                eprintln!("Stripe subscription update task failed:\n{:#?}", err);
-            }
-            Ok(Err(err)) => {
-                eprintln!("Stripe subscription update task failed:\n{:#?}", err);
-            }
-            Ok(Ok(())) => {
-                // Nothing to do
-            }
-        }
-    }
-}
-
 pub async fn update_roles(
+    db_connection: &sqlx::PgPool,
     discord_api: &crate::discord::CacheAndHttp,
     stripe_client: &stripe::Client,
+    guild_config: &GuildConfig,
 ) -> Result<(), crate::meetup::Error> {
+    let auditor = Auditor::new(
+        AuditSource::Task("subscription roles"),
+        db_connection,
+        discord_api,
+        guild_config,
+    );
     // Get all active subscriptions from Stripe
     let subscriptions = crate::stripe::list_active_subscriptions(stripe_client).await?;
     // For each subscription, find which product and customer are associated with it
     // and add the Discord name stored in Stripe to the appropriate list
     let mut new_champions = vec![];
     let mut new_insiders = vec![];
+    let mut supporter_tiers = HashMap::new();
     for subscription in &subscriptions {
         // Since we don't have try blocks yet we need to match on every single error...
         let (customer, product) = match get_customer_and_product(stripe_client, subscription).await
@@ -95,17 +55,23 @@ pub async fn update_roles(
                 continue;
             }
         };
-        let discord_id =
-            match ensure_customer_has_discord_id(&customer, stripe_client, discord_api).await {
-                Ok(discord_id) => discord_id,
-                Err(err) => {
-                    eprintln!(
-                        "Error in update_roles ensure_customer_has_discord_id:\n{:#?}",
-                        err
-                    );
-                    continue;
-                }
-            };
+        let discord_id = match ensure_customer_has_discord_id(
+            &customer,
+            stripe_client,
+            discord_api,
+            guild_config.guild_id,
+        )
+        .await
+        {
+            Ok(discord_id) => discord_id,
+            Err(err) => {
+                eprintln!(
+                    "Error in update_roles ensure_customer_has_discord_id:\n{:#?}",
+                    err
+                );
+                continue;
+            }
+        };
         if let Some(discord_id) = discord_id {
             let is_champion_product = product
                 .name
@@ -121,6 +87,9 @@ pub async fn update_roles(
             if is_insider_product {
                 new_insiders.push(discord_id);
             }
+            if let Some(tier) = product.name.as_deref().and_then(product_tier) {
+                supporter_tiers.insert(discord_id, tier.to_string());
+            }
         } else {
             eprintln!(
                 "Could not find Discord ID for Stripe customer {} ({:?})",
@@ -128,6 +97,8 @@ pub async fn update_roles(
             );
         }
     }
+    // Remember the tiers for the members' profiles
+    store_supporter_tiers(&supporter_tiers, db_connection).await?;
     // Now, check which Discord users already have the Champion and Insider roles
     let mut current_champions = vec![];
     let mut current_gm_champions = vec![];
@@ -135,16 +106,14 @@ pub async fn update_roles(
     let mut current_gms = vec![];
     let members = discord_api
         .cache
-        .guild(crate::discord::sync::ids::GUILD_ID)
+        .guild(guild_config.guild_id)
         .map(|guild| guild.members.clone())
         .ok_or_else(|| simple_error::SimpleError::new("Did not find guild in cache"))?;
     for (&user_id, member) in &members {
-        let is_champion = member.roles.contains(&ids::CHAMPION_ID);
-        let is_gm_champion = member.roles.contains(&ids::GM_CHAMPION_ID);
-        let is_insider = member.roles.contains(&ids::INSIDER_ID);
-        let is_gm = member
-            .roles
-            .contains(&crate::discord::sync::ids::GAME_MASTER_ID);
+        let is_champion = member.roles.contains(&guild_config.champion_role_id);
+        let is_gm_champion = member.roles.contains(&guild_config.gm_champion_role_id);
+        let is_insider = member.roles.contains(&guild_config.insider_role_id);
+        let is_gm = member.roles.contains(&guild_config.game_master_role_id);
         if is_champion {
             current_champions.push(user_id);
         }
@@ -172,9 +141,11 @@ pub async fn update_roles(
                 // Assign GM champion role
                 if let Err(err) = add_member_role(
                     discord_api,
+                    guild_config.guild_id,
                     *new_champion,
-                    ids::GM_CHAMPION_ID,
+                    guild_config.gm_champion_role_id,
                     Some("Automatic role assignment due to being a GM champion"),
+                    &auditor,
                 )
                 .await
                 {
@@ -185,9 +156,11 @@ pub async fn update_roles(
                 // Remove (non-GM) champion role
                 if let Err(err) = remove_member_role(
                     discord_api,
+                    guild_config.guild_id,
                     *new_champion,
-                    ids::CHAMPION_ID,
+                    guild_config.champion_role_id,
                     Some("Automatic role removal due to being upgraded to a GM champion"),
+                    &auditor,
                 )
                 .await
                 {
@@ -200,9 +173,11 @@ pub async fn update_roles(
                 // Assign champion role
                 if let Err(err) = add_member_role(
                     discord_api,
+                    guild_config.guild_id,
                     *new_champion,
-                    ids::CHAMPION_ID,
+                    guild_config.champion_role_id,
                     Some("Automatic role assignment due to being a champion"),
+                    &auditor,
                 )
                 .await
                 {
@@ -213,9 +188,11 @@ pub async fn update_roles(
                 // Remove GM champion role
                 if let Err(err) = remove_member_role(
                     discord_api,
+                    guild_config.guild_id,
                     *new_champion,
-                    ids::GM_CHAMPION_ID,
+                    guild_config.gm_champion_role_id,
                     Some("Automatic role removal due to no longer being a GM champion"),
+                    &auditor,
                 )
                 .await
                 {
@@ -229,9 +206,11 @@ pub async fn update_roles(
             // Assign insider role
             if let Err(err) = add_member_role(
                 discord_api,
+                guild_config.guild_id,
                 *new_insider,
-                ids::INSIDER_ID,
+                guild_config.insider_role_id,
                 Some("Automatic role assignment due to being an insider"),
+                &auditor,
             )
             .await
             {
@@ -244,9 +223,11 @@ pub async fn update_roles(
             // Remove champion role
             if let Err(err) = remove_member_role(
                 discord_api,
+                guild_config.guild_id,
                 *current_champion,
-                ids::CHAMPION_ID,
+                guild_config.champion_role_id,
                 Some("Automatic role removal due to no longer being a champion"),
+                &auditor,
             )
             .await
             {
@@ -261,9 +242,11 @@ pub async fn update_roles(
             // Remove GM champion role
             if let Err(err) = remove_member_role(
                 discord_api,
+                guild_config.guild_id,
                 *current_gm_champion,
-                ids::GM_CHAMPION_ID,
+                guild_config.gm_champion_role_id,
                 Some("Automatic role removal due to no longer being a GM or a champion"),
+                &auditor,
             )
             .await
             {
@@ -276,9 +259,11 @@ pub async fn update_roles(
             // Remove insider role
             if let Err(err) = remove_member_role(
                 discord_api,
+                guild_config.guild_id,
                 *current_insider,
-                ids::INSIDER_ID,
+                guild_config.insider_role_id,
                 Some("Automatic role removal due to no longer being an insider"),
+                &auditor,
             )
             .await
             {
@@ -289,6 +274,35 @@ pub async fn update_roles(
     Ok(())
 }
 
+// The supporter tier named in a product, e.g. "Adept"
+pub fn product_tier(product_name: &str) -> Option<&str> {
+    CHAMPION_PRODUCT_REGEX
+        .captures(product_name)
+        .and_then(|captures| captures.get(1))
+        .map(|tier| tier.as_str())
+}
+
+async fn store_supporter_tiers(
+    supporter_tiers: &HashMap<UserId, String>,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    sqlx::query!(r#"UPDATE "member" SET supporter_tier = NULL WHERE supporter_tier IS NOT NULL"#)
+        .execute(&mut *tx)
+        .await?;
+    for (discord_id, tier) in supporter_tiers {
+        sqlx::query!(
+            r#"UPDATE "member" SET supporter_tier = $2 WHERE discord_id = $1"#,
+            discord_id.get() as i64,
+            tier
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
 pub async fn get_customer_and_product(
     client: &stripe::Client,
     subscription: &stripe::Subscription,
@@ -328,11 +342,12 @@ async fn ensure_customer_has_discord_id(
     customer: &stripe::Customer,
     client: &stripe::Client,
     discord_api: &crate::discord::CacheAndHttp,
+    guild_id: GuildId,
 ) -> Result<Option<UserId>, crate::meetup::Error> {
     let discord_id = customer
         .metadata
         .as_ref()
-        .and_then(|metadata| metadata.get("_hyperion_discord_id"))
+        .and_then(|metadata| metadata.get(crate::stripe::DISCORD_ID_METADATA_KEY))
         .map(|id| id.parse::<u64>())
         .transpose()
         .unwrap_or(None);
@@ -345,26 +360,27 @@ async fn ensure_customer_has_discord_id(
         let discord_username = match customer
             .metadata
             .as_ref()
-            .and_then(|metadata| metadata.get("Discord"))
+            .and_then(|metadata| metadata.get(crate::stripe::DISCORD_USERNAME_METADATA_KEY))
         {
             None => return Ok(None),
             Some(username) => username,
         };
-        let discord_id = match discord_username_to_id(discord_api, discord_username).await? {
-            Some(id) => id,
-            None => {
-                eprintln!(
-                    "Could not find Discord ID for username `{}`",
-                    discord_username
-                );
-                return Ok(None);
-            }
-        };
+        let discord_id =
+            match discord_username_to_id(discord_api, guild_id, discord_username).await? {
+                Some(id) => id,
+                None => {
+                    eprintln!(
+                        "Could not find Discord ID for username `{}`",
+                        discord_username
+                    );
+                    return Ok(None);
+                }
+            };
         // Try to store the Discord ID in Stripe.
         // Don't fail this method if it doesn't work, just log it.
         let mut new_metadata = HashMap::new();
         new_metadata.insert(
-            "_hyperion_discord_id".to_string(),
+            crate::stripe::DISCORD_ID_METADATA_KEY.to_string(),
             format!("{}", discord_id),
         );
         if let Err(err) = stripe::Customer::update(
@@ -390,11 +406,10 @@ async fn ensure_customer_has_discord_id(
 // TODO: move to discord utils
 pub async fn discord_username_to_id(
     discord_api: &crate::discord::CacheAndHttp,
+    guild_id: GuildId,
     username: &str,
 ) -> Result<Option<UserId>, crate::meetup::Error> {
-    let mut members = crate::discord::sync::ids::GUILD_ID
-        .members_iter(discord_api.http())
-        .boxed();
+    let mut members = guild_id.members_iter(discord_api.http()).boxed();
     while let Some(member_result) = members.next().await {
         let member = member_result?;
         if member.user.name == username
@@ -414,22 +429,22 @@ pub async fn discord_username_to_id(
 // TODO: move to discord utils
 pub async fn add_member_role(
     discord_api: &crate::discord::CacheAndHttp,
+    guild_id: GuildId,
     user_id: UserId,
     role_id: RoleId,
     audit_log_reason: Option<&str>,
+    auditor: &Auditor<'_>,
 ) -> Result<(), crate::meetup::Error> {
     match discord_api
         .http()
-        .add_member_role(
-            crate::discord::sync::ids::GUILD_ID,
-            user_id,
-            role_id,
-            audit_log_reason,
-        )
+        .add_member_role(guild_id, user_id, role_id, audit_log_reason)
         .await
     {
         Ok(_) => {
             println!("Assigned user {} to role {}", user_id, role_id);
+            auditor
+                .record(AuditEntry::role_added(user_id, role_id))
+                .await;
             Ok(())
         }
         Err(err) => {
@@ -437,30 +452,33 @@ pub async fn add_member_role(
                 "Could not assign user {} to role {}:\n{:#?}",
                 user_id, role_id, err
             );
+            auditor
+                .record(AuditEntry::role_added(user_id, role_id).failed(&err))
+                .await;
             Err(err.into())
         }
     }
 }
 
 // TODO: move to discord utils
-async fn remove_member_role(
+pub async fn remove_member_role(
     discord_api: &crate::discord::CacheAndHttp,
+    guild_id: GuildId,
     user_id: UserId,
     role_id: RoleId,
     audit_log_reason: Option<&str>,
+    auditor: &Auditor<'_>,
 ) -> Result<(), crate::meetup::Error> {
     match discord_api
         .http()
-        .remove_member_role(
-            crate::discord::sync::ids::GUILD_ID,
-            user_id,
-            role_id,
-            audit_log_reason,
-        )
+        .remove_member_role(guild_id, user_id, role_id, audit_log_reason)
         .await
     {
         Ok(_) => {
             println!("Removed role {} from user {}", role_id, user_id);
+            auditor
+                .record(AuditEntry::role_removed(user_id, role_id))
+                .await;
             Ok(())
         }
         Err(err) => {
@@ -468,7 +486,22 @@ async fn remove_member_role(
                 "Could not remove role {} from user {}:\n{:#?}",
                 role_id, user_id, err
             );
+            auditor
+                .record(AuditEntry::role_removed(user_id, role_id).failed(&err))
+                .await;
             Err(err.into())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers_from_product_names() {
+        assert_eq!(product_tier("Adept Champion (monthly)"), Some("Adept"));
+        assert_eq!(product_tier("legendary supporter"), Some("legendary"));
+        assert_eq!(product_tier("Gift card"), None);
+    }
+}
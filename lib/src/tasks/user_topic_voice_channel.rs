@@ -1,4 +1,3 @@
-use redis::AsyncCommands;
 use serenity::{builder::EditChannel, model::channel::Channel};
 use std::time::Duration;
 
@@ -6,43 +5,38 @@ pub const DEFAULT_USER_TOPIC_VOICE_CHANNEL_NAME: &str = "Your topic (ask Hyperio
 
 // Resets the user topic voice channel
 pub async fn reset_user_topic_voice_channel_task(
-    redis_client: redis::Client,
-    mut discord_api: crate::discord::CacheAndHttp,
-) -> ! {
-    // Do this every minute
-    let mut interval_timer = tokio::time::interval_at(
-        tokio::time::Instant::now() + tokio::time::Duration::from_secs(10),
-        tokio::time::Duration::from_secs(60),
-    );
-    // Run forever
-    loop {
-        // Wait for the next interval tick
-        interval_timer.tick().await;
-        let mut redis_connection = match redis_client.get_multiplexed_async_connection().await {
-            Ok(con) => con,
-            Err(err) => {
-                eprintln!(
-                    "User topic voice channel reset task: Could not acquire Redis connection:\n{:#?}",
-                    err
-                );
-                continue;
-            }
-        };
-        if let Err(err) =
-            reset_user_topic_voice_channel(&mut redis_connection, &mut discord_api).await
-        {
-            eprintln!("User topic voice channel reset task failed:\n{:#?}", err);
-        }
-    }
+    db_connection: &sqlx::PgPool,
+    discord_api: &crate::discord::CacheAndHttp,
+    guild_config: &crate::discord::guild_config::GuildConfig,
+) -> Result<(), crate::meetup::Error> {
+    reset_user_topic_voice_channel(db_connection, discord_api, guild_config).await
+}
+
+// Remembers when the topic was set, such that the channel doesn't get reset
+// right away
+pub async fn set_topic_time(
+    db_connection: &sqlx::PgPool,
+    topic_time: chrono::DateTime<chrono::Utc>,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO ephemeral_settings (user_topic_voice_channel_topic_time)
+            VALUES ($1)
+            ON CONFLICT (id) DO UPDATE
+            SET user_topic_voice_channel_topic_time = $1"#,
+        topic_time
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
 }
 
 async fn reset_user_topic_voice_channel(
-    redis_connection: &mut redis::aio::MultiplexedConnection,
+    db_connection: &sqlx::PgPool,
     discord_api: &crate::discord::CacheAndHttp,
+    guild_config: &crate::discord::guild_config::GuildConfig,
 ) -> Result<(), crate::meetup::Error> {
     // Check if there is a user topic voice channel
-    let voice_channel_id = if let Some(id) = crate::discord::sync::ids::USER_TOPIC_VOICE_CHANNEL_ID
-    {
+    let voice_channel_id = if let Some(id) = guild_config.user_topic_voice_channel_id {
         id
     } else {
         return Ok(());
@@ -62,16 +56,12 @@ async fn reset_user_topic_voice_channel(
         return Ok(());
     }
     // Check if the voice channel has not been renamed very recently
-    let topic_time = match redis_connection
-        .get::<_, Option<String>>("user_topic_voice_channel_topic_time")
-        .await?
-    {
-        Some(time) => match chrono::DateTime::parse_from_rfc3339(&time) {
-            Ok(time) => Some(time.with_timezone(&chrono::Utc)),
-            Err(_) => None,
-        },
-        None => None,
-    };
+    let topic_time = sqlx::query_scalar!(
+        r#"SELECT user_topic_voice_channel_topic_time FROM ephemeral_settings"#
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten();
     if let Some(topic_time) = topic_time {
         if chrono::Utc::now() - topic_time < chrono::Duration::minutes(2) {
             return Ok(());
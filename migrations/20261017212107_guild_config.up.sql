@@ -0,0 +1,40 @@
+BEGIN;
+
+-- Discord guild, role, category and channel IDs the bot works with.
+-- There is exactly one row per database.
+CREATE TABLE guild_config (
+    id bool PRIMARY KEY DEFAULT TRUE,
+    guild_id bigint NOT NULL,
+    bot_admin_role_id bigint NOT NULL,
+    organiser_role_id bigint NOT NULL,
+    game_master_role_id bigint NOT NULL,
+    dice_roller_bot_role_id bigint,
+    admin_role_id bigint,
+    champion_role_id bigint NOT NULL,
+    insider_role_id bigint NOT NULL,
+    gm_champion_role_id bigint NOT NULL,
+    one_shot_category_ids bigint[] NOT NULL DEFAULT '{}',
+    campaign_category_ids bigint[] NOT NULL DEFAULT '{}',
+    voice_channel_category_ids bigint[] NOT NULL DEFAULT '{}',
+    bot_alerts_channel_id bigint,
+    free_spots_channel_id bigint,
+    user_topic_voice_channel_id bigint,
+    CONSTRAINT onerow CHECK (id)
+);
+
+-- Seed the IDs that used to be compiled into the bot
+DO $$
+BEGIN
+    IF current_database() = 'bottest' THEN
+        -- Test server
+        INSERT INTO guild_config (guild_id, bot_admin_role_id, organiser_role_id, game_master_role_id, dice_roller_bot_role_id, admin_role_id, champion_role_id, insider_role_id, gm_champion_role_id, one_shot_category_ids, campaign_category_ids, voice_channel_category_ids, bot_alerts_channel_id, free_spots_channel_id, user_topic_voice_channel_id)
+        VALUES (601070848446824509, 606829075226689536, 689914933357314090, 606913167439822987, NULL, NULL, 670250507436294144, 670250754422079488, 671107703703207940, '{607561808429056042}', '{607561949651402772}', '{601070848446824512}', 650656330390175764, 704988201038643270, 807270405672140831);
+    ELSIF current_database() = 'bot' THEN
+        -- SwissRPG server
+        INSERT INTO guild_config (guild_id, bot_admin_role_id, organiser_role_id, game_master_role_id, dice_roller_bot_role_id, admin_role_id, champion_role_id, insider_role_id, gm_champion_role_id, one_shot_category_ids, campaign_category_ids, voice_channel_category_ids, bot_alerts_channel_id, free_spots_channel_id, user_topic_voice_channel_id)
+        VALUES (401856510709202945, 610541498852966436, 539447673988841492, 412946716892069888, 600612886368223274, 412927099855437825, 670197555166052362, 670201953883783169, 671111220119470093, '{562607292176924694}', '{414074722259828736,651006290998329354}', '{401856511233753110,831140794952843324}', 650660608705822723, 706131908102324345, 811601700736729129);
+    END IF;
+END
+$$;
+
+COMMIT;
@@ -0,0 +1,9 @@
+BEGIN;
+
+ALTER TABLE event_participant
+DROP COLUMN from_discord;
+
+ALTER TABLE event
+DROP COLUMN open_seats;
+
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN;
+
+-- Seats that are still free according to SwissRPG (not counting RSVPs made on Discord)
+ALTER TABLE event
+ADD COLUMN open_seats integer;
+
+-- RSVPs made with the buttons on the Discord announcement. Those are not known
+-- to Meetup or SwissRPG, so the syncs must not remove them.
+ALTER TABLE event_participant
+ADD COLUMN from_discord boolean NOT NULL DEFAULT FALSE;
+
+COMMIT;
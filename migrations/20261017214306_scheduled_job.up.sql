@@ -0,0 +1,16 @@
+BEGIN;
+
+-- Last run of each recurring job of the scheduler
+CREATE TABLE scheduled_job (
+    name text PRIMARY KEY,
+    -- The slot of the schedule the last scheduled run was for. Used to catch
+    -- up on runs that were missed while the bot was down.
+    last_scheduled_time timestamp (0) with time zone,
+    last_start_time timestamp (0) with time zone,
+    last_duration_ms bigint,
+    last_outcome text CHECK (last_outcome IN ('success', 'failure', 'timeout')),
+    last_error text,
+    last_success_time timestamp (0) with time zone
+);
+
+COMMIT;
@@ -0,0 +1,13 @@
+BEGIN;
+
+ALTER TABLE event_series
+    DROP COLUMN reminder_delay_hours,
+    DROP COLUMN reminder_interval_hours,
+    DROP COLUMN deletion_delay_hours;
+
+ALTER TABLE event_series_type
+    DROP COLUMN reminder_delay_hours,
+    DROP COLUMN reminder_interval_hours,
+    DROP COLUMN deletion_delay_hours;
+
+COMMIT;
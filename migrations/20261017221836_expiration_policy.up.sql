@@ -0,0 +1,18 @@
+BEGIN;
+
+-- How long after a series' last session its channel gets reminded of its
+-- expiration, how often the reminder is repeated and how long after
+-- "end adventure" the channel gets deleted
+ALTER TABLE event_series_type
+    ADD COLUMN reminder_delay_hours integer NOT NULL DEFAULT 24 CHECK (reminder_delay_hours >= 0),
+    ADD COLUMN reminder_interval_hours integer NOT NULL DEFAULT 48 CHECK (reminder_interval_hours > 0),
+    ADD COLUMN deletion_delay_hours integer NOT NULL DEFAULT 8 CHECK (deletion_delay_hours >= 0);
+UPDATE event_series_type SET reminder_delay_hours = 72, reminder_interval_hours = 96 WHERE "type" = 'campaign';
+
+-- Per-series overrides of the above, NULL means the series type's value is used
+ALTER TABLE event_series
+    ADD COLUMN reminder_delay_hours integer CHECK (reminder_delay_hours >= 0),
+    ADD COLUMN reminder_interval_hours integer CHECK (reminder_interval_hours > 0),
+    ADD COLUMN deletion_delay_hours integer CHECK (deletion_delay_hours >= 0);
+
+COMMIT;
@@ -0,0 +1,8 @@
+BEGIN;
+
+ALTER TABLE event_series_text_channel
+    DROP COLUMN event_series_id,
+    DROP COLUMN transcript_file,
+    DROP COLUMN transcript_time;
+
+COMMIT;
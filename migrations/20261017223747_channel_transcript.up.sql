@@ -0,0 +1,18 @@
+BEGIN;
+
+-- The series a text channel belongs to. Unlike event_series.discord_text_channel_id
+-- this is kept after the series moved on to a new channel.
+ALTER TABLE event_series_text_channel
+    ADD COLUMN event_series_id integer REFERENCES event_series (id);
+UPDATE event_series_text_channel
+    SET event_series_id = event_series.id
+    FROM event_series
+    WHERE event_series.discord_text_channel_id = event_series_text_channel.discord_id;
+
+-- Transcript of the channel's messages, exported right before the channel
+-- gets deleted. The file name is relative to the transcript directory.
+ALTER TABLE event_series_text_channel
+    ADD COLUMN transcript_file text,
+    ADD COLUMN transcript_time timestamp (0) with time zone;
+
+COMMIT;
@@ -0,0 +1,12 @@
+BEGIN;
+
+ALTER TABLE event_series_text_channel
+    DROP COLUMN archived,
+    DROP COLUMN pre_archive_name,
+    DROP COLUMN pre_archive_category_id;
+
+ALTER TABLE guild_config
+    DROP COLUMN archive_category_id,
+    DROP COLUMN archive_retention_days;
+
+COMMIT;
@@ -0,0 +1,17 @@
+BEGIN;
+
+-- When an archive category is configured, expired game channels are moved
+-- there instead of being deleted right away. They are deleted once the
+-- retention period is over.
+ALTER TABLE guild_config
+    ADD COLUMN archive_category_id bigint,
+    ADD COLUMN archive_retention_days integer NOT NULL DEFAULT 90 CHECK (archive_retention_days >= 0);
+
+-- Set when the channel was moved to the archive. The name and category from
+-- before are kept so that the channel can be unarchived.
+ALTER TABLE event_series_text_channel
+    ADD COLUMN archived timestamp (0) with time zone,
+    ADD COLUMN pre_archive_name text,
+    ADD COLUMN pre_archive_category_id bigint;
+
+COMMIT;
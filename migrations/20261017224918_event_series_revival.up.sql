@@ -0,0 +1,19 @@
+BEGIN;
+
+-- A series whose channel had already been deleted when it got a new session
+-- is revived with new channels and roles. The IDs it had before are kept here.
+CREATE SEQUENCE event_series_revival_id_seq START WITH 1000;
+CREATE TABLE event_series_revival (
+    id integer PRIMARY KEY DEFAULT nextval('event_series_revival_id_seq'),
+    event_series_id integer NOT NULL REFERENCES event_series (id),
+    revived timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    old_discord_text_channel_id bigint REFERENCES event_series_text_channel (discord_id),
+    old_discord_voice_channel_id bigint REFERENCES event_series_voice_channel (discord_id),
+    old_discord_role_id bigint REFERENCES event_series_role (discord_id),
+    old_discord_host_role_id bigint REFERENCES event_series_host_role (discord_id),
+    welcome_message_sent boolean NOT NULL DEFAULT FALSE
+);
+ALTER SEQUENCE event_series_revival_id_seq OWNED BY event_series_revival.id;
+CREATE INDEX event_series_revival_event_series_id_idx ON event_series_revival USING btree (event_series_id);
+
+COMMIT;
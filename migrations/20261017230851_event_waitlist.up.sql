@@ -0,0 +1,18 @@
+BEGIN;
+
+-- Players waiting for a seat in a full session. When a seat frees up, the
+-- next one in line gets a claim link which is valid until claim_expires.
+CREATE SEQUENCE event_waitlist_id_seq START WITH 1000;
+CREATE TABLE event_waitlist (
+    id integer PRIMARY KEY DEFAULT nextval('event_waitlist_id_seq'),
+    event_id integer NOT NULL REFERENCES event (id),
+    member_id integer NOT NULL REFERENCES "member" (id),
+    joined timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    claim_token text UNIQUE,
+    claim_expires timestamp (0) with time zone,
+    CONSTRAINT event_waitlist_event_member_unique UNIQUE (event_id, member_id)
+);
+ALTER SEQUENCE event_waitlist_id_seq OWNED BY event_waitlist.id;
+CREATE INDEX event_waitlist_event_id_idx ON event_waitlist USING btree (event_id);
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TABLE free_spots_notification;
+DROP TABLE free_spots_subscription;
+
+COMMIT;
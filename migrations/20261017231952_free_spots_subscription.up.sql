@@ -0,0 +1,30 @@
+BEGIN;
+
+-- Players get a DM when a game matching one of their subscriptions has free
+-- spots. Filters which are NULL match every game.
+CREATE SEQUENCE free_spots_subscription_id_seq START WITH 1000;
+CREATE TABLE free_spots_subscription (
+    id integer PRIMARY KEY DEFAULT nextval('free_spots_subscription_id_seq'),
+    member_id integer NOT NULL REFERENCES "member" (id),
+    -- free_spots::Location code
+    "location" text,
+    is_online boolean,
+    -- ISO weekdays, 1 is Monday
+    weekdays integer[],
+    game_system text,
+    created timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+ALTER SEQUENCE free_spots_subscription_id_seq OWNED BY free_spots_subscription.id;
+CREATE INDEX free_spots_subscription_member_id_idx ON free_spots_subscription USING btree (member_id);
+
+-- Events that a member has already been notified about. The event key is the
+-- Meetup event ID or the SwissRPG session UUID.
+CREATE TABLE free_spots_notification (
+    member_id integer NOT NULL REFERENCES "member" (id),
+    event_key text NOT NULL,
+    notified timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    CONSTRAINT free_spots_notification_pk PRIMARY KEY (member_id, event_key)
+);
+CREATE INDEX free_spots_notification_event_key_idx ON free_spots_notification USING btree (event_key);
+
+COMMIT;
@@ -0,0 +1,35 @@
+BEGIN;
+
+-- Cities (and "online") that games get grouped by, e.g. in the free spots
+-- channel. The code matches the SwissRPG location tag code.
+CREATE SEQUENCE location_id_seq START WITH 1000;
+CREATE TABLE "location" (
+    id integer PRIMARY KEY DEFAULT nextval('location_id_seq'),
+    code text NOT NULL UNIQUE,
+    "name" text NOT NULL,
+    lat double precision,
+    lng double precision,
+    color text NOT NULL DEFAULT '#99AAB5' CHECK (color ~ '^#[0-9A-Fa-f]{6}$'),
+    -- File name in the static file directory or a full URL
+    icon text,
+    group_link text,
+    sort_order integer NOT NULL DEFAULT 0,
+    is_online boolean NOT NULL DEFAULT FALSE,
+    CONSTRAINT location_coordinates_check CHECK ((lat IS NULL) = (lng IS NULL))
+);
+ALTER SEQUENCE location_id_seq OWNED BY "location".id;
+
+INSERT INTO "location" (code, "name", lat, lng, color, icon, group_link, sort_order, is_online) VALUES
+    ('zurich', 'Zürich', 47.376888, 8.541694, '#0073E5', 'thumbnail_Zurich.png', 'https://www.meetup.com/SwissRPG-Zurich/', 10, FALSE),
+    ('st_gallen', 'St. Gallen', 47.4256037, 9.3741491, '#16A74E', 'thumbnail_St_Gallen.png', 'https://www.meetup.com/SwissRPG-Zurich/', 20, FALSE),
+    ('basel', 'Basel', 47.559601, 7.588576, '#010202', 'thumbnail_Basel.png', 'https://www.meetup.com/SwissRPG-Central/', 30, FALSE),
+    ('luzern', 'Luzern', 47.045540, 8.308010, '#268BCC', 'thumbnail_Luzern.png', 'https://www.meetup.com/SwissRPG-Central/', 40, FALSE),
+    ('lugano', 'Lugano', 46.003601, 8.953620, '#E8423F', 'thumbnail_Ticino.png', 'https://www.meetup.com/SwissRPG-Central/', 50, FALSE),
+    ('geneva', 'Geneva', 46.204391, 6.143158, '#E8423F', 'thumbnail_Geneva.png', 'https://www.meetup.com/SwissRPG-Romandie/', 60, FALSE),
+    ('lausanne', 'Lausanne', 46.519316, 6.6345432, '#16A74E', 'thumbnail_Vaud.png', 'https://www.meetup.com/SwissRPG-Romandie/', 70, FALSE),
+    ('bern', 'Bern', 46.9489217, 7.4433158, '#FFD730', 'thumbnail_Bern.png', 'https://www.meetup.com/SwissRPG-Central/', 80, FALSE),
+    ('aarau', 'Aarau', 47.3934732, 8.0606556, '#268BCC', 'thumbnail_Aargau.png', 'https://www.meetup.com/SwissRPG-Central/', 90, FALSE),
+    ('chur', 'Chur', 46.8533507, 9.5275838, '#FEFEFE', 'thumbnail_Graubunden.png', 'https://www.meetup.com/SwissRPG-Central/', 100, FALSE),
+    ('online', 'Online', NULL, NULL, '#FF1744', 'thumbnail_Online.png', 'https://www.meetup.com/SwissRPG-Zurich/', 110, TRUE);
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE "member" DROP COLUMN calendar_token;
+
+COMMIT;
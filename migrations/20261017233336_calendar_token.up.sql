@@ -0,0 +1,6 @@
+BEGIN;
+
+-- Secret part of the member's personal calendar feed URLs
+ALTER TABLE "member" ADD COLUMN calendar_token text UNIQUE;
+
+COMMIT;
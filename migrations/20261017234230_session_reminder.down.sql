@@ -0,0 +1,8 @@
+BEGIN;
+
+DROP TABLE event_reminder;
+ALTER TABLE event_series DROP COLUMN session_reminder_dm;
+ALTER TABLE event_series DROP COLUMN session_reminder_minutes;
+ALTER TABLE event_series_type DROP COLUMN session_reminder_minutes;
+
+COMMIT;
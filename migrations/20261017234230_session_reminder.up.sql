@@ -0,0 +1,19 @@
+BEGIN;
+
+-- How many minutes before a session its channel gets reminded. Series can
+-- override the default of their type, an empty array mutes the reminders.
+ALTER TABLE event_series_type ADD COLUMN session_reminder_minutes integer[] NOT NULL DEFAULT '{1440,60}';
+ALTER TABLE event_series ADD COLUMN session_reminder_minutes integer[];
+ALTER TABLE event_series ADD COLUMN session_reminder_dm boolean NOT NULL DEFAULT FALSE;
+
+-- Reminders that were already sent. The start time is part of the key so
+-- that rescheduled sessions get reminded again.
+CREATE TABLE event_reminder (
+    event_id integer NOT NULL REFERENCES event (id),
+    start_time timestamp (0) with time zone NOT NULL,
+    reminder_minutes integer NOT NULL,
+    sent timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    CONSTRAINT event_reminder_pk PRIMARY KEY (event_id, start_time, reminder_minutes)
+);
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE guild_config DROP COLUMN audit_log_channel_id;
+DROP TABLE audit_log;
+
+COMMIT;
@@ -0,0 +1,32 @@
+BEGIN;
+
+-- Every change the bot makes on Discord (roles, permissions, deleted
+-- channels). Rows are only ever inserted, except that erasing a member's data
+-- removes their Discord ID from the rows and their mirrored messages.
+CREATE SEQUENCE audit_log_id_seq START WITH 1000;
+CREATE TABLE audit_log (
+    id integer PRIMARY KEY DEFAULT nextval('audit_log_id_seq'),
+    "time" timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    -- The Discord user who ran the command, NULL for the bot's own tasks
+    actor_discord_id bigint,
+    -- The command or task that made the change
+    "source" text NOT NULL,
+    "action" text NOT NULL,
+    target_user_discord_id bigint,
+    target_channel_discord_id bigint,
+    target_role_discord_id bigint,
+    "before" text,
+    "after" text,
+    -- Set if the change failed
+    error text,
+    -- The message in the audit log channel that mirrors this row
+    mirror_channel_id bigint,
+    mirror_message_id bigint
+);
+ALTER SEQUENCE audit_log_id_seq OWNED BY audit_log.id;
+CREATE INDEX audit_log_target_user_idx ON audit_log USING btree (target_user_discord_id, "time");
+
+-- Optional private channel the audit log gets mirrored to
+ALTER TABLE guild_config ADD COLUMN audit_log_channel_id bigint;
+
+COMMIT;
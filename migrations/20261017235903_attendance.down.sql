@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TABLE event_attendance_request;
+DROP TABLE event_attendance;
+
+COMMIT;
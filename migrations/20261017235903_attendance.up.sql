@@ -0,0 +1,20 @@
+BEGIN;
+
+-- Who actually showed up to a session, marked by a host afterwards
+CREATE TABLE event_attendance (
+    event_id integer NOT NULL REFERENCES event (id),
+    member_id integer NOT NULL REFERENCES "member" (id),
+    attended boolean NOT NULL,
+    marked_by integer REFERENCES "member" (id),
+    marked_time timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    CONSTRAINT event_attendance_pk PRIMARY KEY (event_id, member_id)
+);
+CREATE INDEX event_attendance_member_id_idx ON event_attendance USING btree (member_id);
+
+-- Sessions whose hosts were already asked to mark the attendance
+CREATE TABLE event_attendance_request (
+    event_id integer PRIMARY KEY REFERENCES event (id),
+    sent timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE guild_config DROP COLUMN spam_quarantine_role_id;
+DROP TABLE spam_rule;
+
+COMMIT;
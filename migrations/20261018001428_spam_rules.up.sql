@@ -0,0 +1,22 @@
+BEGIN;
+
+-- What the spam filter does when one of its rules triggers. The admins are
+-- always alerted.
+CREATE TABLE spam_rule (
+    "rule" text PRIMARY KEY,
+    delete_messages boolean NOT NULL DEFAULT FALSE,
+    -- Times the author out for this long, NULL for no timeout
+    timeout_minutes integer CHECK (timeout_minutes > 0),
+    -- Assigns the guild's spam quarantine role to the author
+    quarantine boolean NOT NULL DEFAULT FALSE
+);
+INSERT INTO spam_rule ("rule", delete_messages, timeout_minutes, quarantine) VALUES
+    ('word-list', FALSE, NULL, FALSE),
+    ('link-flood', TRUE, 60, FALSE),
+    ('repeated-message', TRUE, 60, FALSE),
+    ('new-account-invite', TRUE, NULL, TRUE),
+    ('mass-mention', TRUE, 60, FALSE);
+
+ALTER TABLE guild_config ADD COLUMN spam_quarantine_role_id bigint;
+
+COMMIT;
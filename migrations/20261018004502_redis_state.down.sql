@@ -0,0 +1,11 @@
+BEGIN;
+
+ALTER TABLE ephemeral_settings DROP COLUMN user_topic_voice_channel_topic_time;
+DROP TABLE orphaned_discord_object;
+DROP TABLE spam_word;
+DROP TABLE csrf_token;
+DROP TABLE web_login_link;
+DROP TABLE meetup_linking;
+DROP TABLE schedule_session_flow;
+
+COMMIT;
@@ -0,0 +1,50 @@
+BEGIN;
+
+-- Short-lived links that used to live in Redis. Expired rows are deleted
+-- periodically and never returned.
+CREATE TABLE schedule_session_flow (
+    id bigint PRIMARY KEY,
+    event_series_id integer NOT NULL REFERENCES event_series (id) ON DELETE CASCADE,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+CREATE TABLE meetup_linking (
+    linking_id text PRIMARY KEY,
+    discord_id bigint NOT NULL,
+    -- Moved to 10 minutes from now whenever the link is opened
+    expires timestamp (0) with time zone NOT NULL
+);
+
+CREATE TABLE web_login_link (
+    auth_id text PRIMARY KEY,
+    discord_id bigint NOT NULL,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+-- The CSRF state of an OAuth2 authorization, looked up by the random ID in
+-- the visitor's cookie
+CREATE TABLE csrf_token (
+    csrf_user_id text PRIMARY KEY,
+    csrf_state text NOT NULL,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+-- Messages containing any of these trigger the word-list spam rule
+CREATE TABLE spam_word (
+    word text PRIMARY KEY,
+    added timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX spam_word_lower_idx ON spam_word USING btree (lower(word));
+
+-- Channels and roles that the bot created but could not delete again after a
+-- failed sync
+CREATE TABLE orphaned_discord_object (
+    discord_id bigint PRIMARY KEY,
+    kind text NOT NULL CHECK (kind IN ('text', 'voice', 'role')),
+    created timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+
+-- When the user topic voice channel was last renamed
+ALTER TABLE ephemeral_settings ADD COLUMN user_topic_voice_channel_topic_time timestamp (0) with time zone;
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN;
+
+CREATE TABLE schedule_session_flow (
+    id bigint PRIMARY KEY,
+    event_series_id integer NOT NULL REFERENCES event_series (id) ON DELETE CASCADE,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+CREATE TABLE meetup_linking (
+    linking_id text PRIMARY KEY,
+    discord_id bigint NOT NULL,
+    -- Moved to 10 minutes from now whenever the link is opened
+    expires timestamp (0) with time zone NOT NULL
+);
+
+CREATE TABLE web_login_link (
+    auth_id text PRIMARY KEY,
+    discord_id bigint NOT NULL,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+DROP TABLE web_flow;
+
+COMMIT;
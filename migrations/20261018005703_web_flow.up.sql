@@ -0,0 +1,19 @@
+BEGIN;
+
+-- Multi-step web forms and single-use links. The state is the serialized
+-- state of the flow kind, the CSRF token has to be sent back with every form
+-- submission. Expired rows are deleted periodically and never returned.
+CREATE TABLE web_flow (
+    id text PRIMARY KEY,
+    kind text NOT NULL,
+    discord_id bigint NOT NULL, -- The user who requested the flow
+    csrf_token text NOT NULL,
+    "state" jsonb NOT NULL,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+DROP TABLE web_login_link;
+DROP TABLE meetup_linking;
+DROP TABLE schedule_session_flow;
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+ALTER TABLE event_series DROP COLUMN channel_category_id;
+ALTER TABLE event_series DROP COLUMN channel_topic_template;
+ALTER TABLE event_series DROP COLUMN channel_name;
+
+COMMIT;
@@ -0,0 +1,9 @@
+BEGIN;
+
+-- Set by hosts on the series settings page, NULL for what the Discord sync
+-- derives from the next session
+ALTER TABLE event_series ADD COLUMN channel_name text;
+ALTER TABLE event_series ADD COLUMN channel_topic_template text;
+ALTER TABLE event_series ADD COLUMN channel_category_id bigint;
+
+COMMIT;
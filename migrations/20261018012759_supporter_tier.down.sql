@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE "member" DROP COLUMN supporter_tier;
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+-- Kept up to date by the subscription roles task
+ALTER TABLE "member" ADD COLUMN supporter_tier text;
+
+COMMIT;
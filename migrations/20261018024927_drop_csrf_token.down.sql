@@ -0,0 +1,9 @@
+BEGIN;
+
+CREATE TABLE csrf_token (
+    csrf_user_id text PRIMARY KEY,
+    csrf_state text NOT NULL,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+-- The organizer's OAuth2 authorization is a web flow now
+DROP TABLE csrf_token;
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE "location" DROP COLUMN aliases;
+
+COMMIT;
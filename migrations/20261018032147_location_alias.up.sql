@@ -0,0 +1,11 @@
+BEGIN;
+
+-- Other names of a location that free spots filters accept, e.g. the canton
+ALTER TABLE "location" ADD COLUMN aliases text[] NOT NULL DEFAULT '{}';
+
+UPDATE "location" SET aliases = '{Ticino}' WHERE code = 'lugano';
+UPDATE "location" SET aliases = '{Vaud}' WHERE code = 'lausanne';
+UPDATE "location" SET aliases = '{Aargau}' WHERE code = 'aarau';
+UPDATE "location" SET aliases = '{Graubunden}' WHERE code = 'chur';
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE event_waitlist ADD COLUMN claim_token text UNIQUE;
+
+COMMIT;
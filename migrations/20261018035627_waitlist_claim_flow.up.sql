@@ -0,0 +1,8 @@
+BEGIN;
+
+-- Seat claim links are web flows now. Open offers get a new link the next
+-- time the free seats are offered.
+UPDATE event_waitlist SET claim_expires = NULL WHERE claim_token IS NOT NULL;
+ALTER TABLE event_waitlist DROP COLUMN claim_token;
+
+COMMIT;
@@ -8,7 +8,13 @@ CREATE TABLE event_series_text_channel (
     last_expiration_reminder_time timestamp (0) with time zone,
     snooze_until timestamp (0) with time zone,
     deletion_time timestamp (0) with time zone, -- scheduled Discord channel deletion time
-    deleted timestamp (0) with time zone -- set when Discord deletion is confirmed
+    deleted timestamp (0) with time zone, -- set when Discord deletion is confirmed
+    event_series_id integer, -- kept after the series moved on to a new channel
+    transcript_file text, -- exported messages, relative to the transcript directory
+    transcript_time timestamp (0) with time zone,
+    archived timestamp (0) with time zone, -- set when moved to the archive category
+    pre_archive_name text,
+    pre_archive_category_id bigint
 );
 
 CREATE TABLE event_series_voice_channel (
@@ -31,9 +37,17 @@ CREATE TABLE event_series_host_role (
 
 -- More flexible than an enum
 CREATE TABLE event_series_type (
-    "type" text PRIMARY KEY
+    "type" text PRIMARY KEY,
+    -- How long after the last session the channel gets reminded of its
+    -- expiration, how often the reminder is repeated and how long after
+    -- "end adventure" the channel gets deleted
+    reminder_delay_hours integer NOT NULL DEFAULT 24 CHECK (reminder_delay_hours >= 0),
+    reminder_interval_hours integer NOT NULL DEFAULT 48 CHECK (reminder_interval_hours > 0),
+    deletion_delay_hours integer NOT NULL DEFAULT 8 CHECK (deletion_delay_hours >= 0),
+    -- How many minutes before a session its channel gets reminded
+    session_reminder_minutes integer[] NOT NULL DEFAULT '{1440,60}'
 );
-INSERT INTO event_series_type ("type") VALUES ('campaign'), ('adventure');
+INSERT INTO event_series_type ("type", reminder_delay_hours, reminder_interval_hours) VALUES ('campaign', 72, 96), ('adventure', 24, 48);
 
 CREATE SEQUENCE event_series_id_seq START WITH 1000;
 CREATE TABLE event_series (
@@ -45,10 +59,37 @@ CREATE TABLE event_series (
     discord_category_id bigint,
     "type" text NOT NULL REFERENCES event_series_type ("type"),
     redis_series_id text UNIQUE, -- deprecated and unused
-    swissrpg_event_series_id uuid UNIQUE
+    swissrpg_event_series_id uuid UNIQUE,
+    -- Overrides of the series type's expiration policy
+    reminder_delay_hours integer CHECK (reminder_delay_hours >= 0),
+    reminder_interval_hours integer CHECK (reminder_interval_hours > 0),
+    deletion_delay_hours integer CHECK (deletion_delay_hours >= 0),
+    session_reminder_minutes integer[], -- overrides the series type's, empty mutes the reminders
+    session_reminder_dm boolean NOT NULL DEFAULT FALSE,
+    -- Set by hosts on the series settings page, NULL for what the Discord sync
+    -- derives from the next session
+    channel_name text,
+    channel_topic_template text,
+    channel_category_id bigint
 );
 ALTER SEQUENCE event_series_id_seq OWNED BY event_series.id;
 CREATE INDEX event_series_swissrpg_event_series_id_idx ON event_series USING btree (swissrpg_event_series_id);
+ALTER TABLE event_series_text_channel ADD FOREIGN KEY (event_series_id) REFERENCES event_series (id);
+
+CREATE SEQUENCE event_series_revival_id_seq START WITH 1000;
+CREATE TABLE event_series_revival (
+    id integer PRIMARY KEY DEFAULT nextval('event_series_revival_id_seq'),
+    event_series_id integer NOT NULL REFERENCES event_series (id),
+    revived timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    -- The IDs the series had before it was revived
+    old_discord_text_channel_id bigint REFERENCES event_series_text_channel (discord_id),
+    old_discord_voice_channel_id bigint REFERENCES event_series_voice_channel (discord_id),
+    old_discord_role_id bigint REFERENCES event_series_role (discord_id),
+    old_discord_host_role_id bigint REFERENCES event_series_host_role (discord_id),
+    welcome_message_sent boolean NOT NULL DEFAULT FALSE
+);
+ALTER SEQUENCE event_series_revival_id_seq OWNED BY event_series_revival.id;
+CREATE INDEX event_series_revival_event_series_id_idx ON event_series_revival USING btree (event_series_id);
 
 CREATE SEQUENCE event_id_seq START WITH 1000;
 CREATE TABLE event (
@@ -60,7 +101,8 @@ CREATE TABLE event (
     description text NOT NULL,
     is_online boolean NOT NULL DEFAULT FALSE,
     discord_category_id bigint,
-    deleted timestamp (0) with time zone
+    deleted timestamp (0) with time zone,
+    open_seats integer
 );
 ALTER SEQUENCE event_id_seq OWNED BY event.id;
 CREATE INDEX event_start_time_idx ON event USING btree (start_time);
@@ -93,6 +135,8 @@ CREATE TABLE "member" (
     meetup_oauth2_access_token text,
     meetup_oauth2_refresh_token text,
     meetup_oauth2_last_token_refresh_time timestamp (0) with time zone,
+    calendar_token text UNIQUE, -- secret part of the personal calendar feed URLs
+    supporter_tier text, -- tier of the active Stripe subscription, e.g. "Adept"
     CONSTRAINT is_identifiable CHECK (meetup_id IS NOT NULL or discord_id IS NOT NULL)
 );
 ALTER SEQUENCE member_id_seq OWNED BY "member".id;
@@ -120,11 +164,107 @@ CREATE INDEX event_hosts_member_id_idx ON event_host USING btree (member_id);
 CREATE TABLE event_participant (
 	event_id integer NOT NULL REFERENCES event (id),
 	member_id integer NOT NULL REFERENCES "member" (id),
+	from_discord boolean NOT NULL DEFAULT FALSE,
 	CONSTRAINT event_participants_pk PRIMARY KEY (event_id, member_id)
 );
 CREATE INDEX event_participants_event_id_idx ON event_participant USING btree (event_id);
 CREATE INDEX event_participants_member_id_idx ON event_participant USING btree (member_id);
 
+-- Reminders that were already sent. The start time is part of the key so
+-- that rescheduled sessions get reminded again.
+CREATE TABLE event_reminder (
+    event_id integer NOT NULL REFERENCES event (id),
+    start_time timestamp (0) with time zone NOT NULL,
+    reminder_minutes integer NOT NULL,
+    sent timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    CONSTRAINT event_reminder_pk PRIMARY KEY (event_id, start_time, reminder_minutes)
+);
+
+CREATE SEQUENCE event_waitlist_id_seq START WITH 1000;
+CREATE TABLE event_waitlist (
+    id integer PRIMARY KEY DEFAULT nextval('event_waitlist_id_seq'),
+    event_id integer NOT NULL REFERENCES event (id),
+    member_id integer NOT NULL REFERENCES "member" (id),
+    joined timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    -- Set while a seat is on offer, the claim link is a web flow
+    claim_expires timestamp (0) with time zone,
+    CONSTRAINT event_waitlist_event_member_unique UNIQUE (event_id, member_id)
+);
+ALTER SEQUENCE event_waitlist_id_seq OWNED BY event_waitlist.id;
+CREATE INDEX event_waitlist_event_id_idx ON event_waitlist USING btree (event_id);
+
+-- Who actually showed up to a session, marked by a host afterwards
+CREATE TABLE event_attendance (
+    event_id integer NOT NULL REFERENCES event (id),
+    member_id integer NOT NULL REFERENCES "member" (id),
+    attended boolean NOT NULL,
+    marked_by integer REFERENCES "member" (id),
+    marked_time timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    CONSTRAINT event_attendance_pk PRIMARY KEY (event_id, member_id)
+);
+CREATE INDEX event_attendance_member_id_idx ON event_attendance USING btree (member_id);
+
+-- Sessions whose hosts were already asked to mark the attendance
+CREATE TABLE event_attendance_request (
+    event_id integer PRIMARY KEY REFERENCES event (id),
+    sent timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+
+CREATE SEQUENCE free_spots_subscription_id_seq START WITH 1000;
+CREATE TABLE free_spots_subscription (
+    id integer PRIMARY KEY DEFAULT nextval('free_spots_subscription_id_seq'),
+    member_id integer NOT NULL REFERENCES "member" (id),
+    "location" text,
+    is_online boolean,
+    weekdays integer[],
+    game_system text,
+    created timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+ALTER SEQUENCE free_spots_subscription_id_seq OWNED BY free_spots_subscription.id;
+CREATE INDEX free_spots_subscription_member_id_idx ON free_spots_subscription USING btree (member_id);
+
+CREATE TABLE free_spots_notification (
+    member_id integer NOT NULL REFERENCES "member" (id),
+    event_key text NOT NULL,
+    notified timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    CONSTRAINT free_spots_notification_pk PRIMARY KEY (member_id, event_key)
+);
+CREATE INDEX free_spots_notification_event_key_idx ON free_spots_notification USING btree (event_key);
+
+-- Cities (and "online") that games get grouped by, e.g. in the free spots
+-- channel. The code matches the SwissRPG location tag code.
+CREATE SEQUENCE location_id_seq START WITH 1000;
+CREATE TABLE "location" (
+    id integer PRIMARY KEY DEFAULT nextval('location_id_seq'),
+    code text NOT NULL UNIQUE,
+    "name" text NOT NULL,
+    lat double precision,
+    lng double precision,
+    color text NOT NULL DEFAULT '#99AAB5' CHECK (color ~ '^#[0-9A-Fa-f]{6}$'),
+    -- File name in the static file directory or a full URL
+    icon text,
+    group_link text,
+    sort_order integer NOT NULL DEFAULT 0,
+    is_online boolean NOT NULL DEFAULT FALSE,
+    -- Other names that free spots filters accept, e.g. the canton
+    aliases text[] NOT NULL DEFAULT '{}',
+    CONSTRAINT location_coordinates_check CHECK ((lat IS NULL) = (lng IS NULL))
+);
+ALTER SEQUENCE location_id_seq OWNED BY "location".id;
+
+INSERT INTO "location" (code, "name", lat, lng, color, icon, group_link, sort_order, is_online, aliases) VALUES
+    ('zurich', 'Zürich', 47.376888, 8.541694, '#0073E5', 'thumbnail_Zurich.png', 'https://www.meetup.com/SwissRPG-Zurich/', 10, FALSE, '{}'),
+    ('st_gallen', 'St. Gallen', 47.4256037, 9.3741491, '#16A74E', 'thumbnail_St_Gallen.png', 'https://www.meetup.com/SwissRPG-Zurich/', 20, FALSE, '{}'),
+    ('basel', 'Basel', 47.559601, 7.588576, '#010202', 'thumbnail_Basel.png', 'https://www.meetup.com/SwissRPG-Central/', 30, FALSE, '{}'),
+    ('luzern', 'Luzern', 47.045540, 8.308010, '#268BCC', 'thumbnail_Luzern.png', 'https://www.meetup.com/SwissRPG-Central/', 40, FALSE, '{}'),
+    ('lugano', 'Lugano', 46.003601, 8.953620, '#E8423F', 'thumbnail_Ticino.png', 'https://www.meetup.com/SwissRPG-Central/', 50, FALSE, '{Ticino}'),
+    ('geneva', 'Geneva', 46.204391, 6.143158, '#E8423F', 'thumbnail_Geneva.png', 'https://www.meetup.com/SwissRPG-Romandie/', 60, FALSE, '{}'),
+    ('lausanne', 'Lausanne', 46.519316, 6.6345432, '#16A74E', 'thumbnail_Vaud.png', 'https://www.meetup.com/SwissRPG-Romandie/', 70, FALSE, '{Vaud}'),
+    ('bern', 'Bern', 46.9489217, 7.4433158, '#FFD730', 'thumbnail_Bern.png', 'https://www.meetup.com/SwissRPG-Central/', 80, FALSE, '{}'),
+    ('aarau', 'Aarau', 47.3934732, 8.0606556, '#268BCC', 'thumbnail_Aargau.png', 'https://www.meetup.com/SwissRPG-Central/', 90, FALSE, '{Aargau}'),
+    ('chur', 'Chur', 46.8533507, 9.5275838, '#FEFEFE', 'thumbnail_Graubunden.png', 'https://www.meetup.com/SwissRPG-Central/', 100, FALSE, '{Graubunden}'),
+    ('online', 'Online', NULL, NULL, '#FF1744', 'thumbnail_Online.png', 'https://www.meetup.com/SwissRPG-Zurich/', 110, TRUE, '{}');
+
 CREATE TABLE organizer_token (
     id bool PRIMARY KEY DEFAULT TRUE,
     meetup_access_token text NOT NULL,
@@ -136,6 +276,7 @@ CREATE TABLE organizer_token (
 CREATE TABLE ephemeral_settings (
     id bool PRIMARY KEY DEFAULT TRUE,
     cookie_key bytea,
+    user_topic_voice_channel_topic_time timestamp (0) with time zone, -- last renaming of the user topic voice channel
     CONSTRAINT onerow CHECK (id)
 );
 
@@ -146,4 +287,102 @@ CREATE TABLE web_session (
     member_id integer NOT NULL REFERENCES "member" (id),
     last_used timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
-ALTER SEQUENCE web_session_id_seq OWNED BY web_session.id;
\ No newline at end of file
+ALTER SEQUENCE web_session_id_seq OWNED BY web_session.id;
+
+CREATE TABLE guild_config (
+    id bool PRIMARY KEY DEFAULT TRUE,
+    guild_id bigint NOT NULL,
+    bot_admin_role_id bigint NOT NULL,
+    organiser_role_id bigint NOT NULL,
+    game_master_role_id bigint NOT NULL,
+    dice_roller_bot_role_id bigint,
+    admin_role_id bigint,
+    champion_role_id bigint NOT NULL,
+    insider_role_id bigint NOT NULL,
+    gm_champion_role_id bigint NOT NULL,
+    one_shot_category_ids bigint[] NOT NULL DEFAULT '{}',
+    campaign_category_ids bigint[] NOT NULL DEFAULT '{}',
+    voice_channel_category_ids bigint[] NOT NULL DEFAULT '{}',
+    bot_alerts_channel_id bigint,
+    free_spots_channel_id bigint,
+    user_topic_voice_channel_id bigint,
+    archive_category_id bigint, -- expired channels are archived instead of deleted when set
+    archive_retention_days integer NOT NULL DEFAULT 90 CHECK (archive_retention_days >= 0),
+    audit_log_channel_id bigint, -- the audit log is mirrored here when set
+    spam_quarantine_role_id bigint, -- assigned to spammers by the spam rules that quarantine
+    CONSTRAINT onerow CHECK (id)
+);
+
+CREATE TABLE scheduled_job (
+    name text PRIMARY KEY,
+    last_scheduled_time timestamp (0) with time zone,
+    last_start_time timestamp (0) with time zone,
+    last_duration_ms bigint,
+    last_outcome text CHECK (last_outcome IN ('success', 'failure', 'timeout')),
+    last_error text,
+    last_success_time timestamp (0) with time zone
+);
+
+-- Every change the bot makes on Discord (roles, permissions, deleted
+-- channels) and every export and erasure of member data. Rows are only ever
+-- inserted, except that erasing a member's data removes their Discord ID from
+-- the rows and their mirrored messages.
+CREATE SEQUENCE audit_log_id_seq START WITH 1000;
+CREATE TABLE audit_log (
+    id integer PRIMARY KEY DEFAULT nextval('audit_log_id_seq'),
+    "time" timestamp (0) with time zone NOT NULL DEFAULT NOW(),
+    actor_discord_id bigint, -- NULL for the bot's own tasks and erased members
+    "source" text NOT NULL,
+    "action" text NOT NULL,
+    target_user_discord_id bigint,
+    target_channel_discord_id bigint,
+    target_role_discord_id bigint,
+    "before" text,
+    "after" text,
+    error text, -- set if the change failed
+    -- the message in the audit log channel that mirrors this row
+    mirror_channel_id bigint,
+    mirror_message_id bigint
+);
+ALTER SEQUENCE audit_log_id_seq OWNED BY audit_log.id;
+CREATE INDEX audit_log_target_user_idx ON audit_log USING btree (target_user_discord_id, "time");
+
+-- What the spam filter does when a rule triggers, the admins are always alerted
+CREATE TABLE spam_rule (
+    "rule" text PRIMARY KEY,
+    delete_messages boolean NOT NULL DEFAULT FALSE,
+    timeout_minutes integer CHECK (timeout_minutes > 0), -- NULL for no timeout
+    quarantine boolean NOT NULL DEFAULT FALSE
+);
+INSERT INTO spam_rule ("rule", delete_messages, timeout_minutes, quarantine) VALUES
+    ('word-list', FALSE, NULL, FALSE),
+    ('link-flood', TRUE, 60, FALSE),
+    ('repeated-message', TRUE, 60, FALSE),
+    ('new-account-invite', TRUE, NULL, TRUE),
+    ('mass-mention', TRUE, 60, FALSE);
+
+-- Multi-step web forms and single-use links, expired rows are deleted
+-- periodically and never returned
+CREATE TABLE web_flow (
+    id text PRIMARY KEY,
+    kind text NOT NULL,
+    discord_id bigint NOT NULL, -- The user who requested the flow
+    csrf_token text NOT NULL,
+    "state" jsonb NOT NULL,
+    expires timestamp (0) with time zone NOT NULL
+);
+
+-- Messages containing any of these trigger the word-list spam rule
+CREATE TABLE spam_word (
+    word text PRIMARY KEY,
+    added timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
+CREATE UNIQUE INDEX spam_word_lower_idx ON spam_word USING btree (lower(word));
+
+-- Channels and roles that the bot created but could not delete again after a
+-- failed sync
+CREATE TABLE orphaned_discord_object (
+    discord_id bigint PRIMARY KEY,
+    kind text NOT NULL CHECK (kind IN ('text', 'voice', 'role')),
+    created timestamp (0) with time zone NOT NULL DEFAULT NOW()
+);
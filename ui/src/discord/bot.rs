@@ -1,36 +1,50 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use futures_util::lock::Mutex as AsyncMutex;
-use lib::{strings, swissrpg::client::SwissRPGClient};
+use lib::{
+    attendance,
+    discord::{guild_config::SharedGuildConfig, rsvp, waitlist},
+    free_spots_subscription,
+    location::Locations,
+    strings,
+    swissrpg::client::SwissRPGClient,
+};
 use serenity::{
     all::{ApplicationId, GuildMemberUpdateEvent},
     async_trait,
-    builder::CreateMessage,
+    builder::{
+        CreateAutocompleteResponse, CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse,
+    },
     model::{
-        application::Interaction,
-        channel::Message,
+        application::{CommandInteraction, ComponentInteraction, Interaction},
+        channel::{Message, Reaction},
         gateway::{GatewayIntents, Ready},
         guild::Member,
-        id::{GuildId, UserId},
+        id::{GuildId, MessageId, UserId},
         user::User,
     },
     prelude::*,
 };
 
-use super::commands::{CommandContext, PreparedCommands};
+use super::commands::{Command, CommandContext, PreparedCommands};
 
 pub async fn create_discord_client(
     discord_token: &str,
     application_id: ApplicationId,
-    redis_client: redis::Client,
     pool: sqlx::PgPool,
     async_meetup_client: Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>,
     oauth2_consumer: Arc<lib::meetup::oauth2::OAuth2Consumer>,
     stripe_client: Arc<stripe::Client>,
     swissrpg_client: Arc<SwissRPGClient>,
+    guild_config: SharedGuildConfig,
+    transcript_directory: Arc<PathBuf>,
     shutdown_signal: Arc<AtomicBool>,
 ) -> Result<Client, lib::meetup::Error> {
     // Create a new instance of the Client, logging in as a bot. This will
@@ -69,11 +83,12 @@ pub async fn create_discord_client(
         data.insert::<BotIdKey>(bot_id);
         data.insert::<BotNameKey>(bot_name);
         data.insert::<AsyncMeetupClientKey>(async_meetup_client);
-        data.insert::<RedisClientKey>(redis_client);
         data.insert::<PoolKey>(pool);
         data.insert::<OAuth2ConsumerKey>(oauth2_consumer);
         data.insert::<StripeClientKey>(stripe_client);
         data.insert::<SwissRPGClientKey>(swissrpg_client);
+        data.insert::<GuildConfigKey>(guild_config);
+        data.insert::<TranscriptDirectoryKey>(transcript_directory);
         data.insert::<ShutdownSignalKey>(shutdown_signal);
         data.insert::<PreparedCommandsKey>(prepared_commands);
     }
@@ -96,11 +111,6 @@ impl TypeMapKey for AsyncMeetupClientKey {
     type Value = Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>;
 }
 
-pub struct RedisClientKey;
-impl TypeMapKey for RedisClientKey {
-    type Value = redis::Client;
-}
-
 pub struct OAuth2ConsumerKey;
 impl TypeMapKey for OAuth2ConsumerKey {
     type Value = Arc<lib::meetup::oauth2::OAuth2Consumer>;
@@ -116,11 +126,28 @@ impl TypeMapKey for SwissRPGClientKey {
     type Value = Arc<SwissRPGClient>;
 }
 
+pub struct GuildConfigKey;
+impl TypeMapKey for GuildConfigKey {
+    type Value = SharedGuildConfig;
+}
+
+pub struct TranscriptDirectoryKey;
+impl TypeMapKey for TranscriptDirectoryKey {
+    type Value = Arc<PathBuf>;
+}
+
 pub struct ShutdownSignalKey;
 impl TypeMapKey for ShutdownSignalKey {
     type Value = Arc<AtomicBool>;
 }
 
+// Inserted by the app once the jobs have been set up, since those need the
+// Discord client
+pub struct SchedulerKey;
+impl TypeMapKey for SchedulerKey {
+    type Value = lib::tasks::scheduler::Scheduler;
+}
+
 pub(crate) struct PreparedCommandsKey;
 impl TypeMapKey for PreparedCommandsKey {
     type Value = Arc<PreparedCommands>;
@@ -188,8 +215,17 @@ impl Handler {
                 return Ok(());
             }
         };
-        // Check whether the user has the required permissions
         let command = commands.commands[i];
+        Self::run_command(cmdctx, command, captures).await
+    }
+
+    // Checks whether the user has the required permissions and calls the
+    // command. Shared between mention and slash commands.
+    async fn run_command<'a>(
+        cmdctx: &'a mut CommandContext,
+        command: &'static Command,
+        captures: regex::Captures<'a>,
+    ) -> Result<(), lib::meetup::Error> {
         match command.level {
             super::commands::CommandLevel::Everybody => (),
             super::commands::CommandLevel::AdminOnly => {
@@ -231,14 +267,19 @@ impl EventHandler for Handler {
     // Event handlers are dispatched through a threadpool, and so multiple
     // events can be dispatched simultaneously.
     async fn message(&self, ctx: Context, msg: Message) {
-        let (bot_id, shutdown_signal) = {
+        let (bot_id, shutdown_signal, guild_id) = {
             let data = ctx.data.read().await;
             let bot_id = data.get::<BotIdKey>().expect("Bot ID was not set").clone();
             let shutdown_signal = data
                 .get::<ShutdownSignalKey>()
                 .expect("Shutdown signal was not set")
                 .load(Ordering::Acquire);
-            (bot_id, shutdown_signal)
+            let guild_id = data
+                .get::<GuildConfigKey>()
+                .expect("Guild config was not set")
+                .get()
+                .guild_id;
+            (bot_id, shutdown_signal, guild_id)
         };
         // Ignore all messages written by the bot itself
         if msg.author.id == bot_id {
@@ -246,8 +287,8 @@ impl EventHandler for Handler {
         }
         // Ignore all messages that might have come from another guild
         // (shouldn't happen, but who knows)
-        if let Some(guild_id) = msg.guild_id {
-            if guild_id != lib::discord::sync::ids::GUILD_ID {
+        if let Some(msg_guild_id) = msg.guild_id {
+            if msg_guild_id != guild_id {
                 return;
             }
         }
@@ -314,12 +355,23 @@ impl EventHandler for Handler {
     // private channels, and more.
     //
     // In this case, just print what the current user's username is.
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        // (Re-)register the slash commands. Overwriting the whole set also
+        // removes commands that don't exist anymore.
+        match serenity::model::application::Command::set_global_commands(
+            &ctx.http,
+            super::commands::slash_commands(),
+        )
+        .await
+        {
+            Ok(commands) => println!("Registered {} slash commands", commands.len()),
+            Err(err) => eprintln!("Could not register the slash commands:\n{:#?}", err),
+        }
     }
 
     async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
-        if new_member.guild_id != lib::discord::sync::ids::GUILD_ID {
+        if new_member.guild_id != Self::guild_config(&ctx).await.get().guild_id {
             return;
         }
         Self::send_welcome_message(&ctx, &new_member.user).await;
@@ -345,6 +397,14 @@ impl EventHandler for Handler {
             .ok();
     }
 
+    async fn reaction_add(&self, ctx: Context, reaction: Reaction) {
+        Self::handle_free_spots_reaction(&ctx, &reaction, true).await;
+    }
+
+    async fn reaction_remove(&self, ctx: Context, reaction: Reaction) {
+        Self::handle_free_spots_reaction(&ctx, &reaction, false).await;
+    }
+
     async fn cache_ready(&self, ctx: Context, guilds: Vec<GuildId>) {
         let guild_id = match guilds.as_slice() {
             [guild] => guild,
@@ -353,6 +413,20 @@ impl EventHandler for Handler {
                 return;
             }
         };
+        // Make sure that the configured IDs match what is actually there
+        let guild_config = Self::guild_config(&ctx).await.get();
+        if *guild_id != guild_config.guild_id {
+            eprintln!(
+                "The bot is in guild {} but is configured for guild {}",
+                guild_id, guild_config.guild_id
+            );
+        }
+        let problems = guild_config.validate(&ctx.cache);
+        if problems.is_empty() {
+            println!("Guild config validated");
+        } else {
+            eprintln!("Guild config problems:\n{}", problems.join("\n"));
+        }
         let members = match ctx.cache.guild(guild_id).map(|guild| guild.members.clone()) {
             Some(members) => members,
             None => return,
@@ -365,54 +439,397 @@ impl EventHandler for Handler {
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        // let (bot_id, shutdown_signal) = {
-        //     let data = ctx.data.read().await;
-        //     let bot_id = data.get::<BotIdKey>().expect("Bot ID was not set").clone();
-        //     let shutdown_signal = data
-        //         .get::<ShutdownSignalKey>()
-        //         .expect("Shutdown signal was not set")
-        //         .load(Ordering::Acquire);
-        //     (bot_id, shutdown_signal)
-        // };
+        let (bot_id, shutdown_signal, guild_id) = {
+            let data = ctx.data.read().await;
+            let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
+            let shutdown_signal = data
+                .get::<ShutdownSignalKey>()
+                .expect("Shutdown signal was not set")
+                .load(Ordering::Acquire);
+            let guild_id = data
+                .get::<GuildConfigKey>()
+                .expect("Guild config was not set")
+                .get()
+                .guild_id;
+            (bot_id, shutdown_signal, guild_id)
+        };
         // In contrast to the message handler we don't need to check that this
         // is indeed a command.
         let interaction = match interaction {
             Interaction::Command(inner) => inner,
+            Interaction::Autocomplete(inner) => {
+                if inner.guild_id.is_none_or(|id| id == guild_id) {
+                    Self::handle_autocomplete(ctx, inner).await;
+                }
+                return;
+            }
+            Interaction::Component(inner) => {
+                if inner.guild_id == Some(guild_id) {
+                    if attendance::parse_custom_id(&inner.data.custom_id).is_some() {
+                        Self::handle_attendance_button(&ctx, &inner).await;
+                    } else if super::spam::parse_custom_id(&inner.data.custom_id).is_some() {
+                        Self::handle_spam_alert_button(&ctx, &inner).await;
+                    } else {
+                        Self::handle_rsvp_button(&ctx, &inner).await;
+                    }
+                }
+                return;
+            }
             _ => return,
         };
-
-        // Ignore all messages that might have come from another guild
+        // Ignore all commands that might have come from another guild
         // (shouldn't happen, but who knows)
-        if interaction.guild_id != Some(lib::discord::sync::ids::GUILD_ID) {
-            return;
+        if let Some(interaction_guild_id) = interaction.guild_id {
+            if interaction_guild_id != guild_id {
+                return;
+            }
         }
-        match interaction.data.name.as_str() {
-            "link-meetup" => {
-                interaction
-                    .channel_id
-                    .say(&ctx, "Yessir! Linking Meetup")
-                    .await
-                    .ok();
+        let command = match super::commands::find_slash_command(&interaction.data.name) {
+            Some(command) => command,
+            None => {
+                eprintln!("Unrecognized slash command: {}", interaction.data.name);
+                let response = CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(strings::INVALID_COMMAND(bot_id))
+                        .ephemeral(true),
+                );
+                interaction.create_response(&ctx, response).await.ok();
+                return;
             }
-            "unlink-meetup" => {
-                interaction
-                    .channel_id
-                    .say(&ctx, "Un-linking Meetup")
-                    .await
-                    .ok();
+        };
+        if shutdown_signal {
+            let response = CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content("Sorry, I can not help you right now. I am about to shut down!")
+                    .ephemeral(true),
+            );
+            interaction.create_response(&ctx, response).await.ok();
+            return;
+        }
+        // Most commands answer in the channel, so we only acknowledge the
+        // interaction here and remove the acknowledgement once the command ran,
+        // unless the command responded to the interaction itself
+        if let Err(err) = interaction.defer_ephemeral(&ctx).await {
+            eprintln!("Could not acknowledge slash command:\n{:#?}", err);
+            return;
+        }
+        let msg = Self::message_from_interaction(&interaction);
+        let mut cmdctx = CommandContext::new_slash(ctx, msg, interaction.clone());
+        // Poor man's try block
+        let res: Result<(), lib::meetup::Error> = async {
+            let (regex, input) = command.slash_captures_input(&interaction.data.options)?;
+            let captures = regex.captures(&input).ok_or_else(|| {
+                simple_error::SimpleError::new("Slash command options did not match")
+            })?;
+            Self::run_command(&mut cmdctx, command, captures).await
+        }
+        .await;
+        match res {
+            Ok(()) => {
+                if !cmdctx.interaction_answered() {
+                    interaction.delete_response(&cmdctx.ctx).await.ok();
+                }
             }
-            _ => {
+            Err(err) => {
+                eprintln!("Error in slash command handler:\n{:#?}", err);
                 interaction
-                    .channel_id
-                    .say(&ctx, "Unknown command")
+                    .edit_response(
+                        &cmdctx.ctx,
+                        EditInteractionResponse::new().content(lib::strings::UNSPECIFIED_ERROR),
+                    )
                     .await
                     .ok();
             }
-        };
+        }
     }
 }
 
 impl Handler {
+    // The commands expect a message, so we make one up from the interaction
+    fn message_from_interaction(interaction: &CommandInteraction) -> Message {
+        let mut msg = Message::default();
+        msg.id = MessageId::new(interaction.id.get());
+        msg.channel_id = interaction.channel_id;
+        msg.guild_id = interaction.guild_id;
+        msg.author = interaction.user.clone();
+        msg.content = format!("/{}", interaction.data.name);
+        msg
+    }
+
+    // Suggestions for slash command options while they are typed
+    async fn handle_autocomplete(ctx: Context, interaction: CommandInteraction) {
+        let command = match super::commands::find_slash_command(&interaction.data.name) {
+            Some(command) => command,
+            None => return,
+        };
+        let option = match interaction.data.autocomplete() {
+            Some(option) => option,
+            None => return,
+        };
+        let msg = Self::message_from_interaction(&interaction);
+        let cmdctx = CommandContext::new(ctx, msg);
+        let choices = match command
+            .autocomplete(&cmdctx, option.name, option.value)
+            .await
+        {
+            Ok(choices) => choices,
+            Err(err) => {
+                eprintln!("Error in autocomplete handler:\n{:#?}", err);
+                vec![]
+            }
+        };
+        let response = choices
+            .into_iter()
+            .fold(CreateAutocompleteResponse::new(), |response, choice| {
+                response.add_string_choice(choice.clone(), choice)
+            });
+        interaction
+            .create_response(
+                &cmdctx.ctx,
+                CreateInteractionResponse::Autocomplete(response),
+            )
+            .await
+            .ok();
+    }
+
+    // Join / Leave buttons on session announcements
+    async fn handle_rsvp_button(ctx: &Context, interaction: &ComponentInteraction) {
+        let (action, event_id) = match rsvp::parse_custom_id(&interaction.data.custom_id) {
+            Some(rsvp) => rsvp,
+            None => return,
+        };
+        let pool = ctx
+            .data
+            .read()
+            .await
+            .get::<PoolKey>()
+            .cloned()
+            .expect("Postgres pool was not set");
+        let user_id = interaction.user.id;
+        let result = match action {
+            rsvp::RsvpAction::Join => rsvp::join(event_id, user_id, &pool)
+                .await
+                .map(|result| result.message().to_string()),
+            rsvp::RsvpAction::Leave => match rsvp::leave(event_id, user_id, &pool).await {
+                // "Leave" also takes players off the waitlist
+                Ok(rsvp::RsvpResult::NotJoined) => {
+                    waitlist::leave_waitlist(event_id, user_id, &pool)
+                        .await
+                        .map(|result| match result {
+                            waitlist::WaitlistResult::NotWaiting => {
+                                rsvp::RsvpResult::NotJoined.message().to_string()
+                            }
+                            result => result.message(),
+                        })
+                }
+                result => result.map(|result| result.message().to_string()),
+            },
+            rsvp::RsvpAction::Waitlist => waitlist::join_waitlist(event_id, user_id, &pool)
+                .await
+                .map(|result| result.message()),
+        };
+        // The seat that was just freed up goes to the next player in line
+        if action == rsvp::RsvpAction::Leave {
+            let discord_api: lib::discord::CacheAndHttp = ctx.into();
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                if let Err(err) = waitlist::offer_free_seats(event_id, &pool, &discord_api).await {
+                    eprintln!(
+                        "Could not offer the free seats of event {}:\n{:#?}",
+                        event_id.0, err
+                    );
+                }
+            });
+        }
+        let message = match result {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("Error in RSVP button handler:\n{:#?}", err);
+                let response = CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(strings::UNSPECIFIED_ERROR)
+                        .ephemeral(true),
+                );
+                interaction.create_response(ctx, response).await.ok();
+                return;
+            }
+        };
+        // Update the attendee count on the announcement and tell the user
+        // privately how it went
+        let response = match rsvp::RsvpState::load(event_id, &pool).await {
+            Ok(Some(state)) => CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new().embed(state.embed()),
+            ),
+            Ok(None) => CreateInteractionResponse::Acknowledge,
+            Err(err) => {
+                eprintln!(
+                    "Could not load the RSVP state of event {}:\n{:#?}",
+                    event_id.0, err
+                );
+                CreateInteractionResponse::Acknowledge
+            }
+        };
+        if let Err(err) = interaction.create_response(ctx, response).await {
+            eprintln!("Could not respond to RSVP button click:\n{:#?}", err);
+            return;
+        }
+        let followup = CreateInteractionResponseFollowup::new()
+            .content(message)
+            .ephemeral(true);
+        interaction.create_followup(ctx, followup).await.ok();
+    }
+
+    // Player buttons on the attendance sheets posted after a session
+    async fn handle_attendance_button(ctx: &Context, interaction: &ComponentInteraction) {
+        let (event_id, action) = match attendance::parse_custom_id(&interaction.data.custom_id) {
+            Some(attendance) => attendance,
+            None => return,
+        };
+        let pool = ctx
+            .data
+            .read()
+            .await
+            .get::<PoolKey>()
+            .cloned()
+            .expect("Postgres pool was not set");
+        let guild_config = Self::guild_config(ctx).await.get();
+        let is_bot_admin = interaction
+            .member
+            .as_ref()
+            .is_some_and(|member| member.roles.contains(&guild_config.bot_admin_role_id));
+        // Poor man's try block
+        let res: Result<Option<attendance::AttendanceSheet>, lib::meetup::Error> = async {
+            let member_id = {
+                let mut tx = pool.begin().await?;
+                let member_id =
+                    lib::db::get_or_create_member_for_discord_id(&mut tx, interaction.user.id)
+                        .await?;
+                tx.commit().await?;
+                member_id
+            };
+            if !is_bot_admin && !attendance::may_mark_attendance(event_id, member_id, &pool).await?
+            {
+                return Ok(None);
+            }
+            match action {
+                attendance::AttendanceAction::Toggle(player_id) => {
+                    attendance::toggle_attendance(event_id, player_id, member_id, &pool).await?
+                }
+                attendance::AttendanceAction::RemainingAttended => {
+                    attendance::mark_remaining_attended(event_id, member_id, &pool).await?
+                }
+            }
+            attendance::AttendanceSheet::load(event_id, &pool).await
+        }
+        .await;
+        let response = match res {
+            Ok(Some(sheet)) => CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(sheet.content())
+                    .components(sheet.components()),
+            ),
+            Ok(None) => CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(strings::ATTENDANCE_NOT_A_HOST)
+                    .ephemeral(true),
+            ),
+            Err(err) => {
+                eprintln!("Error in attendance button handler:\n{:#?}", err);
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(strings::UNSPECIFIED_ERROR)
+                        .ephemeral(true),
+                )
+            }
+        };
+        if let Err(err) = interaction.create_response(ctx, response).await {
+            eprintln!("Could not respond to attendance button click:\n{:#?}", err);
+        }
+    }
+
+    // The "ban" and "false positive" buttons on spam alerts
+    async fn handle_spam_alert_button(ctx: &Context, interaction: &ComponentInteraction) {
+        let pool = ctx
+            .data
+            .read()
+            .await
+            .get::<PoolKey>()
+            .cloned()
+            .expect("Postgres pool was not set");
+        let guild_config = Self::guild_config(ctx).await.get();
+        super::spam::handle_alert_button(ctx, interaction, &guild_config, &pool).await;
+    }
+
+    // Reacting to one of the location messages in the free spots channel
+    // subscribes to notifications for that location
+    async fn handle_free_spots_reaction(ctx: &Context, reaction: &Reaction, subscribe: bool) {
+        let guild_config = Self::guild_config(ctx).await.get();
+        if guild_config.free_spots_channel_id != Some(reaction.channel_id) {
+            return;
+        }
+        let (bot_id, pool) = {
+            let data = ctx.data.read().await;
+            let bot_id = *data.get::<BotIdKey>().expect("Bot ID was not set");
+            let pool = data
+                .get::<PoolKey>()
+                .cloned()
+                .expect("Postgres pool was not set");
+            (bot_id, pool)
+        };
+        let user_id = match reaction.user_id {
+            Some(user_id) if user_id != bot_id => user_id,
+            _ => return,
+        };
+        let message = match reaction.message(ctx).await {
+            Ok(message) => message,
+            Err(err) => {
+                eprintln!("Could not fetch the reacted to message:\n{:#?}", err);
+                return;
+            }
+        };
+        if message.author.id != bot_id {
+            return;
+        }
+        let locations = match Locations::load(&pool).await {
+            Ok(locations) => locations,
+            Err(err) => {
+                eprintln!("Could not load the locations:\n{:#?}", err);
+                return;
+            }
+        };
+        let location = message
+            .embeds
+            .first()
+            .and_then(|embed| embed.author.as_ref())
+            .and_then(|author| locations.find_by_city(&author.name));
+        let location = match location {
+            Some(location) => location,
+            None => return,
+        };
+        let result = if subscribe {
+            free_spots_subscription::subscribe_location(user_id, location, &pool).await
+        } else {
+            free_spots_subscription::unsubscribe_location(user_id, location, &pool)
+                .await
+                .map(|_| ())
+        };
+        if let Err(err) = result {
+            eprintln!(
+                "Could not update the free spots subscription of {}:\n{:#?}",
+                user_id, err
+            );
+        }
+    }
+
+    async fn guild_config(ctx: &Context) -> SharedGuildConfig {
+        ctx.data
+            .read()
+            .await
+            .get::<GuildConfigKey>()
+            .cloned()
+            .expect("Guild config was not set")
+    }
+
     async fn send_welcome_message(ctx: &Context, user: &User) {
         user.direct_message(ctx, CreateMessage::new().content(strings::WELCOME_MESSAGE))
             .await
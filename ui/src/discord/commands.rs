@@ -1,40 +1,70 @@
-use std::{future::Future, pin::Pin, sync::Arc};
+use std::{
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use futures_util::lock::Mutex as AsyncMutex;
-use lib::swissrpg::client::SwissRPGClient;
+use lib::{
+    discord::guild_config::{GuildConfig, SharedGuildConfig},
+    swissrpg::client::SwissRPGClient,
+    tasks::scheduler::Scheduler,
+};
 use once_cell::sync::OnceCell;
 use regex::{Regex, RegexSet};
 use serenity::{
+    builder::{CreateCommand, CreateCommandOption, EditInteractionResponse},
     model::{
+        application::{
+            CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+            InteractionContext,
+        },
         channel::{Channel, Message},
         id::UserId,
+        Permissions,
     },
     prelude::*,
 };
 
 mod add_user;
+mod audit;
+mod calendar;
 // mod clone_event;
-mod count_inactive;
 mod end_adventure;
 #[cfg(feature = "bottest")]
 mod end_all;
+mod expiration;
+mod free_spots;
+mod guild_config;
 mod help;
+mod jobs;
 mod link_meetup;
 mod list_players;
 mod list_subscriptions;
+mod location;
 mod login;
 mod manage_channel;
+mod member_data;
 // mod mention_channel;
 mod numcached;
 // mod refresh_meetup_token;
+mod reminders;
 mod remind_expiration;
 mod schedule_session;
 mod snooze;
+mod spam;
+mod stats;
 mod stop;
 mod sync_discord;
 mod sync_meetup;
 mod sync_subscriptions;
 mod topic;
+mod unarchive;
+mod waitlist;
 // mod test;
 mod whois;
 
@@ -47,6 +77,8 @@ static ALL_COMMANDS: &[&Command] = &[
     &topic::SET_VOICE_TOPIC_COMMAND,
     &sync_meetup::SYNC_MEETUP_COMMAND,
     &sync_discord::SYNC_DISCORD_COMMAND,
+    &sync_discord::SYNC_DISCORD_DRY_RUN_COMMAND,
+    &sync_discord::SYNC_CHANNEL_COMMAND,
     &remind_expiration::REMIND_EXPIRATION_COMMAND,
     &add_user::ADD_USER_COMMAND,
     &add_user::ADD_HOST_COMMAND,
@@ -66,11 +98,37 @@ static ALL_COMMANDS: &[&Command] = &[
     &manage_channel::MANAGE_CHANNEL_COMMAND,
     // &mention_channel::MENTION_CHANNEL_COMMAND,
     &snooze::SNOOZE_COMMAND,
-    &count_inactive::COUNT_INACTIVE_COMMAND,
-    &count_inactive::COUNT_MEMBERS_COMMAND,
+    &expiration::EXPIRATION_COMMAND,
+    &expiration::EXPIRATION_DEFAULT_COMMAND,
+    &stats::STATS_COMMAND,
     // &clone_event::CLONE_EVENT_COMMAND,
     // &test::TEST_COMMAND,
     &login::LOGIN_COMMAND,
+    &guild_config::GUILD_CONFIG_COMMAND,
+    &guild_config::RELOAD_GUILD_CONFIG_COMMAND,
+    &jobs::LIST_JOBS_COMMAND,
+    &jobs::RUN_JOB_COMMAND,
+    &unarchive::UNARCHIVE_COMMAND,
+    &waitlist::WAITLIST_COMMAND,
+    &free_spots::FREE_SPOTS_SUBSCRIBE_COMMAND,
+    &free_spots::FREE_SPOTS_UNSUBSCRIBE_COMMAND,
+    &free_spots::FREE_SPOTS_SUBSCRIPTIONS_COMMAND,
+    &location::LIST_LOCATIONS_COMMAND,
+    &location::ADD_LOCATION_COMMAND,
+    &location::SET_LOCATION_COMMAND,
+    &location::IMPORT_LOCATIONS_COMMAND,
+    &calendar::CALENDAR_COMMAND,
+    &reminders::REMINDERS_COMMAND,
+    &audit::AUDIT_COMMAND,
+    &stats::MEMBER_STATS_COMMAND,
+    &spam::SPAM_WORDS_COMMAND,
+    &spam::ADD_SPAM_WORD_COMMAND,
+    &spam::REMOVE_SPAM_WORD_COMMAND,
+    &spam::RELOAD_SPAM_COMMAND,
+    &spam::SPAM_RULES_COMMAND,
+    &spam::SET_SPAM_RULE_COMMAND,
+    &member_data::EXPORT_DATA_COMMAND,
+    &member_data::ERASE_DATA_COMMAND,
 ];
 
 const MENTION_PATTERN: &'static str = r"(?:<@!?(?P<mention_id>[0-9]+)>)";
@@ -93,6 +151,12 @@ pub struct RegexParts<'a> {
 
 type CommandResult<'a> = Pin<Box<dyn Future<Output = Result<(), lib::meetup::Error>> + Send + 'a>>;
 
+type AutocompleteResult<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<String>, lib::meetup::Error>> + Send + 'a>>;
+
+// Discord shows at most 25 suggestions
+const MAX_AUTOCOMPLETE_CHOICES: usize = 25;
+
 pub(crate) struct Command {
     pub regex: fn(&RegexParts<'_>) -> String,
     pub level: CommandLevel,
@@ -100,6 +164,153 @@ pub(crate) struct Command {
                   + Sync
                   + 'static),
     pub help: &'static [HelpEntry],
+    pub slash: SlashCommand,
+}
+
+pub(crate) struct SlashCommand {
+    pub name: &'static str,
+    pub options: &'static [SlashOption],
+}
+
+pub(crate) struct SlashOption {
+    /// Name of the regex capture group the option's value is passed as
+    pub capture: &'static str,
+    pub name: &'static str,
+    pub kind: SlashOptionKind,
+    pub description: &'static str,
+    pub required: bool,
+    /// Returns the values that are suggested while the option is typed
+    pub autocomplete: Option<for<'a> fn(&'a CommandContext) -> AutocompleteResult<'a>>,
+}
+
+pub(crate) enum SlashOptionKind {
+    User,
+    String,
+    Integer,
+}
+
+impl Command {
+    pub fn slash_command(&self) -> CreateCommand {
+        // Discord allows at most 100 characters for descriptions
+        let description = self
+            .help
+            .first()
+            .map_or(self.slash.name, |help| help.explanation);
+        let mut command = CreateCommand::new(self.slash.name)
+            .description(truncate_description(description))
+            .contexts(vec![InteractionContext::Guild, InteractionContext::BotDm]);
+        // Only decides who sees the command, the command level is still
+        // checked when it runs. Hosts can manage the messages of their
+        // channels. Bot admins are only known by their role, server admins can
+        // grant it the admin commands in the server's integration settings.
+        match self.level {
+            CommandLevel::Everybody => (),
+            CommandLevel::HostAndAdminOnly => {
+                command = command.default_member_permissions(Permissions::MANAGE_MESSAGES)
+            }
+            CommandLevel::AdminOnly => {
+                command = command.default_member_permissions(Permissions::ADMINISTRATOR)
+            }
+        }
+        for option in self.slash.options {
+            let kind = match option.kind {
+                SlashOptionKind::User => CommandOptionType::User,
+                SlashOptionKind::String => CommandOptionType::String,
+                SlashOptionKind::Integer => CommandOptionType::Integer,
+            };
+            command = command.add_option(
+                CreateCommandOption::new(
+                    kind,
+                    option.name,
+                    truncate_description(option.description),
+                )
+                .required(option.required)
+                .set_autocomplete(option.autocomplete.is_some()),
+            );
+        }
+        command
+    }
+
+    // Slash commands don't have a message that could be matched against the
+    // command's regex. Instead, we build a regex whose capture groups are
+    // named after the options and a matching text, such that the command
+    // function can read the option values from the captures just as if the
+    // command had been typed out.
+    pub fn slash_captures_input(
+        &self,
+        data_options: &[CommandDataOption],
+    ) -> Result<(Regex, String), lib::meetup::Error> {
+        let mut patterns = vec![];
+        let mut values = vec![];
+        for option in self.slash.options {
+            let value = match data_options.iter().find(|data| data.name == option.name) {
+                Some(data) => match &data.value {
+                    CommandDataOptionValue::User(id) => id.get().to_string(),
+                    CommandDataOptionValue::String(value) => value.replace('\u{1f}', " "),
+                    CommandDataOptionValue::Integer(value) => value.to_string(),
+                    _ => continue,
+                },
+                None => continue,
+            };
+            patterns.push(format!(r"(?P<{}>[^\x1f]*)", option.capture));
+            values.push(value);
+        }
+        let regex = Regex::new(&format!(r"(?s)^{}$", patterns.join(r"\x1f")))?;
+        Ok((regex, values.join("\u{1f}")))
+    }
+
+    // Suggests the values of the option that contain what was typed so far
+    pub(crate) async fn autocomplete(
+        &self,
+        context: &CommandContext,
+        option_name: &str,
+        input: &str,
+    ) -> Result<Vec<String>, lib::meetup::Error> {
+        let values = match self
+            .slash
+            .options
+            .iter()
+            .find(|option| option.name == option_name)
+            .and_then(|option| option.autocomplete)
+        {
+            Some(values) => values(context).await?,
+            None => return Ok(vec![]),
+        };
+        Ok(autocomplete_choices(values, input))
+    }
+}
+
+fn autocomplete_choices(values: Vec<String>, input: &str) -> Vec<String> {
+    let input = input.trim().to_lowercase();
+    values
+        .into_iter()
+        .filter(|value| value.to_lowercase().contains(&input))
+        .take(MAX_AUTOCOMPLETE_CHOICES)
+        .collect()
+}
+
+fn truncate_description(description: &str) -> String {
+    if description.chars().count() <= 100 {
+        description.to_string()
+    } else {
+        let mut truncated: String = description.chars().take(99).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+pub(crate) fn slash_commands() -> Vec<CreateCommand> {
+    ALL_COMMANDS
+        .iter()
+        .map(|command| command.slash_command())
+        .collect()
+}
+
+pub(crate) fn find_slash_command(name: &str) -> Option<&'static Command> {
+    ALL_COMMANDS
+        .iter()
+        .copied()
+        .find(|command| command.slash.name == name)
 }
 
 pub(crate) struct HelpEntry {
@@ -111,8 +322,6 @@ pub struct CommandContext {
     pub ctx: Context,
     pub msg: Message,
     // pub captures: regex::Captures<'a>,
-    redis_client: OnceCell<redis::Client>,
-    async_redis_connection: OnceCell<redis::aio::MultiplexedConnection>,
     meetup_client: OnceCell<Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>>,
     oauth2_consumer: OnceCell<Arc<lib::meetup::oauth2::OAuth2Consumer>>,
     stripe_client: OnceCell<Arc<stripe::Client>>,
@@ -120,6 +329,10 @@ pub struct CommandContext {
     bot_id: OnceCell<UserId>,
     channel: OnceCell<Channel>,
     pool: OnceCell<sqlx::PgPool>,
+    // Set if the command was invoked as a slash command
+    interaction: Option<CommandInteraction>,
+    // Whether the command responded to the slash command interaction itself
+    interaction_answered: AtomicBool,
 }
 
 impl CommandContext {
@@ -127,8 +340,6 @@ impl CommandContext {
         CommandContext {
             ctx,
             msg,
-            redis_client: OnceCell::new(),
-            async_redis_connection: OnceCell::new(),
             meetup_client: OnceCell::new(),
             oauth2_consumer: OnceCell::new(),
             stripe_client: OnceCell::new(),
@@ -136,38 +347,42 @@ impl CommandContext {
             bot_id: OnceCell::new(),
             channel: OnceCell::new(),
             pool: OnceCell::new(),
+            interaction: None,
+            interaction_answered: AtomicBool::new(false),
+        }
+    }
+
+    pub fn new_slash(ctx: Context, msg: Message, interaction: CommandInteraction) -> Self {
+        CommandContext {
+            interaction: Some(interaction),
+            ..CommandContext::new(ctx, msg)
         }
     }
 
-    pub async fn redis_client(&self) -> Result<redis::Client, lib::meetup::Error> {
-        if let Some(client) = self.redis_client.get() {
-            Ok(client.clone())
+    pub fn interaction_answered(&self) -> bool {
+        self.interaction_answered.load(Ordering::Acquire)
+    }
+
+    // Confirms that the command was successful. Slash commands have no
+    // message that could be reacted to, so their response is edited instead.
+    pub async fn react_success(&self) {
+        if self.interaction.is_some() {
+            self.reply("\u{2705}").await;
         } else {
-            let data = self.ctx.data.read().await;
-            let client = data
-                .get::<super::bot::RedisClientKey>()
-                .cloned()
-                .ok_or_else(|| simple_error::SimpleError::new("Redis client was not set"))?;
-            Ok(self.redis_client.get_or_init(move || client).clone())
+            self.msg.react(&self.ctx, '\u{2705}').await.ok();
         }
     }
 
-    pub async fn async_redis_connection<'b>(
-        &'b mut self,
-    ) -> Result<&'b mut redis::aio::MultiplexedConnection, lib::meetup::Error> {
-        if self.async_redis_connection.get().is_some() {
-            Ok(self
-                .async_redis_connection
-                .get_mut()
-                .expect("Async redis connection not set. This is a bug."))
+    // Replies to the command's message or, for slash commands, with the
+    // (only to the user visible) interaction response
+    pub async fn reply(&self, content: impl Into<String>) {
+        if let Some(interaction) = &self.interaction {
+            let response = EditInteractionResponse::new().content(content);
+            if interaction.edit_response(&self.ctx, response).await.is_ok() {
+                self.interaction_answered.store(true, Ordering::Release);
+            }
         } else {
-            let redis_client = self.redis_client().await?;
-            let async_redis_connection = redis_client.get_multiplexed_async_connection().await?;
-            self.async_redis_connection.set(async_redis_connection).ok();
-            Ok(self
-                .async_redis_connection
-                .get_mut()
-                .expect("Async redis connection not set. This is a bug."))
+            self.msg.reply(&self.ctx, content).await.ok();
         }
     }
 
@@ -273,6 +488,38 @@ impl CommandContext {
         }
     }
 
+    pub async fn shared_guild_config(&self) -> Result<SharedGuildConfig, lib::meetup::Error> {
+        let data = self.ctx.data.read().await;
+        let guild_config = data
+            .get::<super::bot::GuildConfigKey>()
+            .cloned()
+            .ok_or_else(|| simple_error::SimpleError::new("Guild config was not set"))?;
+        Ok(guild_config)
+    }
+
+    pub async fn scheduler(&self) -> Result<Scheduler, lib::meetup::Error> {
+        let data = self.ctx.data.read().await;
+        let scheduler = data
+            .get::<super::bot::SchedulerKey>()
+            .cloned()
+            .ok_or_else(|| simple_error::SimpleError::new("Job scheduler was not set"))?;
+        Ok(scheduler)
+    }
+
+    pub async fn transcript_directory(&self) -> Result<Arc<PathBuf>, lib::meetup::Error> {
+        let data = self.ctx.data.read().await;
+        let transcript_directory = data
+            .get::<super::bot::TranscriptDirectoryKey>()
+            .cloned()
+            .ok_or_else(|| simple_error::SimpleError::new("Transcript directory was not set"))?;
+        Ok(transcript_directory)
+    }
+
+    // Not cached in the context, so that a reload is picked up immediately
+    pub async fn guild_config(&self) -> Result<Arc<GuildConfig>, lib::meetup::Error> {
+        Ok(self.shared_guild_config().await?.get())
+    }
+
     pub async fn bot_id(&self) -> Result<UserId, lib::meetup::Error> {
         if let Some(&bot_id) = self.bot_id.get() {
             Ok(bot_id)
@@ -290,6 +537,36 @@ impl CommandContext {
         }
     }
 
+    // Syncs the event series to Discord in the background
+    pub async fn spawn_series_sync(
+        &self,
+        series_id: lib::db::EventSeriesId,
+    ) -> Result<(), lib::meetup::Error> {
+        let pool = self.pool().await?;
+        let discord_api: lib::discord::CacheAndHttp = (&self.ctx).into();
+        let guild_config = self.guild_config().await?;
+        let bot_id = self.bot_id().await?;
+        let swissrpg_client = self.swissrpg_client().await?;
+        tokio::spawn(async move {
+            if let Err(err) = lib::discord::sync::sync_single_series(
+                series_id,
+                &pool,
+                &discord_api,
+                &guild_config,
+                bot_id,
+                swissrpg_client.base_url(),
+            )
+            .await
+            {
+                eprintln!(
+                    "Discord sync of event series {} failed:\n{:#?}",
+                    series_id.0, err
+                );
+            }
+        });
+        Ok(())
+    }
+
     pub async fn channel(&self) -> Result<Channel, lib::meetup::Error> {
         if let Some(channel) = self.channel.get() {
             Ok(channel.clone())
@@ -308,13 +585,14 @@ impl CommandContext {
     }
 
     pub async fn is_admin(&self) -> Result<bool, lib::meetup::Error> {
+        let guild_config = self.guild_config().await?;
         Ok(self
             .msg
             .author
             .has_role(
                 &self.ctx,
-                lib::discord::sync::ids::GUILD_ID,
-                lib::discord::sync::ids::BOT_ADMIN_ID,
+                guild_config.guild_id,
+                guild_config.bot_admin_role_id,
             )
             .await?)
     }
@@ -410,3 +688,92 @@ pub(crate) fn prepare_commands(
         bot_mention: Regex::new(&format!(r"^\s*{}", bot_mention))?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn slash_command_names_are_unique() {
+        let mut names = HashSet::new();
+        for command in ALL_COMMANDS {
+            assert!(
+                names.insert(command.slash.name),
+                "Duplicate slash command name {}",
+                command.slash.name
+            );
+        }
+    }
+
+    #[test]
+    fn slash_options_become_captures() {
+        let command = &add_user::ADD_USER_COMMAND;
+        let data_options: Vec<CommandDataOption> = serde_json::from_value(serde_json::json!([
+            {"name": "user", "type": 6, "value": "123456789"}
+        ]))
+        .unwrap();
+        let (regex, text) = command.slash_captures_input(&data_options).unwrap();
+        let captures = regex.captures(&text).unwrap();
+        assert_eq!(
+            captures.name("mention_id").map(|m| m.as_str()),
+            Some("123456789")
+        );
+    }
+
+    #[test]
+    fn missing_optional_slash_options_are_not_captured() {
+        let command = &whois::WHOIS_COMMAND;
+        let data_options: Vec<CommandDataOption> = serde_json::from_value(serde_json::json!([
+            {"name": "meetup-id", "type": 4, "value": 42}
+        ]))
+        .unwrap();
+        let (regex, text) = command.slash_captures_input(&data_options).unwrap();
+        let captures = regex.captures(&text).unwrap();
+        assert!(captures.name("mention_id").is_none());
+        assert_eq!(
+            captures.name("meetup_user_id").map(|m| m.as_str()),
+            Some("42")
+        );
+    }
+
+    #[test]
+    fn restricted_slash_commands_have_default_permissions() {
+        let permissions = |command: &Command| {
+            serde_json::to_value(command.slash_command()).unwrap()["default_member_permissions"]
+                .clone()
+        };
+        assert_eq!(permissions(&login::LOGIN_COMMAND), serde_json::Value::Null);
+        assert_eq!(
+            permissions(&add_user::ADD_USER_COMMAND),
+            serde_json::json!(Permissions::MANAGE_MESSAGES.bits().to_string())
+        );
+        assert_eq!(
+            permissions(&stop::STOP_COMMAND),
+            serde_json::json!(Permissions::ADMINISTRATOR.bits().to_string())
+        );
+    }
+
+    #[test]
+    fn autocomplete_suggests_matching_values() {
+        let values = || {
+            ["reminder delay", "reminder interval", "deletion delay"]
+                .map(str::to_string)
+                .to_vec()
+        };
+        assert_eq!(
+            autocomplete_choices(values(), " Reminder"),
+            vec!["reminder delay", "reminder interval"]
+        );
+        assert_eq!(autocomplete_choices(values(), "").len(), 3);
+        let many = (0..100).map(|i| i.to_string()).collect();
+        assert_eq!(
+            autocomplete_choices(many, "").len(),
+            MAX_AUTOCOMPLETE_CHOICES
+        );
+        let command = serde_json::to_value(location::SET_LOCATION_COMMAND.slash_command()).unwrap();
+        assert_eq!(command["options"][0]["autocomplete"], true);
+        assert_eq!(command["options"][1]["autocomplete"], false);
+    }
+}
@@ -1,7 +1,10 @@
 use std::num::NonZeroU64;
 
 use command_macro::command;
-use lib::discord::CacheAndHttp;
+use lib::{
+    audit::{AuditEntry, AuditSource, Auditor},
+    discord::CacheAndHttp,
+};
 use serenity::{
     all::Mentionable,
     model::{channel::PermissionOverwriteType, id::UserId, permissions::Permissions},
@@ -14,6 +17,7 @@ use serenity::{
     "add `@some-user`",
     "_(in game channel or managed channel)_ adds a user to the channel."
 )]
+#[option(mention_id: User, "The user to add to the channel", name = "user")]
 fn add_user<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -47,6 +51,7 @@ fn add_user<'a>(
     "add host `@some-user`",
     "_(in game channel or managed channel)_ makes a user an additional Host. _(Desktop only)_"
 )]
+#[option(mention_id: User, "The user to make an additional host", name = "user")]
 fn add_host<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -80,6 +85,7 @@ fn add_host<'a>(
     "remove `@some-user`",
     "_(in game channel or managed channel)_ removes a user from the channel."
 )]
+#[option(mention_id: User, "The user to remove from the channel", name = "user")]
 fn remove_user<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -113,6 +119,7 @@ fn remove_user<'a>(
     "remove host `@some-user`",
     "_(in game channel or managed channel)_ makes a user no longer a Host."
 )]
+#[option(mention_id: User, "The host to remove from the channel", name = "user")]
 fn remove_host<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -162,6 +169,23 @@ async fn channel_add_or_remove_user_impl(
     // Managed channels and hosts don't use roles but user-specific permission overwrites
     let discord_api: CacheAndHttp = Into::into(&context.ctx);
     let pool = context.pool().await?;
+    let guild_config = context.guild_config().await?;
+    let guild_id = guild_config.guild_id;
+    let command = match (add, as_host) {
+        (true, false) => "add user",
+        (true, true) => "add host",
+        (false, false) => "remove user",
+        (false, true) => "remove host",
+    };
+    let auditor = Auditor::new(
+        AuditSource::Command {
+            actor: context.msg.author.id,
+            command,
+        },
+        &pool,
+        &discord_api,
+        &guild_config,
+    );
     let mut tx = pool.begin().await?;
     if is_game_channel && !is_managed_channel {
         let channel_roles = lib::get_channel_roles(context.msg.channel_id, &mut tx).await?;
@@ -205,7 +229,7 @@ async fn channel_add_or_remove_user_impl(
                 .ctx
                 .http
                 .add_member_role(
-                    lib::discord::sync::ids::GUILD_ID,
+                    guild_id,
                     discord_id,
                     channel_roles.user,
                     Some("Role assignment due to 'add user' command"),
@@ -213,7 +237,10 @@ async fn channel_add_or_remove_user_impl(
                 .await
             {
                 Ok(()) => {
-                    context.msg.react(&context.ctx, '\u{2705}').await.ok();
+                    auditor
+                        .record(AuditEntry::role_added(discord_id, channel_roles.user))
+                        .await;
+                    context.react_success().await;
                     context
                         .msg
                         .channel_id
@@ -223,6 +250,9 @@ async fn channel_add_or_remove_user_impl(
                 }
                 Err(err) => {
                     eprintln!("Could not assign channel role: {}", err);
+                    auditor
+                        .record(AuditEntry::role_added(discord_id, channel_roles.user).failed(&err))
+                        .await;
                     context
                         .msg
                         .channel_id
@@ -241,6 +271,7 @@ async fn channel_add_or_remove_user_impl(
                     context.msg.channel_id,
                     discord_id,
                     new_permissions,
+                    &auditor,
                 )
                 .await
                 {
@@ -282,6 +313,7 @@ async fn channel_add_or_remove_user_impl(
                         voice_channel_id,
                         discord_id,
                         new_permissions,
+                        &auditor,
                     )
                     .await
                     {
@@ -301,11 +333,12 @@ async fn channel_add_or_remove_user_impl(
         } else {
             // Try to remove the user from the channel
             if let Some(host_role) = channel_roles.host {
+                let audit_entry = AuditEntry::role_removed(discord_id, host_role);
                 if let Err(err) = context
                     .ctx
                     .http
                     .remove_member_role(
-                        lib::discord::sync::ids::GUILD_ID,
+                        guild_id,
                         discord_id,
                         host_role,
                         Some("Role removal due to 'remove user' command"),
@@ -313,12 +346,15 @@ async fn channel_add_or_remove_user_impl(
                     .await
                 {
                     eprintln!("Could not remove host channel role:\n{:#?}", err);
+                    auditor.record(audit_entry.failed(&err)).await;
                     context
                         .msg
                         .channel_id
                         .say(&context.ctx, lib::strings::CHANNEL_ROLE_REMOVE_ERROR)
                         .await
                         .ok();
+                } else {
+                    auditor.record(audit_entry).await;
                 }
             }
             if as_host {
@@ -330,6 +366,7 @@ async fn channel_add_or_remove_user_impl(
                     context.msg.channel_id,
                     discord_id,
                     permissions_to_remove,
+                    &auditor,
                 )
                 .await
                 {
@@ -355,6 +392,7 @@ async fn channel_add_or_remove_user_impl(
                         voice_channel_id,
                         discord_id,
                         permissions_to_remove,
+                        &auditor,
                     )
                     .await
                     {
@@ -373,6 +411,8 @@ async fn channel_add_or_remove_user_impl(
             } else {
                 // Remove user completely
                 // Remove direct permissions
+                let audit_entry =
+                    AuditEntry::permissions_deleted(context.msg.channel_id, discord_id);
                 if let Err(err) = context
                     .msg
                     .channel_id
@@ -380,6 +420,7 @@ async fn channel_add_or_remove_user_impl(
                     .await
                 {
                     eprintln!("Could not remove channel permissions:\n{:#?}", err);
+                    auditor.record(audit_entry.failed(&err)).await;
                     context
                         .msg
                         .channel_id
@@ -389,9 +430,12 @@ async fn channel_add_or_remove_user_impl(
                         )
                         .await
                         .ok();
+                } else {
+                    auditor.record(audit_entry).await;
                 }
                 // Also remove permissions from a possibly existing voice channel
                 if let Some(voice_channel_id) = voice_channel_id {
+                    let audit_entry = AuditEntry::permissions_deleted(voice_channel_id, discord_id);
                     if let Err(err) = voice_channel_id
                         .delete_permission(
                             &context.ctx,
@@ -400,6 +444,7 @@ async fn channel_add_or_remove_user_impl(
                         .await
                     {
                         eprintln!("Could not revoke voice channel permissions:\n{:#?}", err);
+                        auditor.record(audit_entry.failed(&err)).await;
                         context
                             .msg
                             .channel_id
@@ -409,13 +454,15 @@ async fn channel_add_or_remove_user_impl(
                             )
                             .await
                             .ok();
+                    } else {
+                        auditor.record(audit_entry).await;
                     }
                 }
                 match context
                     .ctx
                     .http
                     .remove_member_role(
-                        lib::discord::sync::ids::GUILD_ID,
+                        guild_id,
                         discord_id,
                         channel_roles.user,
                         Some("Role removal due to 'remove user' command"),
@@ -424,6 +471,12 @@ async fn channel_add_or_remove_user_impl(
                 {
                     Err(err) => {
                         eprintln!("Could not remove channel role: {}", err);
+                        auditor
+                            .record(
+                                AuditEntry::role_removed(discord_id, channel_roles.user)
+                                    .failed(&err),
+                            )
+                            .await;
                         context
                             .msg
                             .channel_id
@@ -431,10 +484,14 @@ async fn channel_add_or_remove_user_impl(
                             .await
                             .ok();
                     }
-                    _ => (),
+                    Ok(()) => {
+                        auditor
+                            .record(AuditEntry::role_removed(discord_id, channel_roles.user))
+                            .await
+                    }
                 }
             }
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react_success().await;
             // Remember which users were removed manually
             let series_id = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
             match series_id {
@@ -465,6 +522,10 @@ async fn channel_add_or_remove_user_impl(
                 }
             }
         }
+        // Bring the channel's roles and permissions up to date right away
+        if let Some(series_id) = lib::get_channel_series(context.msg.channel_id, &mut tx).await? {
+            context.spawn_series_sync(series_id).await?;
+        }
     } else if is_managed_channel && !is_game_channel {
         if add {
             let new_permissions = if as_host {
@@ -481,6 +542,7 @@ async fn channel_add_or_remove_user_impl(
                 context.msg.channel_id,
                 discord_id,
                 new_permissions,
+                &auditor,
             )
             .await?;
             if permissions_changed {
@@ -491,7 +553,7 @@ async fn channel_add_or_remove_user_impl(
                     .await
                     .ok();
             }
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react_success().await;
         } else {
             // Assume that users with the VIEW_CHANNEL, MANAGE_MESSAGES and
             // MENTION_EVERYONE permission are channel hosts
@@ -521,9 +583,10 @@ async fn channel_add_or_remove_user_impl(
                 context.msg.channel_id,
                 discord_id,
                 permissions_to_remove,
+                &auditor,
             )
             .await?;
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react_success().await;
         }
     }
     Ok(())
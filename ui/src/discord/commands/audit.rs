@@ -0,0 +1,70 @@
+use std::num::NonZeroU64;
+
+use command_macro::command;
+use serenity::{
+    all::Mentionable,
+    builder::{CreateAllowedMentions, CreateMessage},
+    model::id::UserId,
+};
+
+// Keeps the reply below Discord's message length limit
+const MAX_MESSAGE_LENGTH: usize = 1900;
+
+#[command]
+#[regex(r"audit\s+{mention_pattern}", mention_pattern)]
+#[level(admin)]
+#[help(
+    "audit `@some-user`",
+    "shows the recent role and permission changes the bot made for a user"
+)]
+#[option(mention_id: User, "The user to show the history of", name = "user")]
+fn audit<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let discord_id = captures.name("mention_id").unwrap().as_str();
+    let discord_id = match discord_id.parse::<NonZeroU64>() {
+        Ok(id) => UserId::from(id),
+        _ => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    let history = lib::audit::get_user_history(discord_id, 20, &pool).await?;
+    let mut message = if history.is_empty() {
+        format!(
+            "The bot did not change any roles or permissions of {} yet.",
+            discord_id.mention()
+        )
+    } else {
+        format!("**Recent changes for {}:**", discord_id.mention())
+    };
+    for row in &history {
+        let line = row.describe();
+        if message.len() + line.len() + 1 > MAX_MESSAGE_LENGTH {
+            message += "\n…";
+            break;
+        }
+        message += "\n";
+        message += &line;
+    }
+    // Don't ping the users and roles in the log
+    context
+        .msg
+        .channel_id
+        .send_message(
+            &context.ctx,
+            CreateMessage::new()
+                .content(message)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await
+        .ok();
+    Ok(())
+}
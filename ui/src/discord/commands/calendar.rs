@@ -0,0 +1,60 @@
+use command_macro::command;
+use lib::calendar::{self, MemberFeed};
+use serenity::builder::CreateMessage;
+
+#[command]
+#[regex(r"calendar(?:\s+(?P<reset>reset))?")]
+#[help(
+    "calendar `[reset]`",
+    "sends you links to calendar feeds of your sessions. `reset` replaces the links with new ones."
+)]
+#[option(reset: String, "Set to \"reset\" to invalidate your old links", name = "reset", optional)]
+fn calendar<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let reset = captures
+        .name("reset")
+        .is_some_and(|reset| reset.as_str().eq_ignore_ascii_case("reset"));
+    let user_id = context.msg.author.id;
+    let pool = context.pool().await?;
+    let calendar_token = if reset {
+        calendar::reset_calendar_token(user_id, &pool).await?
+    } else {
+        calendar::get_or_create_calendar_token(user_id, &pool).await?
+    };
+    // In a game channel, the game's feed is included as well
+    let series_id = {
+        let mut tx = pool.begin().await?;
+        lib::get_channel_series(context.msg.channel_id, &mut tx).await?
+    };
+    let series_url = series_id.map(calendar::series_feed_url);
+    let bot_id = context.bot_id().await?;
+    let message = lib::strings::CALENDAR_LINKS_MESSAGE(
+        &calendar::member_feed_url(&calendar_token, MemberFeed::Sessions),
+        &calendar::member_feed_url(&calendar_token, MemberFeed::Hosting),
+        series_url.as_deref(),
+        bot_id,
+    );
+    let dm = context
+        .msg
+        .author
+        .direct_message(&context.ctx, CreateMessage::new().content(message))
+        .await;
+    match dm {
+        Ok(_) => {
+            context.react_success().await;
+        }
+        Err(why) => {
+            eprintln!("Error sending calendar DM: {:?}", why);
+            context
+                .reply(
+                    "There was an error trying to send you your calendar links.\nDo you have \
+                     direct messages disabled? In that case send me a private message with the \
+                     text \"calendar\".",
+                )
+                .await;
+        }
+    }
+    Ok(())
+}
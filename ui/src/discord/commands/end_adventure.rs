@@ -7,8 +7,8 @@ use serenity::model::channel::Channel;
 #[level(host)]
 #[help(
     "end adventure",
-    "sets the channel for closure at the end of an adventure. The channel won't be deleted \
-     immediately but within 24 hours."
+    "sets the channel for closure at the end of an adventure. The channel won't be archived or \
+     deleted immediately but after the delay shown by \"expiration\"."
 )]
 fn end_adventure<'a>(
     context: &'a mut super::CommandContext,
@@ -47,10 +47,13 @@ fn end_adventure<'a>(
             )
             .await
             .ok(),
-        EndAdventureResult::NewlyMarkedForDeletion(_) => context
+        EndAdventureResult::NewlyMarkedForDeletion(deletion_time) => context
             .msg
             .channel_id
-            .say(&context.ctx, lib::strings::CHANNEL_MARKED_FOR_CLOSING)
+            .say(
+                &context.ctx,
+                lib::strings::CHANNEL_MARKED_FOR_CLOSING(deletion_time),
+            )
             .await
             .ok(),
     };
@@ -59,7 +62,7 @@ fn end_adventure<'a>(
         Ok(Channel::Guild(channel)) => &channel.name,
         _ => "'unknown'",
     };
-    if let Some(bot_alerts_channel_id) = lib::discord::sync::ids::BOT_ALERTS_CHANNEL_ID {
+    if let Some(bot_alerts_channel_id) = context.guild_config().await?.bot_alerts_channel_id {
         bot_alerts_channel_id
             .say(
                 &context.ctx,
@@ -11,6 +11,7 @@ fn end_all<'a>(
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let pool = context.pool().await?;
+    let guild_config = context.guild_config().await?;
     // Find all channels which can be ended
     let channel_ids = sqlx::query!(
         r#"SELECT discord_id FROM event_series_text_channel WHERE
@@ -51,8 +52,11 @@ fn end_all<'a>(
         let end_adventure_result = lib::end_adventure::end_adventure(channel_id, &mut tx).await?;
         tx.commit().await?;
         match end_adventure_result {
-            EndAdventureResult::NewlyMarkedForDeletion(_) => channel_id
-                .say(&context.ctx, lib::strings::CHANNEL_MARKED_FOR_CLOSING)
+            EndAdventureResult::NewlyMarkedForDeletion(deletion_time) => channel_id
+                .say(
+                    &context.ctx,
+                    lib::strings::CHANNEL_MARKED_FOR_CLOSING(deletion_time),
+                )
                 .await
                 .ok(),
             EndAdventureResult::NotAGameChannel
@@ -78,7 +82,7 @@ fn end_all<'a>(
             )
             .await
             .ok();
-        if let Some(bot_alerts_channel_id) = lib::discord::sync::ids::BOT_ALERTS_CHANNEL_ID {
+        if let Some(bot_alerts_channel_id) = guild_config.bot_alerts_channel_id {
             if bot_alerts_channel_id != context.msg.channel_id {
                 bot_alerts_channel_id
                     .say(
@@ -0,0 +1,189 @@
+use command_macro::command;
+use lib::expiration_policy::{self, PolicySetting};
+
+#[command]
+#[regex(
+    r"expiration(?:\s+(?P<setting>reminder[\s_-]*delay|reminder[\s_-]*interval|deletion[\s_-]*delay)\s+(?P<value>default|[0-9]+\s*(?:d|h|days?|hours?)))?"
+)]
+#[level(host)]
+#[help(
+    "expiration",
+    "_(in game channel)_ shows when this channel gets reminded of its expiration and when it gets \
+     deleted after \"end adventure\""
+)]
+#[help(
+    "expiration `reminder delay|reminder interval|deletion delay` `X days|X hours|default`",
+    "_(in game channel)_ changes one of these settings for this channel only"
+)]
+#[option(setting: String, "reminder delay, reminder interval or deletion delay", name = "setting", optional, autocomplete = setting_names)]
+#[option(value: String, "For example \"30 days\", \"12 hours\" or \"default\"", name = "value", optional)]
+fn expiration<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let mut tx = pool.begin().await?;
+    let series_id = match lib::get_channel_series(context.msg.channel_id, &mut tx).await? {
+        Some(series_id) => series_id,
+        None => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let change = match (captures.name("setting"), captures.name("value")) {
+        (Some(setting), Some(value)) => Some((setting.as_str(), value.as_str())),
+        _ => None,
+    };
+    if let Some((setting, value)) = change {
+        let setting = PolicySetting::parse(setting);
+        let value = if value.trim().eq_ignore_ascii_case("default") {
+            Some(None)
+        } else {
+            expiration_policy::parse_hours(value).map(Some)
+        };
+        let (setting, hours) = match (setting, value) {
+            (Some(setting), Some(hours)) => (setting, hours),
+            _ => {
+                context
+                    .msg
+                    .channel_id
+                    .say(
+                        &context.ctx,
+                        "I didn't get that. Try for example \"expiration reminder interval 30 \
+                         days\".",
+                    )
+                    .await
+                    .ok();
+                return Ok(());
+            }
+        };
+        if let Some(hours) = hours {
+            if !setting.is_valid(hours) {
+                context
+                    .msg
+                    .channel_id
+                    .say(&context.ctx, setting.valid_range())
+                    .await
+                    .ok();
+                return Ok(());
+            }
+        }
+        expiration_policy::set_series_override(series_id, setting, hours, &mut tx).await?;
+    }
+    let policy = expiration_policy::get_series_policy(series_id, &mut tx).await?;
+    tx.commit().await?;
+    let mut message = format!(
+        "Expiration settings of this {}:\n",
+        if policy.is_campaign() {
+            "campaign"
+        } else {
+            "adventure"
+        }
+    );
+    for setting in PolicySetting::ALL {
+        let type_hours = policy.type_policy.hours(setting);
+        message += &match policy.overrides.hours(setting) {
+            Some(hours) => format!(
+                "**{}**: {} _(default for {}s: {})_\n",
+                setting.name(),
+                expiration_policy::format_hours(hours),
+                policy.series_type,
+                expiration_policy::format_hours(type_hours)
+            ),
+            None => format!(
+                "**{}**: {} _(default)_\n",
+                setting.name(),
+                expiration_policy::format_hours(type_hours)
+            ),
+        };
+    }
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(
+    r"expiration\s+default\s+(?P<series_type>campaign|adventure)\s+(?P<setting>reminder[\s_-]*delay|reminder[\s_-]*interval|deletion[\s_-]*delay)\s+(?P<value>[0-9]+\s*(?:d|h|days?|hours?))"
+)]
+#[level(admin)]
+#[help(
+    "expiration default `campaign|adventure` `reminder delay|reminder interval|deletion delay` `X days|X hours`",
+    "changes an expiration setting for all campaigns or adventures that don't have their own"
+)]
+#[option(series_type: String, "campaign or adventure", name = "type", autocomplete = series_types)]
+#[option(setting: String, "reminder delay, reminder interval or deletion delay", name = "setting", autocomplete = setting_names)]
+#[option(value: String, "For example \"3 days\" or \"12 hours\"", name = "value")]
+fn expiration_default<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let series_type = captures
+        .name("series_type")
+        .unwrap()
+        .as_str()
+        .to_lowercase();
+    let setting = PolicySetting::parse(captures.name("setting").unwrap().as_str());
+    let hours = expiration_policy::parse_hours(captures.name("value").unwrap().as_str());
+    let (setting, hours) = match (setting, hours) {
+        (Some(setting), Some(hours)) => (setting, hours),
+        _ => {
+            context
+                .msg
+                .channel_id
+                .say(
+                    &context.ctx,
+                    "I didn't get that. Try for example \"expiration default campaign reminder \
+                     delay 3 days\".",
+                )
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    if !setting.is_valid(hours) {
+        context
+            .msg
+            .channel_id
+            .say(&context.ctx, setting.valid_range())
+            .await
+            .ok();
+        return Ok(());
+    }
+    let pool = context.pool().await?;
+    let updated = expiration_policy::set_type_default(
+        &series_type,
+        setting,
+        hours,
+        &mut *pool.acquire().await?,
+    )
+    .await?;
+    let message = if updated {
+        format!(
+            "The {} of {}s is now {}",
+            setting.name(),
+            series_type,
+            expiration_policy::format_hours(hours)
+        )
+    } else {
+        format!("There is no series type \"{}\"", series_type)
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+fn setting_names(_: &super::CommandContext) -> super::AutocompleteResult<'_> {
+    Box::pin(async move {
+        Ok(["reminder delay", "reminder interval", "deletion delay"]
+            .map(str::to_string)
+            .to_vec())
+    })
+}
+
+fn series_types(_: &super::CommandContext) -> super::AutocompleteResult<'_> {
+    Box::pin(async move { Ok(["campaign", "adventure"].map(str::to_string).to_vec()) })
+}
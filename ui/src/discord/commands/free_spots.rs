@@ -0,0 +1,112 @@
+use command_macro::command;
+use lib::{
+    free_spots_subscription::{self, SubscriptionFilter},
+    location::Locations,
+};
+
+#[command]
+#[regex(r"free\s*spots\s+subscribe(?:\s+(?P<filter>.+))?")]
+#[help(
+    "free spots subscribe `[filter]`",
+    "sends you a message when a game matching the filter has free spots. Filter by city, `online` or `in-person`, weekdays and `system:name`, e.g. `zurich saturday system:Pathfinder`."
+)]
+#[option(filter: String, "Which games you want to hear about", name = "filter", optional)]
+fn free_spots_subscribe<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let filter = captures.name("filter").map_or("", |filter| filter.as_str());
+    let pool = context.pool().await?;
+    let locations = Locations::load(&pool).await?;
+    let filter = match SubscriptionFilter::parse(filter, &locations) {
+        Ok(filter) => filter,
+        Err(err) => {
+            let bot_id = context.bot_id().await?;
+            context
+                .msg
+                .channel_id
+                .say(
+                    &context.ctx,
+                    lib::strings::FREE_SPOTS_INVALID_FILTER(&err, bot_id),
+                )
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let subscription_id =
+        free_spots_subscription::subscribe(context.msg.author.id, &filter, &pool).await?;
+    context
+        .msg
+        .channel_id
+        .say(
+            &context.ctx,
+            lib::strings::FREE_SPOTS_SUBSCRIBED(subscription_id, &filter.describe(&locations)),
+        )
+        .await
+        .ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"free\s*spots\s+unsubscribe(?:\s+#?(?P<subscription_id>[0-9]+))?")]
+#[help(
+    "free spots unsubscribe `[number]`",
+    "removes one of your free spots subscriptions, or all of them if no number is given."
+)]
+#[option(subscription_id: Integer, "The subscription to remove", name = "number", optional)]
+fn free_spots_unsubscribe<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let subscription_id = match captures.name("subscription_id") {
+        Some(subscription_id) => Some(
+            subscription_id
+                .as_str()
+                .parse::<i32>()
+                .map_err(|_err| simple_error::SimpleError::new("Invalid subscription number"))?,
+        ),
+        None => None,
+    };
+    let pool = context.pool().await?;
+    let num_removed =
+        free_spots_subscription::unsubscribe(context.msg.author.id, subscription_id, &pool).await?;
+    context
+        .msg
+        .channel_id
+        .say(
+            &context.ctx,
+            lib::strings::FREE_SPOTS_UNSUBSCRIBED(num_removed),
+        )
+        .await
+        .ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"free\s*spots\s+subscriptions")]
+#[help("free spots subscriptions", "lists your free spots subscriptions.")]
+fn free_spots_subscriptions<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let subscriptions =
+        free_spots_subscription::get_subscriptions(context.msg.author.id, &pool).await?;
+    let locations = Locations::load(&pool).await?;
+    let message = if subscriptions.is_empty() {
+        lib::strings::FREE_SPOTS_NO_SUBSCRIPTIONS.to_string()
+    } else {
+        let mut message = "Your free spots subscriptions:".to_string();
+        for subscription in subscriptions {
+            message.push_str(&format!(
+                "\n**#{}**: {}",
+                subscription.id,
+                subscription.filter.describe(&locations)
+            ));
+        }
+        message
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
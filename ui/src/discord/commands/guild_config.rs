@@ -0,0 +1,140 @@
+use command_macro::command;
+use lib::discord::guild_config::GuildConfig;
+use serenity::{
+    all::Mentionable,
+    model::id::{ChannelId, RoleId},
+};
+
+#[command]
+#[regex(r"guild\s*config")]
+#[level(admin)]
+#[help(
+    "guild config",
+    "shows the guild configuration and any problems with it"
+)]
+fn guild_config<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let guild_config = context.guild_config().await?;
+    let message = describe_guild_config(context, &guild_config);
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"guild\s*config\s+reload")]
+#[level(admin)]
+#[help(
+    "guild config reload",
+    "reloads the guild configuration from the database"
+)]
+fn reload_guild_config<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let shared_guild_config = context.shared_guild_config().await?;
+    let guild_config = match shared_guild_config.reload(&pool).await {
+        Ok(guild_config) => guild_config,
+        Err(err) => {
+            eprintln!("Could not reload the guild config:\n{:#?}", err);
+            context
+                .msg
+                .channel_id
+                .say(
+                    &context.ctx,
+                    "Could not reload the guild configuration, keeping the old one.",
+                )
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let message = format!(
+        "Reloaded the guild configuration.\n{}",
+        describe_guild_config(context, &guild_config)
+    );
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+fn describe_guild_config(context: &super::CommandContext, guild_config: &GuildConfig) -> String {
+    // Print role names instead of mentions in order not to ping anyone
+    let role = |role_id: RoleId| {
+        let name = context
+            .ctx
+            .cache
+            .guild(guild_config.guild_id)
+            .and_then(|guild| guild.roles.get(&role_id).map(|role| role.name.clone()));
+        match name {
+            Some(name) => format!("{} ({})", name, role_id),
+            None => format!("unknown role ({})", role_id),
+        }
+    };
+    let optional_role = |role_id: Option<RoleId>| role_id.map_or("-".to_string(), role);
+    let channel = |channel_id: Option<ChannelId>| {
+        channel_id.map_or("-".to_string(), |id| id.mention().to_string())
+    };
+    let channels = |channel_ids: &[ChannelId]| {
+        if channel_ids.is_empty() {
+            "-".to_string()
+        } else {
+            channel_ids
+                .iter()
+                .map(|id| id.mention().to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+    };
+    let mut message = format!(
+        "**Guild:** {}\n\
+         **Bot admin role:** {}\n\
+         **Organiser role:** {}\n\
+         **Game master role:** {}\n\
+         **Dice roller bot role:** {}\n\
+         **Admin role:** {}\n\
+         **Champion role:** {}\n\
+         **Insider role:** {}\n\
+         **GM champion role:** {}\n\
+         **One-shot categories:** {}\n\
+         **Campaign categories:** {}\n\
+         **Voice channel categories:** {}\n\
+         **Bot alerts channel:** {}\n\
+         **Free spots channel:** {}\n\
+         **User topic voice channel:** {}\n\
+         **Archive category:** {}\n\
+         **Archive retention:** {} days\n\
+         **Audit log channel:** {}\n\
+         **Spam quarantine role:** {}\n",
+        guild_config.guild_id,
+        role(guild_config.bot_admin_role_id),
+        role(guild_config.organiser_role_id),
+        role(guild_config.game_master_role_id),
+        optional_role(guild_config.dice_roller_bot_role_id),
+        optional_role(guild_config.admin_role_id),
+        role(guild_config.champion_role_id),
+        role(guild_config.insider_role_id),
+        role(guild_config.gm_champion_role_id),
+        channels(&guild_config.one_shot_category_ids),
+        channels(&guild_config.campaign_category_ids),
+        channels(&guild_config.voice_channel_category_ids),
+        channel(guild_config.bot_alerts_channel_id),
+        channel(guild_config.free_spots_channel_id),
+        channel(guild_config.user_topic_voice_channel_id),
+        channel(guild_config.archive_category_id),
+        guild_config.archive_retention_days,
+        channel(guild_config.audit_log_channel_id),
+        optional_role(guild_config.spam_quarantine_role_id),
+    );
+    let problems = guild_config.validate(&context.ctx.cache);
+    if problems.is_empty() {
+        message += "No problems found.";
+    } else {
+        message += "**Problems:**\n";
+        for problem in problems {
+            message += &format!("- {}\n", problem);
+        }
+    }
+    message
+}
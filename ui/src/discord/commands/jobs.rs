@@ -0,0 +1,89 @@
+use command_macro::command;
+use lib::tasks::scheduler::TriggerResult;
+
+#[command]
+#[regex(r"jobs")]
+#[level(admin)]
+#[help("jobs", "lists the recurring jobs and how their last runs went")]
+fn list_jobs<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let scheduler = context.scheduler().await?;
+    let statuses = scheduler.status().await?;
+    let mut message = String::new();
+    for status in statuses {
+        let timestamp = |time: Option<chrono::DateTime<chrono::Utc>>| {
+            time.map_or("never".to_string(), |time| {
+                format!("<t:{}:R>", time.timestamp())
+            })
+        };
+        let last_run = match (status.last_outcome, status.last_duration) {
+            (Some(outcome), Some(duration)) => format!(
+                "{} ({}, {}s)",
+                timestamp(status.last_start_time),
+                outcome.as_str(),
+                duration.as_secs()
+            ),
+            _ => timestamp(status.last_start_time),
+        };
+        message += &format!(
+            "**{}** `{}`{}\nLast run: {}\nLast success: {}\nNext run: {}\n",
+            status.name,
+            status.schedule,
+            if status.is_running {
+                " _(running)_"
+            } else {
+                ""
+            },
+            last_run,
+            timestamp(status.last_success_time),
+            timestamp(status.next_run),
+        );
+        if let Some(error) = status.last_error {
+            // Keep the message below Discord's length limit
+            let error: String = error
+                .lines()
+                .next()
+                .unwrap_or("")
+                .chars()
+                .take(150)
+                .collect();
+            message += &format!("Last error: `{}`\n", error);
+        }
+        message += "\n";
+    }
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"run\s*job\s+(?P<job_name>[^\s]+)")]
+#[level(admin)]
+#[help("run job `job-name`", "runs one of the recurring jobs right now")]
+#[option(job_name: String, "The name of the job (see \"jobs\")", name = "name", autocomplete = job_names)]
+fn run_job<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let job_name = captures.name("job_name").unwrap().as_str();
+    let scheduler = context.scheduler().await?;
+    let message = match scheduler.trigger(job_name) {
+        TriggerResult::Started => format!("Started job {}", job_name),
+        TriggerResult::AlreadyRunning => format!("Job {} is already running", job_name),
+        TriggerResult::UnknownJob => format!("There is no job called {}", job_name),
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+fn job_names(context: &super::CommandContext) -> super::AutocompleteResult<'_> {
+    Box::pin(async move {
+        let scheduler = context.scheduler().await?;
+        Ok(scheduler
+            .job_names()
+            .into_iter()
+            .map(str::to_string)
+            .collect())
+    })
+}
@@ -43,11 +43,7 @@ fn link_meetup<'a>(
         return Ok(());
     };
     let user_id = context.msg.author.id;
-    let url = lib::meetup::oauth2::generate_meetup_linking_link(
-        context.async_redis_connection().await?,
-        user_id,
-    )
-    .await?;
+    let url = lib::meetup::oauth2::generate_meetup_linking_link(&pool, user_id).await?;
     let message_builder = CreateMessage::new().content(lib::strings::MEETUP_LINKING_MESSAGE(&url));
     let dm = context
         .msg
@@ -56,15 +52,17 @@ fn link_meetup<'a>(
         .await;
     match dm {
         Ok(_) => {
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react_success().await;
         }
         Err(why) => {
             eprintln!("Error sending Meetup linking DM: {:?}", why);
-            context.msg.reply(
-                &context.ctx,
-                "There was an error trying to send you instructions.\nDo you have direct messages \
-                 disabled? In that case send me a private message with the text \"link meetup\".",
-            ).await.ok();
+            context
+                .reply(
+                    "There was an error trying to send you instructions.\nDo you have direct \
+                     messages disabled? In that case send me a private message with the text \
+                     \"link meetup\".",
+                )
+                .await;
         }
     }
     Ok(())
@@ -91,6 +89,8 @@ fn unlink_meetup<'a>(
     "link meetup `@some-user` `meetup-ID`",
     "link another user's Meetup and Discord profile."
 )]
+#[option(mention_id: User, "The Discord user to link", name = "user")]
+#[option(meetupid: Integer, "The Meetup ID to link", name = "meetup-id")]
 fn link_meetup_bot_admin<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -183,6 +183,7 @@ fn link_meetup_bot_admin<'a>(
     "unlink meetup `@some-user`",
     "unlink another user's Meetup and Discord profile."
 )]
+#[option(mention_id: User, "The Discord user to unlink", name = "user")]
 fn unlink_meetup_bot_admin<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
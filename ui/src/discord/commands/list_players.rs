@@ -15,6 +15,7 @@ fn list_players<'a>(
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let pool = context.pool().await?;
+    let guild_id = context.guild_config().await?.guild_id;
     let mut tx = pool.begin().await?;
     // Check whether this is a bot controlled channel
     let event_series = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
@@ -150,10 +151,7 @@ fn list_players<'a>(
             // in order not to unnecessarily ping them
             let user_mention = if is_in_channel {
                 match discord_id.to_user(&context.ctx).await {
-                    Ok(user) => match user
-                        .nick_in(&context.ctx, lib::discord::sync::ids::GUILD_ID)
-                        .await
-                    {
+                    Ok(user) => match user.nick_in(&context.ctx, guild_id).await {
                         Some(nick) => nick,
                         None => user.name,
                     },
@@ -218,6 +216,33 @@ fn list_players<'a>(
             reply += &format!("• {}\n", discord_id.mention());
         }
     }
+    let waitlists = lib::discord::waitlist::get_waitlists(&event_ids, &pool).await?;
+    for event in &events {
+        let waitlist: Vec<_> = waitlists
+            .iter()
+            .filter(|entry| entry.event_id == event.id)
+            .collect();
+        if waitlist.is_empty() {
+            continue;
+        }
+        reply += &format!(
+            "\n\nWaitlist for the session on <t:{}:F>:\n",
+            event.time.timestamp()
+        );
+        for (position, entry) in waitlist.iter().enumerate() {
+            let user = entry
+                .discord_id
+                .map_or("unknown user".to_string(), |id| id.mention().to_string());
+            reply += &format!("{}. {}", position + 1, user);
+            if let Some(claim_expires) = entry.claim_expires {
+                reply += &format!(
+                    " (offered a seat, can claim it until <t:{}:f>)",
+                    claim_expires.timestamp()
+                );
+            }
+            reply += "\n";
+        }
+    }
     const LIMIT: usize = serenity::constants::MESSAGE_CODE_LIMIT;
     // Split the reply if necessary
     let mut reply = reply.as_str();
@@ -0,0 +1,222 @@
+use command_macro::command;
+use lib::{
+    discord::CacheAndHttp,
+    free_spots::EventCollector,
+    location::{self, LocationUpdate, Locations},
+};
+
+#[command]
+#[regex(r"locations?")]
+#[level(admin)]
+#[help("locations", "lists the locations that games are grouped by")]
+fn list_locations<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let locations = Locations::load(&pool).await?;
+    let mut message = "**Locations:**".to_string();
+    for location in locations.iter() {
+        let coordinates = match (location.lat, location.lng) {
+            (Some(lat), Some(lng)) => format!("{}, {}", lat, lng),
+            _ if location.is_online => "online".to_string(),
+            _ => "no coordinates".to_string(),
+        };
+        message.push_str(&format!(
+            "\n`{}` {} ({}) · colour {} · icon {} · link <{}> · order {} · aliases {}",
+            location.code,
+            location.name,
+            coordinates,
+            location.color,
+            location.icon.as_deref().unwrap_or("-"),
+            location.group_link.as_deref().unwrap_or("-"),
+            location.sort_order,
+            if location.aliases.is_empty() {
+                "-".to_string()
+            } else {
+                location.aliases.join(", ")
+            }
+        ));
+    }
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"location\s+add\s+(?P<code>[a-z0-9_]+)\s+(?P<name>.+)")]
+#[level(admin)]
+#[help(
+    "location add `code` `name`",
+    "adds a location. The code should match the SwissRPG location tag."
+)]
+#[option(code: String, "The SwissRPG location tag code", name = "code")]
+#[option(name: String, "The name shown on Discord", name = "name")]
+fn add_location<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let code = captures
+        .name("code")
+        .expect("Regex capture does not contain 'code'")
+        .as_str();
+    let name = captures
+        .name("name")
+        .expect("Regex capture does not contain 'name'")
+        .as_str()
+        .trim();
+    // The slash command does not go through the regex
+    if !location::is_valid_code(code) {
+        context
+            .msg
+            .channel_id
+            .say(
+                &context.ctx,
+                "Location codes may only contain lowercase letters, digits and underscores.",
+            )
+            .await
+            .ok();
+        return Ok(());
+    }
+    let pool = context.pool().await?;
+    let message = if location::add_location(code, name, &pool).await? {
+        format!(
+            "Added the location `{}`. Use \"location set {} coordinates\", \"icon\", \"colour\" \
+             and \"link\" to complete it.",
+            code, code
+        )
+    } else {
+        format!("There already is a location `{}`.", code)
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(
+    r"location\s+set\s+(?P<code>[a-z0-9_]+)\s+(?P<field>name|coordinates|colou?r|icon|link|order|online|aliases)\s+(?P<value>.+)"
+)]
+#[level(admin)]
+#[help(
+    "location set `code` `name|coordinates|colour|icon|link|order|online|aliases` `value`",
+    "changes a location. Aliases are separated by commas. Use `none` to remove the \
+     coordinates, icon, link or aliases."
+)]
+#[option(code: String, "The location code", name = "code", autocomplete = location_codes)]
+#[option(field: String, "name, coordinates, colour, icon, link, order, online or aliases", name = "field")]
+#[option(value: String, "The new value", name = "value")]
+fn set_location<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let code = captures
+        .name("code")
+        .expect("Regex capture does not contain 'code'")
+        .as_str();
+    let field = captures
+        .name("field")
+        .expect("Regex capture does not contain 'field'")
+        .as_str();
+    let value = captures
+        .name("value")
+        .expect("Regex capture does not contain 'value'")
+        .as_str();
+    let update = match LocationUpdate::parse(field, value) {
+        Ok(update) => update,
+        Err(err) => {
+            context.msg.channel_id.say(&context.ctx, err).await.ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    // Remember the old name to find the location's free spots message
+    let old_name = match &update {
+        LocationUpdate::Name(_) => Locations::load(&pool)
+            .await?
+            .find_by_code(code)
+            .map(|location| location.name.clone()),
+        _ => None,
+    };
+    let message = if location::update_location(code, &update, &pool).await? {
+        if let (Some(old_name), LocationUpdate::Name(new_name)) = (old_name, &update) {
+            let guild_config = context.guild_config().await?;
+            if let Some(channel_id) = guild_config.free_spots_channel_id {
+                let discord_api: CacheAndHttp = Into::into(&context.ctx);
+                if let Err(err) = EventCollector::rename_location_message(
+                    &discord_api,
+                    channel_id,
+                    &old_name,
+                    new_name,
+                )
+                .await
+                {
+                    eprintln!(
+                        "Could not rename the free spots message of location {}:\n{:#?}",
+                        code, err
+                    );
+                }
+            }
+        }
+        format!(
+            "Updated the location `{}`. The free spots channel will show the change after the \
+             next update.",
+            code
+        )
+    } else {
+        format!("There is no location `{}`.", code)
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"locations?\s+import")]
+#[level(admin)]
+#[help(
+    "locations import",
+    "adds the SwissRPG location tags which are not a location yet"
+)]
+fn import_locations<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let swissrpg_client = context.swissrpg_client().await?;
+    let new_codes = match location::import_swissrpg_locations(&swissrpg_client, &pool).await {
+        Ok(new_codes) => new_codes,
+        Err(err) => {
+            eprintln!("Could not import the SwissRPG locations:\n{:#?}", err);
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, "Could not import the SwissRPG locations.")
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let message = if new_codes.is_empty() {
+        "All SwissRPG locations are known already.".to_string()
+    } else {
+        format!(
+            "Added the locations {}. They still need coordinates and an icon.",
+            new_codes
+                .iter()
+                .map(|code| format!("`{}`", code))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+fn location_codes(context: &super::CommandContext) -> super::AutocompleteResult<'_> {
+    Box::pin(async move {
+        let pool = context.pool().await?;
+        let locations = Locations::load(&pool).await?;
+        Ok(locations
+            .iter()
+            .map(|location| location.code.clone())
+            .collect())
+    })
+}
@@ -3,16 +3,18 @@ use serenity::builder::CreateMessage;
 
 #[command]
 #[regex(r"login")]
-#[level(admin)]
-#[help("login", "Log in to the web interface")]
+#[help(
+    "login",
+    "sends you a link to log in to the web interface, where you can see your profile and manage \
+     the games you host."
+)]
 fn login<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let user_id = context.msg.author.id;
-    let url =
-        crate::web::auth::generate_login_link(context.async_redis_connection().await?, user_id)
-            .await?;
+    let pool = context.pool().await?;
+    let url = crate::web::auth::generate_login_link(&pool, user_id).await?;
     let dm = context
         .msg
         .author
@@ -23,20 +25,17 @@ fn login<'a>(
         .await;
     match dm {
         Ok(_) => {
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react_success().await;
         }
         Err(why) => {
             eprintln!("Error sending login DM: {:?}", why);
             context
-                .msg
                 .reply(
-                    &context.ctx,
                     "There was an error trying to send you a login link.\nDo you have direct \
                      messages disabled? In that case send me a private message with the text \
                      \"login\".",
                 )
-                .await
-                .ok();
+                .await;
         }
     }
     Ok(())
@@ -1,4 +1,5 @@
 use command_macro::command;
+use lib::audit::{AuditSource, Auditor};
 use serenity::model::{
     channel::{Channel, PermissionOverwrite, PermissionOverwriteType},
     permissions::Permissions,
@@ -57,18 +58,30 @@ fn manage_channel<'a>(
             return Ok(());
         }
     };
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
+    let auditor = Auditor::new(
+        AuditSource::Command {
+            actor: context.msg.author.id,
+            command: "manage channel",
+        },
+        &pool,
+        &discord_api,
+        &guild_config,
+    );
     // Step 2: Grant the bot continued access to the channel
     let bot_id = context.bot_id().await?;
-    channel
-        .create_permission(
-            &context.ctx,
-            PermissionOverwrite {
-                allow: Permissions::VIEW_CHANNEL,
-                deny: Permissions::empty(),
-                kind: PermissionOverwriteType::Member(bot_id),
-            },
-        )
-        .await?;
+    lib::discord::set_permission_overwrite(
+        &discord_api,
+        channel_id,
+        PermissionOverwrite {
+            allow: Permissions::VIEW_CHANNEL,
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(bot_id),
+        },
+        &auditor,
+    )
+    .await?;
     // Step 3: Grant all current users access to the channel
     let mut current_channel_members = channel.members(&context.ctx)?;
     for member in &mut current_channel_members {
@@ -83,18 +96,19 @@ fn manage_channel<'a>(
         if is_admin {
             continue;
         }
-        channel
-            .create_permission(
-                &context.ctx,
-                PermissionOverwrite {
-                    allow: Permissions::VIEW_CHANNEL,
-                    deny: Permissions::empty(),
-                    kind: PermissionOverwriteType::Member(member.user.id),
-                },
-            )
-            .await?;
+        lib::discord::set_permission_overwrite(
+            &discord_api,
+            channel_id,
+            PermissionOverwrite {
+                allow: Permissions::VIEW_CHANNEL,
+                deny: Permissions::empty(),
+                kind: PermissionOverwriteType::Member(member.user.id),
+            },
+            &auditor,
+        )
+        .await?;
     }
     tx.commit().await?;
-    context.msg.react(&context.ctx, '\u{2705}').await.ok();
+    context.react_success().await;
     Ok(())
 }
@@ -0,0 +1,182 @@
+use std::num::NonZeroU64;
+
+use command_macro::command;
+use lib::{
+    audit::{AuditEntry, AuditSource, Auditor},
+    discord::CacheAndHttp,
+    gdpr::{self, ErasureResult},
+};
+use serenity::{
+    all::Mentionable,
+    builder::{CreateAttachment, CreateMessage},
+    model::id::UserId,
+};
+
+async fn member_id_for_discord_id(
+    discord_id: UserId,
+    pool: &sqlx::PgPool,
+) -> Result<Option<lib::db::MemberId>, lib::meetup::Error> {
+    let member_id = sqlx::query_scalar!(
+        r#"SELECT id FROM "member" WHERE discord_id = $1"#,
+        discord_id.get() as i64
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(member_id.map(lib::db::MemberId))
+}
+
+#[command]
+#[regex(r"export\s*(?:my\s*)?data")]
+#[help(
+    "export my data",
+    "sends you everything the bot knows about you as a JSON file."
+)]
+fn export_data<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let user_id = context.msg.author.id;
+    let pool = context.pool().await?;
+    let message = match member_id_for_discord_id(user_id, &pool).await? {
+        Some(member_id) => {
+            let stripe_client = context.stripe_client().await?;
+            let transcript_directory = context.transcript_directory().await?;
+            let export =
+                gdpr::export_member_data(member_id, &pool, &stripe_client, &transcript_directory)
+                    .await?;
+            CreateMessage::new()
+                .content("Here is everything I know about you.")
+                .add_file(CreateAttachment::bytes(
+                    serde_json::to_string_pretty(&export)?.into_bytes(),
+                    "swissrpg-data.json",
+                ))
+        }
+        None => CreateMessage::new().content("I don't have any data about you."),
+    };
+    let dm = context
+        .msg
+        .author
+        .direct_message(&context.ctx, message)
+        .await;
+    match dm {
+        Ok(_) => {
+            context.react_success().await;
+        }
+        Err(why) => {
+            eprintln!("Error sending data export DM: {:?}", why);
+            context
+                .reply(
+                    "There was an error trying to send you your data.\nDo you have direct \
+                     messages disabled? In that case send me a private message with the text \
+                     \"export my data\".",
+                )
+                .await;
+            return Ok(());
+        }
+    }
+    let discord_api: CacheAndHttp = Into::into(&context.ctx);
+    let guild_config = context.guild_config().await?;
+    let auditor = Auditor::new(
+        AuditSource::Command {
+            actor: user_id,
+            command: "export data",
+        },
+        &pool,
+        &discord_api,
+        &guild_config,
+    );
+    auditor.record(AuditEntry::data_exported(user_id)).await;
+    Ok(())
+}
+
+#[command]
+#[regex(r"erase\s*data\s+{mention_pattern}", mention_pattern)]
+#[level(admin)]
+#[help(
+    "erase data `@some-user`",
+    "deletes everything the bot knows about a user, for example on a GDPR erasure request. This \
+     can't be undone."
+)]
+#[option(mention_id: User, "The user whose data to erase", name = "user")]
+fn erase_data<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let discord_id = captures.name("mention_id").unwrap().as_str();
+    let discord_id = match discord_id.parse::<NonZeroU64>() {
+        Ok(id) => UserId::from(id),
+        _ => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    let member_id = match member_id_for_discord_id(discord_id, &pool).await? {
+        Some(member_id) => member_id,
+        None => {
+            context
+                .msg
+                .channel_id
+                .say(
+                    &context.ctx,
+                    format!("I don't have any data about {}", discord_id.mention()),
+                )
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let stripe_client = context.stripe_client().await?;
+    let transcript_directory = context.transcript_directory().await?;
+    let discord_api: CacheAndHttp = Into::into(&context.ctx);
+    let guild_config = context.guild_config().await?;
+    let auditor = Auditor::new(
+        AuditSource::Command {
+            actor: context.msg.author.id,
+            command: "erase data",
+        },
+        &pool,
+        &discord_api,
+        &guild_config,
+    );
+    let erasure_result = gdpr::erase_member_data(
+        member_id,
+        &pool,
+        &stripe_client,
+        &transcript_directory,
+        &auditor,
+    )
+    .await?;
+    let message = match erasure_result {
+        ErasureResult::Erased {
+            num_stripe_customers,
+            num_unedited_log_messages,
+        } => {
+            let mut message = format!(
+                "Erased the data of {} (member {}) and unlinked {} Stripe customer(s)",
+                discord_id.mention(),
+                member_id.0,
+                num_stripe_customers
+            );
+            if num_unedited_log_messages > 0 {
+                message += &format!(
+                    ". {} message(s) in the audit log channel could not be edited and still \
+                     mention them, please remove those by hand.",
+                    num_unedited_log_messages
+                );
+            }
+            message
+        }
+        ErasureResult::HostsUpcomingSessions => format!(
+            "{} hosts upcoming sessions. Please remove them as a host first.",
+            discord_id.mention()
+        ),
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
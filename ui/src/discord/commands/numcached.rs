@@ -8,7 +8,10 @@ pub fn numcached<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
-    let num_cached_members = lib::discord::sync::ids::GUILD_ID
+    let num_cached_members = context
+        .guild_config()
+        .await?
+        .guild_id
         .to_guild_cached(&context.ctx)
         .map(|guild| guild.members.len());
     if let Some(num_cached_members) = num_cached_members {
@@ -14,9 +14,18 @@ fn remind_expiration<'a>(
     let pool = context.pool().await?;
     let bot_id = context.bot_id().await?;
     let mut discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
+    let transcript_directory = context.transcript_directory().await?;
     // Spawn the end-of-game task
     tokio::spawn(async move {
-        lib::tasks::end_of_game::end_of_game_task(&pool, &mut discord_api, bot_id).await
+        lib::tasks::end_of_game::end_of_game_task(
+            &pool,
+            &mut discord_api,
+            &guild_config,
+            bot_id,
+            &transcript_directory,
+        )
+        .await
     });
     context
         .msg
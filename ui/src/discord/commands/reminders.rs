@@ -0,0 +1,138 @@
+use command_macro::command;
+use lib::tasks::session_reminder;
+
+lazy_static::lazy_static! {
+    // Matched against the setting on its own, such that the slash command's
+    // "setting" option works the same as the text command
+    static ref SETTING_REGEX: regex::Regex = regex::Regex::new(
+        r"^(?i)(?:(?P<dm>dm\s+(?:on|off))|(?P<off>off|mute)|(?P<default>default)|(?P<times>[0-9][0-9a-z\s,]*))$"
+    )
+    .unwrap();
+}
+
+fn usage_message() -> String {
+    format!(
+        "I didn't get that. Try for example \"reminders 24h 1h\". Up to {} reminders between {} \
+         minutes and {} days before the session are possible.",
+        session_reminder::MAX_NUM_REMINDERS,
+        session_reminder::MIN_REMINDER_MINUTES,
+        session_reminder::MAX_REMINDER_MINUTES / (24 * 60)
+    )
+}
+
+#[command]
+#[regex(r"reminders?(?:\s+(?P<setting>.+))?")]
+#[level(host)]
+#[help(
+    "reminders",
+    "_(in game channel)_ shows when this channel gets reminded of upcoming sessions"
+)]
+#[help(
+    "reminders `X hours Y minutes ...|off|default`",
+    "_(in game channel)_ changes when this channel gets reminded, e.g. `reminders 24h 1h`"
+)]
+#[help(
+    "reminders dm `on|off`",
+    "_(in game channel)_ also reminds the players of this channel by DM"
+)]
+#[option(setting: String, "For example \"24h 1h\", \"off\", \"default\" or \"dm on\"", name = "setting", optional)]
+fn reminders<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let series_id = {
+        let mut tx = pool.begin().await?;
+        lib::get_channel_series(context.msg.channel_id, &mut tx).await?
+    };
+    let series_id = match series_id {
+        Some(series_id) => series_id,
+        None => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let setting = captures
+        .name("setting")
+        .map(|setting| setting.as_str().trim())
+        .filter(|setting| !setting.is_empty());
+    if let Some(setting) = setting {
+        let setting = match SETTING_REGEX.captures(setting) {
+            Some(setting) => setting,
+            None => {
+                context
+                    .msg
+                    .channel_id
+                    .say(&context.ctx, usage_message())
+                    .await
+                    .ok();
+                return Ok(());
+            }
+        };
+        if let Some(dm) = setting.name("dm") {
+            let dm = dm.as_str().to_lowercase().ends_with("on");
+            session_reminder::set_series_reminder_dm(series_id, dm, &pool).await?;
+        } else if setting.name("off").is_some() {
+            session_reminder::set_series_reminder_minutes(series_id, Some(&[]), &pool).await?;
+        } else if setting.name("default").is_some() {
+            session_reminder::set_series_reminder_minutes(series_id, None, &pool).await?;
+        } else if let Some(times) = setting.name("times") {
+            let minutes = match session_reminder::parse_reminder_minutes(times.as_str()) {
+                Some(minutes) if session_reminder::are_valid_reminder_minutes(&minutes) => minutes,
+                _ => {
+                    context
+                        .msg
+                        .channel_id
+                        .say(&context.ctx, usage_message())
+                        .await
+                        .ok();
+                    return Ok(());
+                }
+            };
+            session_reminder::set_series_reminder_minutes(series_id, Some(&minutes), &pool).await?;
+        }
+    }
+    let settings = session_reminder::get_series_settings(series_id, &pool).await?;
+    let mut message = match &settings.reminder_minutes {
+        Some(minutes) => format!(
+            "**Session reminders**: {} _(default for {}s: {})_",
+            session_reminder::format_reminder_minutes(minutes),
+            settings.series_type,
+            session_reminder::format_reminder_minutes(&settings.type_reminder_minutes)
+        ),
+        None => format!(
+            "**Session reminders**: {} _(default)_",
+            session_reminder::format_reminder_minutes(&settings.type_reminder_minutes)
+        ),
+    };
+    message += &format!(
+        "\n**Reminders by DM**: {}",
+        if settings.dm { "on" } else { "off" }
+    );
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings() {
+        let setting = SETTING_REGEX.captures("DM on").unwrap();
+        assert_eq!(setting.name("dm").unwrap().as_str(), "DM on");
+        let setting = SETTING_REGEX.captures("24h 1h").unwrap();
+        assert_eq!(setting.name("times").unwrap().as_str(), "24h 1h");
+        assert!(SETTING_REGEX
+            .captures("default")
+            .unwrap()
+            .name("default")
+            .is_some());
+        assert!(SETTING_REGEX.captures("sometimes").is_none());
+    }
+}
@@ -28,9 +28,14 @@ fn schedule_session<'a>(
         return Ok(());
     };
     // Create a new Flow
-    let flow =
-        lib::flow::ScheduleSessionFlow::new(context.async_redis_connection().await?, event_series)
-            .await?;
+    let flow = lib::flow::ScheduleSessionFlow::new(
+        &pool,
+        context.msg.author.id,
+        lib::flow::ScheduleSession {
+            event_series_id: event_series.0,
+        },
+    )
+    .await?;
     let link = format!("{}/schedule_session/{}", lib::urls::BASE_URL, flow.id);
     context
         .msg
@@ -44,6 +49,6 @@ fn schedule_session<'a>(
         )
         .await
         .ok();
-    context.msg.react(&context.ctx, '\u{2705}').await.ok();
+    context.react_success().await;
     Ok(())
 }
@@ -7,6 +7,7 @@ use command_macro::command;
     "snooze `X` days",
     "_(in game channel)_ snoozes reminders for _X_ days"
 )]
+#[option(num_days: Integer, "The number of days to snooze reminders for", name = "days")]
 fn snooze<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -0,0 +1,213 @@
+use command_macro::command;
+use serenity::utils::MessageBuilder;
+
+use crate::discord::spam::{self, SpamActions, SpamRule};
+
+#[command]
+#[regex(r"spam\s+words")]
+#[level(admin)]
+#[help("spam words", "lists the words that trigger a spam alert")]
+fn spam_words<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let spam_filter = spam::reload_spam_filter(context).await?;
+    let message = if spam_filter.words().is_empty() {
+        "The spam word list is empty.".to_string()
+    } else {
+        let mut message = MessageBuilder::new();
+        message.push_bold_line("Spam words:");
+        for word in spam_filter.words() {
+            message.push("- ");
+            message.push_mono_line_safe(word.as_str());
+        }
+        message.build()
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"spam\s+words?\s+add\s+(?P<word>.+)")]
+#[level(admin)]
+#[help("spam word add `word`", "adds a word or phrase to the spam word list")]
+#[option(word: String, "The word or phrase, case is ignored", name = "word")]
+fn add_spam_word<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let word = captures
+        .name("word")
+        .expect("Regex capture does not contain 'word'")
+        .as_str()
+        .trim();
+    if !spam::is_valid_word(word) {
+        let message = format!(
+            "Spam words need at least {} characters, shorter ones would match almost every \
+             message.",
+            spam::MIN_WORD_LENGTH
+        );
+        context.msg.channel_id.say(&context.ctx, message).await.ok();
+        return Ok(());
+    }
+    let pool = context.pool().await?;
+    let added = spam::add_word(word, &pool).await?;
+    spam::reload_spam_filter(context).await?;
+    let message = if added {
+        "Added to the spam word list."
+    } else {
+        "This is already on the spam word list."
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"spam\s+words?\s+remove\s+(?P<word>.+)")]
+#[level(admin)]
+#[help(
+    "spam word remove `word`",
+    "removes a word or phrase from the spam word list"
+)]
+#[option(word: String, "The word or phrase exactly as listed", name = "word")]
+fn remove_spam_word<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let word = captures
+        .name("word")
+        .expect("Regex capture does not contain 'word'")
+        .as_str()
+        .trim();
+    let pool = context.pool().await?;
+    let removed = spam::remove_word(word, &pool).await?;
+    spam::reload_spam_filter(context).await?;
+    let message = if removed {
+        "Removed from the spam word list."
+    } else {
+        "This is not on the spam word list."
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"spam\s+reload")]
+#[level(admin)]
+#[help(
+    "spam reload",
+    "reloads the spam word list and rules after they were changed elsewhere"
+)]
+fn reload_spam<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let spam_filter = spam::reload_spam_filter(context).await?;
+    let message = format!(
+        "Reloaded the spam filter with {} words.",
+        spam_filter.words().len()
+    );
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"spam\s+rules")]
+#[level(admin)]
+#[help("spam rules", "shows when the spam filter triggers and what it does")]
+fn spam_rules<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let spam_filter = spam::reload_spam_filter(context).await?;
+    let message = describe_rules(context, &spam_filter).await?;
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"spam\s+rule\s+(?P<rule>[a-z-]+)\s+(?P<actions>.+)")]
+#[level(admin)]
+#[help(
+    "spam rule `rule` `alert|delete|timeout minutes|quarantine`",
+    "sets what happens when a spam rule triggers, e.g. `spam rule link-flood delete timeout 60`. \
+     The admins are always alerted."
+)]
+#[option(rule: String, "One of the rules listed by \"spam rules\"", name = "rule")]
+#[option(actions: String, "For example \"delete timeout 60\" or \"alert\"", name = "actions")]
+fn set_spam_rule<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let rule = captures
+        .name("rule")
+        .expect("Regex capture does not contain 'rule'")
+        .as_str();
+    let actions = captures
+        .name("actions")
+        .expect("Regex capture does not contain 'actions'")
+        .as_str();
+    let rule = match SpamRule::from_name(rule) {
+        Some(rule) => rule,
+        None => {
+            let rules: Vec<_> = SpamRule::ALL.iter().map(SpamRule::as_str).collect();
+            let message = format!("Unknown rule. The rules are: {}", rules.join(", "));
+            context.msg.channel_id.say(&context.ctx, message).await.ok();
+            return Ok(());
+        }
+    };
+    let actions = match SpamActions::parse(actions) {
+        Some(actions) => actions,
+        None => {
+            context
+                .msg
+                .channel_id
+                .say(
+                    &context.ctx,
+                    "I didn't understand the actions. Use any of `delete`, `timeout` followed by \
+                     the minutes, and `quarantine`, or just `alert`.",
+                )
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    spam::set_rule_actions(rule, actions, &pool).await?;
+    spam::reload_spam_filter(context).await?;
+    let mut message = format!("**{}** now does: {}", rule.as_str(), actions.describe());
+    if actions.quarantine
+        && context
+            .guild_config()
+            .await?
+            .spam_quarantine_role_id
+            .is_none()
+    {
+        message += "\nThere is no spam quarantine role in the guild configuration yet.";
+    }
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
+
+async fn describe_rules(
+    context: &super::CommandContext,
+    spam_filter: &spam::SpamFilter,
+) -> Result<String, lib::meetup::Error> {
+    let mut message = "**Spam rules:**".to_string();
+    for rule in SpamRule::ALL {
+        message += &format!(
+            "\n`{}` when {}: {}",
+            rule.as_str(),
+            rule.description(),
+            spam_filter.actions(rule).describe()
+        );
+    }
+    let guild_config = context.guild_config().await?;
+    if guild_config.bot_alerts_channel_id.is_none() {
+        message += "\nThere is no bot alerts channel, so nobody gets alerted.";
+    }
+    if guild_config.spam_quarantine_role_id.is_none() {
+        message += "\nThere is no spam quarantine role, so nobody gets quarantined.";
+    }
+    Ok(message)
+}
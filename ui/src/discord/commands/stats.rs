@@ -0,0 +1,179 @@
+use std::num::NonZeroU64;
+
+use command_macro::command;
+use lib::stats::{ActivityStats, MonthlyStats};
+use serenity::{
+    all::Mentionable,
+    builder::{CreateAllowedMentions, CreateMessage},
+    model::id::UserId,
+};
+
+fn month_line(label: &str, month: &MonthlyStats) -> String {
+    let mut line = format!(
+        "**{}** ({}): {} sessions ({} online, {} in person), {} players ({} new",
+        label,
+        month.month.format("%B %Y"),
+        month.num_sessions,
+        month.num_online_sessions,
+        month.num_in_person_sessions(),
+        month.num_players,
+        month.num_new_players
+    );
+    if let Some(retention) = month.retention() {
+        line += &format!(", {:.0}% retention", retention * 100.0);
+    }
+    line += &format!("), {} hosts", month.num_hosts);
+    line
+}
+
+#[command]
+#[regex(r"(?:stats|count\s*inactive|count\s*members)")]
+#[level(admin)]
+#[help(
+    "stats",
+    "shows the number of members as well as session, player and host statistics"
+)]
+fn stats<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    // (members, members without any role)
+    let member_counts = context
+        .guild_config()
+        .await?
+        .guild_id
+        .to_guild_cached(&context.ctx)
+        .map(|guild| {
+            let num_inactive = guild
+                .members
+                .iter()
+                .filter(|(_id, member)| member.roles.is_empty())
+                .count();
+            (guild.members.len(), num_inactive)
+        });
+    let pool = context.pool().await?;
+    let stats = ActivityStats::load(lib::stats::DEFAULT_NUM_MONTHS, &pool).await?;
+    let mut message = match member_counts {
+        Some((num_members, num_inactive)) => format!(
+            "There are {} members, {} of them without any role.",
+            num_members, num_inactive
+        ),
+        None => "Could not find the guild to count its members.".to_string(),
+    };
+    let mut months = stats.months.iter().rev();
+    if let Some(month) = months.next() {
+        message += &format!("\n{}", month_line("This month", month));
+    }
+    if let Some(month) = months.next() {
+        message += &format!("\n{}", month_line("Last month", month));
+    }
+    let summary = &stats.summary;
+    message += &format!(
+        "\n**Last {} months**: {} sessions ({} online, {} in person), {} players ({} new), {} \
+         hosts",
+        lib::stats::DEFAULT_NUM_MONTHS,
+        summary.num_sessions,
+        summary.num_online_sessions,
+        summary.num_in_person_sessions(),
+        summary.num_players,
+        summary.num_new_players,
+        summary.num_hosts
+    );
+    if let Some(host) = stats.hosts.first() {
+        message += &format!(
+            "\nBusiest host: {} with {} sessions",
+            host.name, host.num_sessions
+        );
+    }
+    message += &format!("\n<{}>", lib::stats::page_url());
+    context
+        .msg
+        .channel_id
+        .send_message(
+            &context.ctx,
+            CreateMessage::new()
+                .content(message)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await
+        .ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"stats\s+{mention_pattern}", mention_pattern)]
+#[level(admin)]
+#[help(
+    "stats `@some-user`",
+    "shows how often a user showed up to the sessions they signed up for"
+)]
+#[option(mention_id: User, "The user to show the attendance of", name = "user")]
+fn member_stats<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let discord_id = captures.name("mention_id").unwrap().as_str();
+    let discord_id = match discord_id.parse::<NonZeroU64>() {
+        Ok(id) => UserId::from(id),
+        _ => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    let member = lib::db::discord_ids_to_members(&[discord_id], &pool).await?;
+    let member = match member.into_iter().next() {
+        Some((_, Some(member))) => member,
+        _ => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, "I don't know about any games of this user.")
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let stats = lib::attendance::get_member_stats(member.id, &pool).await?;
+    let mut message = format!(
+        "**Attendance of {}**\nSigned up for {} past sessions. Showed up to {} and missed {} of \
+         the marked sessions",
+        discord_id.mention(),
+        stats.num_rsvps,
+        stats.num_attended,
+        stats.num_no_shows
+    );
+    match stats.attendance_rate() {
+        Some(rate) => message += &format!(" ({:.0}% attendance).", rate * 100.0),
+        None => message += ".",
+    }
+    if !stats.recent_no_shows.is_empty() {
+        message += "\n**Recent no-shows:**";
+        for session in &stats.recent_no_shows {
+            message += &format!(
+                "\n- {} (<t:{}:d>)",
+                session.title,
+                session.start_time.timestamp()
+            );
+        }
+    }
+    message += &format!("\n<{}>", lib::attendance::member_stats_url(member.id));
+    // Don't ping the user
+    context
+        .msg
+        .channel_id
+        .send_message(
+            &context.ctx,
+            CreateMessage::new()
+                .content(message)
+                .allowed_mentions(CreateAllowedMentions::new()),
+        )
+        .await
+        .ok();
+    Ok(())
+}
@@ -1,4 +1,5 @@
 use command_macro::command;
+use serenity::builder::{CreateAllowedMentions, CreateAttachment, CreateMessage};
 
 #[command]
 #[regex(r"sync\s*discord")]
@@ -8,21 +9,17 @@ fn sync_discord<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
-    let mut redis_connection = context
-        .redis_client()
-        .await?
-        .get_multiplexed_async_connection()
-        .await?;
     let pool = context.pool().await?;
     let mut discord_api = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
     let bot_id = context.bot_id().await?;
     let swissrpg_client = context.swissrpg_client().await?;
     // Spawn the syncing task
     tokio::spawn(async move {
         lib::discord::sync::sync_discord(
-            &mut redis_connection,
             &pool,
             &mut discord_api,
+            &guild_config,
             bot_id,
             swissrpg_client.base_url(),
         )
@@ -36,3 +33,104 @@ fn sync_discord<'a>(
         .ok();
     Ok(())
 }
+
+#[command]
+#[regex(r"sync\s*channel")]
+#[level(host)]
+#[help(
+    "sync channel",
+    "_(in game channel)_ synchronizes only this channel's roles and permissions with the database"
+)]
+fn sync_channel<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let series_id =
+        lib::get_channel_series(context.msg.channel_id, &mut pool.begin().await?).await?;
+    let series_id = if let Some(series_id) = series_id {
+        series_id
+    } else {
+        context
+            .msg
+            .channel_id
+            .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .await
+            .ok();
+        return Ok(());
+    };
+    context.spawn_series_sync(series_id).await?;
+    context
+        .msg
+        .channel_id
+        .say(&context.ctx, "Started synchronizing this channel")
+        .await
+        .ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"sync\s*discord\s+dry[\s-]?run(?:\s+(?P<format>json))?")]
+#[level(admin)]
+#[help(
+    "sync discord dry-run `[json]`",
+    "Shows what a Discord synchronization would change without changing anything"
+)]
+#[option(format: String, "Set to \"json\" to get the plan as a JSON file", name = "format", optional)]
+fn sync_discord_dry_run<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let as_json = captures
+        .name("format")
+        .is_some_and(|format| format.as_str().eq_ignore_ascii_case("json"));
+    let pool = context.pool().await?;
+    let discord_api = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
+    let bot_id = context.bot_id().await?;
+    let swissrpg_client = context.swissrpg_client().await?;
+    let plan = lib::discord::sync_plan::plan_sync_discord(
+        &pool,
+        &discord_api,
+        &guild_config,
+        bot_id,
+        swissrpg_client.base_url(),
+    )
+    .await?;
+    // The plan mentions users and roles, which shouldn't get pinged
+    let mut message = CreateMessage::new().allowed_mentions(CreateAllowedMentions::new());
+    if as_json {
+        message = message
+            .content(format!(
+                "A sync would make {} change(s)",
+                plan.num_actions()
+            ))
+            .add_file(CreateAttachment::bytes(
+                plan.to_json()?.into_bytes(),
+                "sync-plan.json",
+            ));
+    } else {
+        let summary = plan.summary();
+        // Discord messages are limited to 2000 characters
+        if summary.chars().count() <= 2000 {
+            message = message.content(summary);
+        } else {
+            message = message
+                .content(format!(
+                    "A sync would make {} change(s), see the attached plan",
+                    plan.num_actions()
+                ))
+                .add_file(CreateAttachment::bytes(
+                    summary.into_bytes(),
+                    "sync-plan.md",
+                ));
+        }
+    }
+    context
+        .msg
+        .channel_id
+        .send_message(&context.ctx, message)
+        .await
+        .ok();
+    Ok(())
+}
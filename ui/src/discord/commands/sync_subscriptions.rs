@@ -8,10 +8,18 @@ fn sync_subscriptions<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
     let stripe_client = context.stripe_client().await?;
     let discord_api = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
     tokio::spawn(async move {
-        lib::tasks::subscription_roles::update_roles(&discord_api, &stripe_client).await
+        lib::tasks::subscription_roles::update_roles(
+            &pool,
+            &discord_api,
+            &stripe_client,
+            &guild_config,
+        )
+        .await
     });
     let _ = context.msg.channel_id.say(&context.ctx, "Copy that");
     Ok(())
@@ -1,5 +1,4 @@
 use command_macro::command;
-use redis::{AsyncCommands, RedisResult};
 use serenity::{builder::EditChannel, model::channel::Channel};
 use std::time::Duration;
 
@@ -9,6 +8,8 @@ use std::time::Duration;
     "topic `some topic`",
     "renames the user topic voice channel to the specified topic"
 )]
+#[slash(name = "topic")]
+#[option(topic: String, "The new topic of the voice channel")]
 fn set_voice_topic<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -16,16 +17,17 @@ fn set_voice_topic<'a>(
     // Indicate that something is happening
     let _typing_indicator = context.msg.channel_id.start_typing(&context.ctx.http);
     // Check if there is a user topic voice channel
-    let voice_channel_id = if let Some(id) = lib::discord::sync::ids::USER_TOPIC_VOICE_CHANNEL_ID {
-        id
-    } else {
-        context
-            .msg
-            .channel_id
-            .say(&context.ctx, "No voice channel has been configured")
-            .await?;
-        return Ok(());
-    };
+    let voice_channel_id =
+        if let Some(id) = context.guild_config().await?.user_topic_voice_channel_id {
+            id
+        } else {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, "No voice channel has been configured")
+                .await?;
+            return Ok(());
+        };
     // Check whether the specified topic is ok
     let topic = if let Some(topic) = captures.name("topic") {
         topic.as_str()
@@ -125,14 +127,11 @@ fn set_voice_topic<'a>(
         }
         Ok(Ok(_)) => (),
     }
-    // Try to store the renaming time in Redis
-    if let Ok(con) = context.async_redis_connection().await {
-        let _: RedisResult<()> = con
-            .set(
-                "user_topic_voice_channel_topic_time",
-                chrono::Utc::now().to_rfc3339(),
-            )
-            .await;
+    // Try to store the renaming time
+    if let Ok(pool) = context.pool().await {
+        lib::tasks::user_topic_voice_channel::set_topic_time(&pool, chrono::Utc::now())
+            .await
+            .ok();
     }
     context
         .msg
@@ -0,0 +1,62 @@
+use command_macro::command;
+use lib::discord::archive::UnarchiveResult;
+use serenity::model::id::ChannelId;
+
+#[command]
+#[regex(r"unarchive(?:\s+(?P<channel><#[0-9]+>|[0-9]+))?")]
+#[level(admin)]
+#[help(
+    "unarchive `#channel`",
+    "moves an archived game channel back to its category and lets its players write again, \
+     e.g. when a campaign resumes"
+)]
+#[option(channel: String, "The archived channel, if not used in it", name = "channel", optional)]
+fn unarchive<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let channel_id = match captures.name("channel") {
+        Some(channel) => {
+            let channel_id = channel
+                .as_str()
+                .trim()
+                .trim_start_matches("<#")
+                .trim_end_matches('>')
+                .parse::<u64>()
+                .ok()
+                .filter(|&id| id != 0);
+            match channel_id {
+                Some(channel_id) => ChannelId::new(channel_id),
+                None => {
+                    context
+                        .msg
+                        .channel_id
+                        .say(&context.ctx, "That doesn't look like a channel to me")
+                        .await
+                        .ok();
+                    return Ok(());
+                }
+            }
+        }
+        None => context.msg.channel_id,
+    };
+    let pool = context.pool().await?;
+    let guild_config = context.guild_config().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let result =
+        lib::discord::archive::unarchive_channel(channel_id, &pool, &discord_api, &guild_config)
+            .await?;
+    let message = match result {
+        UnarchiveResult::NotAGameChannel => lib::strings::CHANNEL_NOT_BOT_CONTROLLED,
+        UnarchiveResult::NotArchived => "This channel is not archived",
+        UnarchiveResult::AlreadyDeleted => {
+            "This channel has already been deleted, it can't be unarchived anymore"
+        }
+        UnarchiveResult::Unarchived => {
+            "Unarchived the channel. Its players get access again with the next Discord sync once \
+             there is an upcoming session."
+        }
+    };
+    context.msg.channel_id.say(&context.ctx, message).await.ok();
+    Ok(())
+}
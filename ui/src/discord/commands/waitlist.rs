@@ -0,0 +1,57 @@
+use command_macro::command;
+use lib::discord::waitlist;
+
+#[command]
+#[regex(r"waitlist(?:\s+(?P<action>join|leave))?")]
+#[help(
+    "waitlist `[leave]`",
+    "_(in game channel)_ puts you on the waitlist for the next session if it is full, or takes you off it again."
+)]
+#[option(action: String, "Set to \"leave\" to leave the waitlist", name = "action", optional)]
+fn waitlist<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let leave = captures
+        .name("action")
+        .is_some_and(|action| action.as_str().eq_ignore_ascii_case("leave"));
+    let pool = context.pool().await?;
+    if !context.is_game_channel(None).await? {
+        context
+            .msg
+            .channel_id
+            .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .await
+            .ok();
+        return Ok(());
+    }
+    let event_id = match waitlist::get_channel_next_event(context.msg.channel_id, &pool).await? {
+        Some(event_id) => event_id,
+        None => {
+            context
+                .msg
+                .channel_id
+                .say(&context.ctx, lib::strings::WAITLIST_NO_UPCOMING_SESSION)
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let user_id = context.msg.author.id;
+    let result = if leave {
+        let result = waitlist::leave_waitlist(event_id, user_id, &pool).await?;
+        // In case a seat had been offered to the player
+        let discord_api = (&context.ctx).into();
+        waitlist::offer_free_seats(event_id, &pool, &discord_api).await?;
+        result
+    } else {
+        waitlist::join_waitlist(event_id, user_id, &pool).await?
+    };
+    context
+        .msg
+        .channel_id
+        .say(&context.ctx, result.message())
+        .await
+        .ok();
+    Ok(())
+}
@@ -25,6 +25,9 @@ use serenity::{all::Mentionable, model::id::UserId};
     "whois meetup `meetup-ID`",
     "shows the Discord user linked to the provided Meetup profile"
 )]
+#[option(mention_id: User, "The Discord user to look up", name = "user", optional)]
+#[option(discord_username: String, "The Discord username to look up", name = "username", optional)]
+#[option(meetup_user_id: Integer, "The Meetup ID to look up", name = "meetup-id", optional)]
 fn whois<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -128,7 +131,10 @@ async fn whois_by_discord_username_tag(
     context: &mut super::CommandContext,
     username_tag: &str,
 ) -> Result<(), lib::meetup::Error> {
-    let discord_id = lib::discord::sync::ids::GUILD_ID
+    let discord_id = context
+        .guild_config()
+        .await?
+        .guild_id
         .to_guild_cached(&context.ctx)
         .map(|guild| guild.member_named(username_tag).map(|m| m.user.id));
     let discord_id = if let Some(discord_id) = discord_id {
@@ -1,19 +1,408 @@
+// Spam detection for messages outside of game channels. Every message is
+// checked against the word list and the author's recent messages for link
+// floods, repeated messages across channels, invite links from new accounts
+// and mass mentions. Depending on the rule that triggered, the bot deletes
+// the messages, times the author out or quarantines them, and alerts the
+// admins with buttons to ban the author or dismiss the alert.
+
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder};
-use redis::AsyncCommands;
-use serenity::{model::id::ChannelId, prelude::*};
-use std::sync::Arc;
+use lib::{
+    audit::{AuditEntry, AuditSource, Auditor},
+    discord::guild_config::GuildConfig,
+    tasks::subscription_roles,
+};
+use serenity::{
+    builder::{
+        CreateActionRow, CreateAllowedMentions, CreateButton, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, EditMember,
+    },
+    model::{
+        application::{ButtonStyle, ComponentInteraction},
+        id::{ChannelId, MessageId, UserId},
+        Timestamp,
+    },
+    prelude::*,
+};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Messages are compared with the author's messages from this time window
+const TRACKING_WINDOW: Duration = Duration::from_secs(2 * 60);
+// More links than this in a single message or within the tracking window
+// are a link flood
+const MAX_LINKS_PER_MESSAGE: usize = 5;
+const MAX_LINKS_IN_WINDOW: usize = 10;
+// Posting the same message in this many channels within the tracking window
+const REPEATED_MESSAGE_CHANNELS: usize = 3;
+// Shorter messages like "hi" or "thanks" are not compared
+const MIN_REPEATED_MESSAGE_LENGTH: usize = 10;
+// Mentioning more users and roles than this in a single message
+const MAX_MENTIONS: usize = 5;
+// Invite links are only allowed from accounts and members older than this
+const NEW_ACCOUNT_DAYS: i64 = 7;
+const NEW_MEMBER_HOURS: i64 = 24;
+// Discord doesn't allow longer timeouts
+const MAX_TIMEOUT_MINUTES: i32 = 28 * 24 * 60;
+// Users without recent messages are forgotten once there are this many
+const MAX_TRACKED_USERS: usize = 1000;
+// Keeps the quoted message in the alert below Discord's length limit
+const MAX_QUOTED_LENGTH: usize = 1000;
+// Shorter spam words would match within almost every message
+pub const MIN_WORD_LENGTH: usize = 3;
+
+const CUSTOM_ID_PREFIX: &str = "spam:";
+
+lazy_static::lazy_static! {
+    static ref LINK_REGEX: regex::Regex = regex::Regex::new(r"(?i)\bhttps?://[^\s<>]+").unwrap();
+    static ref INVITE_REGEX: regex::Regex =
+        regex::Regex::new(r"(?i)\b(?:discord\.gg|discord(?:app)?\.com/invite)/[a-z0-9-]+").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SpamRule {
+    WordList,
+    LinkFlood,
+    RepeatedMessage,
+    NewAccountInvite,
+    MassMention,
+}
+
+impl SpamRule {
+    pub const ALL: [SpamRule; 5] = [
+        SpamRule::WordList,
+        SpamRule::LinkFlood,
+        SpamRule::RepeatedMessage,
+        SpamRule::NewAccountInvite,
+        SpamRule::MassMention,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpamRule::WordList => "word-list",
+            SpamRule::LinkFlood => "link-flood",
+            SpamRule::RepeatedMessage => "repeated-message",
+            SpamRule::NewAccountInvite => "new-account-invite",
+            SpamRule::MassMention => "mass-mention",
+        }
+    }
+
+    pub fn from_name(rule: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == rule)
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            SpamRule::WordList => "a message contains a word from the spam word list".to_string(),
+            SpamRule::LinkFlood => format!(
+                "more than {} links in one message or {} links within {} minutes",
+                MAX_LINKS_PER_MESSAGE,
+                MAX_LINKS_IN_WINDOW,
+                TRACKING_WINDOW.as_secs() / 60
+            ),
+            SpamRule::RepeatedMessage => format!(
+                "the same message in {} channels within {} minutes",
+                REPEATED_MESSAGE_CHANNELS,
+                TRACKING_WINDOW.as_secs() / 60
+            ),
+            SpamRule::NewAccountInvite => format!(
+                "an invite link from an account younger than {} days or a member who joined less \
+                 than {} hours ago",
+                NEW_ACCOUNT_DAYS, NEW_MEMBER_HOURS
+            ),
+            SpamRule::MassMention => format!(
+                "more than {} mentions in one message or a mention of everyone",
+                MAX_MENTIONS
+            ),
+        }
+    }
+}
+
+// What happens when a rule triggers, in addition to the alert
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpamActions {
+    pub delete_messages: bool,
+    pub timeout_minutes: Option<i32>,
+    pub quarantine: bool,
+}
+
+impl SpamActions {
+    // Parses a list like "delete timeout 60 quarantine". "alert" on its own
+    // stands for no actions besides the alert.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut actions = SpamActions::default();
+        let mut words = text.split(|c: char| c.is_whitespace() || c == ',');
+        let mut any_word = false;
+        while let Some(word) = words.next() {
+            match word.to_lowercase().as_str() {
+                "" => continue,
+                "alert" => (),
+                "delete" => actions.delete_messages = true,
+                "quarantine" => actions.quarantine = true,
+                "timeout" => {
+                    let minutes = words.find(|word| !word.is_empty())?.parse::<i32>().ok()?;
+                    if minutes <= 0 || minutes > MAX_TIMEOUT_MINUTES {
+                        return None;
+                    }
+                    actions.timeout_minutes = Some(minutes);
+                }
+                _ => return None,
+            }
+            any_word = true;
+        }
+        if any_word {
+            Some(actions)
+        } else {
+            None
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut parts = vec![];
+        if self.delete_messages {
+            parts.push("delete".to_string());
+        }
+        if let Some(minutes) = self.timeout_minutes {
+            parts.push(format!("timeout {}", minutes));
+        }
+        if self.quarantine {
+            parts.push("quarantine".to_string());
+        }
+        parts.push("alert".to_string());
+        parts.join(", ")
+    }
+}
+
+// The word list and the actions of every rule
+pub struct SpamFilter {
+    words: Vec<String>,
+    matcher: AhoCorasick,
+    actions: HashMap<SpamRule, SpamActions>,
+}
+
+impl SpamFilter {
+    async fn load(db_connection: &sqlx::PgPool) -> Result<Self, lib::meetup::Error> {
+        let words = sqlx::query_scalar!(r#"SELECT word FROM spam_word ORDER BY added, word"#)
+            .fetch_all(db_connection)
+            .await?;
+        let matcher = AhoCorasickBuilder::new()
+            .ascii_case_insensitive(true)
+            .build(&words)
+            .map_err(|err| {
+                simple_error::SimpleError::new(format!(
+                    "Failed to build the aho-corasick matcher: {}",
+                    err
+                ))
+            })?;
+        let actions = sqlx::query!(
+            r#"SELECT "rule", delete_messages, timeout_minutes, quarantine FROM spam_rule"#
+        )
+        .fetch_all(db_connection)
+        .await?
+        .into_iter()
+        .filter_map(|row| {
+            let rule = SpamRule::from_name(&row.rule)?;
+            let actions = SpamActions {
+                delete_messages: row.delete_messages,
+                timeout_minutes: row.timeout_minutes,
+                quarantine: row.quarantine,
+            };
+            Some((rule, actions))
+        })
+        .collect();
+        Ok(SpamFilter {
+            words,
+            matcher,
+            actions,
+        })
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    // Rules without a database entry only alert
+    pub fn actions(&self, rule: SpamRule) -> SpamActions {
+        self.actions.get(&rule).copied().unwrap_or_default()
+    }
+}
+
+// Words are unique regardless of their case
+pub fn is_valid_word(word: &str) -> bool {
+    word.trim().chars().count() >= MIN_WORD_LENGTH
+}
+
+pub async fn add_word(
+    word: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, lib::meetup::Error> {
+    let num_added = sqlx::query!(
+        r#"INSERT INTO spam_word (word) VALUES ($1) ON CONFLICT DO NOTHING"#,
+        word
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected();
+    Ok(num_added > 0)
+}
+
+pub async fn remove_word(
+    word: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, lib::meetup::Error> {
+    let num_removed = sqlx::query!(
+        r#"DELETE FROM spam_word WHERE lower(word) = lower($1)"#,
+        word
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected();
+    Ok(num_removed > 0)
+}
+
+pub async fn set_rule_actions(
+    rule: SpamRule,
+    actions: SpamActions,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), lib::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO spam_rule ("rule", delete_messages, timeout_minutes, quarantine)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT ("rule") DO UPDATE
+        SET delete_messages = EXCLUDED.delete_messages, timeout_minutes = EXCLUDED.timeout_minutes, quarantine = EXCLUDED.quarantine"#,
+        rule.as_str(),
+        actions.delete_messages,
+        actions.timeout_minutes,
+        actions.quarantine
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+struct TrackedMessage {
+    time: Instant,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    // Lowercase with collapsed whitespace
+    content: String,
+    num_links: usize,
+}
+
+impl TrackedMessage {
+    fn new(time: Instant, channel_id: ChannelId, message_id: MessageId, content: &str) -> Self {
+        TrackedMessage {
+            time,
+            channel_id,
+            message_id,
+            content: content
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .to_lowercase(),
+            num_links: LINK_REGEX.find_iter(content).count(),
+        }
+    }
+}
+
+struct Detection {
+    rule: SpamRule,
+    trigger: String,
+    // The messages that get deleted if the rule deletes
+    messages: Vec<(ChannelId, MessageId)>,
+}
+
+// Every user's messages from the tracking window
+#[derive(Default)]
+struct MessageTracker {
+    messages: HashMap<UserId, VecDeque<TrackedMessage>>,
+}
+
+impl MessageTracker {
+    // Remembers the message and checks the author's recent messages for link
+    // floods and repeated messages
+    fn track(&mut self, user_id: UserId, message: TrackedMessage) -> Option<Detection> {
+        let now = message.time;
+        let is_recent =
+            |message: &TrackedMessage| now.duration_since(message.time) < TRACKING_WINDOW;
+        if self.messages.len() > MAX_TRACKED_USERS {
+            self.messages
+                .retain(|_, messages| messages.back().is_some_and(is_recent));
+        }
+        let recent = self.messages.entry(user_id).or_default();
+        while recent.front().is_some_and(|message| !is_recent(message)) {
+            recent.pop_front();
+        }
+        recent.push_back(message);
+        let latest = recent.back()?;
+        if latest.num_links > MAX_LINKS_PER_MESSAGE {
+            return Some(Detection {
+                rule: SpamRule::LinkFlood,
+                trigger: format!("{} links in one message", latest.num_links),
+                messages: vec![(latest.channel_id, latest.message_id)],
+            });
+        }
+        let num_links: usize = recent.iter().map(|message| message.num_links).sum();
+        if num_links > MAX_LINKS_IN_WINDOW {
+            return Some(Detection {
+                rule: SpamRule::LinkFlood,
+                trigger: format!(
+                    "{} links within {} minutes",
+                    num_links,
+                    TRACKING_WINDOW.as_secs() / 60
+                ),
+                messages: recent
+                    .iter()
+                    .filter(|message| message.num_links > 0)
+                    .map(|message| (message.channel_id, message.message_id))
+                    .collect(),
+            });
+        }
+        if latest.content.chars().count() >= MIN_REPEATED_MESSAGE_LENGTH {
+            let copies: Vec<_> = recent
+                .iter()
+                .filter(|message| message.content == latest.content)
+                .collect();
+            let channels: HashSet<_> = copies.iter().map(|message| message.channel_id).collect();
+            if channels.len() >= REPEATED_MESSAGE_CHANNELS {
+                return Some(Detection {
+                    rule: SpamRule::RepeatedMessage,
+                    trigger: format!("the same message in {} channels", channels.len()),
+                    messages: copies
+                        .iter()
+                        .map(|message| (message.channel_id, message.message_id))
+                        .collect(),
+                });
+            }
+        }
+        None
+    }
+
+    // Once a user was caught, their earlier messages should not trigger again
+    fn forget(&mut self, user_id: UserId) {
+        self.messages.remove(&user_id);
+    }
+}
+
+struct SpamFilterKey;
+impl TypeMapKey for SpamFilterKey {
+    type Value = Arc<SpamFilter>;
+}
+
+struct MessageTrackerKey;
+impl TypeMapKey for MessageTrackerKey {
+    type Value = Arc<Mutex<MessageTracker>>;
+}
 
-type SpamList = Arc<(Vec<String>, AhoCorasick)>;
 struct GameChannelsList {
     channel_ids: Vec<ChannelId>,
     last_updated: std::time::Instant,
 }
 
-struct SpamListKey;
-impl TypeMapKey for SpamListKey {
-    type Value = SpamList;
-}
-
 struct GameChannelsListKey;
 impl TypeMapKey for GameChannelsListKey {
     type Value = Arc<GameChannelsList>;
@@ -22,66 +411,429 @@ impl TypeMapKey for GameChannelsListKey {
 pub async fn message_hook(
     cmdctx: &mut super::commands::CommandContext,
 ) -> Result<(), lib::meetup::Error> {
-    let alert_channel_id = if let Some(channel_id) = lib::discord::sync::ids::BOT_ALERTS_CHANNEL_ID
-    {
-        channel_id
-    } else {
+    let guild_config = cmdctx.guild_config().await?;
+    // Bots and the people running the server are trusted
+    if cmdctx.msg.author.bot {
         return Ok(());
-    };
+    }
+    if let Some(member) = &cmdctx.msg.member {
+        let trusted_roles = [
+            Some(guild_config.bot_admin_role_id),
+            Some(guild_config.organiser_role_id),
+            guild_config.admin_role_id,
+        ];
+        if member
+            .roles
+            .iter()
+            .any(|role| trusted_roles.contains(&Some(*role)))
+        {
+            return Ok(());
+        }
+    }
     // Ignore messages from game channels
     let game_channels = get_game_channels_list(cmdctx).await?;
     if game_channels.channel_ids.contains(&cmdctx.msg.channel_id) {
         return Ok(());
     }
-    let spam_list = get_spam_list(cmdctx).await?;
-    let (word_list, spam_matcher) = &*spam_list;
-    if let Some(mat) = spam_matcher.find(&cmdctx.msg.content) {
-        let word = &word_list[mat.pattern()];
-        let mut msg = serenity::utils::MessageBuilder::new();
-        msg.push_bold("Spam Alert ");
-        if let Some(admin_role_id) = lib::discord::sync::ids::ADMIN_ROLE_ID {
-            msg.mention(&admin_role_id);
-        }
-        msg.push("\nTrigger: ");
-        msg.push_line_safe(word);
-        msg.push("User: ");
-        msg.mention(&cmdctx.msg.author.id);
-        msg.push("\nMessage: ");
-        msg.push_line_safe(&cmdctx.msg.content);
-        msg.push(format!(
+    let spam_filter = get_spam_filter(cmdctx).await?;
+    let tracker = get_message_tracker(cmdctx).await;
+    let msg = &cmdctx.msg;
+    let tracked = TrackedMessage::new(Instant::now(), msg.channel_id, msg.id, &msg.content);
+    let tracked_detection = tracker
+        .lock()
+        .expect("Spam tracker lock poisoned")
+        .track(msg.author.id, tracked);
+    let this_message = vec![(msg.channel_id, msg.id)];
+    let detection = if let Some(mat) = spam_filter.matcher.find(&msg.content) {
+        Some(Detection {
+            rule: SpamRule::WordList,
+            trigger: spam_filter.words[mat.pattern()].clone(),
+            messages: this_message,
+        })
+    } else if let Some(invite) = INVITE_REGEX
+        .find(&msg.content)
+        .filter(|_| is_new_member(msg))
+    {
+        Some(Detection {
+            rule: SpamRule::NewAccountInvite,
+            trigger: invite.as_str().to_string(),
+            messages: this_message,
+        })
+    } else if msg.mention_everyone || msg.mentions.len() + msg.mention_roles.len() > MAX_MENTIONS {
+        Some(Detection {
+            rule: SpamRule::MassMention,
+            trigger: if msg.mention_everyone {
+                "a mention of everyone".to_string()
+            } else {
+                format!("{} mentions", msg.mentions.len() + msg.mention_roles.len())
+            },
+            messages: this_message,
+        })
+    } else {
+        tracked_detection
+    };
+    let detection = match detection {
+        Some(detection) => detection,
+        None => return Ok(()),
+    };
+    tracker
+        .lock()
+        .expect("Spam tracker lock poisoned")
+        .forget(msg.author.id);
+    let actions = spam_filter.actions(detection.rule);
+    handle_detection(cmdctx, &guild_config, detection, actions).await
+}
+
+// Accounts that were created or joined the server just now
+fn is_new_member(msg: &serenity::model::channel::Message) -> bool {
+    let now = Timestamp::now().unix_timestamp();
+    let account_age = now - msg.author.id.created_at().unix_timestamp();
+    let member_age = msg
+        .member
+        .as_ref()
+        .and_then(|member| member.joined_at)
+        .map(|joined_at| now - joined_at.unix_timestamp());
+    account_age < NEW_ACCOUNT_DAYS * 24 * 60 * 60
+        || member_age.is_some_and(|age| age < NEW_MEMBER_HOURS * 60 * 60)
+}
+
+async fn handle_detection(
+    cmdctx: &mut super::commands::CommandContext,
+    guild_config: &GuildConfig,
+    detection: Detection,
+    actions: SpamActions,
+) -> Result<(), lib::meetup::Error> {
+    let pool = cmdctx.pool().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&cmdctx.ctx).into();
+    let ctx = &cmdctx.ctx;
+    let msg = &cmdctx.msg;
+    let user_id = msg.author.id;
+    let auditor = Auditor::new(
+        AuditSource::Task("spam filter"),
+        &pool,
+        &discord_api,
+        guild_config,
+    );
+    // Descriptions of what was done for the alert
+    let mut taken_actions = vec![];
+    if actions.delete_messages {
+        let mut num_deleted = 0;
+        for &(channel_id, message_id) in &detection.messages {
+            match channel_id.delete_message(ctx, message_id).await {
+                Ok(()) => num_deleted += 1,
+                Err(err) => eprintln!("Could not delete spam message:\n{:#?}", err),
+            }
+        }
+        taken_actions.push(format!(
+            "deleted {} of {} messages",
+            num_deleted,
+            detection.messages.len()
+        ));
+    }
+    if let Some(minutes) = actions.timeout_minutes {
+        let until = Timestamp::now().unix_timestamp() + i64::from(minutes) * 60;
+        let until = Timestamp::from_unix_timestamp(until).unwrap_or_default();
+        let edit = EditMember::new()
+            .disable_communication_until_datetime(until)
+            .audit_log_reason("Spam");
+        let audit_entry = AuditEntry::member_timed_out(user_id, minutes);
+        match guild_config.guild_id.edit_member(ctx, user_id, edit).await {
+            Ok(_) => {
+                auditor.record(audit_entry).await;
+                taken_actions.push(format!("timed out for {} minutes", minutes));
+            }
+            Err(err) => {
+                eprintln!("Could not time out spammer {}:\n{:#?}", user_id, err);
+                auditor.record(audit_entry.failed(&err)).await;
+                taken_actions.push("**could not time out**".to_string());
+            }
+        }
+    }
+    if actions.quarantine {
+        match guild_config.spam_quarantine_role_id {
+            Some(role_id) => {
+                let res = subscription_roles::add_member_role(
+                    &discord_api,
+                    guild_config.guild_id,
+                    user_id,
+                    role_id,
+                    Some("Spam"),
+                    &auditor,
+                )
+                .await;
+                match res {
+                    Ok(()) => taken_actions.push("quarantined".to_string()),
+                    Err(_) => taken_actions.push("**could not quarantine**".to_string()),
+                }
+            }
+            None => taken_actions.push("**no quarantine role configured**".to_string()),
+        }
+    }
+    let alert_channel_id = if let Some(channel_id) = guild_config.bot_alerts_channel_id {
+        channel_id
+    } else {
+        return Ok(());
+    };
+    let mut quoted: String = msg.content.chars().take(MAX_QUOTED_LENGTH).collect();
+    if quoted.len() < msg.content.len() {
+        quoted.push('…');
+    }
+    let mut alert = serenity::utils::MessageBuilder::new();
+    alert.push_bold("Spam Alert ");
+    if let Some(admin_role_id) = guild_config.admin_role_id {
+        alert.mention(&admin_role_id);
+    }
+    alert.push("\nRule: ");
+    alert.push_safe(detection.rule.as_str());
+    alert.push(" (");
+    alert.push_safe(detection.trigger.as_str());
+    alert.push_line(")");
+    alert.push("User: ");
+    alert.mention(&user_id);
+    alert.push("\nMessage: ");
+    alert.push_line_safe(quoted);
+    if !actions.delete_messages {
+        alert.push_line(format!(
             "https://discordapp.com/channels/{guild_id}/{channel_id}/{message_id}",
-            guild_id = lib::discord::sync::ids::GUILD_ID.get(),
-            channel_id = cmdctx.msg.channel_id.get(),
-            message_id = cmdctx.msg.id.get()
+            guild_id = guild_config.guild_id.get(),
+            channel_id = msg.channel_id.get(),
+            message_id = msg.id.get()
         ));
-        alert_channel_id.say(&cmdctx.ctx, msg.build()).await?;
+    }
+    if taken_actions.is_empty() {
+        alert.push("Actions: none");
+    } else {
+        alert.push(format!("Actions: {}", taken_actions.join(", ")));
+    }
+    let buttons = CreateActionRow::Buttons(vec![
+        CreateButton::new(custom_id(AlertAction::Ban, user_id))
+            .label("Ban")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(custom_id(AlertAction::FalsePositive, user_id))
+            .label("False positive")
+            .style(ButtonStyle::Secondary),
+    ]);
+    alert_channel_id
+        .send_message(
+            ctx,
+            CreateMessage::new()
+                .content(alert.build())
+                .components(vec![buttons]),
+        )
+        .await?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertAction {
+    Ban,
+    // Lifts the timeout and the quarantine
+    FalsePositive,
+}
+
+// The buttons on spam alerts carry the action and the user in their custom
+// ID, e.g. "spam:ban:1234" or "spam:ok:1234"
+fn custom_id(action: AlertAction, user_id: UserId) -> String {
+    let action = match action {
+        AlertAction::Ban => "ban",
+        AlertAction::FalsePositive => "ok",
+    };
+    format!("{}{}:{}", CUSTOM_ID_PREFIX, action, user_id.get())
+}
+
+pub fn parse_custom_id(custom_id: &str) -> Option<(AlertAction, UserId)> {
+    let (action, user_id) = custom_id.strip_prefix(CUSTOM_ID_PREFIX)?.split_once(':')?;
+    let action = match action {
+        "ban" => AlertAction::Ban,
+        "ok" => AlertAction::FalsePositive,
+        _ => return None,
+    };
+    let user_id = user_id.parse::<u64>().ok().filter(|&id| id != 0)?;
+    Some((action, UserId::new(user_id)))
+}
+
+// The "ban" and "false positive" buttons on spam alerts. Only admins can use
+// them.
+pub async fn handle_alert_button(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+    guild_config: &GuildConfig,
+    pool: &sqlx::PgPool,
+) {
+    let (action, user_id) = match parse_custom_id(&interaction.data.custom_id) {
+        Some(alert) => alert,
+        None => return,
+    };
+    let is_admin = interaction.member.as_ref().is_some_and(|member| {
+        member.roles.contains(&guild_config.bot_admin_role_id)
+            || guild_config
+                .admin_role_id
+                .is_some_and(|role_id| member.roles.contains(&role_id))
+    });
+    if !is_admin {
+        let response = CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("Only admins can handle spam alerts.")
+                .ephemeral(true),
+        );
+        interaction.create_response(ctx, response).await.ok();
+        return;
+    }
+    let admin_id = interaction.user.id;
+    let res = match action {
+        AlertAction::Ban => ban(ctx, user_id, admin_id, guild_config, pool)
+            .await
+            .map(|_| format!("**Banned** by <@{}>", admin_id.get())),
+        AlertAction::FalsePositive => lift_sanctions(ctx, user_id, admin_id, guild_config, pool)
+            .await
+            .map(|_| format!("**False positive** according to <@{}>", admin_id.get())),
+    };
+    let response = match res {
+        Ok(resolution) => CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(format!("{}\n{}", interaction.message.content, resolution))
+                .components(vec![])
+                .allowed_mentions(CreateAllowedMentions::new()),
+        ),
+        Err(err) => {
+            eprintln!("Error in spam alert button handler:\n{:#?}", err);
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(lib::strings::UNSPECIFIED_ERROR)
+                    .ephemeral(true),
+            )
+        }
+    };
+    if let Err(err) = interaction.create_response(ctx, response).await {
+        eprintln!("Could not respond to spam alert button click:\n{:#?}", err);
+    }
+}
+
+async fn ban(
+    ctx: &Context,
+    user_id: UserId,
+    admin_id: UserId,
+    guild_config: &GuildConfig,
+    pool: &sqlx::PgPool,
+) -> Result<(), lib::meetup::Error> {
+    let discord_api: lib::discord::CacheAndHttp = ctx.into();
+    let auditor = Auditor::new(
+        AuditSource::Command {
+            actor: admin_id,
+            command: "spam ban",
+        },
+        pool,
+        &discord_api,
+        guild_config,
+    );
+    let audit_entry = AuditEntry::member_banned(user_id);
+    match guild_config
+        .guild_id
+        .ban_with_reason(ctx, user_id, 1, "Spam")
+        .await
+    {
+        Ok(()) => {
+            auditor.record(audit_entry).await;
+            Ok(())
+        }
+        Err(err) => {
+            auditor.record(audit_entry.failed(&err)).await;
+            Err(err.into())
+        }
+    }
+}
+
+// Ends the timeout and removes the quarantine role, if any
+async fn lift_sanctions(
+    ctx: &Context,
+    user_id: UserId,
+    admin_id: UserId,
+    guild_config: &GuildConfig,
+    pool: &sqlx::PgPool,
+) -> Result<(), lib::meetup::Error> {
+    let member = match guild_config.guild_id.member(ctx, user_id).await {
+        Ok(member) => member,
+        // The user already left
+        Err(_) => return Ok(()),
+    };
+    let discord_api: lib::discord::CacheAndHttp = ctx.into();
+    let auditor = Auditor::new(
+        AuditSource::Command {
+            actor: admin_id,
+            command: "spam false positive",
+        },
+        pool,
+        &discord_api,
+        guild_config,
+    );
+    if member.communication_disabled_until.is_some() {
+        let edit = EditMember::new()
+            .enable_communication()
+            .audit_log_reason("False spam alert");
+        let audit_entry = AuditEntry::time_out_ended(user_id);
+        match guild_config.guild_id.edit_member(ctx, user_id, edit).await {
+            Ok(_) => auditor.record(audit_entry).await,
+            Err(err) => {
+                auditor.record(audit_entry.failed(&err)).await;
+                return Err(err.into());
+            }
+        }
+    }
+    if let Some(role_id) = guild_config.spam_quarantine_role_id {
+        if member.roles.contains(&role_id) {
+            subscription_roles::remove_member_role(
+                &discord_api,
+                guild_config.guild_id,
+                user_id,
+                role_id,
+                Some("False spam alert"),
+                &auditor,
+            )
+            .await?;
+        }
     }
     Ok(())
 }
 
-async fn get_spam_list(
+async fn get_spam_filter(
+    cmdctx: &mut super::commands::CommandContext,
+) -> Result<Arc<SpamFilter>, lib::meetup::Error> {
+    // Check if the spam filter is already in the data map
+    if let Some(spam_filter) = cmdctx.ctx.data.read().await.get::<SpamFilterKey>() {
+        return Ok(spam_filter.clone());
+    }
+    reload_spam_filter(cmdctx).await
+}
+
+// Loads the word list and the rules again, e.g. after they were edited
+pub async fn reload_spam_filter(
     cmdctx: &mut super::commands::CommandContext,
-) -> Result<SpamList, lib::meetup::Error> {
-    // Check if the spam list is already in the data map
-    if let Some(spam_list) = cmdctx.ctx.data.read().await.get::<SpamListKey>() {
-        return Ok(spam_list.clone());
-    }
-    // There is no spam list in the data map yet -> query it and store it in the data map
-    // Query the spam list from Redis
-    let redis_connection = cmdctx.async_redis_connection().await?;
-    let word_list: Vec<String> = redis_connection.lrange("spam_word_list", 0, -1).await?;
-    let word_matcher = AhoCorasickBuilder::new()
-        .ascii_case_insensitive(true)
-        .build(&word_list)
-        .expect("Failed to build the aho-corasick matcher");
-    let spam_list = Arc::new((word_list, word_matcher));
+) -> Result<Arc<SpamFilter>, lib::meetup::Error> {
+    let pool = cmdctx.pool().await?;
+    let spam_filter = Arc::new(SpamFilter::load(&pool).await?);
+    cmdctx
+        .ctx
+        .data
+        .write()
+        .await
+        .insert::<SpamFilterKey>(spam_filter.clone());
+    Ok(spam_filter)
+}
+
+async fn get_message_tracker(
+    cmdctx: &super::commands::CommandContext,
+) -> Arc<Mutex<MessageTracker>> {
+    if let Some(tracker) = cmdctx.ctx.data.read().await.get::<MessageTrackerKey>() {
+        return tracker.clone();
+    }
     cmdctx
         .ctx
         .data
         .write()
         .await
-        .insert::<SpamListKey>(spam_list.clone());
-    Ok(spam_list)
+        .entry::<MessageTrackerKey>()
+        .or_default()
+        .clone()
 }
 
 async fn get_game_channels_list(
@@ -114,3 +866,66 @@ async fn get_game_channels_list(
         .insert::<GameChannelsListKey>(channels_list.clone());
     Ok(channels_list)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_floods_and_parse_actions() {
+        let mut tracker = MessageTracker::default();
+        let user = UserId::new(1);
+        let start = Instant::now();
+        let message = |seconds: u64, channel: u64, id: u64, content: &str| {
+            TrackedMessage::new(
+                start + Duration::from_secs(seconds),
+                ChannelId::new(channel),
+                MessageId::new(id),
+                content,
+            )
+        };
+        let spam = "Free   Nitro at https://example.com";
+        assert!(tracker.track(user, message(0, 1, 1, spam)).is_none());
+        assert!(tracker.track(user, message(10, 2, 2, spam)).is_none());
+        // The first copy is outside of the tracking window
+        assert!(tracker.track(user, message(121, 3, 3, spam)).is_none());
+        let detection = tracker
+            .track(
+                user,
+                message(122, 4, 4, "free nitro AT https://example.com"),
+            )
+            .unwrap();
+        assert_eq!(detection.rule, SpamRule::RepeatedMessage);
+        assert_eq!(detection.messages.len(), 3);
+        tracker.forget(user);
+        let links = "https://a.ch https://b.ch https://c.ch https://d.ch https://e.ch";
+        assert!(tracker.track(user, message(200, 1, 5, links)).is_none());
+        assert!(tracker.track(user, message(201, 1, 6, links)).is_none());
+        let detection = tracker
+            .track(user, message(202, 1, 7, "https://f.ch"))
+            .unwrap();
+        assert_eq!(detection.rule, SpamRule::LinkFlood);
+        assert_eq!(detection.messages.len(), 3);
+
+        assert_eq!(
+            SpamActions::parse("delete, timeout 60"),
+            Some(SpamActions {
+                delete_messages: true,
+                timeout_minutes: Some(60),
+                quarantine: false,
+            })
+        );
+        assert_eq!(SpamActions::parse("alert"), Some(SpamActions::default()));
+        assert_eq!(SpamActions::parse("timeout"), None);
+        assert_eq!(SpamActions::parse("kick"), None);
+        assert_eq!(
+            parse_custom_id(&custom_id(AlertAction::Ban, UserId::new(42))),
+            Some((AlertAction::Ban, UserId::new(42)))
+        );
+        assert!(INVITE_REGEX.is_match("join discord.gg/abc123 now"));
+        assert!(is_valid_word("nitro"));
+        assert!(is_valid_word("€€€"));
+        assert!(!is_valid_word("  ab "));
+        assert!(!is_valid_word(" "));
+    }
+}
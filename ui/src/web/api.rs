@@ -137,6 +137,7 @@ async fn check_discord_username_handler(
 ) -> Result<StatusCode, WebError> {
     let id = lib::tasks::subscription_roles::discord_username_to_id(
         &state.discord_cache_http,
+        state.guild_config.get().guild_id,
         &discord_username.0,
     )
     .await?;
@@ -0,0 +1,259 @@
+use std::{collections::HashMap, sync::Arc};
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Form, Path},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono_tz::Europe;
+use lib::{
+    attendance::{self, AttendanceSheet},
+    db,
+};
+
+use super::{auth, auth::AuthenticatedMember, server::State, MessageTemplate, WebError};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route(
+            "/attendance/:event_id",
+            get(attendance_handler).post(attendance_post_handler),
+        )
+        .route("/attendance/member/:member_id", get(member_stats_handler))
+        .route_layer(axum::middleware::from_fn(auth::auth))
+}
+
+struct PlayerView {
+    member_id: i32,
+    name: String,
+    attended: bool,
+    no_show: bool,
+}
+
+#[derive(Template)]
+#[template(path = "attendance.html")]
+struct AttendanceTemplate {
+    title: String,
+    start_time: String,
+    players: Vec<PlayerView>,
+    saved: bool,
+    flow_id: String,
+    csrf_token: String,
+}
+
+// Started when the attendance sheet is opened and completed when it is saved,
+// such that the form can only be submitted by the member who opened it
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct AttendanceForm {
+    event_id: i32,
+}
+
+impl lib::flow::FlowState for AttendanceForm {
+    const KIND: &'static str = "attendance";
+    const TTL_MINUTES: i32 = 60;
+}
+
+type AttendanceFlow = lib::flow::Flow<AttendanceForm>;
+
+struct MissedSessionView {
+    title: String,
+    date: String,
+}
+
+#[derive(Template)]
+#[template(path = "attendance_stats.html")]
+struct AttendanceStatsTemplate {
+    name: String,
+    num_rsvps: i64,
+    num_attended: i64,
+    num_no_shows: i64,
+    attendance_rate: Option<String>,
+    recent_no_shows: Vec<MissedSessionView>,
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.with_timezone(&Europe::Zurich)
+        .format("%d.%m.%Y %H:%M")
+        .to_string()
+}
+
+// Loads the attendance sheet if the member may edit it
+async fn load_sheet(
+    state: &State,
+    member_id: db::MemberId,
+    event_id: i32,
+) -> Result<Result<AttendanceSheet, Response>, WebError> {
+    let event_id = db::EventId(event_id);
+    let sheet = match AttendanceSheet::load(event_id, &state.pool).await? {
+        Some(sheet) => sheet,
+        None => {
+            let template: MessageTemplate =
+                ("Session not found", "There is no such session").into();
+            return Ok(Err(template.into_response()));
+        }
+    };
+    if !attendance::may_mark_attendance(event_id, member_id, &state.pool).await?
+        && !auth::is_bot_admin(state, member_id).await?
+    {
+        return Err(WebError::Unauthorized(Some(
+            lib::strings::ATTENDANCE_NOT_A_HOST.into(),
+        )));
+    }
+    Ok(Ok(sheet))
+}
+
+async fn attendance_template(
+    state: &State,
+    member_id: db::MemberId,
+    sheet: AttendanceSheet,
+    saved: bool,
+) -> Result<AttendanceTemplate, WebError> {
+    let discord_id = match auth::member_discord_id(state, member_id).await? {
+        Some(discord_id) => discord_id,
+        None => {
+            return Err(WebError::Unauthorized(Some(
+                lib::strings::ATTENDANCE_NOT_A_HOST.into(),
+            )))
+        }
+    };
+    let flow = AttendanceFlow::new(
+        &state.pool,
+        discord_id,
+        AttendanceForm {
+            event_id: sheet.event_id.0,
+        },
+    )
+    .await?;
+    Ok(AttendanceTemplate {
+        title: sheet.title,
+        start_time: format_time(sheet.start_time),
+        players: sheet
+            .players
+            .into_iter()
+            .map(|player| PlayerView {
+                member_id: player.member_id.0,
+                name: player.name,
+                attended: player.attended == Some(true),
+                no_show: player.attended == Some(false),
+            })
+            .collect(),
+        saved,
+        flow_id: flow.id,
+        csrf_token: flow.csrf_token,
+    })
+}
+
+async fn attendance_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(event_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let sheet = match load_sheet(&state, member_id, event_id).await? {
+        Ok(sheet) => sheet,
+        Err(response) => return Ok(response),
+    };
+    Ok(attendance_template(&state, member_id, sheet, false)
+        .await?
+        .into_response())
+}
+
+// The form has one radio button group per player, named after the member ID
+async fn attendance_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(event_id): Path<i32>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    let sheet = match load_sheet(&state, member_id, event_id).await? {
+        Ok(sheet) => sheet,
+        Err(response) => return Ok(response),
+    };
+    // The form has to come from an attendance sheet of this session that was
+    // opened by the same member
+    let form_field = |name: &str| form_data.get(name).map(String::as_str).unwrap_or_default();
+    if let Some(response) = super::take_form_flow(
+        &state,
+        member_id,
+        form_field("flow_id"),
+        form_field("csrf_token"),
+        |form: &AttendanceForm| form.event_id == event_id,
+        "the attendance sheet",
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+    for player in &sheet.players {
+        let attended = match form_data
+            .get(&format!("player_{}", player.member_id.0))
+            .map(String::as_str)
+        {
+            Some("attended") => true,
+            Some("no_show") => false,
+            _ => continue,
+        };
+        attendance::set_attendance(
+            sheet.event_id,
+            player.member_id,
+            attended,
+            member_id,
+            &state.pool,
+        )
+        .await?;
+    }
+    let sheet = match AttendanceSheet::load(sheet.event_id, &state.pool).await? {
+        Some(sheet) => sheet,
+        None => return Err(simple_error::SimpleError::new("Session disappeared").into()),
+    };
+    Ok(attendance_template(&state, member_id, sheet, true)
+        .await?
+        .into_response())
+}
+
+// Members can see their own attendance, bot admins everyone's
+async fn member_stats_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(viewer_id)): Extension<AuthenticatedMember>,
+    Path(member_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let member_id = db::MemberId(member_id);
+    if member_id != viewer_id && !auth::is_bot_admin(&state, viewer_id).await? {
+        return Err(WebError::Unauthorized(Some(
+            "You can only see your own attendance".into(),
+        )));
+    }
+    let name = sqlx::query_scalar!(
+        r#"SELECT COALESCE(discord_nick, 'Member ' || id) AS "name!" FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+    let name = match name {
+        Some(name) => name,
+        None => {
+            let template: MessageTemplate = ("Member not found", "There is no such member").into();
+            return Ok(template.into_response());
+        }
+    };
+    let stats = attendance::get_member_stats(member_id, &state.pool).await?;
+    let template = AttendanceStatsTemplate {
+        name,
+        num_rsvps: stats.num_rsvps,
+        num_attended: stats.num_attended,
+        num_no_shows: stats.num_no_shows,
+        attendance_rate: stats
+            .attendance_rate()
+            .map(|rate| format!("{:.0}%", rate * 100.0)),
+        recent_no_shows: stats
+            .recent_no_shows
+            .into_iter()
+            .map(|session| MissedSessionView {
+                title: session.title,
+                date: format_time(session.start_time),
+            })
+            .collect(),
+    };
+    Ok(template.into_response())
+}
@@ -13,8 +13,7 @@ use axum::{
 use base64::{engine::general_purpose, Engine as _};
 use cookie::{Cookie, CookieJar, Key, SameSite};
 use lib::db::MemberId;
-use redis::AsyncCommands;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use serenity::{builder::CreateMessage, model::id::UserId};
 use simple_error::SimpleError;
 
@@ -26,28 +25,31 @@ pub fn create_routes() -> Router {
         .route("/login", post(auth_handler_post))
         .route("/logout", post(logout_handler))
 }
-// In Redis: session ID, member ID and last used time
+// In the database: session ID, member ID and last used time
 // Routes protected by auth:
-// - is there a session ID (signed or secret) cookie? Is the session ID in Redis and is the last used time not too long ago?
+// - is there a session ID (signed or secret) cookie? Is the session ID in the database and is the last used time not too long ago?
 //   Then allow access for that Member (Member should probably be a return type for auth)
 //   Update the last used time to "now"
-// - no cookie or last used time too far in the past? Delete the session from Redis, delete the cookie and show login instructions (get link from Hyperion)
+// - no cookie or last used time too far in the past? Delete the session from the database, delete the cookie and show login instructions (get link from Hyperion)
 // - possibly in the future: require 2FA for admins (like TOTP) for first login and if the last used time is older than a certain threshold (but not so old that it would count as expired)
 
+// Logging into the website through the link that the bot sends in a DM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WebLogin {}
+
+impl lib::flow::FlowState for WebLogin {
+    const KIND: &'static str = "web_login";
+    const TTL_MINUTES: i32 = 10;
+}
+
+type WebLoginFlow = lib::flow::Flow<WebLogin>;
+
 pub async fn generate_login_link(
-    redis_connection: &mut redis::aio::MultiplexedConnection,
+    db_connection: &sqlx::PgPool,
     discord_id: UserId,
 ) -> Result<String, lib::meetup::Error> {
-    let auth_id = lib::new_random_id(16);
-    let redis_key = format!("web_session_auth:{}:discord_user", auth_id);
-    let mut pipe = redis::pipe();
-    let _: () = pipe
-        .set(&redis_key, discord_id.get())
-        .ignore()
-        .expire(&redis_key, 10 * 60)
-        .query_async(redis_connection)
-        .await?;
-    return Ok(format!("{}/login/{}", lib::urls::BASE_URL, auth_id));
+    let flow = WebLoginFlow::new(db_connection, discord_id, WebLogin {}).await?;
+    return Ok(format!("{}/login/{}", lib::urls::BASE_URL, flow.id));
 }
 
 const AUTH_COOKIE_NAME: &'static str = "__Host-Hyperion-Session-Id";
@@ -55,37 +57,41 @@ const AUTH_COOKIE_NAME: &'static str = "__Host-Hyperion-Session-Id";
 #[derive(Deserialize)]
 struct AuthForm {
     auth_id: String, // base64 encoded
+    csrf_token: String,
 }
 
 #[derive(Template)]
 #[template(path = "login.html")]
 struct AuthTemplate<'a> {
     auth_id: &'a str,
+    csrf_token: &'a str,
 }
 
+#[derive(Clone, Copy)]
 pub struct AuthenticatedMember(pub MemberId);
 
 async fn auth_handler_get(
     Path(auth_id): Path<String>,
     state: Extension<Arc<State>>,
 ) -> Result<Response, WebError> {
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
-    let redis_key = format!("web_session_auth:{}:discord_user", &auth_id);
     // Check if this auth ID is valid
-    let discord_id: Option<u64> = redis_connection.get(redis_key).await?;
-    if discord_id.is_none() {
-        let template: MessageTemplate = (
-            "This link seems to have expired",
-            "Get a new link with the \"login\" command",
-        )
-            .into();
-        return Ok(template.into_response());
-    }
+    let flow = WebLoginFlow::retrieve(&state.pool, &auth_id).await?;
+    let flow = match flow {
+        Some(flow) => flow,
+        None => {
+            let template: MessageTemplate = (
+                "This link seems to have expired",
+                "Get a new link with the \"login\" command",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+    };
     // Show the login form
-    let template = AuthTemplate { auth_id: &auth_id };
+    let template = AuthTemplate {
+        auth_id: &flow.id,
+        csrf_token: &flow.csrf_token,
+    };
     Ok(template.into_response())
 }
 
@@ -93,17 +99,10 @@ async fn auth_handler_post(
     state: Extension<Arc<State>>,
     form: Form<AuthForm>,
 ) -> Result<Response, WebError> {
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
-    let redis_key = format!("web_session_auth:{}:discord_user", form.auth_id);
     // This is a one-time use link. Expire it now.
-    let mut pipe = redis::pipe();
-    pipe.get(&redis_key).del(&redis_key);
-    let (discord_id, _): (Option<u64>, u32) = pipe.query_async(&mut redis_connection).await?;
-    let discord_id = match discord_id {
-        Some(id) => UserId::new(id),
+    let flow = WebLoginFlow::take(&state.pool, &form.auth_id, &form.csrf_token).await?;
+    let discord_id = match flow {
+        Some(flow) => flow.discord_id,
         None => {
             let template: MessageTemplate = (
                 "This link seems to have expired",
@@ -187,7 +186,7 @@ async fn get_or_create_cookie_key(state: &State) -> Result<Key, WebError> {
     Ok(key)
 }
 
-struct RemoveAuthCookie<T: IntoResponse>(pub T);
+pub struct RemoveAuthCookie<T: IntoResponse>(pub T);
 
 impl<T: IntoResponse> IntoResponse for RemoveAuthCookie<T> {
     fn into_response(self) -> Response {
@@ -295,3 +294,33 @@ pub async fn auth<B>(mut req: Request<B>, next: Next<B>) -> Result<Response, Web
     Ok(next.run(req).await)
     // Since this is a middleware we have the option of adjusting the response here (e.g. adding Set-Cookie headers)
 }
+
+pub async fn member_discord_id(
+    state: &State,
+    member_id: MemberId,
+) -> Result<Option<UserId>, WebError> {
+    let discord_id = sqlx::query_scalar!(
+        r#"SELECT discord_id FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    Ok(discord_id.map(|discord_id| UserId::new(discord_id as u64)))
+}
+
+// Whether the member has the bot admin role on Discord
+pub async fn is_bot_admin(state: &State, member_id: MemberId) -> Result<bool, WebError> {
+    let discord_id = match member_discord_id(state, member_id).await? {
+        Some(discord_id) => discord_id,
+        None => return Ok(false),
+    };
+    let guild_config = state.guild_config.get();
+    let member = guild_config
+        .guild_id
+        .member(&state.discord_cache_http, discord_id)
+        .await;
+    Ok(match member {
+        Ok(member) => member.roles.contains(&guild_config.bot_admin_role_id),
+        Err(_) => false,
+    })
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use lib::calendar::{self, CalendarEntry, MemberFeed};
+
+use super::{server::State, WebError};
+
+// The calendar token in the URL authenticates the member, since calendar
+// apps can't log in
+pub fn create_routes() -> Router {
+    Router::new()
+        .route(
+            "/calendar/:calendar_token/sessions.ics",
+            get(member_sessions_handler),
+        )
+        .route(
+            "/calendar/:calendar_token/hosting.ics",
+            get(member_hosting_handler),
+        )
+        .route(
+            "/calendar/series/:series_id/sessions.ics",
+            get(series_handler),
+        )
+}
+
+fn ical_response(calendar_name: &str, entries: Option<Vec<CalendarEntry>>) -> Response {
+    match entries {
+        Some(entries) => (
+            [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+            calendar::to_ical(calendar_name, &entries),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+async fn member_sessions_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(calendar_token): Path<String>,
+) -> Result<Response, WebError> {
+    let entries =
+        calendar::get_member_feed(&calendar_token, MemberFeed::Sessions, &state.pool).await?;
+    Ok(ical_response("My SwissRPG sessions", entries))
+}
+
+async fn member_hosting_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(calendar_token): Path<String>,
+) -> Result<Response, WebError> {
+    let entries =
+        calendar::get_member_feed(&calendar_token, MemberFeed::Hosting, &state.pool).await?;
+    Ok(ical_response("SwissRPG sessions I host", entries))
+}
+
+async fn series_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(series_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let series_id = lib::db::EventSeriesId(series_id);
+    let entries = calendar::get_series_feed(series_id, &state.pool).await?;
+    let calendar_name = entries
+        .as_ref()
+        .and_then(|entries| entries.last())
+        .map_or("SwissRPG sessions".to_string(), |entry| entry.title.clone());
+    Ok(ical_response(&calendar_name, entries))
+}
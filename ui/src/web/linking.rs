@@ -3,25 +3,32 @@ use std::{borrow::Cow, sync::Arc};
 use askama::Template;
 use askama_axum::IntoResponse;
 use axum::{
-    body,
     extract::{Extension, OriginalUri, Path, Query},
-    headers::HeaderMap,
     response::Response,
     routing::get,
     Router,
 };
-use cookie::Cookie;
-use lib::{DefaultStr, LinkingAction, LinkingMemberDiscord, LinkingMemberMeetup, LinkingResult};
+use lib::{
+    meetup::oauth2::MeetupLinkingFlow, DefaultStr, LinkingAction, LinkingMemberDiscord,
+    LinkingMemberMeetup, LinkingResult,
+};
 use oauth2::{AuthorizationCode, CsrfToken, RedirectUrl, Scope, TokenResponse};
-use redis::AsyncCommands;
-use serde::Deserialize;
-use serenity::model::id::UserId;
+use serde::{Deserialize, Serialize};
 
-use super::{server::State, MessageTemplate, WebError};
+use super::{
+    auth::{self, AuthenticatedMember},
+    server::State,
+    MessageTemplate, WebError,
+};
 
+// Only the organizer authorization needs a web login. The linking link is
+// DM'd to the member who asked for it and is a credential of its own, and
+// Meetup redirects back without the session cookie, which is SameSite=Strict.
+// The redirects are bound to the flow by its CSRF token instead.
 pub fn create_routes() -> Router {
     Router::new()
         .route("/authorize", get(authorize_handler))
+        .route_layer(axum::middleware::from_fn(auth::auth))
         .route("/authorize/redirect", get(authorize_redirect_handler))
         .route("/link/:linking_id", get(link_handler))
         .route(
@@ -50,73 +57,45 @@ struct LinkingTemplate<'a> {
     authorize_url: &'a str,
 }
 
-async fn generate_csrf_cookie(
-    redis_connection: &mut redis::aio::MultiplexedConnection,
-    csrf_state: &str,
-) -> Result<Cookie<'static>, lib::meetup::Error> {
-    let random_csrf_user_id = lib::new_random_id(16);
-    let redis_csrf_key = format!("csrf:{}", &random_csrf_user_id);
-    let _: () = redis_connection
-        .set_ex(&redis_csrf_key, csrf_state, 3600)
-        .await?;
-    Ok(Cookie::build(("csrf_user_id", random_csrf_user_id))
-        .domain(lib::urls::DOMAIN)
-        .http_only(true)
-        .same_site(cookie::SameSite::Lax)
-        .max_age(cookie::time::Duration::hours(1))
-        .into())
-}
+// Logging in with the organizer's Meetup account, which the bot then uses for
+// the Meetup API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OrganizerAuthorization {}
 
-async fn check_csrf_cookie(
-    redis_connection: &mut redis::aio::MultiplexedConnection,
-    headers: &hyper::HeaderMap<hyper::header::HeaderValue>,
-    csrf_state: &str,
-) -> Result<bool, lib::meetup::Error> {
-    let csrf_user_id_cookie =
-        headers
-            .get_all(hyper::header::COOKIE)
-            .iter()
-            .find_map(|header_value| {
-                if let Ok(header_value) = header_value.to_str() {
-                    if let Ok(cookie) = Cookie::parse(header_value) {
-                        if cookie.name() == "csrf_user_id" {
-                            return Some(cookie);
-                        }
-                    }
-                }
-                None
-            });
-    let csrf_user_id_cookie = match csrf_user_id_cookie {
-        None => return Ok(false),
-        Some(csrf_user_id_cookie) => csrf_user_id_cookie,
-    };
-    let redis_csrf_key = format!("csrf:{}", csrf_user_id_cookie.value_trimmed());
-    let csrf_stored_state: Option<String> = redis_connection.get(&redis_csrf_key).await?;
-    let csrf_stored_state: String = match csrf_stored_state {
-        None => return Ok(false),
-        Some(csrf_stored_state) => csrf_stored_state,
-    };
-    Ok(csrf_state == csrf_stored_state)
+impl lib::flow::FlowState for OrganizerAuthorization {
+    const KIND: &'static str = "organizer_authorization";
+    const TTL_MINUTES: i32 = 60;
 }
 
-async fn authorize_handler(Extension(state): Extension<Arc<State>>) -> Result<Response, WebError> {
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
+type OrganizerAuthorizationFlow = lib::flow::Flow<OrganizerAuthorization>;
+
+async fn authorize_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    let discord_id = match auth::member_discord_id(&state, member_id).await? {
+        Some(discord_id) => discord_id,
+        None => {
+            return Err(WebError::Unauthorized(Some(
+                "Your account is not linked to Discord".into(),
+            )))
+        }
+    };
+    // The flow's ID and CSRF token are sent as the OAuth2 state, such that
+    // Meetup hands them back to the redirect handler
+    let flow =
+        OrganizerAuthorizationFlow::new(&state.pool, discord_id, OrganizerAuthorization {}).await?;
+    let csrf_state = CsrfToken::new(format!("{}.{}", flow.id, flow.csrf_token));
     // Generate the authorization URL to which we'll redirect the user.
-    let (authorize_url, csrf_state) = state
+    let (authorize_url, _csrf_state) = state
         .oauth2_consumer
         .authorization_client
-        .authorize_url(CsrfToken::new_random)
+        .authorize_url(|| csrf_state)
         .add_scope(Scope::new("ageless".to_string()))
         .add_scope(Scope::new("basic".to_string()))
         .add_scope(Scope::new("event_management".to_string()))
         .url();
-    // Store the generated CSRF token so we can compare it to the one
-    // returned by Meetup later
-    let csrf_cookie = generate_csrf_cookie(&mut redis_connection, csrf_state.secret()).await?;
-    let mut response = MessageTemplate {
+    let template = MessageTemplate {
         title: Cow::Borrowed("Login with Meetup"),
         safe_content: Some(Cow::Owned(format!(
             "<a href=\"{}\">Login with Meetup</a>",
@@ -124,34 +103,32 @@ async fn authorize_handler(Extension(state): Extension<Arc<State>>) -> Result<Re
         ))),
         content: None,
         img_url: None,
-    }
-    .into_response();
-    response.headers_mut().insert(
-        hyper::header::SET_COOKIE,
-        csrf_cookie.to_string().try_into()?,
-    );
-    Ok(response)
+    };
+    Ok(template.into_response())
 }
 
 async fn authorize_redirect_handler(
     Extension(state): Extension<Arc<State>>,
     Query(query): Query<LinkQuery>,
-    headers: HeaderMap,
 ) -> Result<MessageTemplate, WebError> {
     if let Some(error) = query.error {
         return Ok(("OAuth2 error", error).into());
     }
-    // let mut redis_connection = state.redis_client.get_multiplexed_async_connection().await?;
-    // Compare the CSRF state that was returned by Meetup to the one
-    // we have saved
-    // let csrf_is_valid = check_csrf_cookie(&mut redis_connection, &headers, &query.state).await?;
-    // if !csrf_is_valid {
-    //     return Ok((
-    //         "CSRF check failed",
-    //         "Please go back to the first page, reload, and repeat the process",
-    //     )
-    //         .into());
-    // }
+    // Meetup returned the flow's ID and CSRF token as the OAuth2 state. This
+    // is a one-time use link. Expire it now.
+    let flow = match query.state.split_once('.') {
+        Some((flow_id, csrf_token)) => {
+            OrganizerAuthorizationFlow::take(&state.pool, flow_id, csrf_token).await?
+        }
+        None => None,
+    };
+    if flow.is_none() {
+        return Ok((
+            "CSRF check failed",
+            "Please go back to the first page, reload, and repeat the process",
+        )
+            .into());
+    }
     // Exchange the code with a token.
     let code = AuthorizationCode::new(query.code);
     let async_meetup_client = state.async_meetup_client.clone();
@@ -207,29 +184,28 @@ async fn link_handler(
     Extension(state): Extension<Arc<State>>,
     Path(linking_id): Path<String>,
 ) -> Result<Response, WebError> {
-    // The linking ID was stored in Redis when the linking link was created.
-    // Check that it is still valid
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
-    let redis_key = format!("meetup_linking:{}:discord_user", linking_id);
-    let mut pipe = redis::pipe();
-    pipe.expire(&redis_key, 600).ignore().get(&redis_key);
-    let (discord_id,): (Option<u64>,) = pipe.query_async(&mut redis_connection).await?;
-    if discord_id.is_none() {
-        let template: MessageTemplate = (
-            lib::strings::OAUTH2_LINK_EXPIRED_TITLE,
-            lib::strings::OAUTH2_LINK_EXPIRED_CONTENT,
-        )
-            .into();
-        return Ok(template.into_response());
-    }
+    // The linking flow was stored when the linking link was created.
+    // Check that it is still valid and give the user another 10 minutes
+    let flow = MeetupLinkingFlow::retrieve(&state.pool, &linking_id).await?;
+    let flow = match flow {
+        Some(flow) => flow,
+        None => {
+            let template: MessageTemplate = (
+                lib::strings::OAUTH2_LINK_EXPIRED_TITLE,
+                lib::strings::OAUTH2_LINK_EXPIRED_CONTENT,
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+    };
+    flow.renew(&state.pool, 10).await?;
     // TODO: check that this Discord ID is not linked yet before generating an authorization URL
     // Generate the authorization URL to which we'll redirect the user.
     // Two versions: One with just the "basic" scope to identify the user.
     // The second with the "rsvp" scope that will allow us to RSVP the user to events.
-    let csrf_state = CsrfToken::new_random();
+    // The flow's CSRF token is sent as the OAuth2 state, such that Meetup
+    // hands it back to the redirect handler
+    let csrf_state = CsrfToken::new(flow.csrf_token.clone());
     let (_authorize_url_basic, csrf_state) = (*state.oauth2_consumer.link_client)
         .clone()
         .set_redirect_uri(RedirectUrl::new(format!(
@@ -240,7 +216,7 @@ async fn link_handler(
         .authorize_url(|| csrf_state)
         .add_scope(Scope::new("basic".to_string()))
         .url();
-    let (authorize_url_rsvp, csrf_state) = (*state.oauth2_consumer.link_client)
+    let (authorize_url_rsvp, _csrf_state) = (*state.oauth2_consumer.link_client)
         .clone()
         .set_redirect_uri(RedirectUrl::new(format!(
             "{}/link/{}/rsvp/redirect",
@@ -251,17 +227,10 @@ async fn link_handler(
         .add_scope(Scope::new("basic".to_string()))
         .add_scope(Scope::new("rsvp".to_string()))
         .url();
-    // Store the generated CSRF token so we can compare it to the one
-    // returned by Meetup later
-    let csrf_cookie = generate_csrf_cookie(&mut redis_connection, csrf_state.secret()).await?;
     let linking_template = LinkingTemplate {
         authorize_url: authorize_url_rsvp.as_str(),
     };
-    let html_body = linking_template.render()?;
-    let response = Response::builder()
-        .header(hyper::header::SET_COOKIE, csrf_cookie.to_string())
-        .body(body::boxed(body::Full::from(html_body)))?;
-    Ok(response)
+    Ok(linking_template.into_response())
 }
 
 async fn link_redirect_handler(
@@ -272,19 +241,12 @@ async fn link_redirect_handler(
     Path(linking_id): Path<String>,
     Extension(with_rsvp_scope): Extension<WithRsvpScope>,
 ) -> Result<MessageTemplate, WebError> {
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
-    // The linking ID was stored in Redis when the linking link was created.
-    // Check that it is still valid
-    let redis_key = format!("meetup_linking:{}:discord_user", &linking_id);
+    // The linking flow was stored when the linking link was created. Check
+    // that it is still valid and that Meetup returned our CSRF token.
     // This is a one-time use link. Expire it now.
-    let mut pipe = redis::pipe();
-    pipe.get(&redis_key).del(&redis_key);
-    let (discord_id, _): (Option<u64>, u32) = pipe.query_async(&mut redis_connection).await?;
-    let discord_id = match discord_id {
-        Some(id) => UserId::new(id),
+    let flow = MeetupLinkingFlow::take(&state.pool, &linking_id, &query.state).await?;
+    let discord_id = match flow {
+        Some(flow) => flow.discord_id,
         None => {
             return Ok((
                 lib::strings::OAUTH2_LINK_EXPIRED_TITLE,
@@ -297,11 +259,8 @@ async fn link_redirect_handler(
         if error == "access_denied" {
             // The user did not grant access
             // Give them the chance to do it again
-            let linking_url = lib::meetup::oauth2::generate_meetup_linking_link(
-                &mut redis_connection,
-                discord_id,
-            )
-            .await?;
+            let linking_url =
+                lib::meetup::oauth2::generate_meetup_linking_link(&state.pool, discord_id).await?;
             return Ok(MessageTemplate {
                 title: Cow::Borrowed("Linking Failure"),
                 content: None,
@@ -316,16 +275,6 @@ async fn link_redirect_handler(
             return Ok(("OAuth2 error", error.to_string()).into());
         }
     }
-    // Compare the CSRF state that was returned by Meetup to the one
-    // we have saved
-    // let csrf_is_valid = check_csrf_cookie(redis_connection, headers, &query.state).await?;
-    // if !csrf_is_valid {
-    //     return Ok((
-    //         "CSRF check failed",
-    //         "Please go back to the first page, reload, and repeat the process",
-    //     )
-    //         .into());
-    // }
     // Exchange the code with a token.
     let code = AuthorizationCode::new(query.code);
     let redirect_url = RedirectUrl::new(format!("{}{}", lib::urls::BASE_URL, path.path()))?;
@@ -1,9 +1,17 @@
 pub mod api;
+pub mod attendance;
 pub mod auth;
+pub mod calendar;
 pub mod linking;
+pub mod profile;
 pub mod schedule_session;
+pub mod series;
 pub mod server;
+pub mod stats;
 pub mod stripe_webhook_endpoint;
+pub mod swissrpg_webhook_endpoint;
+pub mod transcripts;
+pub mod waitlist;
 
 use std::borrow::Cow;
 
@@ -15,7 +23,6 @@ use axum::{
 };
 use hyper::header::InvalidHeaderValue;
 use lib::BoxedError;
-use redis::RedisError;
 
 #[derive(Template)]
 #[template(path = "message.html")]
@@ -48,6 +55,35 @@ impl From<(&'static str, String)> for MessageTemplate {
     }
 }
 
+// Completes the flow of a form on a page that was opened by the same member.
+// Returns a response that asks to reload the page if the form has expired or
+// its flow was started for something else, like another session.
+async fn take_form_flow<S: lib::flow::FlowState>(
+    state: &server::State,
+    member_id: lib::db::MemberId,
+    flow_id: &str,
+    csrf_token: &str,
+    belongs_to_page: impl FnOnce(&S) -> bool,
+    page: &str,
+) -> Result<Option<Response>, WebError> {
+    let flow = match auth::member_discord_id(state, member_id).await? {
+        Some(discord_id) => {
+            lib::flow::Flow::<S>::take_for(&state.pool, flow_id, csrf_token, discord_id).await?
+        }
+        None => None,
+    };
+    if flow.is_some_and(|flow| belongs_to_page(&flow.state)) {
+        Ok(None)
+    } else {
+        let template: MessageTemplate = (
+            "This form has expired",
+            format!("Please reload {} and try again", page),
+        )
+            .into();
+        Ok(Some(template.into_response()))
+    }
+}
+
 // We can't implement IntoResponse for lib::meetup::Error in this crate so we create a new error type
 #[derive(Debug)]
 pub enum WebError {
@@ -99,12 +135,6 @@ impl From<lib::meetup::Error> for WebError {
     }
 }
 
-impl From<RedisError> for WebError {
-    fn from(err: RedisError) -> Self {
-        WebError::Other(err.into())
-    }
-}
-
 impl From<InvalidHeaderValue> for WebError {
     fn from(err: InvalidHeaderValue) -> Self {
         WebError::Other(err.into())
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Form},
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono_tz::Europe;
+use lib::{
+    audit::{AuditSource, Auditor},
+    calendar::{self, MemberFeed},
+    db,
+    gdpr::{self, ErasureResult},
+    profile, UnlinkingResult,
+};
+use serde::Deserialize;
+use serenity::model::id::UserId;
+
+use super::{
+    auth::{self, AuthenticatedMember, RemoveAuthCookie},
+    server::State,
+    MessageTemplate, WebError,
+};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route("/profile", get(profile_handler))
+        .route("/profile/meetup", post(meetup_post_handler))
+        .route("/profile/calendar", post(calendar_post_handler))
+        .route("/profile/delete", post(delete_post_handler))
+        .route_layer(axum::middleware::from_fn(auth::auth))
+}
+
+// Started whenever the profile page is shown and completed by submitting any
+// of its forms
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct ProfileForm {
+    member_id: i32,
+}
+
+impl lib::flow::FlowState for ProfileForm {
+    const KIND: &'static str = "profile";
+    const TTL_MINUTES: i32 = 60;
+}
+
+type ProfileFlow = lib::flow::Flow<ProfileForm>;
+
+struct SessionView {
+    series_id: i32,
+    title: String,
+    start_time: String,
+    is_host: bool,
+}
+
+#[derive(Template)]
+#[template(path = "profile.html")]
+struct ProfileTemplate {
+    message: Option<String>,
+    flow_id: String,
+    csrf_token: String,
+    discord_name: String,
+    meetup_url: Option<String>,
+    supporter_tier: Option<String>,
+    sessions_feed_url: String,
+    hosting_feed_url: String,
+    upcoming_sessions: Vec<SessionView>,
+    past_sessions: Vec<SessionView>,
+}
+
+#[derive(Deserialize)]
+struct MeetupForm {
+    flow_id: String,
+    csrf_token: String,
+    action: String,
+}
+
+#[derive(Deserialize)]
+struct CalendarForm {
+    flow_id: String,
+    csrf_token: String,
+}
+
+#[derive(Deserialize)]
+struct DeleteForm {
+    flow_id: String,
+    csrf_token: String,
+    confirmation: String,
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.with_timezone(&Europe::Zurich)
+        .format("%d.%m.%Y %H:%M")
+        .to_string()
+}
+
+async fn discord_id(state: &State, member_id: db::MemberId) -> Result<UserId, WebError> {
+    match auth::member_discord_id(state, member_id).await? {
+        Some(discord_id) => Ok(discord_id),
+        None => Err(WebError::Unauthorized(Some(
+            "Your account is not linked to Discord".into(),
+        ))),
+    }
+}
+
+// Checks that a submitted form belongs to a profile page that was opened by
+// the same member
+async fn check_form(
+    state: &State,
+    member_id: db::MemberId,
+    flow_id: &str,
+    csrf_token: &str,
+) -> Result<Option<Response>, WebError> {
+    super::take_form_flow(
+        state,
+        member_id,
+        flow_id,
+        csrf_token,
+        |form: &ProfileForm| form.member_id == member_id.0,
+        "your profile",
+    )
+    .await
+}
+
+async fn profile_response(
+    state: &State,
+    member_id: db::MemberId,
+    message: Option<String>,
+) -> Result<Response, WebError> {
+    let profile = match profile::get_profile(member_id, &state.pool).await? {
+        Some(profile) => profile,
+        None => {
+            let template: MessageTemplate = ("Profile not found", "There is no such member").into();
+            return Ok(template.into_response());
+        }
+    };
+    let discord_id = discord_id(state, member_id).await?;
+    let flow = ProfileFlow::new(
+        &state.pool,
+        discord_id,
+        ProfileForm {
+            member_id: member_id.0,
+        },
+    )
+    .await?;
+    let discord_name = state
+        .discord_cache_http
+        .cache
+        .user(discord_id)
+        .map(|user| user.name.clone())
+        .or(profile.discord_nick)
+        .unwrap_or_else(|| discord_id.to_string());
+    let calendar_token = calendar::get_or_create_calendar_token(discord_id, &state.pool).await?;
+    let now = chrono::Utc::now();
+    let (upcoming_sessions, past_sessions): (Vec<_>, Vec<_>) =
+        profile::get_sessions(member_id, &state.pool)
+            .await?
+            .into_iter()
+            .partition(|session| session.start_time > now);
+    let session_views = |sessions: Vec<profile::ProfileSession>| {
+        sessions
+            .into_iter()
+            .map(|session| SessionView {
+                series_id: session.series_id.0,
+                title: session.title,
+                start_time: format_time(session.start_time),
+                is_host: session.is_host,
+            })
+            .collect::<Vec<_>>()
+    };
+    // Upcoming sessions next first, past sessions latest first
+    let mut upcoming_sessions = session_views(upcoming_sessions);
+    upcoming_sessions.reverse();
+    let template = ProfileTemplate {
+        message,
+        flow_id: flow.id,
+        csrf_token: flow.csrf_token,
+        discord_name,
+        meetup_url: profile
+            .meetup_id
+            .map(|meetup_id| format!("https://www.meetup.com/members/{}/", meetup_id)),
+        supporter_tier: profile.supporter_tier,
+        sessions_feed_url: calendar::member_feed_url(&calendar_token, MemberFeed::Sessions),
+        hosting_feed_url: calendar::member_feed_url(&calendar_token, MemberFeed::Hosting),
+        upcoming_sessions,
+        past_sessions: session_views(past_sessions),
+    };
+    Ok(template.into_response())
+}
+
+async fn profile_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    profile_response(&state, member_id, None).await
+}
+
+// Unlinks the Meetup account or, for linking and relinking, continues with
+// the same OAuth2 flow as the "link meetup" command
+async fn meetup_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Form(form): Form<MeetupForm>,
+) -> Result<Response, WebError> {
+    if let Some(response) = check_form(&state, member_id, &form.flow_id, &form.csrf_token).await? {
+        return Ok(response);
+    }
+    let discord_id = discord_id(&state, member_id).await?;
+    if form.action == "unlink" || form.action == "relink" {
+        let mut tx = state.pool.begin().await?;
+        let unlinking_result = lib::unlink_meetup(discord_id, &mut tx).await?;
+        tx.commit().await?;
+        if form.action == "unlink" {
+            let message = match unlinking_result {
+                UnlinkingResult::Success => "Unlinked your Meetup account",
+                UnlinkingResult::NotLinked => "There is no linked Meetup account",
+            };
+            return profile_response(&state, member_id, Some(message.to_string())).await;
+        }
+    }
+    let url = lib::meetup::oauth2::generate_meetup_linking_link(&state.pool, discord_id).await?;
+    Ok(Redirect::to(&url).into_response())
+}
+
+async fn calendar_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Form(form): Form<CalendarForm>,
+) -> Result<Response, WebError> {
+    if let Some(response) = check_form(&state, member_id, &form.flow_id, &form.csrf_token).await? {
+        return Ok(response);
+    }
+    let discord_id = discord_id(&state, member_id).await?;
+    calendar::reset_calendar_token(discord_id, &state.pool).await?;
+    profile_response(
+        &state,
+        member_id,
+        Some("Your old calendar links don't work anymore, use the new ones below".to_string()),
+    )
+    .await
+}
+
+async fn delete_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Form(form): Form<DeleteForm>,
+) -> Result<Response, WebError> {
+    if let Some(response) = check_form(&state, member_id, &form.flow_id, &form.csrf_token).await? {
+        return Ok(response);
+    }
+    if !form.confirmation.trim().eq_ignore_ascii_case("delete") {
+        return profile_response(
+            &state,
+            member_id,
+            Some("Please type \"delete\" to confirm the deletion of your account".to_string()),
+        )
+        .await;
+    }
+    let discord_id = discord_id(&state, member_id).await?;
+    let auditor = Auditor::new(
+        AuditSource::Web {
+            actor: discord_id,
+            page: "profile",
+        },
+        &state.pool,
+        &state.discord_cache_http,
+        &state.guild_config.get(),
+    );
+    let erasure_result = gdpr::erase_member_data(
+        member_id,
+        &state.pool,
+        &state.stripe_client,
+        &state.transcript_directory,
+        &auditor,
+    )
+    .await?;
+    match erasure_result {
+        ErasureResult::Erased { .. } => {
+            let template: MessageTemplate = (
+                "Your account has been deleted",
+                "The bot has forgotten about you. Signing up for a game again creates a new \
+                 account.",
+            )
+                .into();
+            // The web sessions are gone as well
+            Ok(RemoveAuthCookie(template).into_response())
+        }
+        ErasureResult::HostsUpcomingSessions => {
+            profile_response(
+                &state,
+                member_id,
+                Some(
+                    "You are hosting upcoming sessions. Please hand them over to another host \
+                     before deleting your account."
+                        .to_string(),
+                ),
+            )
+            .await
+        }
+    }
+}
@@ -15,6 +15,10 @@ use serenity::all::Mentionable;
 
 use super::{server::State, MessageTemplate, WebError};
 
+// No web login needed: the flow link is only DM'd to the member who asked
+// for it, so the unguessable link is the credential. Discord links are
+// cross-site navigations which don't carry the SameSite=Strict session cookie
+// anyway.
 pub fn create_routes() -> Router {
     let routes = Router::new().route(
         "/schedule_session/:flow_id",
@@ -39,6 +43,7 @@ pub fn create_routes() -> Router {
                     duration: 150,
                     title: "Test event",
                     link: Some("https://meetup.com/"),
+                    csrf_token: "",
                 };
                 futures::future::ready(template.into_response())
             }),
@@ -70,6 +75,7 @@ struct ScheduleSessionTemplate<'a> {
     duration: u16, // In minutes
     title: &'a str,
     link: Option<&'a str>,
+    csrf_token: &'a str,
 }
 
 #[derive(Template)]
@@ -93,14 +99,10 @@ pub mod filters {
 
 async fn schedule_session_handler(
     Extension(state): Extension<Arc<State>>,
-    Path(flow_id): Path<u64>,
+    Path(flow_id): Path<String>,
 ) -> Result<Response, WebError> {
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
     eprintln!("Retrieving flow...");
-    let flow = lib::flow::ScheduleSessionFlow::retrieve(&mut redis_connection, flow_id).await?;
+    let flow = lib::flow::ScheduleSessionFlow::retrieve(&state.pool, &flow_id).await?;
     let flow = match flow {
         Some(flow) => flow,
         None => {
@@ -109,7 +111,7 @@ async fn schedule_session_handler(
         }
     };
     eprintln!("... got it!\nRetrieving last event...");
-    let event = db::get_last_event_in_series(&state.pool, flow.event_series_id).await?;
+    let event = db::get_last_event_in_series(&state.pool, flow.event_series_id()).await?;
     eprintln!("... got it!");
     match event {
         None => {
@@ -160,6 +162,7 @@ async fn schedule_session_handler(
                             .as_ref()
                             .map(|swissrpg_event| swissrpg_event.url.as_str())
                     }),
+                csrf_token: &flow.csrf_token,
             };
             Ok(template.into_response())
         }
@@ -169,14 +172,10 @@ async fn schedule_session_handler(
 #[tracing::instrument(skip(state, form_data), fields(flow_id = %flow_id))]
 async fn schedule_session_post_handler(
     Extension(state): Extension<Arc<State>>,
-    Path(flow_id): Path<u64>,
+    Path(flow_id): Path<String>,
     Form(form_data): Form<HashMap<String, String>>,
 ) -> Result<Response, WebError> {
-    let mut redis_connection = state
-        .redis_client
-        .get_multiplexed_async_connection()
-        .await?;
-    let flow = lib::flow::ScheduleSessionFlow::retrieve(&mut redis_connection, flow_id).await?;
+    let flow = lib::flow::ScheduleSessionFlow::retrieve(&state.pool, &flow_id).await?;
     let flow = match flow {
         Some(flow) => flow,
         None => {
@@ -184,6 +183,19 @@ async fn schedule_session_post_handler(
             return Ok(template.into_response());
         }
     };
+    // The flow stays valid until the session was scheduled, such that a failed
+    // attempt can be retried with the same link
+    if !form_data
+        .get("csrf_token")
+        .is_some_and(|csrf_token| flow.check_csrf(csrf_token))
+    {
+        let template: MessageTemplate = (
+            "Invalid request",
+            "Please reload the page and try again or request a new link",
+        )
+            .into();
+        return Ok(template.into_response());
+    }
     // Check that the form contains all necessary data
     let transfer_rsvps = form_data
         .get("transfer_rsvps")
@@ -269,13 +281,12 @@ async fn schedule_session_post_handler(
     let date_time = date_time.with_timezone(&chrono::Utc);
 
     // Capture the event series ID before moving the flow
-    let event_series_id = flow.event_series_id;
+    let event_series_id = flow.event_series_id();
 
     // Use the new unified scheduling approach
     let schedule_result = flow
         .schedule(
             state.pool.clone(),
-            redis_connection,
             Some(state.swissrpg_client.clone()),
             date_time,
             duration,
@@ -303,6 +314,9 @@ async fn schedule_session_post_handler(
         }
     };
 
+    // Give the channel its new session right away
+    state.spawn_series_sync(event_series_id);
+
     // Remove any possibly existing channel snoozes
     {
         let mut tx = state.pool.begin().await?;
@@ -340,14 +354,26 @@ async fn schedule_session_post_handler(
                 link = &new_event_url
             )
         };
-        if let Err(err) = lib::discord::util::say_in_event_channel(
-            latest_event.id,
-            &message,
-            &state.pool,
-            &state.discord_cache_http,
-        )
-        .await
-        {
+        // The new session is synced as part of scheduling it. If that worked,
+        // it is the latest event and players can RSVP right in the channel.
+        let announcement_result = if latest_event.time > chrono::Utc::now() {
+            lib::discord::rsvp::announce_event(
+                latest_event.id,
+                &message,
+                &state.pool,
+                &state.discord_cache_http,
+            )
+            .await
+        } else {
+            lib::discord::util::say_in_event_channel(
+                latest_event.id,
+                &message,
+                &state.pool,
+                &state.discord_cache_http,
+            )
+            .await
+        };
+        if let Err(err) = announcement_result {
             eprintln!(
                 "Encountered an error when trying to announce the new session in the \
                  channel:\n{:#?}",
@@ -357,16 +383,20 @@ async fn schedule_session_post_handler(
 
         // If RSVPs were not transferred, announce the new session in the bot alerts channel
         if is_open_game {
+            let guild_config = state.guild_config.get();
             let message = format!(
                 "{organiser_mention}, a new session has been scheduled:\n{link}.\nPlease announce \
                  this session for new players to join. Don't forget to **open RSVPs** when you do \
                  that.",
-                organiser_mention = lib::discord::sync::ids::ORGANISER_ID.mention(),
+                organiser_mention = guild_config.organiser_role_id.mention(),
                 link = &new_event_url,
             );
-            if let Err(err) =
-                lib::discord::util::say_in_bot_alerts_channel(&message, &state.discord_cache_http)
-                    .await
+            if let Err(err) = lib::discord::util::say_in_bot_alerts_channel(
+                &message,
+                &state.discord_cache_http,
+                &guild_config,
+            )
+            .await
             {
                 eprintln!(
                     "Encountered an error when trying to announce a new session in the bot alerts \
@@ -0,0 +1,827 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Form, Path},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono_tz::Europe;
+use lib::{
+    audit::{AuditSource, Auditor},
+    db,
+    end_adventure::EndAdventureResult,
+    expiration_policy::{self, PolicySetting},
+    series_settings::{self, ChannelSettings},
+    tasks::session_reminder,
+};
+use serde::Deserialize;
+use serenity::model::id::ChannelId;
+
+use super::{auth, auth::AuthenticatedMember, server::State, MessageTemplate, WebError};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route("/series", get(series_list_handler))
+        .route("/series/:series_id", get(series_handler))
+        .route("/series/:series_id/channel", post(channel_post_handler))
+        .route("/series/:series_id/reminders", post(reminders_post_handler))
+        .route(
+            "/series/:series_id/expiration",
+            post(expiration_post_handler),
+        )
+        .route("/series/:series_id/hosts", post(hosts_post_handler))
+        .route("/series/:series_id/end", post(end_post_handler))
+        .route_layer(axum::middleware::from_fn(auth::auth))
+}
+
+// Started whenever the settings page is shown and completed by submitting any
+// of its forms, such that the forms can only be submitted by the member who
+// opened the page
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct SeriesSettingsForm {
+    event_series_id: i32,
+}
+
+impl lib::flow::FlowState for SeriesSettingsForm {
+    const KIND: &'static str = "series_settings";
+    const TTL_MINUTES: i32 = 60;
+}
+
+type SeriesSettingsFlow = lib::flow::Flow<SeriesSettingsForm>;
+
+struct HostedSeriesView {
+    series_id: i32,
+    title: String,
+    next_session: String,
+}
+
+#[derive(Template)]
+#[template(path = "series_list.html")]
+struct SeriesListTemplate {
+    series: Vec<HostedSeriesView>,
+}
+
+struct SessionView {
+    title: String,
+    start_time: String,
+    hosts: Vec<String>,
+    players: Vec<String>,
+}
+
+struct MemberView {
+    member_id: i32,
+    name: String,
+}
+
+struct CategoryView {
+    id: u64,
+    name: String,
+    selected: bool,
+}
+
+struct ExpirationView {
+    field: String,
+    name: &'static str,
+    value: String,
+    default: String,
+}
+
+#[derive(Template)]
+#[template(path = "series.html")]
+struct SeriesTemplate {
+    series_id: i32,
+    title: String,
+    message: Option<String>,
+    flow_id: String,
+    csrf_token: String,
+    sessions: Vec<SessionView>,
+    channel_name: String,
+    default_channel_name: String,
+    topic_template: String,
+    default_topic_template: &'static str,
+    categories: Vec<CategoryView>,
+    reminder_minutes: String,
+    default_reminder_minutes: String,
+    reminder_dm: bool,
+    expiration: Vec<ExpirationView>,
+    hosts: Vec<MemberView>,
+    players: Vec<MemberView>,
+}
+
+#[derive(Deserialize)]
+struct ChannelForm {
+    flow_id: String,
+    csrf_token: String,
+    channel_name: String,
+    topic_template: String,
+    category_id: String,
+}
+
+#[derive(Deserialize)]
+struct RemindersForm {
+    flow_id: String,
+    csrf_token: String,
+    reminder_minutes: String,
+    reminder_dm: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ExpirationForm {
+    flow_id: String,
+    csrf_token: String,
+    reminder_delay: String,
+    reminder_interval: String,
+    deletion_delay: String,
+}
+
+impl ExpirationForm {
+    fn value(&self, setting: PolicySetting) -> &str {
+        match setting {
+            PolicySetting::ReminderDelay => &self.reminder_delay,
+            PolicySetting::ReminderInterval => &self.reminder_interval,
+            PolicySetting::DeletionDelay => &self.deletion_delay,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HostsForm {
+    flow_id: String,
+    csrf_token: String,
+    action: String,
+    member_id: i32,
+}
+
+#[derive(Deserialize)]
+struct EndForm {
+    flow_id: String,
+    csrf_token: String,
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.with_timezone(&Europe::Zurich)
+        .format("%d.%m.%Y %H:%M")
+        .to_string()
+}
+
+fn member_name(member: &db::Member) -> String {
+    member
+        .discord_nick
+        .clone()
+        .unwrap_or_else(|| format!("Member {}", member.id.0))
+}
+
+// The form field of an expiration setting, e.g. "reminder_delay"
+fn expiration_field(setting: PolicySetting) -> String {
+    setting.name().replace(' ', "_")
+}
+
+// Hours in a form that parse_hours accepts
+fn hours_input(hours: i32) -> String {
+    if hours > 0 && hours % 24 == 0 {
+        format!("{}d", hours / 24)
+    } else {
+        format!("{}h", hours)
+    }
+}
+
+// Reminder times in a form that parse_reminder_minutes accepts
+fn reminder_minutes_input(minutes: &[i32]) -> String {
+    if minutes.is_empty() {
+        return "off".to_string();
+    }
+    minutes
+        .iter()
+        .map(|&minutes| {
+            if minutes % (24 * 60) == 0 {
+                format!("{}d", minutes / (24 * 60))
+            } else if minutes % 60 == 0 {
+                format!("{}h", minutes / 60)
+            } else {
+                format!("{}m", minutes)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Returns the title of the series' latest session if the member may change the
+// series' settings. Hosts of the series and bot admins can.
+async fn check_access(
+    state: &State,
+    member_id: db::MemberId,
+    series_id: db::EventSeriesId,
+) -> Result<Result<String, Response>, WebError> {
+    let title = sqlx::query_scalar!(
+        r#"SELECT title FROM event WHERE event_series_id = $1 AND deleted IS NULL
+        ORDER BY start_time DESC LIMIT 1"#,
+        series_id.0
+    )
+    .fetch_optional(&state.pool)
+    .await?;
+    let title = match title {
+        Some(title) => title,
+        None => {
+            let template: MessageTemplate = ("Game not found", "There is no such game").into();
+            return Ok(Err(template.into_response()));
+        }
+    };
+    if !series_settings::is_series_host(series_id, member_id, &state.pool).await?
+        && !auth::is_bot_admin(state, member_id).await?
+    {
+        return Err(WebError::Unauthorized(Some(
+            "Only the hosts of this game can change its settings".into(),
+        )));
+    }
+    Ok(Ok(title))
+}
+
+// Checks that a submitted form belongs to a settings page of this series that
+// was opened by the same member
+async fn check_form(
+    state: &State,
+    member_id: db::MemberId,
+    series_id: db::EventSeriesId,
+    flow_id: &str,
+    csrf_token: &str,
+) -> Result<Option<Response>, WebError> {
+    if let Err(response) = check_access(state, member_id, series_id).await? {
+        return Ok(Some(response));
+    }
+    super::take_form_flow(
+        state,
+        member_id,
+        flow_id,
+        csrf_token,
+        |form: &SeriesSettingsForm| form.event_series_id == series_id.0,
+        "the game settings",
+    )
+    .await
+}
+
+async fn series_template(
+    state: &State,
+    member_id: db::MemberId,
+    series_id: db::EventSeriesId,
+    title: String,
+    message: Option<String>,
+) -> Result<SeriesTemplate, WebError> {
+    let discord_id = match auth::member_discord_id(state, member_id).await? {
+        Some(discord_id) => discord_id,
+        None => {
+            return Err(WebError::Unauthorized(Some(
+                "Only the hosts of this game can change its settings".into(),
+            )))
+        }
+    };
+    let flow = SeriesSettingsFlow::new(
+        &state.pool,
+        discord_id,
+        SeriesSettingsForm {
+            event_series_id: series_id.0,
+        },
+    )
+    .await?;
+    let events = db::get_upcoming_events_for_series(&state.pool, series_id).await?;
+    let mut sessions = Vec::with_capacity(events.len());
+    let mut hosts = BTreeMap::new();
+    let mut players = BTreeMap::new();
+    for event in &events {
+        let event_hosts = db::get_events_participants(&[event.id], true, &state.pool).await?;
+        let event_players = db::get_events_participants(&[event.id], false, &state.pool).await?;
+        sessions.push(SessionView {
+            title: event.title.clone(),
+            start_time: format_time(event.time),
+            hosts: event_hosts.iter().map(member_name).collect(),
+            players: event_players.iter().map(member_name).collect(),
+        });
+        for host in &event_hosts {
+            hosts.insert(host.id.0, member_name(host));
+        }
+        for player in &event_players {
+            players.insert(player.id.0, member_name(player));
+        }
+    }
+    // Players who host as well are only listed as hosts
+    players.retain(|member_id, _| !hosts.contains_key(member_id));
+    let member_views = |members: BTreeMap<i32, String>| {
+        members
+            .into_iter()
+            .map(|(member_id, name)| MemberView { member_id, name })
+            .collect::<Vec<_>>()
+    };
+    let channel_settings = ChannelSettings::load(series_id, &state.pool).await?;
+    let default_settings = ChannelSettings::default();
+    let default_channel_name = events
+        .first()
+        .and_then(|event| default_settings.channel_name(event).ok())
+        .unwrap_or_default()
+        .to_string();
+    let guild_config = state.guild_config.get();
+    let categories = {
+        let guild = state.discord_cache_http.cache.guild(guild_config.guild_id);
+        series_settings::selectable_categories(&guild_config)
+            .into_iter()
+            .map(|category_id| CategoryView {
+                id: category_id.get(),
+                name: guild
+                    .as_ref()
+                    .and_then(|guild| guild.channels.get(&category_id))
+                    .map(|category| category.name.clone())
+                    .unwrap_or_else(|| category_id.get().to_string()),
+                selected: channel_settings.category_id == Some(category_id),
+            })
+            .collect()
+    };
+    let reminder_settings = session_reminder::get_series_settings(series_id, &state.pool).await?;
+    let mut connection = state.pool.acquire().await?;
+    let policy = expiration_policy::get_series_policy(series_id, &mut connection).await?;
+    let expiration = PolicySetting::ALL
+        .into_iter()
+        .map(|setting| ExpirationView {
+            field: expiration_field(setting),
+            name: setting.name(),
+            value: policy
+                .overrides
+                .hours(setting)
+                .map(hours_input)
+                .unwrap_or_default(),
+            default: expiration_policy::format_hours(policy.type_policy.hours(setting)),
+        })
+        .collect();
+    Ok(SeriesTemplate {
+        series_id: series_id.0,
+        title: events
+            .first()
+            .map(|event| event.title.clone())
+            .unwrap_or(title),
+        message,
+        flow_id: flow.id,
+        csrf_token: flow.csrf_token,
+        sessions,
+        channel_name: channel_settings.channel_name.unwrap_or_default(),
+        default_channel_name,
+        topic_template: channel_settings.topic_template.unwrap_or_default(),
+        default_topic_template: series_settings::DEFAULT_TOPIC_TEMPLATE,
+        categories,
+        reminder_minutes: reminder_settings
+            .reminder_minutes
+            .as_deref()
+            .map(reminder_minutes_input)
+            .unwrap_or_default(),
+        default_reminder_minutes: session_reminder::format_reminder_minutes(
+            &reminder_settings.type_reminder_minutes,
+        ),
+        reminder_dm: reminder_settings.dm,
+        expiration,
+        hosts: member_views(hosts),
+        players: member_views(players),
+    })
+}
+
+// Shows the settings page, optionally with the outcome of a submitted form
+async fn series_response(
+    state: &State,
+    member_id: db::MemberId,
+    series_id: db::EventSeriesId,
+    message: String,
+) -> Result<Response, WebError> {
+    let title = match check_access(state, member_id, series_id).await? {
+        Ok(title) => title,
+        Err(response) => return Ok(response),
+    };
+    Ok(
+        series_template(state, member_id, series_id, title, Some(message))
+            .await?
+            .into_response(),
+    )
+}
+
+async fn series_list_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    let series = series_settings::hosted_series(member_id, &state.pool).await?;
+    let template = SeriesListTemplate {
+        series: series
+            .into_iter()
+            .map(|series| HostedSeriesView {
+                series_id: series.series_id.0,
+                title: series.title,
+                next_session: format_time(series.next_session),
+            })
+            .collect(),
+    };
+    Ok(template.into_response())
+}
+
+async fn series_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(series_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    let title = match check_access(&state, member_id, series_id).await? {
+        Ok(title) => title,
+        Err(response) => return Ok(response),
+    };
+    Ok(series_template(&state, member_id, series_id, title, None)
+        .await?
+        .into_response())
+}
+
+async fn channel_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(series_id): Path<i32>,
+    Form(form): Form<ChannelForm>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    if let Some(response) = check_form(
+        &state,
+        member_id,
+        series_id,
+        &form.flow_id,
+        &form.csrf_token,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+    let message = save_channel_settings(&state, series_id, &form).await?;
+    series_response(&state, member_id, series_id, message).await
+}
+
+async fn save_channel_settings(
+    state: &Arc<State>,
+    series_id: db::EventSeriesId,
+    form: &ChannelForm,
+) -> Result<String, WebError> {
+    let non_empty = |value: &str| {
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.to_string())
+    };
+    let channel_name = non_empty(&form.channel_name);
+    let topic_template = non_empty(&form.topic_template);
+    if !channel_name
+        .as_deref()
+        .is_none_or(series_settings::is_valid_channel_name)
+    {
+        return Ok("The channel name has to be between 2 and 80 characters long".to_string());
+    }
+    if !topic_template
+        .as_deref()
+        .is_none_or(series_settings::is_valid_topic_template)
+    {
+        return Ok(format!(
+            "The topic can be at most {} characters long",
+            series_settings::MAX_TOPIC_TEMPLATE_LEN
+        ));
+    }
+    let category_id = match form.category_id.trim() {
+        "" => None,
+        category_id => {
+            let selectable_categories =
+                series_settings::selectable_categories(&state.guild_config.get());
+            match category_id.parse::<u64>() {
+                Ok(category_id)
+                    if category_id != 0
+                        && selectable_categories.contains(&ChannelId::new(category_id)) =>
+                {
+                    Some(ChannelId::new(category_id))
+                }
+                _ => return Ok("This category can't be chosen".to_string()),
+            }
+        }
+    };
+    let old_settings = ChannelSettings::load(series_id, &state.pool).await?;
+    let new_settings = ChannelSettings {
+        channel_name,
+        topic_template,
+        category_id,
+    };
+    new_settings.save(series_id, &state.pool).await?;
+    let mut message = "Saved the channel settings".to_string();
+    if new_settings.channel_name != old_settings.channel_name {
+        let events = db::get_upcoming_events_for_series(&state.pool, series_id).await?;
+        let channel_name = match (&new_settings.channel_name, events.first()) {
+            (Some(channel_name), _) => Some(channel_name.as_str()),
+            (None, Some(next_event)) => new_settings.channel_name(next_event).ok(),
+            (None, None) => None,
+        };
+        if let Some(channel_name) = channel_name {
+            let rename = series_settings::rename_channels(
+                series_id,
+                channel_name,
+                &state.pool,
+                &state.discord_cache_http,
+            );
+            match tokio::time::timeout(std::time::Duration::from_secs(5), rename).await {
+                Ok(Ok(())) => (),
+                Ok(Err(err)) => {
+                    eprintln!(
+                        "Could not rename the channels of event series {}:\n{:#?}",
+                        series_id.0, err
+                    );
+                    message = "Saved the channel settings, but the channel could not be \
+                               renamed. Please try again later."
+                        .to_string();
+                }
+                Err(_) => {
+                    message = "Saved the channel settings. Discord is slow to rename the \
+                               channel, it might take a few minutes."
+                        .to_string();
+                }
+            }
+        }
+    }
+    // The topic and category are applied by the Discord sync
+    state.spawn_series_sync(series_id);
+    Ok(message)
+}
+
+async fn reminders_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(series_id): Path<i32>,
+    Form(form): Form<RemindersForm>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    if let Some(response) = check_form(
+        &state,
+        member_id,
+        series_id,
+        &form.flow_id,
+        &form.csrf_token,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+    // Empty goes back to the default, "off" mutes the reminders
+    let reminder_minutes = match form.reminder_minutes.trim() {
+        "" => Ok(None),
+        "off" => Ok(Some(vec![])),
+        value => match session_reminder::parse_reminder_minutes(value) {
+            Some(minutes) if session_reminder::are_valid_reminder_minutes(&minutes) => {
+                Ok(Some(minutes))
+            }
+            _ => Err(format!(
+                "Please enter up to {} reminder times between {} minutes and {} days before \
+                 the session, like \"1d, 2h\"",
+                session_reminder::MAX_NUM_REMINDERS,
+                session_reminder::MIN_REMINDER_MINUTES,
+                session_reminder::MAX_REMINDER_MINUTES / (24 * 60)
+            )),
+        },
+    };
+    let message = match reminder_minutes {
+        Ok(reminder_minutes) => {
+            session_reminder::set_series_reminder_minutes(
+                series_id,
+                reminder_minutes.as_deref(),
+                &state.pool,
+            )
+            .await?;
+            session_reminder::set_series_reminder_dm(
+                series_id,
+                form.reminder_dm.is_some(),
+                &state.pool,
+            )
+            .await?;
+            "Saved the session reminders".to_string()
+        }
+        Err(message) => message,
+    };
+    series_response(&state, member_id, series_id, message).await
+}
+
+async fn expiration_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(series_id): Path<i32>,
+    Form(form): Form<ExpirationForm>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    if let Some(response) = check_form(
+        &state,
+        member_id,
+        series_id,
+        &form.flow_id,
+        &form.csrf_token,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+    // Validate all settings before changing any of them
+    let mut overrides = Vec::with_capacity(PolicySetting::ALL.len());
+    for setting in PolicySetting::ALL {
+        let hours = match form.value(setting).trim() {
+            "" => None,
+            value => match expiration_policy::parse_hours(value) {
+                Some(hours) if setting.is_valid(hours) => Some(hours),
+                Some(_) => {
+                    return series_response(&state, member_id, series_id, setting.valid_range())
+                        .await
+                }
+                None => {
+                    let message = format!(
+                        "The {} should be a duration like \"3d\" or \"12h\"",
+                        setting.name()
+                    );
+                    return series_response(&state, member_id, series_id, message).await;
+                }
+            },
+        };
+        overrides.push((setting, hours));
+    }
+    let mut tx = state.pool.begin().await?;
+    for (setting, hours) in overrides {
+        expiration_policy::set_series_override(series_id, setting, hours, &mut tx).await?;
+    }
+    tx.commit().await?;
+    series_response(
+        &state,
+        member_id,
+        series_id,
+        "Saved the channel expiration".to_string(),
+    )
+    .await
+}
+
+async fn hosts_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(series_id): Path<i32>,
+    Form(form): Form<HostsForm>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    if let Some(response) = check_form(
+        &state,
+        member_id,
+        series_id,
+        &form.flow_id,
+        &form.csrf_token,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+    let message = change_co_hosts(&state, member_id, series_id, &form).await?;
+    series_response(&state, member_id, series_id, message).await
+}
+
+async fn change_co_hosts(
+    state: &Arc<State>,
+    member_id: db::MemberId,
+    series_id: db::EventSeriesId,
+    form: &HostsForm,
+) -> Result<String, WebError> {
+    let event_ids: Vec<_> = db::get_upcoming_events_for_series(&state.pool, series_id)
+        .await?
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+    let hosts = db::get_events_participants(&event_ids, true, &state.pool).await?;
+    let target_id = db::MemberId(form.member_id);
+    match form.action.as_str() {
+        "add" => {
+            // Co-hosts are picked from the players of the upcoming sessions
+            let players = db::get_events_participants(&event_ids, false, &state.pool).await?;
+            let player = match players.iter().find(|player| player.id == target_id) {
+                Some(player) => player,
+                None => return Ok("Only players of upcoming sessions can be co-hosts".to_string()),
+            };
+            if !series_settings::add_co_host(series_id, target_id, &state.pool).await? {
+                return Ok(format!(
+                    "{} already hosts all sessions",
+                    member_name(player)
+                ));
+            }
+            // The Discord sync gives the new co-host their permissions
+            state.spawn_series_sync(series_id);
+            Ok(format!("{} is now a co-host", member_name(player)))
+        }
+        "remove" => {
+            let host = match hosts.iter().find(|host| host.id == target_id) {
+                Some(host) => host,
+                None => return Ok("This member does not host any upcoming session".to_string()),
+            };
+            if hosts.len() < 2 {
+                return Ok("The last host of a game can't be removed".to_string());
+            }
+            series_settings::remove_co_host(series_id, target_id, &state.pool).await?;
+            if let (Some(host_discord_id), Some(actor)) = (
+                host.discord_id,
+                auth::member_discord_id(state, member_id).await?,
+            ) {
+                let auditor = Auditor::new(
+                    AuditSource::Web {
+                        actor,
+                        page: "series settings",
+                    },
+                    &state.pool,
+                    &state.discord_cache_http,
+                    &state.guild_config.get(),
+                );
+                if let Err(err) = series_settings::revoke_host_permissions(
+                    series_id,
+                    host_discord_id,
+                    &state.pool,
+                    &state.discord_cache_http,
+                    &auditor,
+                )
+                .await
+                {
+                    eprintln!(
+                        "Could not revoke the host permissions of member {}:\n{:#?}",
+                        target_id.0, err
+                    );
+                }
+            }
+            state.spawn_series_sync(series_id);
+            Ok(format!("{} is no longer a host", member_name(host)))
+        }
+        _ => Ok("Unknown action".to_string()),
+    }
+}
+
+async fn end_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(series_id): Path<i32>,
+    Form(form): Form<EndForm>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    if let Some(response) = check_form(
+        &state,
+        member_id,
+        series_id,
+        &form.flow_id,
+        &form.csrf_token,
+    )
+    .await?
+    {
+        return Ok(response);
+    }
+    let mut tx = state.pool.begin().await?;
+    let channel_id = lib::get_series_text_channel(series_id, &mut tx).await?;
+    let end_adventure_result = match channel_id {
+        Some(channel_id) => lib::end_adventure::end_adventure(channel_id, &mut tx).await?,
+        None => EndAdventureResult::NotAGameChannel,
+    };
+    tx.commit().await?;
+    let message = match end_adventure_result {
+        EndAdventureResult::NotAGameChannel => "This game has no channel".to_string(),
+        EndAdventureResult::NoExpirationTime => lib::strings::CHANNEL_NO_EXPIRATION.to_string(),
+        EndAdventureResult::NotYetExpired => {
+            "The adventure can only be ended after the last session".to_string()
+        }
+        EndAdventureResult::AlreadyMarkedForDeletion(deletion_time) => format!(
+            "The channel is already marked for closing on {}",
+            format_time(deletion_time)
+        ),
+        EndAdventureResult::NewlyMarkedForDeletion(deletion_time) => {
+            // Same announcements as for the "end adventure" command
+            if let Some(channel_id) = channel_id {
+                let discord_api = &state.discord_cache_http;
+                channel_id
+                    .say(
+                        &discord_api.http,
+                        lib::strings::CHANNEL_MARKED_FOR_CLOSING(deletion_time),
+                    )
+                    .await
+                    .ok();
+                let bot_alerts_channel_id = state.guild_config.get().bot_alerts_channel_id;
+                if let (Some(bot_alerts_channel_id), Some(discord_id)) = (
+                    bot_alerts_channel_id,
+                    auth::member_discord_id(&state, member_id).await?,
+                ) {
+                    let channel_name = channel_id
+                        .name(discord_api)
+                        .await
+                        .unwrap_or_else(|_| "'unknown'".to_string());
+                    bot_alerts_channel_id
+                        .say(
+                            &discord_api.http,
+                            lib::strings::CHANNEL_MARKED_FOR_CLOSING_ALERT(
+                                channel_id,
+                                &channel_name,
+                                discord_id,
+                            ),
+                        )
+                        .await
+                        .ok();
+                }
+            }
+            format!(
+                "The adventure has ended. The channel will be closed on {}.",
+                format_time(deletion_time)
+            )
+        }
+    };
+    series_response(&state, member_id, series_id, message).await
+}
@@ -1,4 +1,4 @@
-use std::{future::Future, sync::Arc};
+use std::{future::Future, path::PathBuf, sync::Arc};
 
 use askama::Template;
 use askama_axum::IntoResponse;
@@ -10,19 +10,55 @@ use axum::{
 use futures_util::lock::Mutex;
 use tower_http::services::ServeDir;
 
-use super::{api, auth, linking, schedule_session, stripe_webhook_endpoint};
+use super::{
+    api, attendance, auth, calendar, linking, profile, schedule_session, series, stats,
+    stripe_webhook_endpoint, swissrpg_webhook_endpoint, transcripts, waitlist,
+};
 
 pub struct State {
     pub oauth2_consumer: Arc<lib::meetup::oauth2::OAuth2Consumer>,
-    pub redis_client: redis::Client,
     pub pool: sqlx::PgPool,
     pub async_meetup_client: Arc<Mutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>,
     pub swissrpg_client: Arc<lib::swissrpg::client::SwissRPGClient>,
     pub discord_cache_http: lib::discord::CacheAndHttp,
+    pub guild_config: lib::discord::guild_config::SharedGuildConfig,
     pub bot_name: String,
     pub stripe_webhook_secret: Option<String>,
     pub stripe_client: Arc<stripe::Client>,
+    pub swissrpg_webhook_secret: Option<String>,
     pub api_keys: Vec<String>,
+    pub transcript_directory: Arc<PathBuf>,
+}
+
+impl State {
+    // Syncs the event series to Discord in the background
+    pub fn spawn_series_sync(self: &Arc<Self>, series_id: lib::db::EventSeriesId) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            if let Err(err) = state.sync_series(series_id).await {
+                eprintln!(
+                    "Discord sync of event series {} failed:\n{:#?}",
+                    series_id.0, err
+                );
+            }
+        });
+    }
+
+    async fn sync_series(
+        &self,
+        series_id: lib::db::EventSeriesId,
+    ) -> Result<(), lib::meetup::Error> {
+        let bot_id = self.discord_cache_http.cache.current_user().id;
+        lib::discord::sync::sync_single_series(
+            series_id,
+            &self.pool,
+            &self.discord_cache_http,
+            &self.guild_config.get(),
+            bot_id,
+            self.swissrpg_client.base_url(),
+        )
+        .await
+    }
 }
 
 #[derive(Template)]
@@ -36,35 +72,47 @@ async fn main_handler() -> impl IntoResponse {
 pub fn create_server(
     oauth2_consumer: Arc<lib::meetup::oauth2::OAuth2Consumer>,
     addr: std::net::SocketAddr,
-    redis_client: redis::Client,
     pool: sqlx::PgPool,
     async_meetup_client: Arc<Mutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>,
     swissrpg_client: Arc<lib::swissrpg::client::SwissRPGClient>,
     discord_cache_http: lib::discord::CacheAndHttp,
+    guild_config: lib::discord::guild_config::SharedGuildConfig,
     bot_name: String,
     stripe_webhook_secret: Option<String>,
     stripe_client: Arc<stripe::Client>,
+    swissrpg_webhook_secret: Option<String>,
     api_keys: Vec<String>,
     static_file_directory: String,
+    transcript_directory: Arc<PathBuf>,
     shutdown_signal: impl Future<Output = ()> + Send + 'static,
 ) -> impl Future<Output = ()> + Send + 'static {
     let state = Arc::new(State {
         oauth2_consumer,
-        redis_client,
         pool,
         async_meetup_client,
         swissrpg_client,
         discord_cache_http,
+        guild_config,
         bot_name,
         stripe_webhook_secret,
         stripe_client,
+        swissrpg_webhook_secret,
         api_keys,
+        transcript_directory,
     });
     let linking_routes = linking::create_routes();
     let schedule_session_routes = schedule_session::create_routes();
     let stripe_webhook_routes = stripe_webhook_endpoint::create_routes();
+    let swissrpg_webhook_routes = swissrpg_webhook_endpoint::create_routes();
     let auth_routes = auth::create_routes();
+    let calendar_routes = calendar::create_routes();
     let api_routes = api::create_routes();
+    let transcript_routes = transcripts::create_routes();
+    let waitlist_routes = waitlist::create_routes();
+    let attendance_routes = attendance::create_routes();
+    let stats_routes = stats::create_routes();
+    let series_routes = series::create_routes();
+    let profile_routes = profile::create_routes();
     let static_route: Router = Router::new().nest_service(
         "/static",
         get_service(ServeDir::new(static_file_directory).append_index_html_on_directories(false)),
@@ -72,7 +120,15 @@ pub fn create_server(
     let router = linking_routes
         .merge(schedule_session_routes)
         .merge(stripe_webhook_routes)
+        .merge(swissrpg_webhook_routes)
         .merge(auth_routes)
+        .merge(calendar_routes)
+        .merge(transcript_routes)
+        .merge(waitlist_routes)
+        .merge(attendance_routes)
+        .merge(stats_routes)
+        .merge(series_routes)
+        .merge(profile_routes)
         .route(
             "/",
             get(main_handler).layer(axum::middleware::from_fn(auth::auth)),
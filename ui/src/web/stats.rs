@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Query},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono_tz::Europe;
+use lib::stats::{self, ActivityStats};
+use serde::Deserialize;
+
+use super::{auth, auth::AuthenticatedMember, server::State, WebError};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route("/stats", get(stats_handler))
+        .route("/stats/monthly.csv", get(monthly_csv_handler))
+        .route("/stats/hosts.csv", get(hosts_csv_handler))
+        .route_layer(axum::middleware::from_fn(auth::auth))
+}
+
+#[derive(Deserialize)]
+struct StatsQuery {
+    months: Option<u32>,
+}
+
+impl StatsQuery {
+    fn num_months(&self) -> u32 {
+        self.months
+            .unwrap_or(stats::DEFAULT_NUM_MONTHS)
+            .clamp(1, stats::MAX_NUM_MONTHS)
+    }
+}
+
+// Bar widths are in percent of the largest value of the chart
+struct MonthView {
+    month: String,
+    num_sessions: i64,
+    num_online_sessions: i64,
+    num_in_person_sessions: i64,
+    num_players: i64,
+    num_new_players: i64,
+    retention: String,
+    num_hosts: i64,
+    num_player_seats: i64,
+    online_bar: i64,
+    in_person_bar: i64,
+    returning_bar: i64,
+    new_bar: i64,
+    other_bar: i64,
+}
+
+struct HostView {
+    name: String,
+    num_sessions: i64,
+    num_online_sessions: i64,
+    num_series: i64,
+    num_player_seats: i64,
+    last_session: String,
+    sessions_bar: i64,
+}
+
+#[derive(Template)]
+#[template(path = "stats.html")]
+struct StatsTemplate {
+    num_months: u32,
+    first_month: String,
+    summary: stats::Summary,
+    months: Vec<MonthView>,
+    hosts: Vec<HostView>,
+}
+
+fn percent(value: i64, max: i64) -> i64 {
+    if max > 0 {
+        value * 100 / max
+    } else {
+        0
+    }
+}
+
+fn stats_template(stats: ActivityStats, num_months: u32) -> StatsTemplate {
+    let max_sessions = stats.months.iter().map(|m| m.num_sessions).max();
+    let max_sessions = max_sessions.unwrap_or(0);
+    let max_players = stats.months.iter().map(|m| m.num_players).max();
+    let max_players = max_players.unwrap_or(0);
+    let max_host_sessions = stats.hosts.first().map_or(0, |host| host.num_sessions);
+    let months = stats
+        .months
+        .iter()
+        .rev()
+        .map(|month| {
+            // New players never played before this month and returning
+            // players played last month, so both are distinct subsets of this
+            // month's players
+            let num_returning = month.num_returning_players;
+            let num_new = month.num_new_players;
+            let num_other = month.num_players - num_returning - num_new;
+            MonthView {
+                month: month.month.format("%Y-%m").to_string(),
+                num_sessions: month.num_sessions,
+                num_online_sessions: month.num_online_sessions,
+                num_in_person_sessions: month.num_in_person_sessions(),
+                num_players: month.num_players,
+                num_new_players: month.num_new_players,
+                retention: month
+                    .retention()
+                    .map(|retention| format!("{:.0}%", retention * 100.0))
+                    .unwrap_or_else(|| "–".to_string()),
+                num_hosts: month.num_hosts,
+                num_player_seats: month.num_player_seats,
+                online_bar: percent(month.num_online_sessions, max_sessions),
+                in_person_bar: percent(month.num_in_person_sessions(), max_sessions),
+                returning_bar: percent(num_returning, max_players),
+                new_bar: percent(num_new, max_players),
+                other_bar: percent(num_other, max_players),
+            }
+        })
+        .collect();
+    let hosts = stats
+        .hosts
+        .into_iter()
+        .map(|host| HostView {
+            name: host.name,
+            num_sessions: host.num_sessions,
+            num_online_sessions: host.num_online_sessions,
+            num_series: host.num_series,
+            num_player_seats: host.num_player_seats,
+            last_session: host
+                .last_session
+                .with_timezone(&Europe::Zurich)
+                .format("%d.%m.%Y")
+                .to_string(),
+            sessions_bar: percent(host.num_sessions, max_host_sessions),
+        })
+        .collect();
+    StatsTemplate {
+        num_months,
+        first_month: stats.first_month.format("%B %Y").to_string(),
+        summary: stats.summary,
+        months,
+        hosts,
+    }
+}
+
+// The statistics are meant for the organisers
+async fn check_bot_admin(state: &State, member_id: lib::db::MemberId) -> Result<(), WebError> {
+    if auth::is_bot_admin(state, member_id).await? {
+        Ok(())
+    } else {
+        Err(WebError::Unauthorized(Some(
+            "Only bot admins can see the statistics".into(),
+        )))
+    }
+}
+
+async fn stats_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response, WebError> {
+    check_bot_admin(&state, member_id).await?;
+    let num_months = query.num_months();
+    let stats = ActivityStats::load(num_months, &state.pool).await?;
+    Ok(stats_template(stats, num_months).into_response())
+}
+
+fn csv_response(filename: &str, csv: String) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        csv,
+    )
+        .into_response()
+}
+
+async fn monthly_csv_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response, WebError> {
+    check_bot_admin(&state, member_id).await?;
+    let stats = ActivityStats::load(query.num_months(), &state.pool).await?;
+    Ok(csv_response("swissrpg-monthly.csv", stats.monthly_csv()))
+}
+
+async fn hosts_csv_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Response, WebError> {
+    check_bot_admin(&state, member_id).await?;
+    let stats = ActivityStats::load(query.num_months(), &state.pool).await?;
+    Ok(csv_response("swissrpg-hosts.csv", stats.hosts_csv()))
+}
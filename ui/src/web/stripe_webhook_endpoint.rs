@@ -90,7 +90,9 @@ async fn stripe_webhook_handler(
         if event.type_ == stripe::EventType::CustomerSubscriptionCreated {
             if let stripe::EventObject::Subscription(subscription) = event.data.object {
                 if let Err(err) = handle_new_subscription(
+                    &state.pool,
                     &state.discord_cache_http,
+                    &state.guild_config.get(),
                     &state.stripe_client,
                     &subscription,
                 )
@@ -106,7 +108,9 @@ async fn stripe_webhook_handler(
 }
 
 async fn handle_new_subscription(
+    db_connection: &sqlx::PgPool,
     discord_api: &lib::discord::CacheAndHttp,
+    guild_config: &lib::discord::guild_config::GuildConfig,
     stripe_client: &stripe::Client,
     subscription: &stripe::Subscription,
 ) -> Result<(), lib::meetup::Error> {
@@ -134,9 +138,19 @@ async fn handle_new_subscription(
         .and_then(|metadata| metadata.get("Discord"))
     {
         // Try to find the Discord user associated with this subscription
-        let id =
-            lib::tasks::subscription_roles::discord_username_to_id(discord_api, username).await?;
+        let id = lib::tasks::subscription_roles::discord_username_to_id(
+            discord_api,
+            guild_config.guild_id,
+            username,
+        )
+        .await?;
         if let Some(discord_id) = id {
+            let auditor = lib::audit::Auditor::new(
+                lib::audit::AuditSource::Task("Stripe webhook"),
+                db_connection,
+                discord_api,
+                guild_config,
+            );
             // TODO: might block
             let discord_user = discord_id.to_user(discord_api).await?;
             let is_champion_product = product.name.as_ref().map_or(false, |name| {
@@ -149,32 +163,36 @@ async fn handle_new_subscription(
                 if let Ok(true) = discord_user
                     .has_role(
                         discord_api,
-                        lib::discord::sync::ids::GUILD_ID,
-                        lib::discord::sync::ids::GAME_MASTER_ID,
+                        guild_config.guild_id,
+                        guild_config.game_master_role_id,
                     )
                     .await
                 {
                     println!("Adding GM Champion role");
                     lib::tasks::subscription_roles::add_member_role(
                         discord_api,
+                        guild_config.guild_id,
                         discord_id,
-                        lib::tasks::subscription_roles::ids::GM_CHAMPION_ID,
+                        guild_config.gm_champion_role_id,
                         Some(
                             "Automatic role assignment due to being a GM champion (via Stripe \
                              Webhook)",
                         ),
+                        &auditor,
                     )
                     .await?;
                 } else {
                     println!("Adding Champion role");
                     lib::tasks::subscription_roles::add_member_role(
                         discord_api,
+                        guild_config.guild_id,
                         discord_id,
-                        lib::tasks::subscription_roles::ids::CHAMPION_ID,
+                        guild_config.champion_role_id,
                         Some(
                             "Automatic role assignment due to being a champion (via Stripe \
                              Webhook)",
                         ),
+                        &auditor,
                     )
                     .await?;
                 }
@@ -183,9 +201,11 @@ async fn handle_new_subscription(
                 println!("Adding Insider role");
                 lib::tasks::subscription_roles::add_member_role(
                     discord_api,
+                    guild_config.guild_id,
                     discord_id,
-                    lib::tasks::subscription_roles::ids::INSIDER_ID,
+                    guild_config.insider_role_id,
                     Some("Automatic role assignment due to being an insider (via Stripe Webhook)"),
+                    &auditor,
                 )
                 .await?;
             }
@@ -0,0 +1,124 @@
+use std::{ops::Deref, sync::Arc};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Extension, TypedHeader},
+    headers::Header,
+    http::StatusCode,
+    routing::post,
+    Router,
+};
+use lazy_static::lazy_static;
+use lib::swissrpg::webhook::{self, WebhookPayload};
+
+use super::server::State;
+
+pub fn create_routes() -> Router {
+    Router::new().route(
+        "/webhooks/swissrpg",
+        post(swissrpg_webhook_handler).layer(DefaultBodyLimit::max(262144)),
+    )
+}
+
+struct SwissRPGSignatureHeader(String);
+
+lazy_static! {
+    static ref SWISSRPG_SIGNATURE_HEADER: axum::headers::HeaderName =
+        axum::headers::HeaderName::from_static(webhook::SIGNATURE_HEADER);
+}
+
+impl Deref for SwissRPGSignatureHeader {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Header for SwissRPGSignatureHeader {
+    fn name() -> &'static axum::headers::HeaderName {
+        &SWISSRPG_SIGNATURE_HEADER
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, axum::headers::Error>
+    where
+        Self: Sized,
+        I: Iterator<Item = &'i axum::headers::HeaderValue>,
+    {
+        let value = values.next().ok_or_else(axum::headers::Error::invalid)?;
+        let value = value
+            .to_str()
+            .map_err(|_| axum::headers::Error::invalid())?;
+        Ok(SwissRPGSignatureHeader(value.into()))
+    }
+
+    fn encode<E: Extend<axum::headers::HeaderValue>>(&self, values: &mut E) {
+        match axum::headers::HeaderValue::from_str(&self.0) {
+            Ok(header_value) => values.extend(Some(header_value)),
+            Err(err) => eprintln!(
+                "Failed to encode SwissRPG-Signature HTTP header: {:#?}",
+                err
+            ),
+        }
+    }
+}
+
+// Called by the SwissRPG platform whenever an event, session or RSVP changes.
+// The periodic sync still runs in case a webhook gets lost.
+async fn swissrpg_webhook_handler(
+    TypedHeader(signature): TypedHeader<SwissRPGSignatureHeader>,
+    Extension(state): Extension<Arc<State>>,
+    payload: Bytes,
+) -> StatusCode {
+    let swissrpg_webhook_secret =
+        if let Some(swissrpg_webhook_secret) = state.swissrpg_webhook_secret.as_deref() {
+            swissrpg_webhook_secret
+        } else {
+            eprintln!("SwissRPG webhook secret not set");
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        };
+    if let Err(err) = webhook::verify_signature(
+        &payload,
+        &signature,
+        swissrpg_webhook_secret,
+        chrono::Utc::now(),
+    ) {
+        eprintln!("SwissRPG webhook: {}", err);
+        return StatusCode::BAD_REQUEST;
+    }
+    let payload: WebhookPayload = match serde_json::from_slice(&payload) {
+        Ok(payload) => payload,
+        Err(err) => {
+            eprintln!("SwissRPG webhook: could not parse the payload:\n{:#?}", err);
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    println!(
+        "SwissRPG webhook: \"{}\" for event series {}",
+        payload.kind, payload.event.uuid
+    );
+    tokio::spawn(async move {
+        if let Err(err) = handle_event_change(&state, &payload).await {
+            eprintln!(
+                "Could not handle SwissRPG webhook for event series {}:\n{:#?}",
+                payload.event.uuid, err
+            );
+        }
+    });
+    StatusCode::OK
+}
+
+async fn handle_event_change(
+    state: &Arc<State>,
+    payload: &WebhookPayload,
+) -> Result<(), lib::BoxedError> {
+    // Nothing upcoming means there is nothing to sync to Discord either
+    if let Some(series_id) =
+        lib::swissrpg::sync::sync_single_event_series(&payload.event, &state.pool).await?
+    {
+        state.spawn_series_sync(series_id);
+    }
+    // The seat count might have gone up
+    lib::discord::waitlist::offer_all_free_seats(&state.pool, &state.discord_cache_http).await?;
+    Ok(())
+}
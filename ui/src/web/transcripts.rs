@@ -0,0 +1,171 @@
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Path},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono_tz::Europe;
+use lib::discord::transcript::{self, Transcript, TranscriptAttachment, TranscriptEmbed};
+use serenity::model::id::ChannelId;
+
+use super::{auth, auth::AuthenticatedMember, server::State, MessageTemplate, WebError};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route("/transcripts", get(transcripts_handler))
+        .route("/transcripts/:channel_id", get(transcript_handler))
+        .route(
+            "/transcripts/:channel_id/download",
+            get(transcript_download_handler),
+        )
+        .route_layer(axum::middleware::from_fn(auth::auth))
+}
+
+struct TranscriptListEntry {
+    channel_id: u64,
+    title: String,
+    date: String,
+}
+
+#[derive(Template)]
+#[template(path = "transcripts.html")]
+struct TranscriptsTemplate {
+    transcripts: Vec<TranscriptListEntry>,
+}
+
+struct MessageView<'a> {
+    author_name: &'a str,
+    time: String,
+    edited: bool,
+    content: &'a str,
+    pinned: bool,
+    attachments: &'a [TranscriptAttachment],
+    embeds: &'a [TranscriptEmbed],
+}
+
+#[derive(Template)]
+#[template(path = "transcript.html")]
+struct TranscriptTemplate<'a> {
+    channel_id: u64,
+    channel_name: &'a str,
+    messages: Vec<MessageView<'a>>,
+}
+
+// Formats a timestamp in Swiss time
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.with_timezone(&Europe::Zurich)
+        .format("%d.%m.%Y %H:%M")
+        .to_string()
+}
+
+fn format_rfc3339(time: &str) -> String {
+    match chrono::DateTime::parse_from_rfc3339(time) {
+        Ok(time) => format_time(time.with_timezone(&chrono::Utc)),
+        Err(_) => time.to_string(),
+    }
+}
+
+async fn transcripts_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    let transcripts = transcript::get_member_transcripts(member_id, &state.pool)
+        .await?
+        .into_iter()
+        .map(|info| TranscriptListEntry {
+            channel_id: info.channel_id.get(),
+            title: info.title.unwrap_or_else(|| "Untitled".to_string()),
+            date: format_time(info.transcript_time),
+        })
+        .collect();
+    Ok(TranscriptsTemplate { transcripts }.into_response())
+}
+
+// Loads the channel's transcript if the member may read it
+async fn load_transcript(
+    state: &State,
+    member_id: lib::db::MemberId,
+    channel_id: u64,
+) -> Result<Result<Transcript, Response>, WebError> {
+    let not_found = || {
+        let template: MessageTemplate = (
+            "Transcript not found",
+            "There is no transcript of this channel",
+        )
+            .into();
+        template.into_response()
+    };
+    if channel_id == 0 {
+        return Ok(Err(not_found()));
+    }
+    let file_name =
+        match transcript::get_transcript_file(ChannelId::new(channel_id), &state.pool).await? {
+            Some(file_name) => file_name,
+            None => return Ok(Err(not_found())),
+        };
+    let transcript = transcript::load_transcript(&state.transcript_directory, &file_name).await?;
+    if !transcript::member_may_access(member_id, &transcript, &state.pool).await? {
+        return Err(WebError::Unauthorized(Some(
+            "Only hosts and players of this channel's games can read its transcript".into(),
+        )));
+    }
+    Ok(Ok(transcript))
+}
+
+async fn transcript_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(channel_id): Path<u64>,
+) -> Result<Response, WebError> {
+    let transcript = match load_transcript(&state, member_id, channel_id).await? {
+        Ok(transcript) => transcript,
+        Err(response) => return Ok(response),
+    };
+    let messages = transcript
+        .messages
+        .iter()
+        .map(|message| MessageView {
+            author_name: &message.author_name,
+            time: format_rfc3339(&message.timestamp),
+            edited: message.edited_timestamp.is_some(),
+            content: &message.content,
+            pinned: message.pinned,
+            attachments: &message.attachments,
+            embeds: &message.embeds,
+        })
+        .collect();
+    let template = TranscriptTemplate {
+        channel_id: transcript.channel_id,
+        channel_name: &transcript.channel_name,
+        messages,
+    };
+    Ok(template.into_response())
+}
+
+async fn transcript_download_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(channel_id): Path<u64>,
+) -> Result<Response, WebError> {
+    let transcript = match load_transcript(&state, member_id, channel_id).await? {
+        Ok(transcript) => transcript,
+        Err(response) => return Ok(response),
+    };
+    let json = serde_json::to_vec_pretty(&transcript).map_err(lib::meetup::Error::from)?;
+    let content_disposition = format!(
+        "attachment; filename=\"{}.json\"",
+        transcript.channel_name.replace(['"', '\\'], "")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (header::CONTENT_DISPOSITION, content_disposition),
+        ],
+        json,
+    )
+        .into_response())
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Form, Path},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono_tz::Europe;
+use lib::discord::waitlist::{self, ClaimResult, WaitlistClaimFlow};
+use serde::Deserialize;
+
+use super::{server::State, MessageTemplate, WebError};
+
+pub fn create_routes() -> Router {
+    Router::new().route(
+        "/waitlist/claim/:flow_id",
+        get(claim_handler).post(claim_post_handler),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "waitlist_claim.html")]
+struct ClaimTemplate {
+    title: String,
+    start_time: String,
+    claim_expires: String,
+    csrf_token: String,
+}
+
+#[derive(Deserialize)]
+struct ClaimForm {
+    csrf_token: String,
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.with_timezone(&Europe::Zurich)
+        .format("%d.%m.%Y %H:%M")
+        .to_string()
+}
+
+// Only shows the offer, the seat is claimed with the form. Otherwise link
+// previews would claim it.
+async fn claim_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<String>,
+) -> Result<Response, WebError> {
+    let flow = WaitlistClaimFlow::retrieve(&state.pool, &flow_id).await?;
+    let offer = match &flow {
+        Some(flow) => waitlist::get_claim_offer(&flow.state, &state.pool).await?,
+        None => None,
+    };
+    let (flow, offer) = match (flow, offer) {
+        (Some(flow), Some(offer)) => (flow, offer),
+        _ => {
+            let template: MessageTemplate = (
+                "This link has expired",
+                "The seat is no longer reserved for you. Sorry!",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+    };
+    Ok(ClaimTemplate {
+        title: offer.title,
+        start_time: format_time(offer.start_time),
+        claim_expires: format_time(offer.claim_expires),
+        csrf_token: flow.csrf_token,
+    }
+    .into_response())
+}
+
+async fn claim_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<String>,
+    Form(form): Form<ClaimForm>,
+) -> Result<Response, WebError> {
+    // This is a one-time use link. Expire it now.
+    let flow = match WaitlistClaimFlow::take(&state.pool, &flow_id, &form.csrf_token).await? {
+        Some(flow) => flow,
+        None => {
+            let template: MessageTemplate = (
+                "This link has expired",
+                "The seat is no longer reserved for you. Sorry!",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+    };
+    let template: MessageTemplate = match waitlist::claim_seat(&flow.state, &state.pool).await? {
+        ClaimResult::Claimed {
+            event_series_id,
+            title,
+        } => {
+            // Gives the player access to the game channel
+            state.spawn_series_sync(event_series_id);
+            (
+                "You're in!",
+                format!("You got a seat in {}. See you at the table!", title),
+            )
+                .into()
+        }
+        ClaimResult::Full => (
+            "Sorry, the seat is gone",
+            "The session filled up in the meantime. You are still on the waitlist and will get \
+             a new link when the next seat frees up.",
+        )
+            .into(),
+        ClaimResult::Expired | ClaimResult::Invalid => (
+            "This link has expired",
+            "The seat is no longer reserved for you. Sorry!",
+        )
+            .into(),
+        ClaimResult::Closed => (
+            "Too late",
+            "This session has already started or does not exist anymore.",
+        )
+            .into(),
+    };
+    Ok(template.into_response())
+}